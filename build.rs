@@ -0,0 +1,27 @@
+// Embeds the commit being built, so bug reports can say exactly which build they came from
+
+use std::path::Path;
+use std::process::Command;
+
+fn git(args: &[&str]) -> Option<String> {
+	let output = Command::new("git").args(args).output().ok()?;
+	output.status.success().then(|| String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+fn main() {
+	let commit = match (git(&["rev-parse", "--short=12", "HEAD"]), git(&["status", "--porcelain", "--untracked-files=no"])) {
+		(Some(commit), Some(changes)) if !changes.is_empty() => format!("{}-dirty", commit),
+		(Some(commit), _) => commit,
+		(None, _) => String::from("unknown"),
+	};
+
+	println!("cargo:rustc-env=MANDELBROT_VIEWER_GIT_COMMIT={}", commit);
+	println!("cargo:rustc-env=MANDELBROT_VIEWER_TARGET={}", std::env::var("TARGET").unwrap());
+
+	// Missing paths would rerun the script on every build, as in a source tarball
+	for path in [".git/HEAD", ".git/index", ".git/refs"] {
+		if Path::new(path).exists() {
+			println!("cargo:rerun-if-changed={}", path);
+		}
+	}
+}
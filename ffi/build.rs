@@ -0,0 +1,21 @@
+// Regenerates include/mandelbrot_viewer.h from the extern "C" functions in src/lib.rs
+
+fn main() {
+	let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+
+	let config = cbindgen::Config {
+		language: cbindgen::Language::C,
+		include_guard: Some(String::from("MANDELBROT_VIEWER_H")),
+		autogen_warning: Some(String::from("/* Generated by cbindgen from src/lib.rs, do not edit */")),
+		..cbindgen::Config::default()
+	};
+
+	cbindgen::Builder::new()
+		.with_crate(&crate_dir)
+		.with_config(config)
+		.generate()
+		.expect("could not generate the C header")
+		.write_to_file(format!("{}/include/mandelbrot_viewer.h", crate_dir));
+
+	println!("cargo:rerun-if-changed=src/lib.rs");
+}
@@ -0,0 +1,60 @@
+#ifndef MANDELBROT_VIEWER_H
+#define MANDELBROT_VIEWER_H
+
+/* Generated by cbindgen from src/lib.rs, do not edit */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#define MANDELBROT_OK 0
+
+#define MANDELBROT_NULL_POINTER -1
+
+#define MANDELBROT_INVALID_VIEW -2
+
+#define MANDELBROT_BUFFER_TOO_SMALL -3
+
+/**
+ * What to render. A magnification of 1 spans -2 to 2 across the smaller side of the image,
+ * rotation is in radians anticlockwise
+ */
+typedef struct MandelbrotView {
+  double center_real;
+  double center_imaginary;
+  double magnification;
+  double rotation;
+  double bailout;
+  double max_iterations;
+} MandelbrotView;
+
+/**
+ * Renders the view as 8 bit RGBA, row major from the top left.
+ * buffer_len is in bytes and must be at least width * height * 4.
+ * Returns MANDELBROT_OK, or one of the negative error codes leaving the buffer untouched
+ *
+ * # Safety
+ * view must point to a MandelbrotView and buffer to at least buffer_len writable bytes
+ */
+int32_t mandelbrot_render_rgba(const struct MandelbrotView *view,
+                               uint32_t width,
+                               uint32_t height,
+                               uint8_t *buffer,
+                               uintptr_t buffer_len);
+
+/**
+ * Renders the escape count of each pixel, row major from the top left, NaN inside the set.
+ * buffer_len is in doubles and must be at least width * height.
+ * Returns MANDELBROT_OK, or one of the negative error codes leaving the buffer untouched
+ *
+ * # Safety
+ * view must point to a MandelbrotView and buffer to at least buffer_len writable doubles
+ */
+int32_t mandelbrot_render_iterations(const struct MandelbrotView *view,
+                                     uint32_t width,
+                                     uint32_t height,
+                                     double *buffer,
+                                     uintptr_t buffer_len);
+
+#endif  /* MANDELBROT_VIEWER_H */
@@ -0,0 +1,163 @@
+// A C API over the render core, see include/mandelbrot_viewer.h
+
+use std::slice;
+use std::sync::Arc;
+
+use mandelbrot_viewer::complex::Complex;
+use mandelbrot_viewer::export;
+use mandelbrot_viewer::gradient::Gradient;
+use mandelbrot_viewer::renderer::{self, Frame, IterationSettings, RenderJob};
+use mandelbrot_viewer::viewport::Viewport;
+
+pub const MANDELBROT_OK: i32 = 0;
+pub const MANDELBROT_NULL_POINTER: i32 = -1;
+pub const MANDELBROT_INVALID_VIEW: i32 = -2;
+pub const MANDELBROT_BUFFER_TOO_SMALL: i32 = -3;
+
+/// What to render. A magnification of 1 spans -2 to 2 across the smaller side of the image,
+/// rotation is in radians anticlockwise
+#[repr(C)]
+#[derive(Copy, Clone)]
+pub struct MandelbrotView {
+	pub center_real: f64,
+	pub center_imaginary: f64,
+	pub magnification: f64,
+	pub rotation: f64,
+
+	pub bailout: f64,
+	pub max_iterations: f64,
+}
+
+fn render_view(view: &MandelbrotView, width: u32, height: u32) -> Option<Frame> {
+	let viewport_values = [view.center_real, view.center_imaginary, view.magnification, view.rotation];
+	let settings = IterationSettings { bailout: view.bailout, ..IterationSettings::mandelbrot(view.max_iterations) };
+
+	let is_valid = width > 0 && height > 0 && viewport_values.iter().all(|value| value.is_finite()) && view.magnification > 0.0;
+
+	if !is_valid || settings.validate().is_err() {
+		return None;
+	}
+
+	let (width, height) = (width as usize, height as usize);
+
+	let mut viewport = Viewport::new(width as f64, height as f64);
+	viewport.set_center(Complex::new(view.center_real, view.center_imaginary));
+	viewport.set_magnification(view.magnification);
+	viewport.set_rotation(view.rotation);
+
+	Some(renderer::render(RenderJob::new(width, height, 1, viewport, settings, Arc::new(Gradient::rainbow())), None))
+}
+
+// Shared checks of both entry points, writes the values into the buffer when everything is valid
+unsafe fn render_into<T: Copy>(
+	view: *const MandelbrotView,
+	width: u32,
+	height: u32,
+	buffer: *mut T,
+	buffer_len: usize,
+	values_per_pixel: usize,
+	values: impl FnOnce(&Frame) -> Vec<T>,
+) -> i32 {
+	if view.is_null() || buffer.is_null() {
+		return MANDELBROT_NULL_POINTER;
+	}
+
+	if buffer_len < width as usize * height as usize * values_per_pixel {
+		return MANDELBROT_BUFFER_TOO_SMALL;
+	}
+
+	let Some(frame) = render_view(&*view, width, height) else {
+		return MANDELBROT_INVALID_VIEW;
+	};
+
+	let values = values(&frame);
+	slice::from_raw_parts_mut(buffer, values.len()).copy_from_slice(&values);
+
+	MANDELBROT_OK
+}
+
+/// Renders the view as 8 bit RGBA, row major from the top left.
+/// buffer_len is in bytes and must be at least width * height * 4.
+/// Returns MANDELBROT_OK, or one of the negative error codes leaving the buffer untouched
+///
+/// # Safety
+/// view must point to a MandelbrotView and buffer to at least buffer_len writable bytes
+#[no_mangle]
+pub unsafe extern "C" fn mandelbrot_render_rgba(
+	view: *const MandelbrotView,
+	width: u32,
+	height: u32,
+	buffer: *mut u8,
+	buffer_len: usize,
+) -> i32 {
+	render_into(view, width, height, buffer, buffer_len, 4, |frame| export::rgba_bytes(&frame.pixels, 8))
+}
+
+/// Renders the escape count of each pixel, row major from the top left, NaN inside the set.
+/// buffer_len is in doubles and must be at least width * height.
+/// Returns MANDELBROT_OK, or one of the negative error codes leaving the buffer untouched
+///
+/// # Safety
+/// view must point to a MandelbrotView and buffer to at least buffer_len writable doubles
+#[no_mangle]
+pub unsafe extern "C" fn mandelbrot_render_iterations(
+	view: *const MandelbrotView,
+	width: u32,
+	height: u32,
+	buffer: *mut f64,
+	buffer_len: usize,
+) -> i32 {
+	render_into(view, width, height, buffer, buffer_len, 1, |frame| {
+		frame.mean_iterations().map(|value| value.unwrap_or(f64::NAN)).collect()
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::ptr;
+
+	const VIEW: MandelbrotView = MandelbrotView {
+		center_real: -0.5,
+		center_imaginary: 0.0,
+		magnification: 1.0,
+		rotation: 0.0,
+
+		bailout: 2.0,
+		max_iterations: 50.0,
+	};
+
+	#[test]
+	fn renders_into_the_buffer() {
+		let mut buffer = vec![0.0; 16];
+		let result = unsafe { mandelbrot_render_iterations(&VIEW, 4, 4, buffer.as_mut_ptr(), buffer.len()) };
+
+		assert_eq!(result, MANDELBROT_OK);
+
+		// The center is inside the set, the corners escape straight away
+		assert!(buffer[2 * 4 + 2].is_nan());
+		assert_eq!(buffer[0], 1.0);
+	}
+
+	#[test]
+	fn rejects_bad_arguments() {
+		let mut buffer = vec![0u8; 16];
+
+		unsafe {
+			assert_eq!(mandelbrot_render_rgba(ptr::null(), 2, 2, buffer.as_mut_ptr(), 16), MANDELBROT_NULL_POINTER);
+			assert_eq!(mandelbrot_render_rgba(&VIEW, 2, 2, buffer.as_mut_ptr(), 15), MANDELBROT_BUFFER_TOO_SMALL);
+
+			let zoomed_out = MandelbrotView { magnification: 0.0, ..VIEW };
+			assert_eq!(mandelbrot_render_rgba(&zoomed_out, 2, 2, buffer.as_mut_ptr(), 16), MANDELBROT_INVALID_VIEW);
+
+			let endless = MandelbrotView { max_iterations: f64::INFINITY, ..VIEW };
+			assert_eq!(mandelbrot_render_rgba(&endless, 2, 2, buffer.as_mut_ptr(), 16), MANDELBROT_INVALID_VIEW);
+
+			let nowhere = MandelbrotView { center_real: f64::NAN, ..VIEW };
+			assert_eq!(mandelbrot_render_rgba(&nowhere, 2, 2, buffer.as_mut_ptr(), 16), MANDELBROT_INVALID_VIEW);
+		}
+
+		assert!(buffer.iter().all(|&byte| byte == 0));
+	}
+}
@@ -0,0 +1,14 @@
+// The config file, which validation should reject rather than let through to panic later
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mandelbrot_viewer::config::Config;
+
+fuzz_target!(|contents: &str| {
+	if let Ok(config) = toml::from_str::<Config>(contents) {
+		if config.validate().is_ok() {
+			config.interior();
+		}
+	}
+});
@@ -0,0 +1,25 @@
+// Animation files given to --animate, sampled across their timeline as rendering would
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mandelbrot_viewer::animation::Flythrough;
+use mandelbrot_viewer::renderer::IterationSettings;
+use mandelbrot_viewer::session::Session;
+use mandelbrot_viewer::viewport::Viewport;
+
+fuzz_target!(|contents: &str| {
+	let settings = IterationSettings::mandelbrot(100.0);
+	let start = Session::capture(&Viewport::new(1.0, 1.0), &settings, "rainbow");
+
+	if let Ok(flythrough) = Flythrough::parse(contents, &start) {
+		let animation = flythrough.animation;
+		let duration = animation.duration();
+
+		animation.frame_count(flythrough.frame_rate);
+
+		for i in 0..=16 {
+			animation.sample(duration * i as f64 / 16.0);
+		}
+	}
+});
@@ -0,0 +1,18 @@
+// The short strings typed or pasted into the viewer and given on the command line
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mandelbrot_viewer::complex::Complex;
+use mandelbrot_viewer::export;
+use mandelbrot_viewer::gradient;
+use mandelbrot_viewer::rays::Angle;
+use mandelbrot_viewer::selection::Region;
+
+fuzz_target!(|text: &str| {
+	let _ = text.parse::<Complex>();
+	let _ = text.parse::<Angle>();
+	let _ = text.parse::<Region>();
+	let _ = export::parse_skew(text);
+	let _ = gradient::parse_hex(text);
+});
@@ -0,0 +1,16 @@
+// Palette files, which are reloaded while the viewer is open as soon as they're saved
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mandelbrot_viewer::gradient::Gradient;
+
+fuzz_target!(|contents: &str| {
+	if let Ok(gradient) = Gradient::parse("fuzz", contents) {
+		for i in 0..=16 {
+			gradient.sample(i as f32 / 16.0);
+		}
+
+		gradient.sample(f32::NAN);
+	}
+});
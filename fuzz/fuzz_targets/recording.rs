@@ -0,0 +1,13 @@
+// Input recordings given to --replay, played back in full
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mandelbrot_viewer::recording::{Recording, Replay};
+
+fuzz_target!(|contents: &str| {
+	if let Ok(recording) = Recording::parse(contents) {
+		let mut replay = Replay::new(recording.events);
+		while replay.next_due(u64::MAX).is_some() {}
+	}
+});
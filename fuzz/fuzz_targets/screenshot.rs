@@ -0,0 +1,10 @@
+// Screenshots given to --open, whose view is read back from their text chunks
+
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use mandelbrot_viewer::export;
+
+fuzz_target!(|image: &[u8]| {
+	let _ = export::read_view(image, "fuzz");
+});
@@ -0,0 +1,85 @@
+// The render core as a Python module, build with maturin from this directory
+
+use std::sync::Arc;
+
+use mandelbrot_viewer::complex::Complex;
+use mandelbrot_viewer::gradient::Gradient;
+use mandelbrot_viewer::renderer::{self, IterationSettings, RenderJob};
+use mandelbrot_viewer::viewport::Viewport;
+use numpy::ndarray::Array2;
+use numpy::{IntoPyArray, PyArray2};
+use pyo3::exceptions::PyValueError;
+use pyo3::prelude::*;
+
+/// Escape counts of a view as a (height, width) float64 array, NaN for points inside the set.
+///
+/// center is (real, imaginary), zoom is the magnification where 1 spans -2 to 2 across the
+/// smaller side, and size is (width, height) in pixels.
+#[pyfunction]
+#[pyo3(signature = (center, zoom, size, iterations, bailout = 2.0))]
+fn render<'py>(
+	py: Python<'py>,
+	center: (f64, f64),
+	zoom: f64,
+	size: (usize, usize),
+	iterations: f64,
+	bailout: f64,
+) -> PyResult<Bound<'py, PyArray2<f64>>> {
+	let (width, height) = size;
+	let (viewport, settings) = view(center, zoom, size, iterations, bailout).map_err(PyValueError::new_err)?;
+
+	// Other Python threads can carry on while the render runs
+	let values: Vec<f64> = py.detach(|| {
+		let frame = renderer::render(RenderJob::new(width, height, 1, viewport, settings, Arc::new(Gradient::rainbow())), None);
+
+		frame.mean_iterations().map(|value| value.unwrap_or(f64::NAN)).collect()
+	});
+
+	let array = Array2::from_shape_vec((height, width), values).expect("a value per pixel");
+
+	Ok(array.into_pyarray(py))
+}
+
+// The arguments checked and turned into what the renderer takes
+fn view(center: (f64, f64), zoom: f64, size: (usize, usize), iterations: f64, bailout: f64) -> Result<(Viewport, IterationSettings), String> {
+	let (width, height) = size;
+
+	if width == 0 || height == 0 {
+		return Err(String::from("size must be at least 1x1"));
+	}
+
+	if !center.0.is_finite() || !center.1.is_finite() || !zoom.is_finite() || zoom <= 0.0 {
+		return Err(String::from("center must be finite and zoom a finite number greater than 0"));
+	}
+
+	let settings = IterationSettings { bailout, ..IterationSettings::mandelbrot(iterations) };
+	settings.validate()?;
+
+	let mut viewport = Viewport::new(width as f64, height as f64);
+	viewport.set_center(Complex::new(center.0, center.1));
+	viewport.set_magnification(zoom);
+
+	Ok((viewport, settings))
+}
+
+#[pymodule]
+#[pyo3(name = "mandelbrot_viewer")]
+fn python_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+	m.add_function(wrap_pyfunction!(render, m)?)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn arguments_no_render_could_finish_are_rejected() {
+		assert!(view((-0.5, 0.0), 1.0, (4, 4), 100.0, 2.0).is_ok());
+
+		assert!(view((-0.5, 0.0), 1.0, (0, 4), 100.0, 2.0).is_err());
+		assert!(view((f64::NAN, 0.0), 1.0, (4, 4), 100.0, 2.0).is_err());
+		assert!(view((-0.5, 0.0), f64::INFINITY, (4, 4), 100.0, 2.0).is_err());
+		assert!(view((-0.5, 0.0), 1.0, (4, 4), f64::INFINITY, 2.0).is_err());
+		assert!(view((-0.5, 0.0), 1.0, (4, 4), 100.0, f64::NAN).is_err());
+	}
+}
@@ -0,0 +1,88 @@
+use ggez::graphics::Color;
+
+use crate::renderer::Frame;
+
+// Passes averaged before the image is considered converged
+pub const MAX_PASSES: u32 = 64;
+
+// Additive recurrence from the plastic constant (the R2 sequence), which spreads
+// successive jitters evenly over the pixel without any randomness
+const R2_ALPHA: (f64, f64) = (0.754_877_666_246_692_8, 0.569_840_290_998_053_3);
+
+// Sub-pixel offset in [-0.5, 0.5) for an accumulation pass, pass 0 is the unjittered render
+pub fn jitter(pass: u32) -> (f64, f64) {
+	if pass == 0 {
+		return (0.0, 0.0);
+	}
+
+	let n = pass as f64;
+
+	((0.5 + R2_ALPHA.0 * n).fract() - 0.5, (0.5 + R2_ALPHA.1 * n).fract() - 0.5)
+}
+
+// Running total of jittered renders of one view, averaged into the displayed frame
+pub struct Accumulation {
+	pub generation: u64,
+	sum: Vec<Color>,
+	passes: u32,
+}
+
+impl Accumulation {
+	pub fn start(frame: &Frame) -> Accumulation {
+		Accumulation {
+			generation: frame.generation,
+			sum: frame.pixels.clone(),
+			passes: 1,
+		}
+	}
+
+	pub fn next_pass(&self) -> Option<u32> {
+		(self.passes < MAX_PASSES).then_some(self.passes)
+	}
+
+	// The pass must be of the same view and resolution as the displayed frame
+	pub fn add(&mut self, pass: &Frame, displayed: &mut Frame) {
+		if pass.pixels.len() != self.sum.len() {
+			return;
+		}
+
+		self.passes += 1;
+		let count = self.passes as f32;
+
+		for ((total, &color), shown) in self.sum.iter_mut().zip(pass.pixels.iter()).zip(displayed.pixels.iter_mut()) {
+			total.r += color.r;
+			total.g += color.g;
+			total.b += color.b;
+			total.a += color.a;
+
+			*shown = Color::new(total.r / count, total.g / count, total.b / count, total.a / count);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn jitter_stays_within_the_pixel() {
+		assert_eq!(jitter(0), (0.0, 0.0));
+
+		for pass in 1..MAX_PASSES {
+			let (x, y) = jitter(pass);
+			assert!((-0.5..0.5).contains(&x) && (-0.5..0.5).contains(&y));
+		}
+	}
+
+	#[test]
+	fn jitter_covers_each_quadrant() {
+		let mut quadrants = [false; 4];
+
+		for pass in 1..16 {
+			let (x, y) = jitter(pass);
+			quadrants[(x >= 0.0) as usize + 2 * (y >= 0.0) as usize] = true;
+		}
+
+		assert_eq!(quadrants, [true; 4]);
+	}
+}
@@ -0,0 +1,71 @@
+use std::io;
+
+// Pinning render workers to cores, so a long render on a many-core or NUMA machine keeps each
+// worker's tiles in one core's caches and memory node instead of migrating between them
+
+pub const SUPPORTED: bool = cfg!(target_os = "linux");
+
+// The cores the calling thread may run on, which new threads inherit
+#[cfg(target_os = "linux")]
+pub fn allowed_cores() -> io::Result<Vec<usize>> {
+	// SAFETY: the set is zeroed and its size passed along with it
+	unsafe {
+		let mut set: libc::cpu_set_t = std::mem::zeroed();
+
+		if libc::sched_getaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &mut set) != 0 {
+			return Err(io::Error::last_os_error());
+		}
+
+		Ok((0..libc::CPU_SETSIZE as usize).filter(|&core| libc::CPU_ISSET(core, &set)).collect())
+	}
+}
+
+// Restricts the calling thread to these cores
+#[cfg(target_os = "linux")]
+pub fn set_current_thread(cores: &[usize]) -> io::Result<()> {
+	// SAFETY: as above, and CPU_SET ignores cores past the end of the set
+	unsafe {
+		let mut set: libc::cpu_set_t = std::mem::zeroed();
+
+		for &core in cores {
+			libc::CPU_SET(core, &mut set);
+		}
+
+		if libc::sched_setaffinity(0, std::mem::size_of::<libc::cpu_set_t>(), &set) != 0 {
+			return Err(io::Error::last_os_error());
+		}
+	}
+
+	Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn allowed_cores() -> io::Result<Vec<usize>> {
+	Err(io::Error::new(io::ErrorKind::Unsupported, "thread pinning is only supported on Linux"))
+}
+
+#[cfg(not(target_os = "linux"))]
+pub fn set_current_thread(_cores: &[usize]) -> io::Result<()> {
+	Err(io::Error::new(io::ErrorKind::Unsupported, "thread pinning is only supported on Linux"))
+}
+
+#[cfg(all(test, target_os = "linux"))]
+mod tests {
+	use super::*;
+
+	use std::thread;
+
+	#[test]
+	fn pins_and_unpins_a_thread() {
+		thread::spawn(|| {
+			let cores = allowed_cores().unwrap();
+			assert!(!cores.is_empty());
+
+			set_current_thread(&cores[..1]).unwrap();
+			assert_eq!(allowed_cores().unwrap(), &cores[..1]);
+
+			set_current_thread(&cores).unwrap();
+			assert_eq!(allowed_cores().unwrap(), cores);
+		}).join().unwrap();
+	}
+}
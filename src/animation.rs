@@ -0,0 +1,605 @@
+use std::f64::consts::TAU;
+use std::io;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use ggez::graphics::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::checkpoint::{self, Checkpoint};
+use crate::complex::Complex;
+use crate::eta::Eta;
+use crate::export::{self, Transparency};
+use crate::gradient::Gradient;
+use crate::memory::MemoryBudget;
+use crate::postprocess::Effects;
+use crate::random_palette;
+use crate::renderer::{self, IterationSettings, RenderJob};
+use crate::session::Session;
+use crate::viewport::Viewport;
+
+// How the camera speeds up and slows down on the way into a keyframe
+#[derive(Copy, Clone, Default, PartialEq, Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Easing {
+	Linear,
+	#[default]
+	Smooth,
+	EaseIn,
+	EaseOut,
+}
+
+impl Easing {
+	fn apply(self, t: f64) -> f64 {
+		match self {
+			Easing::Linear => t,
+			Easing::Smooth => t * t * (3.0 - 2.0 * t),
+			Easing::EaseIn => t * t,
+			Easing::EaseOut => t * (2.0 - t),
+		}
+	}
+}
+
+// A view to pass through, times are seconds from the start of the animation
+#[derive(Clone)]
+pub struct Keyframe {
+	pub time: f64,
+	pub view: Session,
+	pub easing: Easing,
+}
+
+pub const DEFAULT_FRAME_RATE: f64 = 30.0;
+pub const DEFAULT_FRAME_SIZE: (usize, usize) = (1280, 720);
+
+// Over a day of frames at 30 frames per second, far past anything meant to be rendered
+pub const MAX_FRAMES: usize = 3_000_000;
+
+// Animation files are TOML, with a list of keyframes each reached after its duration in seconds.
+// Anything a keyframe leaves out is carried over from the one before it:
+//
+// frame_rate = 30
+// size = [1280, 720]
+//
+// [[keyframes]]
+// center = [-0.5, 0.0]
+// magnification = 1.0
+// palette = "ultra"
+//
+// [[keyframes]]
+// duration = 8.0
+// easing = "ease-out"
+// center = [-0.743643887, 0.131825904]
+// magnification = 5000.0
+// max_iterations = 500
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AnimationFile {
+	frame_rate: Option<f64>,
+	size: Option<(usize, usize)>,
+	keyframes: Vec<KeyframeFile>,
+}
+
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct KeyframeFile {
+	#[serde(default)]
+	duration: f64,
+	#[serde(default)]
+	easing: Easing,
+
+	center: Option<[f64; 2]>,
+	magnification: Option<f64>,
+	rotation: Option<f64>,
+	skew: Option<[f64; 4]>,
+	max_iterations: Option<f64>,
+	palette: Option<String>,
+}
+
+// A parsed animation file, with the frame rate and size to render it at
+pub struct Flythrough {
+	pub animation: Animation,
+	pub frame_rate: f64,
+	pub size: (usize, usize),
+}
+
+impl Flythrough {
+	// The first keyframe inherits from start, usually the default view
+	pub fn parse(contents: &str, start: &Session) -> Result<Flythrough, String> {
+		let file: AnimationFile = toml::from_str(contents).map_err(|e| e.to_string())?;
+
+		if file.keyframes.is_empty() {
+			return Err(String::from("an animation needs at least one keyframe"));
+		}
+
+		let frame_rate = file.frame_rate.unwrap_or(DEFAULT_FRAME_RATE);
+
+		if !frame_rate.is_finite() || frame_rate <= 0.0 {
+			return Err(String::from("frame_rate must be a finite number greater than 0"));
+		}
+
+		let size = file.size.unwrap_or(DEFAULT_FRAME_SIZE);
+
+		if size.0 == 0 || size.1 == 0 {
+			return Err(String::from("size must be at least 1x1"));
+		}
+
+		let mut animation = Animation::default();
+		let mut view = start.clone();
+		let mut time = 0.0;
+
+		for (i, keyframe) in file.keyframes.into_iter().enumerate() {
+			let number = i + 1;
+
+			if !keyframe.duration.is_finite() || keyframe.duration < 0.0 {
+				return Err(format!("keyframe {}: duration must be a finite number of seconds, at least 0", number));
+			}
+
+			view.center = keyframe.center.unwrap_or(view.center);
+			view.magnification = keyframe.magnification.unwrap_or(view.magnification);
+			view.rotation = keyframe.rotation.unwrap_or(view.rotation);
+			view.skew = keyframe.skew.unwrap_or(view.skew);
+			view.max_iterations = keyframe.max_iterations.unwrap_or(view.max_iterations);
+			view.palette = keyframe.palette.unwrap_or(view.palette);
+
+			if !view.is_valid() {
+				return Err(format!("keyframe {}: values must be finite, magnification and the skew determinant non-zero, and max_iterations at least 1", number));
+			}
+
+			time += keyframe.duration;
+			animation.add(Keyframe { time, view: view.clone(), easing: keyframe.easing });
+		}
+
+		// Checked before counting, so a long animation is an error rather than billions of views
+		if time * frame_rate >= MAX_FRAMES as f64 {
+			return Err(format!("the animation would have over {} frames", MAX_FRAMES));
+		}
+
+		Ok(Flythrough { animation, frame_rate, size })
+	}
+}
+
+// Keyframes sorted by time. Between two keyframes the camera moves with the second's easing,
+// zooming at a steady exponential rate with the center moving so the motion looks even on
+// screen. Palettes can't be blended, so each segment keeps its first keyframe's palette
+#[derive(Clone, Default)]
+pub struct Animation {
+	keyframes: Vec<Keyframe>,
+}
+
+fn lerp(a: f64, b: f64, t: f64) -> f64 {
+	a + (b - a) * t
+}
+
+// Turning the short way around, rotations differing by a whole turn look the same
+fn lerp_angle(a: f64, b: f64, t: f64) -> f64 {
+	let difference = (b - a).rem_euclid(TAU);
+	let difference = if difference > TAU / 2.0 { difference - TAU } else { difference };
+
+	a + difference * t
+}
+
+impl Animation {
+	pub fn keyframes(&self) -> &[Keyframe] {
+		&self.keyframes
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.keyframes.is_empty()
+	}
+
+	pub fn add(&mut self, keyframe: Keyframe) {
+		let index = self.keyframes.partition_point(|other| other.time <= keyframe.time);
+		self.keyframes.insert(index, keyframe);
+	}
+
+	pub fn remove_last(&mut self) -> Option<Keyframe> {
+		self.keyframes.pop()
+	}
+
+	pub fn duration(&self) -> f64 {
+		self.keyframes.last().map_or(0.0, |keyframe| keyframe.time)
+	}
+
+	// Held at the first and last keyframes outside the timeline, None without any keyframes
+	pub fn sample(&self, time: f64) -> Option<Session> {
+		let next = self.keyframes.partition_point(|keyframe| keyframe.time <= time);
+
+		let (from, to) = match next {
+			0 => return self.keyframes.first().map(|keyframe| keyframe.view.clone()),
+			i if i == self.keyframes.len() => return self.keyframes.last().map(|keyframe| keyframe.view.clone()),
+			i => (&self.keyframes[i - 1], &self.keyframes[i]),
+		};
+
+		let t = to.easing.apply((time - from.time) / (to.time - from.time));
+		let (a, b) = (&from.view, &to.view);
+
+		// Scale is in plane units per pixel, so the center fraction for a zoom of ratio r after t
+		// of the way is (1 - r^t) / (1 - r), which keeps points heading for the target still on screen
+		let ratio = a.magnification / b.magnification;
+		let center_t = if (ratio - 1.0).abs() < 1e-9 { t } else { (1.0 - ratio.powf(t)) / (1.0 - ratio) };
+
+		let (start, end) = (Complex::new(a.center[0], a.center[1]), Complex::new(b.center[0], b.center[1]));
+		let center = start + (end - start) * center_t;
+
+		let mut view = a.clone();
+		view.center = [center.real(), center.imaginary()];
+		view.magnification = (lerp(a.magnification.ln(), b.magnification.ln(), t)).exp();
+		view.rotation = lerp_angle(a.rotation, b.rotation, t);
+		view.skew = [0, 1, 2, 3].map(|i| lerp(a.skew[i], b.skew[i], t));
+		view.max_iterations = lerp(a.max_iterations, b.max_iterations, t);
+
+		Some(view)
+	}
+
+	pub fn frame_count(&self, frame_rate: f64) -> usize {
+		if self.is_empty() {
+			return 0;
+		}
+
+		(self.duration() * frame_rate).floor() as usize + 1
+	}
+
+	// Every frame of the timeline, from the first keyframe to the last inclusive
+	pub fn views(&self, frame_rate: f64) -> impl Iterator<Item = Session> + '_ {
+		(0..self.frame_count(frame_rate)).filter_map(move |i| self.sample(i as f64 / frame_rate))
+	}
+}
+
+// The Julia set constant circling a point, such as one on the Mandelbrot boundary, morphs the set
+// through every shape along the way and back to where it started
+#[derive(Copy, Clone)]
+pub struct JuliaMorph {
+	pub center: Complex,
+	pub radius: f64,
+	// Seconds per loop
+	pub period: f64,
+}
+
+impl JuliaMorph {
+	pub fn constant(&self, time: f64) -> Complex {
+		self.center + Complex::from_polar(self.radius, TAU * time / self.period)
+	}
+
+	// One loop of the morph, stopping a frame short of the start so it loops seamlessly
+	pub fn views<'a>(&'a self, view: &'a Session, frame_rate: f64) -> impl Iterator<Item = Session> + 'a {
+		let frame_count = (self.period * frame_rate).round().max(1.0) as usize;
+
+		(0..frame_count).map(move |i| {
+			let c = self.constant(i as f64 / frame_rate);
+
+			Session { julia: Some([c.real(), c.imaginary()]), ..view.clone() }
+		})
+	}
+}
+
+pub struct FrameExport {
+	pub size: (usize, usize),
+	pub samples: usize,
+	pub iteration_multiplier: f64,
+	pub bit_depth: u8,
+	pub shade_interior: bool,
+	pub boundary: bool,
+	// Colours odd iteration bands, as RenderJob::stripes
+	pub stripes: Option<Arc<Gradient>>,
+	// Run over each frame before it's saved
+	pub effects: Option<Effects>,
+	pub transparency: Transparency,
+	// Frames render with fewer samples when theirs wouldn't fit
+	pub budget: MemoryBudget,
+	// Checked before each frame, a cancelled export stops with the frames so far kept
+	pub cancelled: Option<Arc<AtomicBool>>,
+}
+
+// What a sequence's frames were rendered with, which has to match for a rerun to keep them
+#[derive(Serialize)]
+struct FramesManifest<'a> {
+	width: usize,
+	height: usize,
+	samples: usize,
+	iteration_multiplier: f64,
+	bit_depth: u8,
+	shade_interior: bool,
+	boundary: bool,
+	transparency: Transparency,
+	palettes: Vec<String>,
+	stripes: Option<String>,
+	effects: Option<Effects>,
+	views: &'a [Session],
+}
+
+impl FrameExport {
+	fn manifest(&self, views: &[Session], palettes: &[Arc<Gradient>]) -> io::Result<String> {
+		let manifest = FramesManifest {
+			width: self.size.0,
+			height: self.size.1,
+			// As rendered, so a different budget renders again
+			samples: self.budget.samples_for(self.size, self.samples, 1),
+			iteration_multiplier: self.iteration_multiplier,
+			bit_depth: self.bit_depth,
+			shade_interior: self.shade_interior,
+			boundary: self.boundary,
+			transparency: self.transparency,
+			palettes: palettes.iter().map(|palette| checkpoint::describe_palette(palette)).collect(),
+			stripes: self.stripes.as_deref().map(checkpoint::describe_palette),
+			effects: self.effects,
+			views,
+		};
+
+		toml::to_string(&manifest).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+	}
+}
+
+const FRAME_PREFIX: &str = "frame_";
+
+// Numbered frame_00000.png onwards, palettes are looked up by name, random ones regenerated, and
+// fall back to the first. Frames already in the directory from an interrupted run of the same
+// sequence are kept rather than rendered again, those of any other are removed first. Returns how
+// many frames the sequence has
+pub fn render_frames(views: impl IntoIterator<Item = Session>, dir: &Path, options: &FrameExport, palettes: &[Arc<Gradient>]) -> io::Result<usize> {
+	let views: Vec<Session> = views.into_iter().collect();
+	let checkpoint = Checkpoint::open(dir, FRAME_PREFIX, &options.manifest(&views, palettes)?)?;
+
+	let frame_name = |i: usize| format!("{}{:05}.png", FRAME_PREFIX, i);
+
+	if checkpoint.is_resuming() {
+		let done = (0..views.len()).filter(|&i| checkpoint.is_done(&frame_name(i))).count();
+		eprintln!("resuming with {} of {} frames already rendered", done, views.len());
+	}
+
+	let (width, height) = options.size;
+	let samples = options.budget.samples_for(options.size, options.samples, 1);
+
+	if samples < options.samples {
+		eprintln!("rendering with {} samples rather than {} to fit the memory budget", samples * samples, options.samples * options.samples);
+	}
+	let mut eta = Eta::new((0..views.len()).filter(|&i| !checkpoint.is_done(&frame_name(i))).count());
+	let mut recycled = None;
+
+	for (i, view) in views.iter().enumerate() {
+		if checkpoint.is_done(&frame_name(i)) {
+			continue;
+		}
+
+		if options.cancelled.as_ref().is_some_and(|cancelled| cancelled.load(Ordering::Relaxed)) {
+			return Err(io::Error::new(io::ErrorKind::Interrupted, format!("stopped before frame {} of {}", i + 1, views.len())));
+		}
+
+		let mut viewport = Viewport::new(width as f64, height as f64);
+		// Every setting is overwritten by the view
+		let mut settings = IterationSettings::mandelbrot(1.0);
+		view.restore(&mut viewport, &mut settings);
+
+		settings.max_iterations = (settings.max_iterations * options.iteration_multiplier).max(1.0).round();
+
+		let interior = palettes.first().map_or(Color::BLACK, |palette| palette.interior());
+
+		let gradient = palettes.iter()
+			.find(|palette| palette.name == view.palette)
+			.cloned()
+			.or_else(|| random_palette::regenerate(&view.palette).map(|gradient| Arc::new(gradient.with_interior(interior))))
+			.or(palettes.first().cloned())
+			.unwrap_or_else(|| Arc::new(Gradient::rainbow()));
+
+		let frame = renderer::render(RenderJob {
+			shade_interior: options.shade_interior,
+			boundary: options.boundary,
+			stripes: options.stripes.clone(),
+			recycled: recycled.take(),
+			..RenderJob::new(width, height, samples, viewport, settings, gradient)
+		}, None);
+
+		let mut pixels = match options.effects {
+			Some(effects) => effects.apply(&frame.pixels, options.size),
+			None => frame.pixels.clone(),
+		};
+
+		options.transparency.apply(&mut pixels, &frame);
+
+		checkpoint.write(&frame_name(i), |path| export::save_png(path, &pixels, options.size, options.bit_depth, view))?;
+
+		recycled = Some(frame);
+
+		eta.record(1);
+		eprintln!("rendered frame {} of {}{}", i + 1, views.len(), eta.describe());
+	}
+
+	checkpoint.finish()?;
+
+	Ok(views.len())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::fs;
+
+	fn view(center: [f64; 2], magnification: f64, rotation: f64, palette: &str) -> Session {
+		let mut viewport = Viewport::new(8.0, 8.0);
+		viewport.set_center(Complex::new(center[0], center[1]));
+		viewport.set_magnification(magnification);
+		viewport.set_rotation(rotation);
+
+		let settings = IterationSettings::mandelbrot(50.0);
+
+		Session::capture(&viewport, &settings, palette)
+	}
+
+	fn animation() -> Animation {
+		let mut animation = Animation::default();
+
+		// Added out of order, they're kept sorted by time
+		animation.add(Keyframe { time: 4.0, view: view([-0.75, 0.1], 100.0, 0.2, "ultra"), easing: Easing::Smooth });
+		animation.add(Keyframe { time: 0.0, view: view([0.0, 0.0], 1.0, TAU - 0.2, "rainbow"), easing: Easing::Smooth });
+
+		animation
+	}
+
+	#[test]
+	fn holds_at_the_ends() {
+		let animation = animation();
+
+		assert_eq!(animation.duration(), 4.0);
+		assert_eq!(animation.sample(-1.0).unwrap().center, [0.0, 0.0]);
+		assert_eq!(animation.sample(9.0).unwrap().center, [-0.75, 0.1]);
+		assert!(Animation::default().sample(0.0).is_none());
+	}
+
+	#[test]
+	fn passes_through_keyframes() {
+		let animation = animation();
+
+		let start = animation.sample(0.0).unwrap();
+		assert_eq!(start.magnification, 1.0);
+		assert_eq!(start.palette, "rainbow");
+
+		let end = animation.sample(4.0).unwrap();
+		assert_eq!(end.magnification, 100.0);
+		assert_eq!(end.palette, "ultra");
+	}
+
+	#[test]
+	fn zooms_exponentially_and_turns_the_short_way() {
+		let halfway = animation().sample(2.0).unwrap();
+
+		// Halfway in time is halfway in log magnification with the easing symmetric
+		assert!((halfway.magnification - 10.0).abs() < 1e-9);
+		assert!(halfway.rotation.rem_euclid(TAU) < 1e-9 || (TAU - halfway.rotation.rem_euclid(TAU)) < 1e-9);
+		assert_eq!(halfway.palette, "rainbow");
+
+		// The center has covered most of the distance by the time the zoom is halfway
+		assert!(halfway.center[0] < -0.6 && halfway.center[0] > -0.75);
+	}
+
+	#[test]
+	fn renders_numbered_frames() {
+		let dir = std::env::temp_dir().join(format!("mandelbrot_viewer_frames_{}", std::process::id()));
+		let options = FrameExport { size: (4, 4), samples: 1, iteration_multiplier: 1.0, bit_depth: 8, shade_interior: false, boundary: false, stripes: None, effects: None, transparency: Transparency::None, budget: MemoryBudget::UNLIMITED, cancelled: None };
+
+		let count = render_frames(animation().views(0.5), &dir, &options, &[Arc::new(Gradient::rainbow())]).unwrap();
+		let written = fs::read_dir(&dir).unwrap().count();
+		fs::remove_dir_all(&dir).unwrap();
+
+		assert_eq!((count, written), (3, 3));
+	}
+
+	#[test]
+	fn resumes_where_an_interrupted_run_left_off() {
+		let dir = std::env::temp_dir().join(format!("mandelbrot_viewer_frames_resume_{}", std::process::id()));
+		let options = FrameExport { size: (4, 4), samples: 1, iteration_multiplier: 1.0, bit_depth: 8, shade_interior: false, boundary: false, stripes: None, effects: None, transparency: Transparency::None, budget: MemoryBudget::UNLIMITED, cancelled: None };
+		let palettes = [Arc::new(Gradient::rainbow())];
+
+		// As if the run stopped after the second frame
+		let views: Vec<Session> = animation().views(0.5).collect();
+		let checkpoint = Checkpoint::open(&dir, FRAME_PREFIX, &options.manifest(&views, &palettes).unwrap()).unwrap();
+		checkpoint.write("frame_00001.png", |path| fs::write(path, "kept")).unwrap();
+
+		let count = render_frames(views.clone(), &dir, &options, &palettes).unwrap();
+		let kept = fs::read_to_string(dir.join("frame_00001.png")).unwrap();
+
+		// Anything else is rendered again over what's there
+		let different = FrameExport { samples: 2, ..options };
+		render_frames(views, &dir, &different, &palettes).unwrap();
+		let rerendered = fs::read(dir.join("frame_00001.png")).unwrap();
+
+		let written = fs::read_dir(&dir).unwrap().count();
+		fs::remove_dir_all(&dir).unwrap();
+
+		assert_eq!((count, written, kept.as_str()), (3, 3, "kept"));
+		assert!(rerendered.starts_with(b"\x89PNG"));
+	}
+
+	#[test]
+	fn cancelled_exports_stop_before_the_next_frame() {
+		let dir = std::env::temp_dir().join(format!("mandelbrot_viewer_frames_cancelled_{}", std::process::id()));
+		let cancelled = Arc::new(AtomicBool::new(true));
+		let options = FrameExport { size: (4, 4), samples: 1, iteration_multiplier: 1.0, bit_depth: 8, shade_interior: false, boundary: false, stripes: None, effects: None, transparency: Transparency::None, budget: MemoryBudget::UNLIMITED, cancelled: Some(cancelled) };
+
+		let error = render_frames(animation().views(0.5), &dir, &options, &[Arc::new(Gradient::rainbow())]).unwrap_err();
+		let written = fs::read_dir(&dir).unwrap().filter(|file| file.as_ref().unwrap().path().extension().is_some_and(|extension| extension == "png")).count();
+		fs::remove_dir_all(&dir).unwrap();
+
+		assert_eq!((error.kind(), written), (io::ErrorKind::Interrupted, 0));
+	}
+
+	#[test]
+	fn counts_frames_inclusive_of_the_last_keyframe() {
+		assert_eq!(animation().frame_count(30.0), 121);
+		assert_eq!(Animation::default().frame_count(30.0), 0);
+	}
+
+	#[test]
+	fn morph_loops_around_its_center() {
+		let morph = JuliaMorph { center: Complex::new(-0.75, 0.0), radius: 0.1, period: 2.0 };
+
+		assert!((morph.constant(0.0) - Complex::new(-0.65, 0.0)).norm_sqr() < 1e-20);
+		assert!((morph.constant(1.0) - Complex::new(-0.85, 0.0)).norm_sqr() < 1e-20);
+
+		let base = view([0.0, 0.0], 1.0, 0.0, "rainbow");
+		let views: Vec<Session> = morph.views(&base, 10.0).collect();
+
+		assert_eq!(views.len(), 20);
+		assert!(views.iter().all(|view| view.julia.is_some() && view.center == [0.0, 0.0]));
+	}
+
+	#[test]
+	fn easings_run_from_zero_to_one() {
+		for easing in [Easing::Linear, Easing::Smooth, Easing::EaseIn, Easing::EaseOut] {
+			assert_eq!((easing.apply(0.0), easing.apply(1.0)), (0.0, 1.0));
+		}
+
+		assert!(Easing::EaseIn.apply(0.5) < 0.5 && Easing::EaseOut.apply(0.5) > 0.5);
+	}
+
+	#[test]
+	fn parses_flythrough_files() {
+		let start = view([0.0, 0.0], 1.0, 0.0, "rainbow");
+
+		let flythrough = Flythrough::parse(r#"
+			frame_rate = 24
+			size = [64, 48]
+
+			[[keyframes]]
+			palette = "ultra"
+
+			[[keyframes]]
+			duration = 2.0
+			easing = "ease-in"
+			center = [-0.75, 0.1]
+			magnification = 50.0
+
+			[[keyframes]]
+			duration = 1.5
+			rotation = 1.0
+		"#, &start).unwrap();
+
+		assert_eq!((flythrough.frame_rate, flythrough.size), (24.0, (64, 48)));
+
+		let keyframes = flythrough.animation.keyframes();
+		assert_eq!(keyframes.iter().map(|keyframe| keyframe.time).collect::<Vec<_>>(), [0.0, 2.0, 3.5]);
+		assert_eq!(keyframes[1].easing, Easing::EaseIn);
+
+		// Left out fields carry over from the keyframe before
+		assert_eq!(keyframes[0].view.palette, "ultra");
+		assert_eq!(keyframes[2].view.palette, "ultra");
+		assert_eq!(keyframes[2].view.center, [-0.75, 0.1]);
+		assert_eq!(keyframes[2].view.magnification, 50.0);
+	}
+
+	#[test]
+	fn rejects_bad_flythroughs() {
+		let start = view([0.0, 0.0], 1.0, 0.0, "rainbow");
+
+		assert!(Flythrough::parse("keyframes = []", &start).is_err());
+		assert!(Flythrough::parse("[[keyframes]]\nduration = -1.0", &start).is_err());
+		assert!(Flythrough::parse("[[keyframes]]\nmagnification = 0.0", &start).is_err());
+		assert!(Flythrough::parse("[[keyframes]]\nzoom = 2.0", &start).is_err());
+		assert!(Flythrough::parse("frame_rate = 0\n[[keyframes]]", &start).is_err());
+		assert!(Flythrough::parse("frame_rate = inf\n[[keyframes]]", &start).is_err());
+		assert!(Flythrough::parse("[[keyframes]]\nduration = inf", &start).is_err());
+		assert!(Flythrough::parse("[[keyframes]]\nmax_iterations = nan", &start).is_err());
+		assert!(Flythrough::parse("frame_rate = 1e6\n[[keyframes]]\nduration = 1e6", &start).is_err());
+	}
+}
@@ -0,0 +1,165 @@
+use crate::complex::Complex;
+use crate::renderer::Frame;
+use crate::viewport::Viewport;
+
+// A slow dive for when the viewer is left alone: it zooms steadily towards the deepest escaping
+// point of the latest frame, which sits on the boundary where the detail is, and starts over
+// once there's nothing left to see
+
+// Seconds for the magnification to double
+const DOUBLING_TIME: f64 = 3.0;
+
+// Share of the remaining distance to the target covered each second
+const STEERING: f64 = 0.6;
+
+// Radians per second, barely enough to notice
+const SPIN: f64 = 0.03;
+
+// Seconds between picking the target again from the latest frame
+const RETARGET_TIME: f64 = 2.0;
+
+// Past this doubles run out of precision
+const MAX_MAGNIFICATION: f64 = 1e12;
+
+// Dives end when less than this share of the view escapes, as it's mostly interior from then on
+const MIN_ESCAPED: f64 = 0.05;
+
+pub struct Autopilot {
+	target: Option<Complex>,
+	// Seconds since the dive started, and since the target was picked
+	time: f64,
+	targeted: f64,
+}
+
+impl Default for Autopilot {
+	fn default() -> Autopilot {
+		Autopilot::new()
+	}
+}
+
+// The deepest escaping pixel in the middle half of the frame, so the dive doesn't veer off
+// towards the edges. None when nothing there escapes
+pub fn pick_target(frame: &Frame) -> Option<Complex> {
+	let (width, height) = (frame.width, frame.height);
+
+	frame.mean_iterations().enumerate()
+		.filter_map(|(i, iterations)| Some((i % width, i / width, iterations?)))
+		.filter(|&(x, y, _)| (width / 4..width - width / 4).contains(&x) && (height / 4..height - height / 4).contains(&y))
+		.max_by(|a, b| a.2.total_cmp(&b.2))
+		.map(|(x, y, _)| frame.viewport.pixel_to_plane(x as f64 + 0.5, y as f64 + 0.5))
+}
+
+// Whether a dive has gone as deep as it usefully can
+pub fn is_exhausted(frame: &Frame) -> bool {
+	let escaped = frame.mean_iterations().filter(Option::is_some).count();
+
+	frame.viewport.magnification() >= MAX_MAGNIFICATION || (escaped as f64) < (frame.width * frame.height) as f64 * MIN_ESCAPED
+}
+
+// The iteration cap scaled up as the dive deepens, as the boundary takes more iterations to resolve
+pub fn iterations_for(base: f64, magnification: f64) -> f64 {
+	(base * (1.0 + magnification.max(1.0).log10())).round()
+}
+
+impl Autopilot {
+	pub fn new() -> Autopilot {
+		Autopilot { target: None, time: 0.0, targeted: f64::NEG_INFINITY }
+	}
+
+	pub fn time(&self) -> f64 {
+		self.time
+	}
+
+	// Takes a new target from a fresh frame now and then, keeping the old one if it has none
+	pub fn observe(&mut self, frame: &Frame) {
+		if self.time - self.targeted < RETARGET_TIME {
+			return;
+		}
+
+		if let Some(target) = pick_target(frame) {
+			self.target = Some(target);
+			self.targeted = self.time;
+		}
+	}
+
+	pub fn step(&mut self, viewport: &mut Viewport, delta_time: f64) {
+		self.time += delta_time;
+
+		if let Some(target) = self.target {
+			let center = viewport.center();
+			let amount = 1.0 - (1.0 - STEERING).powf(delta_time);
+
+			viewport.set_center(center + (target - center) * amount);
+		}
+
+		viewport.set_magnification(viewport.magnification() * 2f64.powf(delta_time / DOUBLING_TIME));
+		viewport.set_rotation(viewport.rotation() + SPIN * delta_time);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use crate::renderer::{self, RenderJob};
+
+	fn frame(magnification: f64) -> Frame {
+		renderer::test_frame((32, 24), Complex::new(-0.5, 0.0), magnification, 100.0)
+	}
+
+	#[test]
+	fn targets_sit_on_the_boundary() {
+		let frame = frame(1.0);
+		let target = pick_target(&frame).unwrap();
+
+		let pixel = frame.viewport.plane_to_pixel(target);
+		let iterations = frame.mean_iterations().nth(pixel.y as usize * 32 + pixel.x as usize).unwrap();
+
+		assert!(iterations.unwrap() > 20.0);
+		assert!(!is_exhausted(&frame));
+
+		// Deep in the set nothing escapes
+		let mut viewport = frame.viewport;
+		viewport.set_center(Complex::new(-0.1, 0.0));
+		viewport.set_magnification(1000.0);
+		let inside = renderer::render(RenderJob::new(32, 24, 1, viewport, frame.settings, frame.gradient), None);
+
+		assert!(pick_target(&inside).is_none());
+		assert!(is_exhausted(&inside));
+	}
+
+	#[test]
+	fn steps_zoom_and_steer_towards_the_target() {
+		let mut autopilot = Autopilot::new();
+		let mut viewport = Viewport::new(32.0, 24.0);
+
+		autopilot.observe(&frame(1.0));
+		let target = autopilot.target.unwrap();
+		let distance = (target - viewport.center()).norm_sqr();
+
+		for _ in 0..20 {
+			autopilot.step(&mut viewport, 0.05);
+		}
+
+		// The target is only picked again after a while
+		autopilot.observe(&frame(1.0));
+		assert_eq!(autopilot.targeted, 0.0);
+
+		for _ in 0..40 {
+			autopilot.step(&mut viewport, 0.05);
+		}
+
+		assert!((viewport.magnification() - 2.0).abs() < 1e-9);
+		assert!((target - viewport.center()).norm_sqr() < distance);
+
+		autopilot.observe(&frame(1.0));
+		assert!(autopilot.targeted > 2.9);
+	}
+
+	#[test]
+	fn iterations_grow_with_depth() {
+		assert_eq!(iterations_for(100.0, 1.0), 100.0);
+		assert_eq!(iterations_for(100.0, 1e6), 700.0);
+		assert_eq!(iterations_for(100.0, 0.5), 100.0);
+	}
+}
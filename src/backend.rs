@@ -0,0 +1,94 @@
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+
+use crate::queue::WorkQueue;
+use crate::renderer::{self, Frame, RenderJob, RenderedTile};
+
+// Where escape counts are computed. Every backend takes the same jobs and returns the same frames,
+// so the viewer, exports and benchmarks can use any of them
+
+pub trait RenderBackend: Send + Sync {
+	// Shown in the about overlay and benchmark results
+	fn name(&self) -> String;
+
+	// Backends which finish a frame all at once can ignore progress, the view just fills in at the end
+	fn render(&self, job: RenderJob, progress: Option<&Sender<RenderedTile>>) -> Result<Frame, String>;
+}
+
+// The tile renderer, spread over the shared work queue
+pub struct Cpu;
+
+impl RenderBackend for Cpu {
+	fn name(&self) -> String {
+		format!("cpu ({} threads)", WorkQueue::shared().threads())
+	}
+
+	fn render(&self, job: RenderJob, progress: Option<&Sender<RenderedTile>>) -> Result<Frame, String> {
+		Ok(renderer::render(job, progress))
+	}
+}
+
+pub const DEFAULT_BACKEND: &str = "cpu";
+
+// Those compiled into this build, others need their cargo feature
+pub const NAMES: &[&str] = &[
+	"cpu",
+	#[cfg(feature = "opencl")]
+	"opencl",
+];
+
+// Fails when the backend isn't compiled in, or finds nothing to run on
+pub fn create(name: &str) -> Result<Arc<dyn RenderBackend>, String> {
+	match name {
+		"cpu" => Ok(Arc::new(Cpu)),
+		#[cfg(feature = "opencl")]
+		"opencl" => Ok(Arc::new(crate::opencl::OpenCl::new()?)),
+		_ => Err(format!("unknown backend {}, this build has {}", name, NAMES.join(", "))),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use crate::complex::Complex;
+	use crate::gradient::Gradient;
+	use crate::renderer::IterationSettings;
+	use crate::viewport::Viewport;
+
+	#[test]
+	fn sample_points_match_the_tile_renderer() {
+		let settings = IterationSettings::mandelbrot(40.0);
+		let job = || RenderJob::new(24, 16, 2, Viewport::new(24.0, 16.0), settings, Arc::new(Gradient::rainbow()));
+
+		// The same iteration the CPU does per point, as another backend would
+		let iterations: Vec<Option<f64>> = renderer::sample_points(&job()).into_iter().map(|c| {
+			let mut z = Complex::new(0.0, 0.0);
+			let mut count = 0.0;
+
+			while z.norm_sqr() < 4.0 {
+				if count > settings.max_iterations {
+					return None;
+				}
+
+				count += 1.0;
+				z = z * z + c;
+			}
+
+			Some(count)
+		}).collect();
+
+		let tiled = Cpu.render(job(), None).unwrap();
+		let converged = vec![false; iterations.len()];
+		let assembled = renderer::frame_from_iterations(job(), iterations, converged);
+
+		assert_eq!(tiled.iterations, assembled.iterations);
+		assert!(tiled.pixels == assembled.pixels);
+	}
+
+	#[test]
+	fn unknown_backends_are_refused() {
+		assert!(create("cpu").is_ok());
+		assert!(create("abacus").err().unwrap().contains("cpu"));
+	}
+}
@@ -0,0 +1,147 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use crate::complex::Complex;
+use crate::gradient::Gradient;
+use crate::backend::RenderBackend;
+use crate::renderer::{IterationSettings, RenderJob};
+use crate::stats::FrameStats;
+use crate::viewport::Viewport;
+
+// Renders a fixed set of views so throughput can be compared between machines and settings
+
+// Each view is rendered this many times and the fastest kept, the first run warming caches
+const RUNS: usize = 3;
+
+pub struct BenchView {
+	pub name: &'static str,
+	pub center: Complex,
+	pub magnification: f64,
+	pub max_iterations: f64,
+}
+
+// From mostly escaping quickly to mostly deep boundary, which stresses the iteration loop
+pub const VIEWS: [BenchView; 4] = [
+	BenchView { name: "full set", center: Complex::new(-0.5, 0.0), magnification: 1.0, max_iterations: 100.0 },
+	BenchView { name: "seahorse valley", center: Complex::new(-0.745, 0.11), magnification: 200.0, max_iterations: 500.0 },
+	BenchView { name: "elephant valley", center: Complex::new(0.285, 0.01), magnification: 500.0, max_iterations: 1000.0 },
+	BenchView { name: "deep spiral", center: Complex::new(-0.743643887, 0.131825904), magnification: 50000.0, max_iterations: 2000.0 },
+];
+
+impl BenchView {
+	// Samples per axis and the iteration multiplier come from the quality, so presets can be compared
+	pub fn job(&self, size: (usize, usize), samples: usize, iteration_multiplier: f64, bailout: f64, gradient: &Arc<Gradient>) -> RenderJob {
+		let mut viewport = Viewport::new(size.0 as f64, size.1 as f64);
+		viewport.set_center(self.center);
+		viewport.set_magnification(self.magnification);
+
+		let settings = IterationSettings { bailout, ..IterationSettings::mandelbrot((self.max_iterations * iteration_multiplier).max(1.0).round()) };
+
+		RenderJob::new(size.0, size.1, samples, viewport, settings, Arc::clone(gradient))
+	}
+}
+
+pub struct BenchResult {
+	pub backend: String,
+	pub view: &'static str,
+	pub pixels: usize,
+	pub iterations: u64,
+	pub time: Duration,
+}
+
+impl BenchResult {
+	pub fn pixels_per_second(&self) -> f64 {
+		self.pixels as f64 / self.time.as_secs_f64()
+	}
+
+	pub fn iterations_per_second(&self) -> f64 {
+		self.iterations as f64 / self.time.as_secs_f64()
+	}
+}
+
+// Stops at the first failed render, naming the backend
+pub fn run(backends: &[Arc<dyn RenderBackend>], size: (usize, usize), samples: usize, iteration_multiplier: f64, bailout: f64) -> Result<Vec<BenchResult>, String> {
+	let gradient = Arc::new(Gradient::rainbow());
+	let mut results = Vec::new();
+
+	for backend in backends {
+		for view in &VIEWS {
+			let mut fastest = Duration::MAX;
+			let mut iterations = 0;
+
+			for _ in 0..RUNS {
+				let job = view.job(size, samples, iteration_multiplier, bailout, &gradient);
+				let max_iterations = job.settings.max_iterations;
+
+				let start = Instant::now();
+				let frame = backend.render(job, None).map_err(|e| format!("{}: {}", backend.name(), e))?;
+				fastest = fastest.min(start.elapsed());
+
+				iterations = FrameStats::compute(&frame.iterations, max_iterations).total_iterations;
+			}
+
+			results.push(BenchResult {
+				backend: backend.name(),
+				view: view.name,
+				pixels: size.0 * size.1,
+				iterations,
+				time: fastest,
+			});
+		}
+	}
+
+	Ok(results)
+}
+
+pub fn report(results: &[BenchResult]) -> String {
+	let mut lines = vec![format!("{:<28} {:<18} {:>10} {:>14} {:>16}", "backend", "view", "time", "pixels/s", "iterations/s")];
+
+	for result in results {
+		lines.push(format!(
+			"{:<28} {:<18} {:>8.1}ms {:>13.2}M {:>15.2}M",
+			result.backend,
+			result.view,
+			result.time.as_secs_f64() * 1000.0,
+			result.pixels_per_second() / 1e6,
+			result.iterations_per_second() / 1e6,
+		));
+	}
+
+	lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use crate::backend;
+
+	#[test]
+	fn measures_every_view_on_every_backend() {
+		let backends = [backend::create("cpu").unwrap(), backend::create("cpu").unwrap()];
+		let results = run(&backends, (16, 12), 1, 0.1, 2.0).unwrap();
+
+		assert_eq!(results.len(), VIEWS.len() * backends.len());
+
+		for result in &results {
+			assert_eq!(result.pixels, 16 * 12);
+			assert!(result.iterations >= result.pixels as u64);
+		}
+
+		assert_eq!(report(&results).lines().count(), results.len() + 1);
+	}
+
+	#[test]
+	fn rates_are_per_second() {
+		let result = BenchResult {
+			backend: String::from("cpu"),
+			view: "full set",
+			pixels: 1000,
+			iterations: 50000,
+			time: Duration::from_millis(500),
+		};
+
+		assert_eq!(result.pixels_per_second(), 2000.0);
+		assert_eq!(result.iterations_per_second(), 100000.0);
+	}
+}
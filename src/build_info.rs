@@ -0,0 +1,53 @@
+use crate::locale::Locale;
+
+// What this binary was built from and with, for the about overlay and --version
+
+pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+pub const GIT_COMMIT: &str = env!("MANDELBROT_VIEWER_GIT_COMMIT");
+pub const TARGET: &str = env!("MANDELBROT_VIEWER_TARGET");
+pub const PROFILE: &str = if cfg!(debug_assertions) { "debug" } else { "release" };
+
+// Capabilities a bug report needs to know about, and whether this build has them
+pub const FEATURES: [(&str, bool); 5] = [
+	("SIMD", false),
+	("GPU shaders", false),
+	("OpenCL", cfg!(feature = "opencl")),
+	("arbitrary precision", false),
+	("num-complex", cfg!(feature = "num-complex")),
+];
+
+pub fn features(locale: &Locale) -> String {
+	FEATURES
+		.iter()
+		.map(|(name, enabled)| format!("{} {}", name, locale.text(if *enabled { "about.yes" } else { "about.no" })))
+		.collect::<Vec<_>>()
+		.join(", ")
+}
+
+// Renderer and display describe the running viewer, which only it can know
+pub fn summary(locale: &Locale, renderer: &str, display: &str) -> String {
+	locale.format("about", &[
+		("version", &VERSION),
+		("commit", &GIT_COMMIT),
+		("profile", &PROFILE),
+		("target", &TARGET),
+		("features", &features(locale)),
+		("renderer", &renderer),
+		("display", &display),
+	])
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn summary_names_the_build() {
+		let summary = summary(&Locale::default(), "CPU", "Vulkan");
+
+		assert!(summary.contains(VERSION));
+		assert!(summary.contains(GIT_COMMIT));
+		assert!(summary.contains("renderer: CPU\ndisplay: Vulkan"));
+		assert!(summary.contains("GPU shaders no"));
+	}
+}
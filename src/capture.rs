@@ -0,0 +1,182 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, SyncSender};
+use std::thread::{self, JoinHandle};
+
+use ggez::graphics::Color;
+
+use crate::export;
+use crate::session::Session;
+
+// Frames waiting to be written before pushing blocks, so a slow disk slows the viewer down
+// rather than dropping frames or holding an unbounded backlog in memory
+const QUEUE_LENGTH: usize = 16;
+
+struct CapturedFrame {
+	pixels: Vec<Color>,
+	size: (usize, usize),
+	session: Session,
+}
+
+// Nearest neighbour, as frames below window resolution are drawn by stretching each pixel
+pub fn upscale(pixels: &[Color], (width, height): (usize, usize), (new_width, new_height): (usize, usize)) -> Vec<Color> {
+	let mut scaled = Vec::with_capacity(new_width * new_height);
+
+	for y in 0..new_height {
+		let row = (y * height / new_height) * width;
+
+		for x in 0..new_width {
+			scaled.push(pixels[row + x * width / new_width]);
+		}
+	}
+
+	scaled
+}
+
+// Size of an image after downscaling by a whole factor, a partial block at the edges still making
+// a pixel
+pub fn downscaled_size((width, height): (usize, usize), factor: usize) -> (usize, usize) {
+	(width.div_ceil(factor), height.div_ceil(factor))
+}
+
+// Averages each block of factor by factor pixels, for frames rendered above window resolution to
+// be shown sharp rather than aliased
+pub fn downscale(pixels: &[Color], (width, height): (usize, usize), factor: usize) -> Vec<Color> {
+	let (new_width, new_height) = downscaled_size((width, height), factor);
+	let mut scaled = Vec::with_capacity(new_width * new_height);
+
+	for y in 0..new_height {
+		let rows = y * factor..((y + 1) * factor).min(height);
+
+		for x in 0..new_width {
+			let columns = x * factor..((x + 1) * factor).min(width);
+			let mut total = Color::new(0.0, 0.0, 0.0, 0.0);
+
+			for row in rows.clone() {
+				for &color in &pixels[row * width + columns.start..row * width + columns.end] {
+					total.r += color.r;
+					total.g += color.g;
+					total.b += color.b;
+					total.a += color.a;
+				}
+			}
+
+			let count = (rows.len() * columns.len()) as f32;
+			scaled.push(Color::new(total.r / count, total.g / count, total.b / count, total.a / count));
+		}
+	}
+
+	scaled
+}
+
+// Writes numbered frame_00000.png onwards into a directory on a background thread
+pub struct FrameRecorder {
+	dir: PathBuf,
+	sender: Option<SyncSender<CapturedFrame>>,
+	writer: Option<JoinHandle<io::Result<usize>>>,
+}
+
+impl FrameRecorder {
+	pub fn start(dir: &Path) -> io::Result<FrameRecorder> {
+		fs::create_dir_all(dir)?;
+
+		let (sender, frames) = mpsc::sync_channel::<CapturedFrame>(QUEUE_LENGTH);
+		let frame_dir = dir.to_path_buf();
+
+		let writer = thread::spawn(move || {
+			let mut count = 0;
+
+			for frame in frames {
+				let path = frame_dir.join(format!("frame_{:05}.png", count));
+				export::save_png(&path, &frame.pixels, frame.size, 8, &frame.session)?;
+
+				count += 1;
+			}
+
+			Ok(count)
+		});
+
+		Ok(FrameRecorder {
+			dir: dir.to_path_buf(),
+			sender: Some(sender),
+			writer: Some(writer),
+		})
+	}
+
+	pub fn dir(&self) -> &Path {
+		&self.dir
+	}
+
+	// Fails once the writer has stopped on an error, which finish then reports
+	pub fn push(&self, pixels: Vec<Color>, size: (usize, usize), session: Session) -> bool {
+		self.sender.as_ref().is_some_and(|sender| sender.send(CapturedFrame { pixels, size, session }).is_ok())
+	}
+
+	// Waits for the queued frames to be written, returning how many were
+	pub fn finish(mut self) -> io::Result<usize> {
+		self.sender.take();
+
+		match self.writer.take().map(JoinHandle::join) {
+			Some(Ok(result)) => result,
+			Some(Err(_)) => Err(io::Error::other("the frame writer panicked")),
+			None => Ok(0),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use crate::renderer::IterationSettings;
+	use crate::viewport::Viewport;
+
+	#[test]
+	fn upscale_repeats_pixels() {
+		let pixels = [Color::RED, Color::GREEN, Color::BLUE, Color::WHITE];
+		let scaled = upscale(&pixels, (2, 2), (4, 3));
+
+		assert_eq!(scaled.len(), 12);
+		assert_eq!(&scaled[0..4], &[Color::RED, Color::RED, Color::GREEN, Color::GREEN]);
+		assert_eq!(&scaled[8..12], &[Color::BLUE, Color::BLUE, Color::WHITE, Color::WHITE]);
+	}
+
+	#[test]
+	fn upscale_to_the_same_size_is_a_copy() {
+		let pixels = [Color::RED, Color::GREEN, Color::BLUE];
+		assert_eq!(upscale(&pixels, (3, 1), (3, 1)), pixels);
+	}
+
+	#[test]
+	fn downscale_averages_blocks() {
+		let pixels = [
+			Color::RED, Color::BLACK, Color::WHITE,
+			Color::BLACK, Color::BLACK, Color::WHITE,
+		];
+		let scaled = downscale(&pixels, (3, 2), 2);
+
+		assert_eq!(downscaled_size((3, 2), 2), (2, 1));
+		assert_eq!(scaled, [Color::new(0.25, 0.0, 0.0, 1.0), Color::WHITE]);
+		assert_eq!(downscale(&pixels, (3, 2), 1), pixels);
+	}
+
+	#[test]
+	fn recorder_writes_every_frame() {
+		let dir = std::env::temp_dir().join(format!("mandelbrot_viewer_recording_{}", std::process::id()));
+		let settings = IterationSettings::mandelbrot(10.0);
+		let session = Session::capture(&Viewport::new(2.0, 2.0), &settings, "rainbow");
+
+		let recorder = FrameRecorder::start(&dir).unwrap();
+
+		for _ in 0..3 {
+			assert!(recorder.push(vec![Color::RED; 4], (2, 2), session.clone()));
+		}
+
+		let count = recorder.finish().unwrap();
+		let written = fs::read_dir(&dir).unwrap().count();
+		fs::remove_dir_all(&dir).unwrap();
+
+		assert_eq!((count, written), (3, 3));
+	}
+}
@@ -0,0 +1,138 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::gradient::{self, Gradient};
+
+// What a long export has finished so far, kept on disk so running the same export again after an
+// interruption carries on from there. The directory holds the finished pieces and a manifest
+// describing the export, pieces only count while the manifest matches. Pieces are named with a
+// prefix of their own, so those of a different export can be told from anything else in the directory
+
+const MANIFEST_FILE: &str = "checkpoint.toml";
+
+// Everything about a palette that shows in a render, for manifests
+pub fn describe_palette(gradient: &Gradient) -> String {
+	format!("name = \"{}\"\ninterior = \"{}\"\n{}", gradient.name, gradient::to_hex(gradient.interior()), gradient.to_toml())
+}
+
+pub struct Checkpoint {
+	dir: PathBuf,
+	resuming: bool,
+}
+
+impl Checkpoint {
+	// Picks up where an identical export left off, or starts afresh by removing the pieces of any
+	// other and writing this one's manifest
+	pub fn open(dir: &Path, prefix: &str, manifest: &str) -> io::Result<Checkpoint> {
+		fs::create_dir_all(dir)?;
+
+		let path = dir.join(MANIFEST_FILE);
+		let resuming = fs::read_to_string(&path).is_ok_and(|existing| existing == manifest);
+
+		if !resuming {
+			// Otherwise another export interrupted under this manifest would count them as its own
+			for entry in fs::read_dir(dir)? {
+				let entry = entry?;
+
+				if entry.file_name().to_string_lossy().starts_with(prefix) && entry.file_type()?.is_file() {
+					fs::remove_file(entry.path())?;
+				}
+			}
+
+			fs::write(&path, manifest)?;
+		}
+
+		Ok(Checkpoint { dir: dir.to_path_buf(), resuming })
+	}
+
+	pub fn is_resuming(&self) -> bool {
+		self.resuming
+	}
+
+	pub fn path(&self, name: &str) -> PathBuf {
+		self.dir.join(name)
+	}
+
+	// Pieces left by a different export are redone
+	pub fn is_done(&self, name: &str) -> bool {
+		self.resuming && self.path(name).is_file()
+	}
+
+	// Written to a temporary file and renamed into place, so a piece is either whole or missing
+	pub fn write(&self, name: &str, write: impl FnOnce(&Path) -> io::Result<()>) -> io::Result<()> {
+		let temporary = self.path(&format!("{}.partial", name));
+
+		write(&temporary)?;
+		fs::rename(&temporary, self.path(name))
+	}
+
+	// For pieces that are the export's output, only the manifest goes
+	pub fn finish(self) -> io::Result<()> {
+		fs::remove_file(self.dir.join(MANIFEST_FILE))
+	}
+
+	// For pieces that have been assembled into something else
+	pub fn remove(self) -> io::Result<()> {
+		fs::remove_dir_all(&self.dir)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn pieces_only_count_for_the_same_export() {
+		let dir = std::env::temp_dir().join(format!("mandelbrot_viewer_checkpoint_{}", std::process::id()));
+		let _ = fs::remove_dir_all(&dir);
+
+		let checkpoint = Checkpoint::open(&dir, "piece_", "size = 1").unwrap();
+		assert!(!checkpoint.is_resuming());
+
+		checkpoint.write("piece_a", |path| fs::write(path, "done")).unwrap();
+		assert!(!checkpoint.is_done("piece_a"));
+
+		// Interrupted before finishing, then run again
+		let checkpoint = Checkpoint::open(&dir, "piece_", "size = 1").unwrap();
+		assert!(checkpoint.is_resuming() && checkpoint.is_done("piece_a") && !checkpoint.is_done("piece_b"));
+
+		// A failed write leaves nothing behind
+		assert!(checkpoint.write("piece_b", |_| Err(io::Error::other("interrupted"))).is_err());
+		assert!(!checkpoint.path("piece_b").exists());
+
+		let different = Checkpoint::open(&dir, "piece_", "size = 2").unwrap();
+		assert!(!different.is_resuming() && !different.is_done("piece_a"));
+
+		different.write("piece_b", |path| fs::write(path, "done")).unwrap();
+		different.finish().unwrap();
+		assert!(!dir.join(MANIFEST_FILE).exists() && dir.join("piece_b").exists());
+
+		Checkpoint::open(&dir, "piece_", "").unwrap().remove().unwrap();
+		assert!(!dir.exists());
+	}
+
+	#[test]
+	fn pieces_of_an_earlier_export_are_removed() {
+		let dir = std::env::temp_dir().join(format!("mandelbrot_viewer_checkpoint_stale_{}", std::process::id()));
+		let _ = fs::remove_dir_all(&dir);
+
+		// A is interrupted after two pieces
+		let a = Checkpoint::open(&dir, "piece_", "size = 1").unwrap();
+		a.write("piece_0", |path| fs::write(path, "a")).unwrap();
+		a.write("piece_1", |path| fs::write(path, "a")).unwrap();
+		fs::write(dir.join("notes.txt"), "kept").unwrap();
+
+		// Then B after one
+		let b = Checkpoint::open(&dir, "piece_", "size = 2").unwrap();
+		b.write("piece_0", |path| fs::write(path, "b")).unwrap();
+
+		// Running B again only skips its own piece
+		let b = Checkpoint::open(&dir, "piece_", "size = 2").unwrap();
+		let (first, second) = (b.is_done("piece_0"), b.is_done("piece_1"));
+		let notes = dir.join("notes.txt").exists();
+		b.remove().unwrap();
+
+		assert!(first && !second && notes);
+	}
+}
@@ -0,0 +1,73 @@
+use crate::export;
+use crate::renderer::Frame;
+
+// Fingerprints of a rendered frame, for checking that two machines render a view identically.
+// Renders are already deterministic: every sample depends only on its own coordinates and is
+// written to its own place whatever order the tiles finish in, a single pass has no jitter, and
+// Rust never reorders or fuses float operations. What can still differ between platforms is
+// libm's trigonometry in rotated views and the gradient's colour conversions, which is why the
+// iteration counts are hashed separately from the colours built from them
+
+// FNV-1a, written out as std's hashers are free to change between Rust releases
+pub fn fnv1a(bytes: impl IntoIterator<Item = u8>) -> u64 {
+	bytes.into_iter().fold(0xcbf29ce484222325, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3))
+}
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct FrameHash {
+	// Of every sample's escape count, with points inside the set as all ones
+	pub iterations: u64,
+	// Of the 8 bit RGBA pixels, as a screenshot would save them
+	pub pixels: u64,
+}
+
+impl FrameHash {
+	pub fn of(frame: &Frame) -> FrameHash {
+		let iterations = frame.iterations.iter()
+			.flat_map(|iterations| iterations.map_or(u64::MAX, f64::to_bits).to_le_bytes());
+
+		FrameHash {
+			iterations: fnv1a(iterations),
+			pixels: fnv1a(export::rgba_bytes(&frame.pixels, 8)),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::sync::Arc;
+
+	use ggez::mint::Point2;
+
+	use crate::gradient::Gradient;
+	use crate::renderer::{self, IterationSettings, RenderJob};
+	use crate::viewport::Viewport;
+
+	#[test]
+	fn fnv1a_matches_reference_values() {
+		assert_eq!(fnv1a(*b""), 0xcbf29ce484222325);
+		assert_eq!(fnv1a(*b"a"), 0xaf63dc4c8601ec8c);
+		assert_eq!(fnv1a(*b"foobar"), 0x85944171f73967e8);
+	}
+
+	#[test]
+	fn tile_order_does_not_change_the_hash() {
+		let settings = IterationSettings::mandelbrot(50.0);
+
+		let hash = |focus: Point2<f64>, tile_size: usize| {
+			let mut job = RenderJob::new(80, 60, 2, Viewport::new(80.0, 60.0), settings, Arc::new(Gradient::rainbow()));
+			job.focus = focus;
+			job.tile_size = tile_size;
+
+			FrameHash::of(&renderer::render(job, None))
+		};
+
+		let centered = hash(Point2 { x: 40.0, y: 30.0 }, 32);
+
+		assert_eq!(centered, hash(Point2 { x: 0.0, y: 0.0 }, 32));
+		assert_eq!(centered, hash(Point2 { x: 80.0, y: 60.0 }, 32));
+		assert_eq!(centered, hash(Point2 { x: 40.0, y: 30.0 }, 7));
+	}
+}
@@ -0,0 +1,313 @@
+use std::io::{self, Write};
+use std::path::PathBuf;
+
+use clap::{CommandFactory, Parser, Subcommand, ValueEnum};
+use clap_complete::Shell;
+
+use mandelbrot_viewer::config::Config;
+use mandelbrot_viewer::export::{self, Transparency};
+use mandelbrot_viewer::formula::Formula;
+
+// Anything given on the command line overrides the config file
+
+const LONG_VERSION: &str = concat!(
+	env!("CARGO_PKG_VERSION"), " (", env!("MANDELBROT_VIEWER_GIT_COMMIT"), ", ", env!("MANDELBROT_VIEWER_TARGET"), ")",
+);
+
+#[derive(Parser)]
+#[command(version, long_version = LONG_VERSION, about = "Interactive Mandelbrot set viewer", disable_help_subcommand = true)]
+pub struct Args {
+	/// Config file to read instead of mandelbrot_viewer.toml
+	#[arg(long, value_name = "PATH")]
+	pub config: Option<PathBuf>,
+
+	/// Maximum frames drawn per second, 0 for uncapped
+	#[arg(long, value_name = "FPS")]
+	pub fps: Option<u32>,
+
+	/// Simulation updates per second, which drives movement
+	#[arg(long, value_name = "RATE")]
+	pub tick_rate: Option<u32>,
+
+	/// Wait for the display's refresh before presenting each frame
+	#[arg(long, value_name = "BOOL")]
+	pub vsync: Option<bool>,
+
+	/// Open on this monitor, counting from 0 in the order the system lists them
+	#[arg(long, value_name = "INDEX")]
+	pub monitor: Option<usize>,
+
+	/// Place the window's top left corner here, in physical pixels from the monitor's top left
+	#[arg(long, value_name = "X,Y", value_parser = parse_position, allow_hyphen_values = true)]
+	pub window_position: Option<[i32; 2]>,
+
+	/// Open the window without a title bar or border
+	#[arg(long, value_name = "BOOL")]
+	pub borderless: Option<bool>,
+
+	/// Fractal to iterate: mandelbrot, burning-ship, celtic, perpendicular-burning-ship, buffalo,
+	/// magnet-1, magnet-2, lambda or nova
+	#[arg(long, value_name = "FORMULA")]
+	pub formula: Option<Formula>,
+
+	/// Render the view with this backend, cpu or opencl in builds with the opencl feature
+	#[arg(long, value_name = "BACKEND")]
+	pub backend: Option<String>,
+
+	/// Worker threads for rendering, shared by the view and background exports
+	#[arg(long, value_name = "COUNT")]
+	pub threads: Option<usize>,
+
+	/// Language of on-screen text, such as de, or auto to follow the system's locale
+	#[arg(long, value_name = "LANGUAGE")]
+	pub language: Option<String>,
+
+	/// Start from the default view instead of restoring the previous session
+	#[arg(long)]
+	pub fresh: bool,
+
+	/// Open at the view a screenshot was taken from
+	#[arg(long, value_name = "PNG")]
+	pub open: Option<PathBuf>,
+
+	/// Record every input event to this file, written when the window closes
+	#[arg(long, value_name = "PATH", conflicts_with = "replay")]
+	pub record: Option<PathBuf>,
+
+	/// Play back a recording made with --record, from the view it started at
+	#[arg(long, value_name = "PATH")]
+	pub replay: Option<PathBuf>,
+
+	/// Skew the starting view by a row major 2x2 matrix, such as 1,0.2,0,1
+	#[arg(long, value_name = "A,B,C,D", value_parser = export::parse_skew, allow_hyphen_values = true)]
+	pub skew: Option<[f64; 4]>,
+
+	/// Render the starting view to an image of this size and exit, without opening a window
+	#[arg(long, value_name = "WIDTHxHEIGHT", value_parser = parse_size)]
+	pub poster: Option<(usize, usize)>,
+
+	/// Magnification multiplier of each zoom step
+	#[arg(long, value_name = "FACTOR")]
+	pub zoom_factor: Option<f64>,
+
+	/// Magnification multiplier of zoom steps taken with shift held
+	#[arg(long, value_name = "FACTOR")]
+	pub fine_zoom_factor: Option<f64>,
+
+	/// Colour of points inside the set, as #rrggbb
+	#[arg(long, value_name = "COLOR")]
+	pub interior_color: Option<String>,
+
+	/// Bits per channel of screenshots and posters, 8 or 16
+	#[arg(long, value_name = "BITS")]
+	pub bit_depth: Option<u8>,
+
+	/// Leave the interior or exterior transparent in screenshots, posters and animation frames:
+	/// none, interior or exterior
+	#[arg(long, value_name = "POINTS")]
+	pub transparency: Option<Transparency>,
+
+	/// Serve map tiles at /{z}/{x}/{y}.png on this address instead of opening a window
+	#[arg(long, value_name = "ADDRESS")]
+	pub serve: Option<String>,
+
+	/// Render map tiles from zoom 0 down to this level into --output, laid out as z/x/y.png
+	#[arg(long, value_name = "ZOOM")]
+	pub pyramid: Option<u32>,
+
+	/// Render a standard set of views at the configured quality without opening a window, and print
+	/// their throughput. The size defaults to 800x600
+	#[arg(long, value_name = "WIDTHxHEIGHT", value_parser = parse_size, num_args = 0..=1, default_missing_value = "800x600")]
+	pub bench: Option<(usize, usize)>,
+
+	/// Render the benchmark's views on every backend compiled in, compare them pixel by pixel against
+	/// the CPU's and exit with an error if any backend disagrees. The size defaults to 400x300
+	#[arg(long, value_name = "WIDTHxHEIGHT", value_parser = parse_size, num_args = 0..=1, default_missing_value = "400x300")]
+	pub selftest: Option<(usize, usize)>,
+
+	/// Render the starting view without opening a window and print hashes of its iteration counts
+	/// and pixels, to check renders match across machines. The size defaults to 800x600
+	#[arg(long, value_name = "WIDTHxHEIGHT", value_parser = parse_size, num_args = 0..=1, default_missing_value = "800x600")]
+	pub hash: Option<(usize, usize)>,
+
+	/// Render the flythrough described by this animation file to numbered frames in --output and exit
+	#[arg(long, value_name = "TOML")]
+	pub animate: Option<PathBuf>,
+
+	/// Import the locations in Fractint PAR files or CSV lists of center and zoom into the location
+	/// library, rendering a thumbnail of each, and exit
+	#[arg(long, value_name = "FILE", num_args = 1..)]
+	pub import: Vec<PathBuf>,
+
+	/// Where --poster, --pyramid or --animate write to, poster.png, tiles and frames by default
+	#[arg(long, value_name = "PATH")]
+	pub output: Option<PathBuf>,
+
+	#[command(subcommand)]
+	pub command: Option<Command>,
+}
+
+// Hidden from the help, as they're for packagers and for setting up a shell once
+#[derive(Subcommand)]
+pub enum Command {
+	/// Print a shell completion script or a man page built from these options
+	#[command(hide = true)]
+	Generate {
+		#[arg(value_enum)]
+		target: Generated,
+	},
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum Generated {
+	Bash,
+	Zsh,
+	Fish,
+	#[value(name = "powershell")]
+	PowerShell,
+	Man,
+}
+
+fn parse_position(position: &str) -> Result<[i32; 2], String> {
+	let (x, y) = position.split_once(',').ok_or_else(|| format!("expected X,Y, got {}", position))?;
+
+	let coordinate = |value: &str| value.trim().parse::<i32>().map_err(|_| format!("invalid coordinate {}", value));
+
+	Ok([coordinate(x)?, coordinate(y)?])
+}
+
+fn parse_size(size: &str) -> Result<(usize, usize), String> {
+	let (width, height) = size.split_once(['x', 'X']).ok_or_else(|| format!("expected WIDTHxHEIGHT, got {}", size))?;
+
+	let dimension = |value: &str| match value.trim().parse::<usize>() {
+		Ok(value) if value > 0 => Ok(value),
+		_ => Err(format!("invalid dimension {}", value)),
+	};
+
+	Ok((dimension(width)?, dimension(height)?))
+}
+
+// Completions for each shell, or a roff man page for section 1
+pub fn generate(target: Generated, writer: &mut impl Write) -> io::Result<()> {
+	let mut command = Args::command();
+	let name = command.get_name().to_string();
+
+	let shell = match target {
+		Generated::Bash => Shell::Bash,
+		Generated::Zsh => Shell::Zsh,
+		Generated::Fish => Shell::Fish,
+		Generated::PowerShell => Shell::PowerShell,
+		Generated::Man => return clap_mangen::Man::new(command).render(writer),
+	};
+
+	// Written out whole, as the generator panics on a closed pipe rather than returning the error
+	let mut script = Vec::new();
+	clap_complete::generate(shell, &mut command, name, &mut script);
+
+	writer.write_all(&script)
+}
+
+impl Args {
+	pub fn apply(&self, config: &mut Config) {
+		if let Some(fps) = self.fps {
+			config.fps = fps;
+		}
+
+		if let Some(tick_rate) = self.tick_rate {
+			config.tick_rate = tick_rate;
+		}
+
+		if let Some(vsync) = self.vsync {
+			config.vsync = vsync;
+		}
+
+		if let Some(monitor) = self.monitor {
+			config.monitor = Some(monitor);
+		}
+
+		if let Some(window_position) = self.window_position {
+			config.window_position = Some(window_position);
+		}
+
+		if let Some(borderless) = self.borderless {
+			config.borderless = borderless;
+		}
+
+		if let Some(formula) = self.formula {
+			config.formula = formula;
+		}
+
+		if let Some(backend) = &self.backend {
+			config.backend = backend.clone();
+		}
+
+		if let Some(threads) = self.threads {
+			config.threads = threads;
+		}
+
+		if let Some(language) = &self.language {
+			config.language = language.clone();
+		}
+
+		if let Some(zoom_factor) = self.zoom_factor {
+			config.zoom_factor = zoom_factor;
+		}
+
+		if let Some(fine_zoom_factor) = self.fine_zoom_factor {
+			config.fine_zoom_factor = fine_zoom_factor;
+		}
+
+		if let Some(interior_color) = &self.interior_color {
+			config.interior_color = interior_color.clone();
+		}
+
+		if let Some(bit_depth) = self.bit_depth {
+			config.bit_depth = bit_depth;
+		}
+
+		if let Some(transparency) = self.transparency {
+			config.transparency = transparency;
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_positions() {
+		assert_eq!(parse_position("1920,0"), Ok([1920, 0]));
+		assert_eq!(parse_position("-1280, 40"), Ok([-1280, 40]));
+
+		assert!(parse_position("1920").is_err());
+		assert!(parse_position("a,b").is_err());
+	}
+
+	#[test]
+	fn generates_completions_and_the_man_page() {
+		Args::command().debug_assert();
+
+		for target in [Generated::Bash, Generated::Zsh, Generated::Fish, Generated::PowerShell, Generated::Man] {
+			let mut output = Vec::new();
+			generate(target, &mut output).unwrap();
+
+			let output = String::from_utf8(output).unwrap();
+			assert!(output.contains("selftest"), "{} leaves out --selftest", target.to_possible_value().unwrap().get_name());
+		}
+
+		// The subcommand only completes, it isn't in the help
+		let help = Args::command().render_long_help().to_string();
+		assert!(help.contains("--import") && !help.contains("generate"));
+	}
+
+	#[test]
+	fn parses_sizes() {
+		assert_eq!(parse_size("32768x16384"), Ok((32768, 16384)));
+		assert_eq!(parse_size("640X480"), Ok((640, 480)));
+
+		assert!(parse_size("640").is_err());
+		assert!(parse_size("0x480").is_err());
+		assert!(parse_size("axb").is_err());
+	}
+}
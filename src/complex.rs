@@ -1,24 +1,103 @@
-use std::ops::{Add, Mul};
+use std::fmt;
+use std::num::ParseFloatError;
+use std::ops::{Add, AddAssign, Div, Mul, MulAssign, Neg, Sub, SubAssign};
+use std::str::FromStr;
 
 // Complex numbers are treated like 2D vectors
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 pub struct Complex {
 	real: f64,
 	imaginary: f64
 }
 
 impl Complex {
-	pub fn new(real: f64, imaginary: f64) -> Complex {
+	pub const fn new(real: f64, imaginary: f64) -> Complex {
 		Complex {
 			real,
 			imaginary,
 		}
 	}
 
+	pub fn real(&self) -> f64 {
+		self.real
+	}
+
+	pub fn imaginary(&self) -> f64 {
+		self.imaginary
+	}
+
+	pub fn is_finite(&self) -> bool {
+		self.real.is_finite() && self.imaginary.is_finite()
+	}
+
+	// Squared magnitude, avoids the sqrt for escape checks
+	pub fn norm_sqr(&self) -> f64 {
+		(self.real * self.real) + (self.imaginary * self.imaginary)
+	}
+
 	// Pythagoras
 	pub fn abs(&self) -> f64 {
-		((self.real * self.real) + (self.imaginary * self.imaginary)).abs()
+		self.norm_sqr().sqrt()
+	}
+
+	// a + bi -> a - bi
+	pub fn conj(&self) -> Complex {
+		Complex {
+			real: self.real,
+			imaginary: -self.imaginary
+		}
+	}
+
+	// Angle from the positive real axis, in (-pi, pi]
+	pub fn arg(&self) -> f64 {
+		self.imaginary.atan2(self.real)
+	}
+
+	// r(cos t + i sin t)
+	pub fn from_polar(r: f64, theta: f64) -> Complex {
+		Complex {
+			real: r * theta.cos(),
+			imaginary: r * theta.sin()
+		}
+	}
+
+	// e^(a + bi) = e^a (cos b + i sin b)
+	pub fn exp(&self) -> Complex {
+		Complex::from_polar(self.real.exp(), self.imaginary)
+	}
+
+	// Principal branch, ln(z) = ln|z| + i arg(z)
+	pub fn ln(&self) -> Complex {
+		Complex {
+			real: self.abs().ln(),
+			imaginary: self.arg()
+		}
+	}
+
+	// sin(a + bi) = sin(a)cosh(b) + i cos(a)sinh(b)
+	pub fn sin(&self) -> Complex {
+		Complex {
+			real: self.real.sin() * self.imaginary.cosh(),
+			imaginary: self.real.cos() * self.imaginary.sinh()
+		}
+	}
+
+	// cos(a + bi) = cos(a)cosh(b) - i sin(a)sinh(b)
+	pub fn cos(&self) -> Complex {
+		Complex {
+			real: self.real.cos() * self.imaginary.cosh(),
+			imaginary: -(self.real.sin() * self.imaginary.sinh())
+		}
+	}
+
+	// De Moivre, z^n = r^n (cos nt + i sin nt)
+	pub fn powf(&self, exponent: f64) -> Complex {
+		if self.real == 0.0 && self.imaginary == 0.0 {
+			return *self;
+		}
+
+		Complex::from_polar(self.abs().powf(exponent), self.arg() * exponent)
 	}
 }
 
@@ -33,6 +112,41 @@ impl Add<Complex> for Complex {
 	}
 }
 
+// Scalars are treated as purely real
+impl Add<f64> for Complex {
+	type Output = Complex;
+
+	fn add(self, rhs: f64) -> Self::Output {
+		Complex {
+			real: self.real + rhs,
+			imaginary: self.imaginary
+		}
+	}
+}
+
+impl AddAssign<Complex> for Complex {
+	fn add_assign(&mut self, rhs: Complex) {
+		*self = *self + rhs;
+	}
+}
+
+impl Sub<Complex> for Complex {
+	type Output = Complex;
+
+	fn sub(self, rhs: Complex) -> Self::Output {
+		Complex {
+			real: self.real - rhs.real,
+			imaginary: self.imaginary - rhs.imaginary
+		}
+	}
+}
+
+impl SubAssign<Complex> for Complex {
+	fn sub_assign(&mut self, rhs: Complex) {
+		*self = *self - rhs;
+	}
+}
+
 impl Mul<Complex> for Complex {
 	type Output = Complex;
 
@@ -43,4 +157,329 @@ impl Mul<Complex> for Complex {
 			imaginary: (self.real * rhs.imaginary) + (self.imaginary * rhs.real)
 		}
 	}
-}
\ No newline at end of file
+}
+
+impl Mul<f64> for Complex {
+	type Output = Complex;
+
+	fn mul(self, rhs: f64) -> Self::Output {
+		Complex {
+			real: self.real * rhs,
+			imaginary: self.imaginary * rhs
+		}
+	}
+}
+
+impl MulAssign<Complex> for Complex {
+	fn mul_assign(&mut self, rhs: Complex) {
+		*self = *self * rhs;
+	}
+}
+
+impl Div<Complex> for Complex {
+	type Output = Complex;
+
+	// (a + bi) / (c + di) = ((ac + bd) + (bc - ad)i) / (c^2 + d^2)
+	fn div(self, rhs: Complex) -> Self::Output {
+		let denominator = (rhs.real * rhs.real) + (rhs.imaginary * rhs.imaginary);
+
+		Complex {
+			real: ((self.real * rhs.real) + (self.imaginary * rhs.imaginary)) / denominator,
+			imaginary: ((self.imaginary * rhs.real) - (self.real * rhs.imaginary)) / denominator
+		}
+	}
+}
+
+impl Neg for Complex {
+	type Output = Complex;
+
+	fn neg(self) -> Self::Output {
+		Complex {
+			real: -self.real,
+			imaginary: -self.imaginary
+		}
+	}
+}
+
+// Written as "a + bi" or "a - bi", honouring any requested precision
+impl fmt::Display for Complex {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		let sign = if self.imaginary.is_sign_negative() { '-' } else { '+' };
+
+		match f.precision() {
+			Some(precision) => write!(f, "{:.*} {} {:.*}i", precision, self.real, sign, precision, self.imaginary.abs()),
+			None => write!(f, "{} {} {}i", self.real, sign, self.imaginary.abs()),
+		}
+	}
+}
+
+#[derive(Debug, PartialEq)]
+pub struct ParseComplexError(String);
+
+impl fmt::Display for ParseComplexError {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "invalid complex number: {}", self.0)
+	}
+}
+
+impl From<ParseFloatError> for ParseComplexError {
+	fn from(e: ParseFloatError) -> ParseComplexError {
+		ParseComplexError(e.to_string())
+	}
+}
+
+// Accepts the Display form as well as lone real ("0.5") or imaginary ("-2i") parts
+impl FromStr for Complex {
+	type Err = ParseComplexError;
+
+	fn from_str(s: &str) -> Result<Complex, Self::Err> {
+		let compact: String = s.chars().filter(|c| !c.is_whitespace()).collect();
+
+		if compact.is_empty() {
+			return Err(ParseComplexError(String::from("empty string")));
+		}
+
+		let Some(imaginary_part) = compact.strip_suffix('i') else {
+			return Ok(Complex::new(compact.parse()?, 0.0));
+		};
+
+		// The split is the last sign which isn't leading or part of an exponent
+		let bytes = imaginary_part.as_bytes();
+		let split = (1..bytes.len())
+			.rev()
+			.find(|&i| (bytes[i] == b'+' || bytes[i] == b'-') && !matches!(bytes[i - 1], b'e' | b'E'))
+			.unwrap_or(0);
+
+		let (real, imaginary) = imaginary_part.split_at(split);
+
+		let real = if real.is_empty() { 0.0 } else { real.parse()? };
+		let imaginary = match imaginary {
+			"" | "+" => 1.0,
+			"-" => -1.0,
+			other => other.parse()?,
+		};
+
+		Ok(Complex::new(real, imaginary))
+	}
+}
+
+#[cfg(feature = "num-complex")]
+impl From<num_complex::Complex<f64>> for Complex {
+	fn from(value: num_complex::Complex<f64>) -> Complex {
+		Complex::new(value.re, value.im)
+	}
+}
+
+#[cfg(feature = "num-complex")]
+impl From<Complex> for num_complex::Complex<f64> {
+	fn from(value: Complex) -> num_complex::Complex<f64> {
+		num_complex::Complex::new(value.real, value.imaginary)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const EPSILON: f64 = 1e-12;
+
+	fn assert_close(a: Complex, b: Complex) {
+		assert!((a.real - b.real).abs() < EPSILON, "real parts differ: {} vs {}", a.real, b.real);
+		assert!((a.imaginary - b.imaginary).abs() < EPSILON, "imaginary parts differ: {} vs {}", a.imaginary, b.imaginary);
+	}
+
+	#[test]
+	fn abs_is_magnitude() {
+		let a = Complex::new(3.0, -4.0);
+
+		assert!((a.norm_sqr() - 25.0).abs() < EPSILON);
+		assert!((a.abs() - 5.0).abs() < EPSILON);
+	}
+
+	#[test]
+	fn sub_undoes_add() {
+		let a = Complex::new(1.5, -2.0);
+		let b = Complex::new(-0.25, 3.0);
+
+		assert_close((a + b) - b, a);
+		assert_close(a - a, Complex::new(0.0, 0.0));
+	}
+
+	#[test]
+	fn assigning_ops_match_binary_ops() {
+		let a = Complex::new(1.5, -2.0);
+		let b = Complex::new(-0.25, 3.0);
+
+		let mut z = a;
+		z += b;
+		assert_close(z, a + b);
+
+		z = a;
+		z -= b;
+		assert_close(z, a - b);
+
+		z = a;
+		z *= b;
+		assert_close(z, a * b);
+	}
+
+	#[test]
+	fn scalar_ops_match_real_complex() {
+		let a = Complex::new(1.5, -2.0);
+
+		assert_close(a + 2.0, a + Complex::new(2.0, 0.0));
+		assert_close(a * 2.0, a * Complex::new(2.0, 0.0));
+	}
+
+	#[test]
+	fn div_undoes_mul() {
+		let a = Complex::new(1.5, -2.0);
+		let b = Complex::new(-0.25, 3.0);
+
+		assert_close((a * b) / b, a);
+		assert_close(a / a, Complex::new(1.0, 0.0));
+	}
+
+	#[test]
+	fn div_by_i() {
+		// (2 + 4i) / i = 4 - 2i
+		assert_close(Complex::new(2.0, 4.0) / Complex::new(0.0, 1.0), Complex::new(4.0, -2.0));
+	}
+
+	#[test]
+	fn neg_is_additive_inverse() {
+		let a = Complex::new(1.5, -2.0);
+
+		assert_close(a + (-a), Complex::new(0.0, 0.0));
+		assert_close(-(-a), a);
+	}
+
+	#[test]
+	fn conj_times_self_is_real() {
+		let a = Complex::new(3.0, 4.0);
+
+		assert_close(a * a.conj(), Complex::new(25.0, 0.0));
+		assert_close(a.conj().conj(), a);
+	}
+
+	#[test]
+	fn conj_distributes_over_mul() {
+		let a = Complex::new(1.5, -2.0);
+		let b = Complex::new(-0.25, 3.0);
+
+		assert_close((a * b).conj(), a.conj() * b.conj());
+	}
+
+	#[test]
+	fn arg_of_axes() {
+		use std::f64::consts::{FRAC_PI_2, PI};
+
+		assert!((Complex::new(1.0, 0.0).arg() - 0.0).abs() < EPSILON);
+		assert!((Complex::new(0.0, 1.0).arg() - FRAC_PI_2).abs() < EPSILON);
+		assert!((Complex::new(-1.0, 0.0).arg() - PI).abs() < EPSILON);
+		assert!((Complex::new(0.0, -1.0).arg() + FRAC_PI_2).abs() < EPSILON);
+	}
+
+	#[test]
+	fn arg_adds_under_mul() {
+		let a = Complex::new(1.0, 1.0);
+		let b = Complex::new(1.0, 2.0);
+
+		assert!(((a * b).arg() - (a.arg() + b.arg())).abs() < EPSILON);
+	}
+
+	#[test]
+	fn exp_of_i_pi() {
+		use std::f64::consts::PI;
+
+		// Euler's identity
+		assert_close(Complex::new(0.0, PI).exp(), Complex::new(-1.0, 0.0));
+	}
+
+	#[test]
+	fn ln_undoes_exp() {
+		let a = Complex::new(0.5, -1.25);
+
+		assert_close(a.exp().ln(), a);
+		assert_close(a.ln().exp(), a);
+	}
+
+	#[test]
+	fn exp_turns_add_into_mul() {
+		let a = Complex::new(0.5, -1.25);
+		let b = Complex::new(-0.75, 2.0);
+
+		assert_close((a + b).exp(), a.exp() * b.exp());
+	}
+
+	#[test]
+	fn sin_cos_pythagorean_identity() {
+		let a = Complex::new(0.7, -0.3);
+		let sin = a.sin();
+		let cos = a.cos();
+
+		assert_close((sin * sin) + (cos * cos), Complex::new(1.0, 0.0));
+	}
+
+	#[test]
+	fn sin_cos_match_real_functions() {
+		let a = Complex::new(1.2, 0.0);
+
+		assert_close(a.sin(), Complex::new(1.2_f64.sin(), 0.0));
+		assert_close(a.cos(), Complex::new(1.2_f64.cos(), 0.0));
+	}
+
+	#[test]
+	fn powf_matches_repeated_mul() {
+		let a = Complex::new(1.5, -2.0);
+
+		assert_close(a.powf(2.0), a * a);
+		assert_close(a.powf(3.0), a * a * a);
+		assert_close(a.powf(0.5) * a.powf(0.5), a);
+		assert_close(Complex::new(0.0, 0.0).powf(2.0), Complex::new(0.0, 0.0));
+	}
+
+	#[test]
+	fn display_signs() {
+		assert_eq!(Complex::new(-0.743, 0.131).to_string(), "-0.743 + 0.131i");
+		assert_eq!(Complex::new(0.25, -1.5).to_string(), "0.25 - 1.5i");
+		assert_eq!(format!("{:.2}", Complex::new(1.0, -0.125)), "1.00 - 0.12i");
+	}
+
+	#[test]
+	fn parse_round_trips_display() {
+		for a in [Complex::new(-0.743, 0.131), Complex::new(0.25, -1.5), Complex::new(1e-20, -3.5e10)] {
+			assert_close(a.to_string().parse().unwrap(), a);
+		}
+	}
+
+	#[test]
+	fn parse_partial_forms() {
+		assert_close("0.5".parse().unwrap(), Complex::new(0.5, 0.0));
+		assert_close("-2i".parse().unwrap(), Complex::new(0.0, -2.0));
+		assert_close("-i".parse().unwrap(), Complex::new(0.0, -1.0));
+		assert_close("3+i".parse().unwrap(), Complex::new(3.0, 1.0));
+		assert_close("1e-3-2e-3i".parse().unwrap(), Complex::new(1e-3, -2e-3));
+	}
+
+	#[test]
+	fn parse_rejects_garbage() {
+		assert!("".parse::<Complex>().is_err());
+		assert!("abc".parse::<Complex>().is_err());
+		assert!("1 + xi".parse::<Complex>().is_err());
+	}
+
+	#[cfg(feature = "num-complex")]
+	#[test]
+	fn num_complex_round_trip() {
+		let a = Complex::new(1.5, -2.0);
+		let b = Complex::new(-0.25, 3.0);
+
+		let converted: num_complex::Complex<f64> = a.into();
+		assert_close(Complex::from(converted), a);
+
+		// Arithmetic agrees on both sides of the conversion
+		let product = num_complex::Complex::<f64>::from(a) * num_complex::Complex::<f64>::from(b);
+		assert_close(product.into(), a * b);
+	}
+}
@@ -0,0 +1,258 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+use ggez::{GameError, GameResult as Result};
+use serde::{Deserialize, Serialize};
+
+use ggez::graphics::Color;
+
+use crate::affinity;
+use crate::backend;
+use crate::complex::Complex;
+use crate::export::Transparency;
+use crate::formula::Formula;
+use crate::gradient;
+use crate::locale;
+use crate::memory::MemoryBudget;
+use crate::postprocess::Effects;
+use crate::quality::{self, Quality};
+use crate::queue;
+use crate::renderer::{self, IterationSettings};
+
+pub const CONFIG_PATH: &str = "mandelbrot_viewer.toml";
+
+// Every field falls back to its default when missing from the file
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Config {
+	// Which fractal is iterated, named like "burning-ship"
+	pub formula: Formula,
+	pub bailout: f64,
+	pub initial_z: [f64; 2],
+	pub max_iterations: f64,
+	pub quality: Quality,
+	// Multiple of the window's resolution the view renders at, from 0.5 to 4, changed with - and =
+	// while running. Above 1 the extra pixels are averaged down for a sharper view
+	pub render_scale: f64,
+	// Colour of points inside the set, as #rrggbb
+	pub interior_color: String,
+	// Shade the Mandelbrot set's interior by distance from the boundary, toggled with Y
+	pub shade_interior: bool,
+	// Draw only the set's boundary as a line on white, toggled with Shift + Y
+	pub boundary: bool,
+	// Colour odd iteration bands with a second palette, named like the others, toggled with Shift + B
+	pub stripes: bool,
+	pub stripe_palette: String,
+	// Run the effects over the coloured view, screenshots and exports, toggled with F12
+	pub post_process: bool,
+	pub effects: Effects,
+	// Stretch the palette across the escape counts in view rather than up to the cap, toggled with
+	// Shift + C
+	pub stretch_palette: bool,
+	// Bits per channel of exported images, 8 or 16
+	pub bit_depth: u8,
+	// Points left see-through in exported images, for compositing
+	pub transparency: Transparency,
+
+	// Magnification multiplier of each zoom step, and of steps taken with shift held
+	pub zoom_factor: f64,
+	pub fine_zoom_factor: f64,
+	// Degrees per second while a rotation key is held
+	pub rotation_speed: f64,
+
+	// 0 leaves the frame rate uncapped
+	pub fps: u32,
+	pub tick_rate: u32,
+	// Seconds without input before frames slow to a trickle and refinement pauses, 0 never does
+	pub idle_timeout: f64,
+	// Seconds without input before an autopilot dive takes over the view, 0 never does
+	pub attract_after: f64,
+	pub vsync: bool,
+
+	// Index into the connected monitors in the order the system lists them, the window is centered
+	// on it unless a position is given. Positions are physical pixels from the monitor's top left
+	pub monitor: Option<usize>,
+	pub window_position: Option<[i32; 2]>,
+	pub borderless: bool,
+
+	// Language of on-screen text, "auto" follows the system's locale
+	pub language: String,
+
+	// What the view is rendered with, one of backend::NAMES
+	pub backend: String,
+	// What the view is compared against with Ctrl + D, its iteration cap times diff_iterations on the
+	// backend named by diff_backend, the view's own when empty
+	pub diff_iterations: f64,
+	pub diff_backend: String,
+
+	// Worker threads shared by the view and background exports, changed with , and . while running
+	pub threads: usize,
+	// Keep each render thread on one core, for big renders on many-core and NUMA machines. Linux only
+	pub pin_threads: bool,
+	// Pixels along each side of the squares renders are split into. Larger tiles queue less work
+	// for huge posters, smaller ones spread small views across more threads
+	pub tile_size: usize,
+	// Pixels rendered past each edge of the window, so small pans shift the frame already there while
+	// the next one renders instead of revealing blank edges. 0 renders only what's shown
+	pub render_margin: usize,
+	// Megabytes the view's frames, poster bands and the tile server's cache may take, 0 for no limit
+	pub memory_budget: f64,
+}
+
+impl Default for Config {
+	fn default() -> Config {
+		Config {
+			formula: Formula::Mandelbrot,
+			bailout: 2.0,
+			initial_z: [0.0, 0.0],
+			max_iterations: 100.0,
+			quality: Quality::Normal,
+			render_scale: 1.0,
+			interior_color: String::from("#000000"),
+			shade_interior: false,
+			boundary: false,
+			stripes: false,
+			stripe_palette: String::from("ultra"),
+			post_process: false,
+			effects: Effects::default(),
+			stretch_palette: false,
+			bit_depth: 8,
+			transparency: Transparency::None,
+
+			zoom_factor: 2.0,
+			fine_zoom_factor: 1.1,
+			rotation_speed: 45.0,
+
+			fps: 60,
+			tick_rate: 60,
+			idle_timeout: 60.0,
+			attract_after: 0.0,
+			vsync: true,
+
+			monitor: None,
+			window_position: None,
+			borderless: false,
+
+			language: String::from("auto"),
+
+			backend: String::from(backend::DEFAULT_BACKEND),
+			diff_iterations: 4.0,
+			diff_backend: String::new(),
+
+			threads: queue::DEFAULT_THREADS,
+			pin_threads: false,
+			tile_size: renderer::DEFAULT_TILE_SIZE,
+			render_margin: 64,
+			memory_budget: 2048.0,
+		}
+	}
+}
+
+impl Config {
+	// A missing default file isn't an error, the defaults are used instead
+	pub fn load(path: Option<&Path>) -> Result<Config> {
+		let is_explicit = path.is_some();
+		let path = path.unwrap_or(Path::new(CONFIG_PATH));
+
+		let contents = match fs::read_to_string(path) {
+			Ok(contents) => contents,
+			Err(e) if e.kind() == ErrorKind::NotFound && !is_explicit => return Ok(Config::default()),
+			Err(e) => return Err(GameError::ConfigError(format!("could not read {}: {}", path.display(), e))),
+		};
+
+		toml::from_str(&contents)
+			.map_err(|e| GameError::ConfigError(format!("could not parse {}: {}", path.display(), e)))
+	}
+
+	// Run after command line overrides have been applied
+	pub fn validate(&self) -> Result {
+		// Checked before the formula's preset can raise a NaN away
+		let settings = IterationSettings {
+			formula: self.formula,
+			bailout: self.bailout,
+			initial_z: Complex::new(self.initial_z[0], self.initial_z[1]),
+			julia: None,
+			max_iterations: self.max_iterations,
+		};
+
+		settings.validate().map_err(GameError::ConfigError)?;
+
+		if !(quality::RENDER_SCALES[0]..=quality::RENDER_SCALES[quality::RENDER_SCALES.len() - 1]).contains(&self.render_scale) {
+			return Err(GameError::ConfigError(String::from("render_scale must be from 0.5 to 4")));
+		}
+
+		if let Err(e) = gradient::parse_hex(&self.interior_color) {
+			return Err(GameError::ConfigError(format!("interior_color: {}", e)));
+		}
+
+		if self.bit_depth != 8 && self.bit_depth != 16 {
+			return Err(GameError::ConfigError(String::from("bit_depth must be 8 or 16")));
+		}
+
+		if self.zoom_factor <= 1.0 || self.fine_zoom_factor <= 1.0 {
+			return Err(GameError::ConfigError(String::from("zoom_factor and fine_zoom_factor must be greater than 1")));
+		}
+
+		if self.language != "auto" && !locale::is_known(&self.language) {
+			let languages: Vec<&str> = locale::languages().collect();
+			return Err(GameError::ConfigError(format!("language must be auto or one of {}", languages.join(", "))));
+		}
+
+		if !backend::NAMES.contains(&self.backend.as_str()) {
+			return Err(GameError::ConfigError(format!("backend must be one of {}, others need their cargo feature", backend::NAMES.join(", "))));
+		}
+
+		if !self.diff_backend.is_empty() && !backend::NAMES.contains(&self.diff_backend.as_str()) {
+			return Err(GameError::ConfigError(format!("diff_backend must be empty or one of {}", backend::NAMES.join(", "))));
+		}
+
+		if self.diff_iterations <= 0.0 || !self.diff_iterations.is_finite() {
+			return Err(GameError::ConfigError(String::from("diff_iterations must be greater than 0")));
+		}
+
+		if self.memory_budget < 0.0 || !self.memory_budget.is_finite() {
+			return Err(GameError::ConfigError(String::from("memory_budget must be a finite number of megabytes, 0 for no limit")));
+		}
+
+		if self.threads == 0 {
+			return Err(GameError::ConfigError(String::from("threads must be at least 1")));
+		}
+
+		if self.pin_threads && !affinity::SUPPORTED {
+			return Err(GameError::ConfigError(String::from("pin_threads is only supported on Linux")));
+		}
+
+		if self.tile_size == 0 {
+			return Err(GameError::ConfigError(String::from("tile_size must be at least 1")));
+		}
+
+		if let Err(e) = self.effects.validate() {
+			return Err(GameError::ConfigError(format!("effects: {}", e)));
+		}
+
+		if self.idle_timeout < 0.0 {
+			return Err(GameError::ConfigError(String::from("idle_timeout can't be negative")));
+		}
+
+		if self.attract_after < 0.0 {
+			return Err(GameError::ConfigError(String::from("attract_after can't be negative")));
+		}
+
+		if self.tick_rate == 0 {
+			return Err(GameError::ConfigError(String::from("tick_rate must be greater than 0")));
+		}
+
+		Ok(())
+	}
+
+	// Black if the colour doesn't parse, which validation catches first
+	pub fn interior(&self) -> Color {
+		gradient::parse_hex(&self.interior_color).unwrap_or(Color::BLACK)
+	}
+
+	pub fn budget(&self) -> MemoryBudget {
+		MemoryBudget::megabytes(self.memory_budget)
+	}
+}
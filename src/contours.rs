@@ -0,0 +1,126 @@
+// Line segments from one point to another, in pixel coordinates of the frame
+pub type Segment = [(f64, f64); 2];
+
+// Where along an edge between two corner values the level is crossed
+fn crossing(a: f64, b: f64, level: f64) -> f64 {
+	if a == b { 0.5 } else { ((level - a) / (b - a)).clamp(0.0, 1.0) }
+}
+
+// Marching squares over a grid of per pixel values, tracing every level which is a multiple of
+// spacing offset by the given phase. Points inside the set have no value and count as higher than
+// any level, so the contours close around the set rather than stopping at its edge
+pub fn contours(values: &[Option<f64>], width: usize, height: usize, spacing: f64, phase: f64) -> Vec<Segment> {
+	let mut segments = Vec::new();
+
+	let value = |x: usize, y: usize| values[y * width + x].unwrap_or(f64::INFINITY);
+
+	for y in 0..height.saturating_sub(1) {
+		for x in 0..width.saturating_sub(1) {
+			// Corners clockwise from the top left
+			let corners = [value(x, y), value(x + 1, y), value(x + 1, y + 1), value(x, y + 1)];
+
+			let low = corners.iter().copied().fold(f64::INFINITY, f64::min);
+			let high = corners.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+			if low == high || !low.is_finite() {
+				continue;
+			}
+
+			// Interior corners are clamped so the level interpolation stays sensible
+			let ceiling = corners.iter().copied().filter(|value| value.is_finite()).fold(low, f64::max) + spacing;
+			let corners = corners.map(|value| value.min(ceiling));
+			let high = high.min(ceiling);
+
+			let mut level = ((low - phase) / spacing).floor() * spacing + phase;
+
+			while level < high {
+				if level > low {
+					segments.extend(cell_segments(x as f64, y as f64, corners, level));
+				}
+
+				level += spacing;
+			}
+		}
+	}
+
+	segments
+}
+
+fn cell_segments(x: f64, y: f64, [top_left, top_right, bottom_right, bottom_left]: [f64; 4], level: f64) -> Vec<Segment> {
+	let top = (x + crossing(top_left, top_right, level), y);
+	let right = (x + 1.0, y + crossing(top_right, bottom_right, level));
+	let bottom = (x + crossing(bottom_left, bottom_right, level), y + 1.0);
+	let left = (x, y + crossing(top_left, bottom_left, level));
+
+	let case = (top_left > level) as u8
+		| ((top_right > level) as u8) << 1
+		| ((bottom_right > level) as u8) << 2
+		| ((bottom_left > level) as u8) << 3;
+
+	match case {
+		0 | 15 => vec![],
+		1 | 14 => vec![[left, top]],
+		2 | 13 => vec![[top, right]],
+		3 | 12 => vec![[left, right]],
+		4 | 11 => vec![[right, bottom]],
+		6 | 9 => vec![[top, bottom]],
+		7 | 8 => vec![[left, bottom]],
+
+		// Saddles, resolved by the average of the corners
+		5 | 10 => {
+			let center_above = (top_left + top_right + bottom_right + bottom_left) / 4.0 > level;
+
+			if (case == 5) == center_above {
+				vec![[left, bottom], [top, right]]
+			} else {
+				vec![[left, top], [right, bottom]]
+			}
+		},
+
+		_ => unreachable!(),
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn single_crossing_is_interpolated() {
+		// A vertical edge between 1 and 3, crossed at 2 halfway along
+		let values = [Some(1.0), Some(3.0), Some(1.0), Some(3.0)];
+		let segments = contours(&values, 2, 2, 10.0, 2.0);
+
+		assert_eq!(segments.len(), 1);
+
+		for (x, _) in segments[0] {
+			assert!((x - 0.5).abs() < 1e-9);
+		}
+	}
+
+	#[test]
+	fn every_level_in_range_is_traced() {
+		let values = [Some(0.0), Some(10.0), Some(0.0), Some(10.0)];
+
+		// Levels 0.5, 2.5, 4.5, 6.5 and 8.5
+		assert_eq!(contours(&values, 2, 2, 2.0, 0.5).len(), 5);
+	}
+
+	#[test]
+	fn flat_and_interior_cells_have_no_contours() {
+		assert!(contours(&[Some(4.0); 4], 2, 2, 1.0, 0.5).is_empty());
+		assert!(contours(&[None; 4], 2, 2, 1.0, 0.5).is_empty());
+	}
+
+	#[test]
+	fn contours_close_around_the_set() {
+		// An interior pixel at the middle of an escaping neighbourhood
+		let mut values = vec![Some(3.0); 9];
+		values[4] = None;
+
+		let segments = contours(&values, 3, 3, 10.0, 3.5);
+
+		// One crossing on each side of the middle pixel
+		assert_eq!(segments.len(), 4);
+	}
+}
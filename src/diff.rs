@@ -0,0 +1,128 @@
+use ggez::graphics::Color;
+
+use crate::renderer::Frame;
+
+// Pixel by pixel differences between two renders of the same view, such as at two iteration caps
+// or on two backends, for checking where one disagrees with the other
+
+// Escape counts closer than this are the same, as backends round differently in the last bits
+const TOLERANCE: f64 = 1e-9;
+
+// Identical pixels are the first render's colours dimmed to this, so differences stand out while
+// the view stays recognisable
+const CONTEXT_BRIGHTNESS: f32 = 0.25;
+
+// Points that escape in only one of the renders
+const ESCAPE_MISMATCH_COLOR: Color = Color::new(1.0, 0.0, 1.0, 1.0);
+
+pub struct Comparison {
+	pub width: usize,
+	pub height: usize,
+	// Each pixel's difference in mean escape count, 0 where both agree and None where the point
+	// escapes in one render but not the other
+	pub differences: Vec<Option<f64>>,
+	pub max_difference: f64,
+	pub differing: usize,
+	pub escape_mismatches: usize,
+}
+
+// Cold to hot, from the smallest difference to the largest
+fn ramp(t: f32) -> Color {
+	let stops = [(0.0, Color::new(0.1, 0.2, 1.0, 1.0)), (0.5, Color::new(1.0, 0.9, 0.0, 1.0)), (1.0, Color::new(1.0, 0.1, 0.0, 1.0))];
+	let t = t.clamp(0.0, 1.0);
+
+	let i = if t < 0.5 { 0 } else { 1 };
+	let ((start, from), (end, to)) = (stops[i], stops[i + 1]);
+	let s = (t - start) / (end - start);
+
+	Color::new(from.r + (to.r - from.r) * s, from.g + (to.g - from.g) * s, from.b + (to.b - from.b) * s, 1.0)
+}
+
+impl Comparison {
+	// Both frames have to be of the same size
+	pub fn new(a: &Frame, b: &Frame) -> Comparison {
+		assert_eq!((a.width, a.height), (b.width, b.height), "compared frames differ in size");
+
+		let differences: Vec<Option<f64>> = a.mean_iterations().zip(b.mean_iterations())
+			.map(|pair| match pair {
+				(Some(a), Some(b)) if (a - b).abs() > TOLERANCE => Some((a - b).abs()),
+				(Some(_), None) | (None, Some(_)) => None,
+				_ => Some(0.0),
+			})
+			.collect();
+
+		let max_difference = differences.iter().flatten().fold(0.0, |max: f64, &difference| max.max(difference));
+		let differing = differences.iter().filter(|difference| *difference != &Some(0.0)).count();
+		let escape_mismatches = differences.iter().filter(|difference| difference.is_none()).count();
+
+		Comparison { width: a.width, height: a.height, differences, max_difference, differing, escape_mismatches }
+	}
+
+	// From 0 to 100
+	pub fn differing_percent(&self) -> f64 {
+		100.0 * self.differing as f64 / self.differences.len().max(1) as f64
+	}
+
+	// Differences coloured on a log scale up to the largest, over a dimmed copy of the view's pixels
+	pub fn heatmap(&self, view: &[Color]) -> Vec<Color> {
+		let scale = (1.0 + self.max_difference).ln();
+
+		self.differences.iter().zip(view).map(|(difference, &color)| match *difference {
+			None => ESCAPE_MISMATCH_COLOR,
+			Some(difference) if difference > 0.0 => ramp(((1.0 + difference).ln() / scale) as f32),
+			Some(_) => Color::new(color.r * CONTEXT_BRIGHTNESS, color.g * CONTEXT_BRIGHTNESS, color.b * CONTEXT_BRIGHTNESS, 1.0),
+		}).collect()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use crate::complex::Complex;
+	use crate::renderer;
+
+	fn frame(max_iterations: f64) -> Frame {
+		renderer::test_frame((24, 16), Complex::new(-0.75, 0.1), 8.0, max_iterations)
+	}
+
+	#[test]
+	fn the_same_render_has_no_differences() {
+		let (a, b) = (frame(100.0), frame(100.0));
+		let comparison = Comparison::new(&a, &b);
+
+		assert_eq!((comparison.differing, comparison.escape_mismatches, comparison.max_difference), (0, 0, 0.0));
+
+		// Nothing but the dimmed view
+		let heatmap = comparison.heatmap(&a.pixels);
+		assert!(heatmap.iter().zip(&a.pixels).all(|(heat, color)| (heat.r - color.r * CONTEXT_BRIGHTNESS).abs() < 1e-6));
+	}
+
+	#[test]
+	fn raising_the_cap_only_changes_points_that_ran_out() {
+		let (low, high) = (frame(30.0), frame(300.0));
+		let comparison = Comparison::new(&low, &high);
+
+		assert!(comparison.escape_mismatches > 0);
+		assert!(comparison.differing_percent() > 0.0 && comparison.differing_percent() < 100.0);
+
+		// Points that escaped under the lower cap escape at the same count under the higher one, so
+		// the only differences are points the lower cap gave up on
+		assert_eq!(comparison.differing, comparison.escape_mismatches);
+
+		for (i, difference) in comparison.differences.iter().enumerate() {
+			if difference.is_none() {
+				assert!(low.mean_iterations().nth(i).unwrap().is_none());
+			}
+		}
+
+		assert!(comparison.heatmap(&low.pixels).contains(&ESCAPE_MISMATCH_COLOR));
+	}
+
+	#[test]
+	fn ramp_runs_from_cold_to_hot() {
+		assert!(ramp(0.0).b > ramp(0.0).r);
+		assert!(ramp(1.0).r > ramp(1.0).b);
+		assert_eq!(ramp(2.0), ramp(1.0));
+	}
+}
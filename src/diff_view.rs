@@ -0,0 +1,116 @@
+use std::sync::Arc;
+
+use ggez::graphics::{Canvas, DrawParam, Image, ImageFormat, Sampler};
+use ggez::mint::Point2;
+use ggez::Context;
+
+use mandelbrot_viewer::backend::RenderBackend;
+use mandelbrot_viewer::capture;
+use mandelbrot_viewer::diff::Comparison;
+use mandelbrot_viewer::export;
+use mandelbrot_viewer::queue::Priority;
+use mandelbrot_viewer::renderer::{Frame, IterationSettings, RenderJob, Renderer};
+
+// Shows where the view's render disagrees with one of the same view at other settings: a higher
+// iteration cap, another backend or both. The other render runs on its own thread after each new
+// view, and the heatmap replaces the view once it's in
+pub struct DiffView {
+	renderer: Renderer,
+	backend: String,
+	// The other render's iteration cap as a multiple of the view's
+	iteration_multiplier: f64,
+	// Generation of the view the render in flight or the heatmap is of
+	generation: Option<u64>,
+	image: Option<Image>,
+	// Frame pixels per heatmap pixel along each side, averaged down like the view
+	factor: usize,
+	comparison: Option<Comparison>,
+}
+
+impl DiffView {
+	pub fn new(backend: Arc<dyn RenderBackend>, iteration_multiplier: f64) -> DiffView {
+		DiffView {
+			backend: backend.name(),
+			renderer: Renderer::with_backend(backend),
+			iteration_multiplier,
+			generation: None,
+			image: None,
+			factor: 1,
+			comparison: None,
+		}
+	}
+
+	// The same view as the frame, at the other settings. Newer views replace any still rendering
+	pub fn request(&mut self, frame: &Frame) {
+		if self.generation == Some(frame.generation) {
+			return;
+		}
+
+		let settings = IterationSettings {
+			max_iterations: (frame.settings.max_iterations * self.iteration_multiplier).max(1.0).round(),
+			..frame.settings
+		};
+
+		self.renderer.submit(RenderJob {
+			generation: frame.generation,
+			priority: Priority::Interactive,
+			..RenderJob::new(frame.width, frame.height, frame.samples, frame.viewport, settings, frame.gradient.clone())
+		});
+
+		self.generation = Some(frame.generation);
+		self.image = None;
+		self.comparison = None;
+	}
+
+	// Compares a finished render against the view's frame, returning whether there's a new heatmap.
+	// The heatmap is averaged down by the factor the view's frame is
+	pub fn update(&mut self, context: &Context, view: &Frame, factor: usize) -> Result<bool, String> {
+		// Progress isn't shown, but has to be drained
+		self.renderer.poll_tiles();
+
+		let Some(other) = self.renderer.poll().pop() else {
+			return Ok(false);
+		};
+
+		let other = other?;
+
+		if other.generation != view.generation || (other.width, other.height) != (view.width, view.height) {
+			return Ok(false);
+		}
+
+		let comparison = Comparison::new(view, &other);
+		let heatmap = capture::downscale(&comparison.heatmap(&view.pixels), (view.width, view.height), factor.max(1));
+		let (width, height) = capture::downscaled_size((view.width, view.height), factor.max(1));
+
+		self.image = Some(Image::from_pixels(context, &export::rgba_bytes(&heatmap, 8), ImageFormat::Rgba8UnormSrgb, width as u32, height as u32));
+		self.factor = factor.max(1);
+		self.comparison = Some(comparison);
+
+		Ok(true)
+	}
+
+	pub fn comparison(&self) -> Option<&Comparison> {
+		self.comparison.as_ref()
+	}
+
+	pub fn iteration_multiplier(&self) -> f64 {
+		self.iteration_multiplier
+	}
+
+	pub fn backend(&self) -> &str {
+		&self.backend
+	}
+
+	// Placed over the window like the view's frame
+	pub fn draw(&self, canvas: &mut Canvas, origin: Point2<f32>, scale: [f32; 2]) {
+		let Some(image) = &self.image else {
+			return;
+		};
+
+		let scale = [scale[0] * self.factor as f32, scale[1] * self.factor as f32];
+
+		canvas.set_sampler(if scale[0] < 1.0 { Sampler::linear_clamp() } else { Sampler::nearest_clamp() });
+		canvas.draw(image, DrawParam::new().dest(origin).scale(scale));
+		canvas.set_sampler(Sampler::default());
+	}
+}
@@ -0,0 +1,123 @@
+use ggez::graphics::Color;
+
+use crate::postprocess::Effects;
+use crate::renderer::Frame;
+
+// Drawn over the highlighted band, picked to stand out from the desaturated remainder
+const BAND_COLOR: Color = Color::new(1.0, 0.85, 0.0, 1.0);
+// How much colour and brightness everything outside the highlighted band keeps
+const BAND_SATURATION: f32 = 0.15;
+const BAND_BRIGHTNESS: f32 = 0.6;
+
+// Applied to the coloured frame on its way to the screen, so toggling doesn't need a re-render
+#[derive(Copy, Clone, Default)]
+pub struct DisplayFilter {
+	pub grayscale: bool,
+	pub inverted: bool,
+	// Whole iteration count of the escape time level set to pick out
+	pub band: Option<u32>,
+	// Run over the whole filtered frame, so only frames displayed in one piece get them
+	pub effects: Option<Effects>,
+}
+
+fn luma(color: Color) -> f32 {
+	0.2126 * color.r + 0.7152 * color.g + 0.0722 * color.b
+}
+
+fn mix(a: f32, b: f32, amount: f32) -> f32 {
+	a + (b - a) * amount
+}
+
+impl DisplayFilter {
+	// Grayscale uses Rec. 709 luma, inversion happens after it so the two combine into a negative print.
+	// The band highlight goes last so it looks the same whichever of those are on
+	pub fn apply(&self, color: Color, iterations: Option<f64>) -> Color {
+		let mut color = color;
+
+		if self.grayscale {
+			let luma = luma(color);
+			color = Color::new(luma, luma, luma, color.a);
+		}
+
+		if self.inverted {
+			color = Color::new(1.0 - color.r, 1.0 - color.g, 1.0 - color.b, color.a);
+		}
+
+		if let Some(band) = self.band {
+			if iterations.is_some_and(|iterations| iterations.floor() == band as f64) {
+				return Color { a: color.a, ..BAND_COLOR };
+			}
+
+			let luma = luma(color);
+			let fade = |channel: f32| mix(luma, channel, BAND_SATURATION) * BAND_BRIGHTNESS;
+
+			color = Color::new(fade(color.r), fade(color.g), fade(color.b), color.a);
+		}
+
+		color
+	}
+
+	pub fn has_effects(&self) -> bool {
+		self.effects.is_some_and(|effects| !effects.is_empty())
+	}
+
+	// Every pixel of a frame through the filter, then the effects
+	pub fn apply_frame(&self, frame: &Frame) -> Vec<Color> {
+		let pixels: Vec<Color> = frame.pixels.iter()
+			.zip(frame.mean_iterations())
+			.map(|(&color, iterations)| self.apply(color, iterations))
+			.collect();
+
+		match self.effects {
+			Some(effects) if self.has_effects() => effects.apply(&pixels, (frame.width, frame.height)),
+			_ => pixels,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn default_leaves_colours_alone() {
+		let color = Color::new(0.2, 0.4, 0.6, 1.0);
+
+		assert_eq!(DisplayFilter::default().apply(color, Some(3.5)), color);
+	}
+
+	#[test]
+	fn grayscale_keeps_white_and_alpha() {
+		let filter = DisplayFilter { grayscale: true, ..DisplayFilter::default() };
+
+		let white = filter.apply(Color::WHITE, None);
+		assert!((white.r - 1.0).abs() < 1e-6 && white.r == white.g && white.g == white.b);
+
+		let green = filter.apply(Color::new(0.0, 1.0, 0.0, 0.5), None);
+		assert!((green.r - 0.7152).abs() < 1e-6 && green.a == 0.5);
+	}
+
+	#[test]
+	fn inversion_combines_with_grayscale() {
+		let inverted = DisplayFilter { inverted: true, ..DisplayFilter::default() };
+		assert_eq!(inverted.apply(Color::new(1.0, 0.25, 0.0, 1.0), None), Color::new(0.0, 0.75, 1.0, 1.0));
+
+		let both = DisplayFilter { grayscale: true, inverted: true, band: None, effects: None };
+		assert_eq!(both.apply(Color::BLACK, None), Color::WHITE);
+	}
+
+	#[test]
+	fn band_highlights_only_its_iterations() {
+		let filter = DisplayFilter { band: Some(4), ..DisplayFilter::default() };
+		let red = Color::new(1.0, 0.0, 0.0, 1.0);
+
+		assert_eq!(filter.apply(red, Some(4.0)), BAND_COLOR);
+		assert_eq!(filter.apply(red, Some(4.99)), BAND_COLOR);
+
+		// Neighbouring bands and the interior are faded towards gray
+		for iterations in [Some(3.99), Some(5.0), None] {
+			let faded = filter.apply(red, iterations);
+			assert!(faded.r < 0.5 && faded.g > 0.0 && faded.r > faded.g);
+		}
+	}
+}
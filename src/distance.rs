@@ -0,0 +1,151 @@
+use crate::complex::Complex;
+
+// Distance estimation for the Mandelbrot set. Inside, the critical orbit of c settles on an attracting
+// cycle, and the cycle's multiplier with its first and second derivatives gives an estimate b of the
+// distance to the boundary, which the true distance lies between b / 4 and b of. Outside, how fast
+// the orbit escapes gives a lower bound that's within a factor of 4
+
+// Longest cycle looked for, points of deeper bulbs are left unshaded
+const MAX_PERIOD: usize = 64;
+
+// Escaped orbits are followed out to here, where |z| ln |z| has settled down
+const ESCAPE_RADIUS: f64 = 1e5;
+
+// Steps past the iterations given for escaped orbits to get there
+const ESCAPE_STEPS: usize = 64;
+
+// Squared distance within which the orbit counts as having come back round, before Newton's
+// method pins the cycle down
+const CYCLE_EPSILON: f64 = 1e-12;
+const NEWTON_STEPS: usize = 4;
+
+// f^p and its derivatives at a point, for f(z) = z² + c
+struct Cycle {
+	z: Complex,
+	dz: Complex,
+	dc: Complex,
+	dzdz: Complex,
+	dcdz: Complex,
+}
+
+fn follow(start: Complex, c: Complex, period: usize) -> Cycle {
+	let zero = Complex::new(0.0, 0.0);
+	let mut cycle = Cycle { z: start, dz: Complex::new(1.0, 0.0), dc: zero, dzdz: zero, dcdz: zero };
+
+	for _ in 0..period {
+		let Cycle { z, dz, dc, dzdz, dcdz } = cycle;
+
+		cycle = Cycle {
+			z: z * z + c,
+			dz: z * dz * 2.0,
+			dc: z * dc * 2.0 + 1.0,
+			dzdz: (dz * dz + z * dzdz) * 2.0,
+			dcdz: (z * dcdz + dz * dc) * 2.0,
+		};
+	}
+
+	cycle
+}
+
+// Distance from c to the boundary in the same units as c, None outside the set or where the orbit
+// hasn't settled within the iterations given
+pub fn interior(c: Complex, start: Complex, iterations: usize) -> Option<f64> {
+	let mut z = start;
+
+	for _ in 0..iterations {
+		z = z * z + c;
+	}
+
+	let mut w = z;
+	let period = (1..=MAX_PERIOD).find(|_| {
+		w = w * w + c;
+		(w - z).norm_sqr() < CYCLE_EPSILON
+	})?;
+
+	// Solves f^p(z) = z, the orbit only approaches the cycle
+	for _ in 0..NEWTON_STEPS {
+		let cycle = follow(z, c, period);
+		z = z - (cycle.z - z) / (cycle.dz + -1.0);
+	}
+
+	let cycle = follow(z, c, period);
+	let multiplier = cycle.dz.norm_sqr();
+
+	if multiplier >= 1.0 {
+		return None;
+	}
+
+	let denominator = cycle.dcdz + cycle.dzdz * cycle.dc / (-cycle.dz + 1.0);
+	Some((1.0 - multiplier) / denominator.abs())
+}
+
+// Lower bound on the distance from a point to the boundary, in the same units, for the parameter
+// plane or the Julia set of c. None if the orbit stays bounded past the iterations given
+pub fn exterior(mut z: Complex, c: Complex, julia: bool, iterations: usize) -> Option<f64> {
+	// Derivative of z with respect to the starting z in Julia sets, and to c otherwise
+	let (mut dz, step) = match julia {
+		true => (Complex::new(1.0, 0.0), 0.0),
+		false => (Complex::new(0.0, 0.0), 1.0),
+	};
+
+	for _ in 0..iterations + ESCAPE_STEPS {
+		if z.norm_sqr() > ESCAPE_RADIUS * ESCAPE_RADIUS {
+			let radius = z.abs();
+			return Some(radius * radius.ln() / dz.abs() / 2.0);
+		}
+
+		dz = z * dz * 2.0 + step;
+		z = z * z + c;
+	}
+
+	None
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn estimate(real: f64, imaginary: f64) -> Option<f64> {
+		interior(Complex::new(real, imaginary), Complex::new(0.0, 0.0), 1000)
+	}
+
+	#[test]
+	fn centers_bound_their_component() {
+		// The main cardioid's cusp at 0.25 is nearest the origin
+		let b = estimate(0.0, 0.0).unwrap();
+		assert!((b - 0.5).abs() < 1e-12, "{}", b);
+		assert!(b / 4.0 <= 0.25 && 0.25 <= b);
+
+		// The period 2 disc has radius 0.25 around -1
+		let b = estimate(-1.0, 0.0).unwrap();
+		assert!(b / 4.0 <= 0.25 && 0.25 <= b, "{}", b);
+	}
+
+	#[test]
+	fn estimates_shrink_towards_the_boundary() {
+		// -0.75 is where the cardioid meets the period 2 disc
+		let b = estimate(-0.7, 0.0).unwrap();
+		assert!(b > 0.0 && b / 4.0 <= 0.05, "{}", b);
+	}
+
+	#[test]
+	fn escaping_points_have_no_estimate() {
+		assert_eq!(estimate(1.0, 0.0), None);
+		assert_eq!(estimate(-0.75, 0.5), None);
+	}
+
+	#[test]
+	fn exterior_estimates_bound_the_distance() {
+		let zero = Complex::new(0.0, 0.0);
+
+		// 0.25 is the cusp of the main cardioid, the nearest point of the set to 1
+		let b = exterior(zero, Complex::new(1.0, 0.0), false, 1000).unwrap();
+		assert!(b <= 0.75 && 0.75 <= b * 4.0, "{}", b);
+
+		// The Julia set of 0 is the unit circle
+		let b = exterior(Complex::new(0.0, 3.0), zero, true, 1000).unwrap();
+		assert!(b <= 2.0 && 2.0 <= b * 4.0, "{}", b);
+
+		assert_eq!(exterior(zero, Complex::new(-0.5, 0.0), false, 1000), None);
+	}
+}
@@ -0,0 +1,90 @@
+use std::time::Instant;
+
+// How long a render has left, going by how quickly its work has been getting done. Work is
+// counted in whatever the render is split into, pixels of finished tiles, rows or frames
+
+// Estimates before this share of the work is done swing too much to be worth showing
+const MIN_DONE: f64 = 0.02;
+
+pub struct Eta {
+	total: f64,
+	done: f64,
+	started: Instant,
+}
+
+// Seconds left at the rate so far, None until there's enough to go on
+pub fn estimate(elapsed: f64, done: f64, total: f64) -> Option<f64> {
+	if total <= 0.0 || done < total * MIN_DONE || done <= 0.0 {
+		return None;
+	}
+
+	Some(elapsed * (total - done).max(0.0) / done)
+}
+
+// Rounded to the two largest units, as 45s, 3m 20s or 2h 05m
+pub fn format_duration(seconds: f64) -> String {
+	let seconds = seconds.max(0.0).round() as u64;
+	let (hours, minutes) = (seconds / 3600, seconds / 60 % 60);
+
+	if hours > 0 {
+		format!("{}h {:02}m", hours, minutes)
+	} else if minutes > 0 {
+		format!("{}m {:02}s", minutes, seconds % 60)
+	} else {
+		format!("{}s", seconds)
+	}
+}
+
+impl Eta {
+	pub fn new(total: usize) -> Eta {
+		Eta { total: total as f64, done: 0.0, started: Instant::now() }
+	}
+
+	pub fn record(&mut self, amount: usize) {
+		self.done += amount as f64;
+	}
+
+	pub fn elapsed(&self) -> f64 {
+		self.started.elapsed().as_secs_f64()
+	}
+
+	// From 0 to 1
+	pub fn fraction(&self) -> f64 {
+		if self.total > 0.0 { (self.done / self.total).min(1.0) } else { 1.0 }
+	}
+
+	pub fn remaining(&self) -> Option<f64> {
+		estimate(self.elapsed(), self.done, self.total)
+	}
+
+	// For progress lines, empty until there's an estimate and once it's down to nothing
+	pub fn describe(&self) -> String {
+		match self.remaining() {
+			Some(seconds) if seconds >= 0.5 => format!(", about {} left", format_duration(seconds)),
+			_ => String::new(),
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn estimates_from_the_rate_so_far() {
+		assert_eq!(estimate(10.0, 25.0, 100.0), Some(30.0));
+		assert_eq!(estimate(10.0, 100.0, 100.0), Some(0.0));
+
+		// Too little done to say, or nothing to do
+		assert_eq!(estimate(10.0, 1.0, 100.0), None);
+		assert_eq!(estimate(10.0, 0.0, 0.0), None);
+	}
+
+	#[test]
+	fn durations_keep_two_units() {
+		assert_eq!(format_duration(45.4), "45s");
+		assert_eq!(format_duration(200.0), "3m 20s");
+		assert_eq!(format_duration(7500.0), "2h 05m");
+		assert_eq!(format_duration(-1.0), "0s");
+	}
+}
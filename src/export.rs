@@ -0,0 +1,744 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::io::{self, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ggez::graphics::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::checkpoint::{self, Checkpoint};
+use crate::complex::Complex;
+use crate::display::DisplayFilter;
+use crate::eta::Eta;
+use crate::formula::Formula;
+use crate::gradient;
+use crate::memory::MemoryBudget;
+use crate::postprocess::Effects;
+use crate::renderer::{self, Frame, RenderJob};
+use crate::session::Session;
+use crate::viewport::IDENTITY_SKEW;
+
+const SCREENSHOT_DIR: &str = "screenshots";
+
+// Rows of a poster rendered at once, only one band is held in memory while it's written out
+const POSTER_BAND_HEIGHT: usize = 64;
+
+// Which points exported images leave see-through, for compositing renders over other artwork.
+// Antialiased edges are as opaque as the share of their samples that are kept
+#[derive(Copy, Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Transparency {
+	#[default]
+	None,
+	Interior,
+	Exterior,
+}
+
+impl Transparency {
+	pub const ALL: [Transparency; 3] = [Transparency::None, Transparency::Interior, Transparency::Exterior];
+
+	pub fn name(self) -> &'static str {
+		match self {
+			Transparency::None => "none",
+			Transparency::Interior => "interior",
+			Transparency::Exterior => "exterior",
+		}
+	}
+
+	// Pixels line up with the frame's, from its first pixel onwards
+	pub fn apply(self, pixels: &mut [Color], frame: &Frame) {
+		if self == Transparency::None {
+			return;
+		}
+
+		let samples_per_pixel = frame.samples * frame.samples;
+
+		for (pixel, samples) in pixels.iter_mut().zip(frame.iterations.chunks(samples_per_pixel)) {
+			let escaped = samples.iter().filter(|sample| sample.is_some()).count() as f32 / samples_per_pixel as f32;
+
+			pixel.a *= match self {
+				Transparency::Interior => escaped,
+				_ => 1.0 - escaped,
+			};
+		}
+	}
+}
+
+impl FromStr for Transparency {
+	type Err = String;
+
+	fn from_str(name: &str) -> Result<Transparency, String> {
+		Transparency::ALL.into_iter().find(|transparency| transparency.name() == name).ok_or_else(|| {
+			let names: Vec<&str> = Transparency::ALL.iter().map(|transparency| transparency.name()).collect();
+			format!("unknown transparency {}, expected one of {}", name, names.join(", "))
+		})
+	}
+}
+
+fn invalid_data(message: String) -> io::Error {
+	io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+// The view is stored as tEXt chunks alongside the standard Software chunk,
+// complex values use the same "a + bi" form as Complex's Display
+fn view_metadata(session: &Session) -> Vec<(&'static str, String)> {
+	let mut metadata = vec![
+		("Software", format!("Mandelbrot Viewer {}", env!("CARGO_PKG_VERSION"))),
+		("Center", Complex::new(session.center[0], session.center[1]).to_string()),
+		("Magnification", session.magnification.to_string()),
+		("Rotation", session.rotation.to_string()),
+		("Skew", session.skew.map(|value| value.to_string()).join(",")),
+		("Inverted", session.inverted.to_string()),
+		("Formula", session.formula.to_string()),
+		("Bailout", session.bailout.to_string()),
+		("InitialZ", Complex::new(session.initial_z[0], session.initial_z[1]).to_string()),
+		("MaxIterations", session.max_iterations.to_string()),
+		("Palette", session.palette.clone()),
+	];
+
+	if let Some([real, imaginary]) = session.julia {
+		metadata.push(("Julia", Complex::new(real, imaginary).to_string()));
+	}
+
+	metadata
+}
+
+fn encoder<W: Write>(writer: W, width: usize, height: usize, bit_depth: u8, session: &Session) -> io::Result<png::Encoder<'static, W>> {
+	let mut encoder = png::Encoder::new(writer, width as u32, height as u32);
+	encoder.set_color(png::ColorType::Rgba);
+	encoder.set_depth(if bit_depth == 16 { png::BitDepth::Sixteen } else { png::BitDepth::Eight });
+
+	for (keyword, text) in view_metadata(session) {
+		encoder.add_text_chunk(keyword.to_string(), text)?;
+	}
+
+	Ok(encoder)
+}
+
+// Colours are kept as floats until here, so 16 bit output keeps the precision 8 bits rounds away.
+// PNG stores 16 bit samples big endian
+pub fn rgba_bytes(pixels: &[Color], bit_depth: u8) -> Vec<u8> {
+	if bit_depth == 16 {
+		let channel = |value: f32| ((value.clamp(0.0, 1.0) * u16::MAX as f32).round() as u16).to_be_bytes();
+
+		return pixels.iter()
+			.flat_map(|color| [channel(color.r), channel(color.g), channel(color.b), channel(color.a)])
+			.flatten()
+			.collect();
+	}
+
+	pixels.iter()
+		.flat_map(|color| {
+			let (r, g, b, a) = color.to_rgba();
+			[r, g, b, a]
+		})
+		.collect()
+}
+
+// bit_depth is 8 or 16 bits per channel
+pub fn save_png(path: &Path, pixels: &[Color], (width, height): (usize, usize), bit_depth: u8, session: &Session) -> io::Result<()> {
+	let file = BufWriter::new(File::create(path)?);
+
+	let mut writer = encoder(file, width, height, bit_depth, session)?.write_header()?;
+	writer.write_image_data(&rgba_bytes(pixels, bit_depth))?;
+
+	Ok(())
+}
+
+// A PNG kept in memory for sending elsewhere, pixels are row major
+pub fn png_bytes(pixels: &[Color], (width, height): (usize, usize), bit_depth: u8, session: &Session) -> io::Result<Vec<u8>> {
+	let mut bytes = Vec::new();
+
+	let mut writer = encoder(&mut bytes, width, height, bit_depth, session)?.write_header()?;
+	writer.write_image_data(&rgba_bytes(pixels, bit_depth))?;
+	writer.finish()?;
+
+	Ok(bytes)
+}
+
+// What a poster's finished bands were rendered with, which has to match for a rerun to keep them
+#[derive(Serialize)]
+struct PosterManifest<'a> {
+	width: usize,
+	height: usize,
+	samples: usize,
+	band_rows: usize,
+	bit_depth: u8,
+	shade_interior: bool,
+	boundary: bool,
+	transparency: Transparency,
+	palette: String,
+	stripes: Option<String>,
+	view: &'a Session,
+	effects: Option<Effects>,
+}
+
+// Finished bands are kept next to the poster until it's been put together
+fn poster_parts(path: &Path) -> PathBuf {
+	let mut name = path.as_os_str().to_owned();
+	name.push(".parts");
+
+	PathBuf::from(name)
+}
+
+const BAND_PREFIX: &str = "band_";
+
+fn band_name(index: usize) -> String {
+	format!("{}{:05}.png", BAND_PREFIX, index)
+}
+
+fn poster_manifest(job: &RenderJob, session: &Session, band_rows: usize, bit_depth: u8, effects: Option<Effects>, transparency: Transparency) -> io::Result<String> {
+	let manifest = PosterManifest {
+		width: job.width,
+		height: job.height,
+		samples: job.samples,
+		band_rows,
+		bit_depth,
+		shade_interior: job.shade_interior,
+		boundary: job.boundary,
+		transparency,
+		palette: checkpoint::describe_palette(&job.gradient),
+		stripes: job.stripes.as_deref().map(checkpoint::describe_palette),
+		view: session,
+		effects,
+	};
+
+	toml::to_string(&manifest).map_err(|e| invalid_data(e.to_string()))
+}
+
+fn save_band(path: &Path, bytes: &[u8], (width, height): (usize, usize), bit_depth: u8) -> io::Result<()> {
+	let mut encoder = png::Encoder::new(BufWriter::new(File::create(path)?), width as u32, height as u32);
+	encoder.set_color(png::ColorType::Rgba);
+	encoder.set_depth(if bit_depth == 16 { png::BitDepth::Sixteen } else { png::BitDepth::Eight });
+
+	encoder.write_header()?.write_image_data(bytes)?;
+
+	Ok(())
+}
+
+fn load_band(path: &Path) -> io::Result<Vec<u8>> {
+	let mut decoder = png::Decoder::new(File::open(path)?);
+	decoder.set_transformations(png::Transformations::IDENTITY);
+
+	let mut reader = decoder.read_info()?;
+	let mut bytes = vec![0; reader.output_buffer_size()];
+	reader.next_frame(&mut bytes)?;
+
+	Ok(bytes)
+}
+
+// Renders an image of any size by bands of rows, each band is compressed and saved before the
+// next is rendered so the whole image never has to fit in memory, then they're joined into one.
+// The job describes the whole poster, bands are at least a tile tall so no tile is cut short.
+// With effects, each band is rendered with enough rows either side for the blurs to reach into.
+// Bands are kept in <path>.parts until the poster is written, so running the same export again
+// after it's interrupted only renders the bands that are missing. Bands are thinned to keep
+// within the budget
+pub fn save_poster(path: &Path, job: RenderJob, bit_depth: u8, effects: Option<Effects>, transparency: Transparency, budget: MemoryBudget) -> io::Result<()> {
+	let (width, height) = (job.width, job.height);
+
+	let effects = effects.filter(|effects| !effects.is_empty());
+	let margin = effects.map_or(0, |effects| effects.margin());
+
+	let band_rows = budget.band_rows(width, job.samples, margin, POSTER_BAND_HEIGHT.max(job.tile_size));
+
+	let session = Session::capture(&job.viewport, &job.settings, &job.gradient.name);
+
+	let manifest = poster_manifest(&job, &session, band_rows, bit_depth, effects, transparency)?;
+	let checkpoint = Checkpoint::open(&poster_parts(path), BAND_PREFIX, &manifest)?;
+	let bands: Vec<usize> = (0..height).step_by(band_rows).collect();
+
+	if checkpoint.is_resuming() {
+		let done = bands.iter().enumerate().filter(|&(i, _)| checkpoint.is_done(&band_name(i))).count();
+		eprintln!("resuming with {} of {} bands already rendered", done, bands.len());
+	}
+
+	let remaining: usize = bands.iter().enumerate()
+		.filter(|&(i, _)| !checkpoint.is_done(&band_name(i)))
+		.map(|(_, &y)| band_rows.min(height - y))
+		.sum();
+
+	let mut eta = Eta::new(remaining);
+	let mut recycled = None;
+
+	for (i, &y) in bands.iter().enumerate() {
+		let band_height = band_rows.min(height - y);
+
+		if checkpoint.is_done(&band_name(i)) {
+			continue;
+		}
+
+		let top = y.saturating_sub(margin);
+		let bottom = (y + band_height + margin).min(height);
+
+		let band = job.viewport.crop(0.0, top as f64, width as f64, (bottom - top) as f64);
+
+		let frame = renderer::render(RenderJob {
+			tile_size: job.tile_size,
+			shade_interior: job.shade_interior,
+			boundary: job.boundary,
+			stripes: job.stripes.clone(),
+			recycled: recycled.take(),
+			..RenderJob::new(width, bottom - top, job.samples, band, job.settings, job.gradient.clone())
+		}, None);
+
+		let mut pixels = match effects {
+			Some(effects) => effects.apply_rows(&frame.pixels, (width, bottom - top), top, height),
+			None => frame.pixels.clone(),
+		};
+
+		transparency.apply(&mut pixels, &frame);
+
+		let bytes = rgba_bytes(&pixels[(y - top) * width..(y - top + band_height) * width], bit_depth);
+		checkpoint.write(&band_name(i), |path| save_band(path, &bytes, (width, band_height), bit_depth))?;
+		recycled = Some(frame);
+
+		eta.record(band_height);
+		eprintln!("rendered {} of {} rows{}", y + band_height, height, eta.describe());
+	}
+
+	let file = BufWriter::new(File::create(path)?);
+
+	let mut writer = encoder(file, width, height, bit_depth, &session)?.write_header()?;
+	let mut stream = writer.stream_writer()?;
+
+	for i in 0..bands.len() {
+		stream.write_all(&load_band(&checkpoint.path(&band_name(i)))?)?;
+	}
+
+	stream.finish()?;
+	checkpoint.remove()?;
+
+	Ok(())
+}
+
+// Exports are saved into the screenshots directory, named by the time they were taken
+fn screenshot_path(extension: &str) -> io::Result<PathBuf> {
+	fs::create_dir_all(SCREENSHOT_DIR)?;
+
+	let timestamp = SystemTime::now()
+		.duration_since(UNIX_EPOCH)
+		.map(|duration| duration.as_millis())
+		.unwrap_or(0);
+
+	Ok(Path::new(SCREENSHOT_DIR).join(format!("mandelbrot-{}.{}", timestamp, extension)))
+}
+
+// Saved as displayed, with the display filter and effects applied
+pub fn save_screenshot(frame: &Frame, filter: DisplayFilter, transparency: Transparency, bit_depth: u8, session: &Session) -> io::Result<PathBuf> {
+	let mut pixels = filter.apply_frame(frame);
+	transparency.apply(&mut pixels, frame);
+
+	let path = screenshot_path("png")?;
+	save_png(&path, &pixels, (frame.width, frame.height), bit_depth, session)?;
+
+	Ok(path)
+}
+
+// One row of the image per line, points inside the set are left empty
+pub fn write_csv(writer: &mut impl Write, frame: &Frame) -> io::Result<()> {
+	let values: Vec<Option<f64>> = frame.mean_iterations().collect();
+
+	for row in values.chunks(frame.width) {
+		let cells: Vec<String> = row.iter()
+			.map(|value| value.map(|value| value.to_string()).unwrap_or_default())
+			.collect();
+
+		writeln!(writer, "{}", cells.join(","))?;
+	}
+
+	Ok(())
+}
+
+// A (height, width) float64 array in NumPy's .npy format, points inside the set are NaN
+pub fn write_npy(writer: &mut impl Write, frame: &Frame) -> io::Result<()> {
+	let mut header = format!("{{'descr': '<f8', 'fortran_order': False, 'shape': ({}, {}), }}", frame.height, frame.width);
+
+	// The magic, version and length take 10 bytes, and the header is padded so the data is 64 byte aligned
+	let unpadded = 10 + header.len() + 1;
+	header.push_str(&" ".repeat(unpadded.next_multiple_of(64) - unpadded));
+	header.push('\n');
+
+	writer.write_all(b"\x93NUMPY\x01\x00")?;
+	writer.write_all(&(header.len() as u16).to_le_bytes())?;
+	writer.write_all(header.as_bytes())?;
+
+	for value in frame.mean_iterations() {
+		writer.write_all(&value.unwrap_or(f64::NAN).to_le_bytes())?;
+	}
+
+	Ok(())
+}
+
+// Saves the raw iteration counts as both CSV and .npy, returning the CSV path
+pub fn save_iterations(frame: &Frame) -> io::Result<PathBuf> {
+	let csv_path = screenshot_path("csv")?;
+	let npy_path = csv_path.with_extension("npy");
+
+	let mut csv = BufWriter::new(File::create(&csv_path)?);
+	write_csv(&mut csv, frame)?;
+	csv.flush()?;
+
+	let mut npy = BufWriter::new(File::create(&npy_path)?);
+	write_npy(&mut npy, frame)?;
+	npy.flush()?;
+
+	Ok(csv_path)
+}
+
+// Four comma separated numbers, a row major 2x2 matrix which has to be invertible
+pub fn parse_skew(text: &str) -> Result<[f64; 4], String> {
+	let values = text.split(',')
+		.map(|value| value.trim().parse::<f64>().map_err(|e| format!("{}: {}", value.trim(), e)))
+		.collect::<Result<Vec<f64>, String>>()?;
+
+	let Ok([a, b, c, d]) = <[f64; 4]>::try_from(values) else {
+		return Err(String::from("expected four numbers like 1,0,0,1"));
+	};
+
+	if a * d - b * c == 0.0 {
+		return Err(String::from("the skew matrix has to be invertible"));
+	}
+
+	Ok([a, b, c, d])
+}
+
+// Reads back the view an image was rendered from
+pub fn load_view(path: &Path) -> io::Result<Session> {
+	read_view(File::open(path)?, &path.display().to_string())
+}
+
+// Name is what the image is called in errors
+pub fn read_view(image: impl Read, name: &str) -> io::Result<Session> {
+	let decoder = png::Decoder::new(image);
+	let reader = decoder.read_info()?;
+
+	let chunks: HashMap<&str, &str> = reader.info().uncompressed_latin1_text.iter()
+		.map(|chunk| (chunk.keyword.as_str(), chunk.text.as_str()))
+		.collect();
+
+	let field = |keyword: &str| -> io::Result<&str> {
+		chunks.get(keyword).copied()
+			.ok_or_else(|| invalid_data(format!("{} has no {} metadata", name, keyword)))
+	};
+
+	let number = |keyword: &str| -> io::Result<f64> {
+		field(keyword)?.parse()
+			.map_err(|e| invalid_data(format!("bad {} metadata: {}", keyword, e)))
+	};
+
+	let complex = |keyword: &str| -> io::Result<[f64; 2]> {
+		let value: Complex = field(keyword)?.parse()
+			.map_err(|e| invalid_data(format!("bad {} metadata: {}", keyword, e)))?;
+
+		Ok([value.real(), value.imaginary()])
+	};
+
+	let session = Session {
+		center: complex("Center")?,
+		magnification: number("Magnification")?,
+		rotation: number("Rotation")?,
+		// Older images predate skewing
+		skew: match chunks.get("Skew") {
+			Some(skew) => parse_skew(skew).map_err(|e| invalid_data(format!("bad Skew metadata: {}", e)))?,
+			None => IDENTITY_SKEW,
+		},
+		inverted: chunks.get("Inverted").is_some_and(|inverted| *inverted == "true"),
+
+		// Older images are all of the Mandelbrot set
+		formula: match chunks.get("Formula") {
+			Some(formula) => formula.parse().map_err(|e| invalid_data(format!("bad Formula metadata: {}", e)))?,
+			None => Formula::Mandelbrot,
+		},
+		bailout: number("Bailout")?,
+		initial_z: complex("InitialZ")?,
+		julia: chunks.contains_key("Julia").then(|| complex("Julia")).transpose()?,
+		max_iterations: number("MaxIterations")?,
+
+		// Images may use a palette which has since been deleted, the caller falls back to the default
+		palette: field("Palette").unwrap_or(gradient::DEFAULT_NAME).to_string(),
+	};
+
+	if !session.is_valid() {
+		return Err(invalid_data(format!("{} has a view which can't be rendered", name)));
+	}
+
+	Ok(session)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::sync::Arc;
+
+	use crate::gradient::Gradient;
+	use crate::renderer::IterationSettings;
+	use crate::viewport::Viewport;
+
+	#[test]
+	fn view_survives_png_round_trip() {
+		let mut viewport = Viewport::new(4.0, 2.0);
+		viewport.set_center(Complex::new(-0.743643887037151, 0.131825904205330));
+		viewport.set_magnification(123456.789);
+		viewport.set_rotation(0.25);
+		viewport.set_skew([1.25, 0.5, 0.0, 0.8]);
+		viewport.set_inverted(true);
+
+		let settings = IterationSettings {
+			bailout: 16.0,
+			initial_z: Complex::new(0.0, -0.5),
+			julia: Some(Complex::new(-0.8, 0.156)),
+			..IterationSettings::mandelbrot(250.0)
+		};
+
+		let frame = Frame {
+			width: 4,
+			height: 2,
+			samples: 1,
+			margin: 0,
+
+			generation: 0,
+			pass: 0,
+
+			iterations: vec![Some(1.0); 8],
+			converged: vec![false; 8],
+			distance: vec![None; 8],
+			boundary: false,
+			palette_range: None,
+			stripes: None,
+			pixels: vec![Color::RED; 8],
+
+			viewport,
+			settings,
+			gradient: Arc::new(Gradient::rainbow()),
+		};
+
+		let path = std::env::temp_dir().join(format!("mandelbrot_viewer_metadata_{}.png", std::process::id()));
+		save_png(&path, &frame.pixels, (frame.width, frame.height), 8, &Session::capture(&viewport, &settings, "sunset")).unwrap();
+
+		let session = load_view(&path).unwrap();
+		fs::remove_file(&path).unwrap();
+
+		assert_eq!(session.center, [-0.743643887037151, 0.131825904205330]);
+		assert_eq!(session.magnification, viewport.magnification());
+		assert_eq!(session.rotation, 0.25);
+		assert_eq!(session.skew, [1.25, 0.5, 0.0, 0.8]);
+		assert!(session.inverted);
+		assert_eq!(session.bailout, 16.0);
+		assert_eq!(session.initial_z, [0.0, -0.5]);
+		assert_eq!(session.julia, Some([-0.8, 0.156]));
+		assert_eq!(session.max_iterations, 250.0);
+		assert_eq!(session.palette, "sunset");
+
+		let view = Session::capture(&viewport, &settings, "sunset");
+
+		for broken in [
+			Session { magnification: 0.0, ..view.clone() },
+			Session { magnification: f64::NAN, ..view.clone() },
+			Session { bailout: -2.0, ..view.clone() },
+			Session { max_iterations: 0.0, ..view.clone() },
+			Session { max_iterations: f64::INFINITY, ..view.clone() },
+			Session { center: [f64::INFINITY, 0.0], ..view.clone() },
+		] {
+			save_png(&path, &frame.pixels, (frame.width, frame.height), 8, &broken).unwrap();
+			let error = load_view(&path).err().unwrap();
+			fs::remove_file(&path).unwrap();
+
+			assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+		}
+	}
+
+	#[test]
+	fn parses_skew() {
+		assert_eq!(parse_skew("1, 0.5, 0, 2"), Ok([1.0, 0.5, 0.0, 2.0]));
+
+		assert!(parse_skew("1,0,0").is_err());
+		assert!(parse_skew("1,2,2,4").is_err());
+		assert!(parse_skew("1,a,0,1").is_err());
+	}
+
+	#[test]
+	fn poster_bands_stitch_into_one_image() {
+		let (width, height) = (40, POSTER_BAND_HEIGHT * 2 + 7);
+
+		let viewport = Viewport::new(width as f64, height as f64);
+		let settings = IterationSettings::mandelbrot(50.0);
+		let gradient = Arc::new(Gradient::rainbow());
+
+		let path = std::env::temp_dir().join(format!("mandelbrot_viewer_poster_{}.png", std::process::id()));
+		save_poster(&path, RenderJob::new(width, height, 1, viewport, settings, gradient.clone()), 8, None, Transparency::None, MemoryBudget::UNLIMITED).unwrap();
+
+		let mut reader = png::Decoder::new(File::open(&path).unwrap()).read_info().unwrap();
+		let mut data = vec![0; reader.output_buffer_size()];
+		reader.next_frame(&mut data).unwrap();
+		fs::remove_file(&path).unwrap();
+
+		// The same as rendering the whole image in one go
+		let whole = renderer::render(RenderJob::new(width, height, 1, viewport, settings, gradient.clone()), None);
+
+		assert_eq!(data, rgba_bytes(&whole.pixels, 8));
+
+		// And in the thinner bands a tight budget leaves room for
+		let budget = MemoryBudget::megabytes(0.05);
+		assert!(budget.band_rows(width, 1, 0, POSTER_BAND_HEIGHT) < POSTER_BAND_HEIGHT / 2);
+
+		save_poster(&path, RenderJob::new(width, height, 1, viewport, settings, gradient), 8, None, Transparency::None, budget).unwrap();
+
+		let mut reader = png::Decoder::new(File::open(&path).unwrap()).read_info().unwrap();
+		let mut thin = vec![0; reader.output_buffer_size()];
+		reader.next_frame(&mut thin).unwrap();
+		fs::remove_file(&path).unwrap();
+
+		assert_eq!(thin, data);
+	}
+
+	#[test]
+	fn poster_effects_match_the_whole_image() {
+		let (width, height) = (24, POSTER_BAND_HEIGHT + 9);
+
+		let viewport = Viewport::new(width as f64, height as f64);
+		let settings = IterationSettings::mandelbrot(50.0);
+		let gradient = Arc::new(Gradient::rainbow());
+		let effects = Effects { blur: 1.0, bloom_radius: 2.0, ..Effects::default() };
+
+		let path = std::env::temp_dir().join(format!("mandelbrot_viewer_poster_effects_{}.png", std::process::id()));
+		save_poster(&path, RenderJob::new(width, height, 1, viewport, settings, gradient.clone()), 8, Some(effects), Transparency::None, MemoryBudget::UNLIMITED).unwrap();
+
+		let mut reader = png::Decoder::new(File::open(&path).unwrap()).read_info().unwrap();
+		let mut data = vec![0; reader.output_buffer_size()];
+		reader.next_frame(&mut data).unwrap();
+		fs::remove_file(&path).unwrap();
+
+		let whole = renderer::render(RenderJob::new(width, height, 1, viewport, settings, gradient), None);
+
+		assert_eq!(data, rgba_bytes(&effects.apply(&whole.pixels, (width, height)), 8));
+	}
+
+	#[test]
+	fn interrupted_posters_keep_their_finished_bands() {
+		let (width, height) = (16, POSTER_BAND_HEIGHT + 5);
+
+		let viewport = Viewport::new(width as f64, height as f64);
+		let settings = IterationSettings::mandelbrot(50.0);
+		let job = || RenderJob::new(width, height, 1, viewport, settings, Arc::new(Gradient::rainbow()));
+
+		let path = std::env::temp_dir().join(format!("mandelbrot_viewer_poster_resume_{}.png", std::process::id()));
+		let parts = poster_parts(&path);
+
+		// As if the first band was finished, picked out in white to tell it apart from a rerender
+		let session = Session::capture(&viewport, &settings, "rainbow");
+		let manifest = poster_manifest(&job(), &session, POSTER_BAND_HEIGHT, 8, None, Transparency::None).unwrap();
+		let checkpoint = Checkpoint::open(&parts, BAND_PREFIX, &manifest).unwrap();
+
+		let white = rgba_bytes(&vec![Color::WHITE; width * POSTER_BAND_HEIGHT], 8);
+		checkpoint.write(&band_name(0), |path| save_band(path, &white, (width, POSTER_BAND_HEIGHT), 8)).unwrap();
+
+		save_poster(&path, job(), 8, None, Transparency::None, MemoryBudget::UNLIMITED).unwrap();
+
+		let mut reader = png::Decoder::new(File::open(&path).unwrap()).read_info().unwrap();
+		let mut data = vec![0; reader.output_buffer_size()];
+		reader.next_frame(&mut data).unwrap();
+		fs::remove_file(&path).unwrap();
+
+		let whole = renderer::render(job(), None);
+		let split = white.len();
+
+		assert_eq!(&data[..split], white);
+		assert_eq!(&data[split..], &rgba_bytes(&whole.pixels, 8)[split..]);
+		assert!(!parts.exists());
+	}
+
+	#[test]
+	fn sixteen_bit_keeps_fractional_colours() {
+		let bytes = rgba_bytes(&[Color::new(1.0, 0.5, 0.0, 1.0)], 16);
+
+		assert_eq!(bytes, [0xff, 0xff, 0x80, 0x00, 0x00, 0x00, 0xff, 0xff]);
+
+		// Two colours which round to the same 8 bit value stay distinct
+		let close = [Color::new(0.5, 0.0, 0.0, 1.0), Color::new(0.501, 0.0, 0.0, 1.0)];
+
+		assert_eq!(rgba_bytes(&close[..1], 8), rgba_bytes(&close[1..], 8));
+		assert_ne!(rgba_bytes(&close[..1], 16), rgba_bytes(&close[1..], 16));
+	}
+
+	fn small_frame() -> Frame {
+		Frame {
+			width: 2,
+			height: 2,
+			samples: 2,
+			margin: 0,
+
+			generation: 0,
+			pass: 0,
+
+			// Each pixel has four samples
+			iterations: vec![
+				Some(1.0), Some(2.0), Some(3.0), Some(4.0),
+				None, None, None, None,
+				Some(5.0), None, None, None,
+				Some(10.0), Some(10.0), Some(10.0), Some(10.0),
+			],
+			converged: vec![false; 16],
+			distance: vec![None; 16],
+			boundary: false,
+			palette_range: None,
+			stripes: None,
+			pixels: vec![Color::BLACK; 4],
+
+			viewport: Viewport::new(2.0, 2.0),
+			settings: IterationSettings::mandelbrot(10.0),
+			gradient: Arc::new(Gradient::rainbow()),
+		}
+	}
+
+	#[test]
+	fn transparency_follows_sample_coverage() {
+		let frame = small_frame();
+		let alpha = |transparency: Transparency| {
+			let mut pixels = frame.pixels.clone();
+			transparency.apply(&mut pixels, &frame);
+			pixels.iter().map(|pixel| pixel.a).collect::<Vec<_>>()
+		};
+
+		assert_eq!(alpha(Transparency::None), [1.0; 4]);
+		assert_eq!(alpha(Transparency::Interior), [1.0, 0.0, 0.25, 1.0]);
+		assert_eq!(alpha(Transparency::Exterior), [0.0, 1.0, 0.75, 0.0]);
+
+		assert_eq!("exterior".parse(), Ok(Transparency::Exterior));
+		assert!("inside".parse::<Transparency>().is_err());
+	}
+
+	#[test]
+	fn csv_averages_samples_and_leaves_interior_empty() {
+		let mut csv = Vec::new();
+		write_csv(&mut csv, &small_frame()).unwrap();
+
+		assert_eq!(String::from_utf8(csv).unwrap(), "2.5,\n5,10\n");
+	}
+
+	#[test]
+	fn npy_header_is_aligned() {
+		let mut npy = Vec::new();
+		write_npy(&mut npy, &small_frame()).unwrap();
+
+		assert_eq!(&npy[..8], b"\x93NUMPY\x01\x00");
+
+		let header_len = u16::from_le_bytes([npy[8], npy[9]]) as usize;
+		let header = std::str::from_utf8(&npy[10..10 + header_len]).unwrap();
+
+		assert_eq!((10 + header_len) % 64, 0);
+		assert!(header.contains("'shape': (2, 2)") && header.ends_with('\n'));
+
+		let values: Vec<f64> = npy[10 + header_len..].chunks(8)
+			.map(|bytes| f64::from_le_bytes(bytes.try_into().unwrap()))
+			.collect();
+
+		assert_eq!(values.len(), 4);
+		assert_eq!(values[0], 2.5);
+		assert!(values[1].is_nan());
+		assert_eq!(values[3], 10.0);
+	}
+}
@@ -0,0 +1,334 @@
+use std::fmt;
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use crate::complex::Complex;
+use crate::viewport::Viewport;
+
+// What z is replaced with each iteration. The abs variants fold z into one quadrant or half plane
+// before squaring it, or fold the square afterwards. The magnets come from renormalising the
+// Ising model and converge on z = 1 as well as escaping, the lambda map is the logistic map. Nova
+// is Newton's method for z³ = 1 with c added to each step, so its orbits mostly converge
+
+#[derive(Copy, Clone, PartialEq, Debug, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Formula {
+	// z²
+	#[default]
+	Mandelbrot,
+	// (|x| + i|y|)²
+	BurningShip,
+	// |Re(z²)| + i Im(z²)
+	Celtic,
+	// (x - i|y|)²
+	PerpendicularBurningShip,
+	// The Celtic fold of the Burning Ship's square
+	Buffalo,
+	// ((z² + c - 1) / (2z + c - 2))²
+	#[serde(rename = "magnet-1")]
+	MagnetOne,
+	// ((z³ + 3(c - 1)z + (c - 1)(c - 2)) / (3z² + 3(c - 2)z + (c - 1)(c - 2) + 1))²
+	#[serde(rename = "magnet-2")]
+	MagnetTwo,
+	// cz(1 - z)
+	Lambda,
+	// z - (z³ - 1) / 3z² + c
+	Nova,
+}
+
+// Where R and switching formulas frame the parameter plane, and the least bailout and iteration cap
+// the fractal needs to be seen properly. Configured values above these are kept
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Preset {
+	pub center: Complex,
+	pub magnification: f64,
+	pub bailout: f64,
+	pub max_iterations: f64,
+}
+
+impl Preset {
+	pub fn viewport(&self, width: f64, height: f64) -> Viewport {
+		let mut viewport = Viewport::new(width, height);
+		viewport.set_center(self.center);
+		viewport.set_magnification(self.magnification);
+
+		viewport
+	}
+}
+
+// Maps of the plane which leave every escape count where it was, so half of a view lying across an
+// axis can be copied from the other half
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Symmetry {
+	// Reflection in the real axis
+	Conjugate,
+	// Half a turn about the origin
+	HalfTurn,
+}
+
+impl Symmetry {
+	pub fn apply(self, z: Complex) -> Complex {
+		match self {
+			Symmetry::Conjugate => z.conj(),
+			Symmetry::HalfTurn => -z,
+		}
+	}
+}
+
+// Orbits of converging formulas stop once a step moves them less than the square root of this
+const CONVERGENCE_EPSILON: f64 = 1e-12;
+
+impl Formula {
+	pub const ALL: [Formula; 9] = [
+		Formula::Mandelbrot,
+		Formula::BurningShip,
+		Formula::Celtic,
+		Formula::PerpendicularBurningShip,
+		Formula::Buffalo,
+		Formula::MagnetOne,
+		Formula::MagnetTwo,
+		Formula::Lambda,
+		Formula::Nova,
+	];
+
+	pub fn name(self) -> &'static str {
+		match self {
+			Formula::Mandelbrot => "mandelbrot",
+			Formula::BurningShip => "burning-ship",
+			Formula::Celtic => "celtic",
+			Formula::PerpendicularBurningShip => "perpendicular-burning-ship",
+			Formula::Buffalo => "buffalo",
+			Formula::MagnetOne => "magnet-1",
+			Formula::MagnetTwo => "magnet-2",
+			Formula::Lambda => "lambda",
+			Formula::Nova => "nova",
+		}
+	}
+
+	pub fn next(self) -> Formula {
+		let index = Formula::ALL.iter().position(|&formula| formula == self).unwrap_or(0);
+		Formula::ALL[(index + 1) % Formula::ALL.len()]
+	}
+
+	pub fn preset(self) -> Preset {
+		let preset = |real, imaginary, magnification, bailout, max_iterations| Preset {
+			center: Complex::new(real, imaginary),
+			magnification,
+			bailout,
+			max_iterations,
+		};
+
+		match self {
+			Formula::Mandelbrot => preset(0.0, 0.0, 1.0, 2.0, 100.0),
+			Formula::BurningShip => preset(-0.4, -0.5, 0.9, 2.0, 100.0),
+			Formula::Celtic => preset(-0.4, 0.0, 1.0, 2.0, 100.0),
+			Formula::PerpendicularBurningShip => preset(-0.5, 0.0, 1.0, 2.0, 100.0),
+			Formula::Buffalo => preset(-0.5, -0.3, 1.0, 2.0, 100.0),
+			Formula::MagnetOne => preset(1.5, 0.0, 0.9, 100.0, 100.0),
+			Formula::MagnetTwo => preset(1.0, 0.0, 1.0, 100.0, 100.0),
+			Formula::Lambda => preset(1.0, 0.0, 0.8, 2.0, 100.0),
+			Formula::Nova => preset(-0.3, 0.0, 2.0, 100.0, 100.0),
+		}
+	}
+
+	// Where orbits of the parameter plane start, before the configured initial z is added. Any other
+	// start misses the critical point and loses the set's connectedness
+	pub fn critical_point(self) -> Complex {
+		match self {
+			Formula::Lambda => Complex::new(0.5, 0.0),
+			// A root of z³ - 1, where Newton's method alone would stay put
+			Formula::Nova => Complex::new(1.0, 0.0),
+			_ => Complex::new(0.0, 0.0),
+		}
+	}
+
+	// Formulas built from arithmetic with real coefficients commute with conjugation, so their
+	// parameter planes mirror whenever the orbits start on the real axis, and their Julia sets do for
+	// real c. Folding the imaginary part away makes the next z the same for z and its conjugate, and
+	// even formulas do the same for z and -z, whatever c is
+	pub fn symmetries(self, initial_z: Complex, julia: Option<Complex>) -> Vec<Symmetry> {
+		let is_real = |z: Complex| z.imaginary() == 0.0;
+		let commutes_with_conjugation = !matches!(self, Formula::BurningShip | Formula::PerpendicularBurningShip | Formula::Buffalo);
+
+		let Some(c) = julia else {
+			let start = self.critical_point() + initial_z;
+			return if commutes_with_conjugation && is_real(start) { vec![Symmetry::Conjugate] } else { Vec::new() };
+		};
+
+		let mut symmetries = Vec::new();
+
+		if !commutes_with_conjugation || is_real(c) {
+			symmetries.push(Symmetry::Conjugate);
+		}
+
+		if matches!(self, Formula::Mandelbrot | Formula::BurningShip | Formula::Celtic | Formula::Buffalo) {
+			symmetries.push(Symmetry::HalfTurn);
+		}
+
+		symmetries
+	}
+
+	// Whether orbits can settle on a finite attractor instead of escaping, which ends them too
+	pub fn converges(self) -> bool {
+		matches!(self, Formula::MagnetOne | Formula::MagnetTwo | Formula::Nova)
+	}
+
+	// |z - previous| < ε, whichever fixed point or cycle the orbit is settling on
+	pub fn has_converged(self, previous: Complex, z: Complex) -> bool {
+		self.converges() && (z - previous).norm_sqr() < CONVERGENCE_EPSILON
+	}
+
+	// Inlined into each formula's own escape loop, so the match is resolved at compile time there.
+	// The OpenCL kernel repeats these operation for operation, so keep the two in step
+	#[inline(always)]
+	pub fn iterate(self, z: Complex, c: Complex) -> Complex {
+		let fold_real = |z: Complex| Complex::new(z.real().abs(), z.imaginary());
+		let abs = |z: Complex| Complex::new(z.real().abs(), z.imaginary().abs());
+
+		match self {
+			Formula::Mandelbrot => z * z + c,
+			Formula::BurningShip => abs(z) * abs(z) + c,
+			Formula::Celtic => fold_real(z * z) + c,
+			Formula::PerpendicularBurningShip => {
+				let folded = Complex::new(z.real(), -z.imaginary().abs());
+				folded * folded + c
+			},
+			Formula::Buffalo => fold_real(abs(z) * abs(z)) + c,
+			Formula::MagnetOne => {
+				let quotient = (z * z + (c + -1.0)) / (z * 2.0 + (c + -2.0));
+				quotient * quotient
+			},
+			Formula::MagnetTwo => {
+				let (a, b) = (c + -1.0, c + -2.0);
+				let quotient = (z * z * z + a * 3.0 * z + a * b) / (z * z * 3.0 + b * 3.0 * z + a * b + 1.0);
+				quotient * quotient
+			},
+			Formula::Lambda => c * z * (-z + 1.0),
+			Formula::Nova => z - (z * z * z + -1.0) / (z * z * 3.0) + c,
+		}
+	}
+}
+
+impl fmt::Display for Formula {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		f.write_str(self.name())
+	}
+}
+
+impl FromStr for Formula {
+	type Err = String;
+
+	fn from_str(name: &str) -> Result<Formula, String> {
+		Formula::ALL.into_iter().find(|formula| formula.name() == name).ok_or_else(|| {
+			let names: Vec<&str> = Formula::ALL.iter().map(|formula| formula.name()).collect();
+			format!("unknown formula {}, expected one of {}", name, names.join(", "))
+		})
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn names_round_trip() {
+		for formula in Formula::ALL {
+			assert_eq!(formula.name().parse::<Formula>(), Ok(formula));
+		}
+
+		assert!("burning ship".parse::<Formula>().is_err());
+	}
+
+	#[test]
+	fn abs_variants_fold_the_square() {
+		let z = Complex::new(-1.0, 2.0);
+		let parts = |formula: Formula| {
+			let squared = formula.iterate(z, Complex::new(0.0, 0.0));
+			(squared.real(), squared.imaginary())
+		};
+
+		assert_eq!(parts(Formula::Mandelbrot), (-3.0, -4.0));
+		assert_eq!(parts(Formula::BurningShip), (-3.0, 4.0));
+		assert_eq!(parts(Formula::Celtic), (3.0, -4.0));
+		assert_eq!(parts(Formula::PerpendicularBurningShip), (-3.0, 4.0));
+		assert_eq!(parts(Formula::Buffalo), (3.0, 4.0));
+
+		// Unlike the Burning Ship, the sign of x survives the fold
+		let mirrored = Complex::new(1.0, 2.0);
+		assert_eq!(Formula::PerpendicularBurningShip.iterate(mirrored, Complex::new(0.0, 0.0)).imaginary(), -4.0);
+	}
+
+	#[test]
+	fn magnets_fix_one_for_every_c() {
+		let one = Complex::new(1.0, 0.0);
+
+		for c in [Complex::new(0.3, 0.0), Complex::new(-1.5, 0.7), Complex::new(2.5, -1.0)] {
+			for formula in [Formula::MagnetOne, Formula::MagnetTwo] {
+				assert!(formula.has_converged(one, formula.iterate(one, c)), "{} at c = {}", formula, c);
+			}
+		}
+
+		// Fixed points of formulas which don't converge are left to the iteration cap
+		assert!(!Formula::Mandelbrot.has_converged(Complex::new(0.0, 0.0), Complex::new(0.0, 0.0)));
+	}
+
+	#[test]
+	fn symmetries_leave_orbits_unchanged() {
+		let zero = Complex::new(0.0, 0.0);
+		let points = [Complex::new(0.3, 0.45), Complex::new(-1.2, 0.1), Complex::new(0.05, -0.7)];
+
+		// Norms of the first few iterates agree, which is all escaping depends on
+		let orbit = |formula: Formula, mut z: Complex, c: Complex| -> Vec<f64> {
+			(0..4).map(|_| {
+				z = formula.iterate(z, c);
+				z.norm_sqr()
+			}).collect()
+		};
+
+		let close = |a: Vec<f64>, b: Vec<f64>| a.iter().zip(&b).all(|(a, b)| (a - b).abs() <= 1e-9 * a.abs().max(1.0));
+
+		for formula in Formula::ALL {
+			let start = formula.critical_point();
+
+			for symmetry in formula.symmetries(zero, None) {
+				for c in points {
+					assert!(close(orbit(formula, start, c), orbit(formula, start, symmetry.apply(c))), "{} {:?} at {}", formula, symmetry, c);
+				}
+			}
+
+			for julia in [Complex::new(-0.8, 0.0), Complex::new(-0.4, 0.6)] {
+				for symmetry in formula.symmetries(zero, Some(julia)) {
+					for z in points {
+						assert!(close(orbit(formula, z, julia), orbit(formula, symmetry.apply(z), julia)), "{} {:?} at {}", formula, symmetry, z);
+					}
+				}
+			}
+		}
+
+		assert!(Formula::Mandelbrot.symmetries(Complex::new(0.0, 0.5), None).is_empty());
+		assert_eq!(Formula::Mandelbrot.symmetries(zero, Some(Complex::new(-0.4, 0.6))), vec![Symmetry::HalfTurn]);
+	}
+
+	#[test]
+	fn lambda_is_the_logistic_map() {
+		let z = Formula::Lambda.iterate(Formula::Lambda.critical_point(), Complex::new(4.0, 0.0));
+		assert_eq!((z.real(), z.imaginary()), (1.0, 0.0));
+	}
+
+	#[test]
+	fn nova_without_c_finds_a_cube_root_of_one() {
+		let mut z = Complex::new(2.0, 0.5);
+
+		for _ in 0..50 {
+			let previous = z;
+			z = Formula::Nova.iterate(z, Complex::new(0.0, 0.0));
+
+			if Formula::Nova.has_converged(previous, z) {
+				break;
+			}
+		}
+
+		assert!((z * z * z + -1.0).norm_sqr() < 1e-12, "{}", z);
+	}
+}
@@ -0,0 +1,187 @@
+use std::num::NonZeroU32;
+
+use ggez::graphics::{Canvas, Color, DrawParam, Image, ImageFormat, Sampler};
+use ggez::mint::Point2;
+use ggez::Context;
+
+use mandelbrot_viewer::capture;
+
+use mandelbrot_viewer::display::DisplayFilter;
+use mandelbrot_viewer::export;
+use mandelbrot_viewer::renderer::{Frame, RenderedTile};
+use mandelbrot_viewer::viewport::Viewport;
+
+// Bytes written to the GPU per update, a band of rows at a time. A 1000x1000 frame takes two
+// updates, which keeps a finishing render from stalling the frame it arrives on
+const UPLOAD_BYTES_PER_UPDATE: usize = 2 * 1024 * 1024;
+
+// The displayed frame as a texture, drawn wherever the view places it. Frames rendered at several
+// times the window's resolution are averaged down by a whole factor first. New frames are written
+// into a second texture and swapped in once complete, so a half uploaded frame is never shown
+pub struct FrameTexture {
+	front: Option<Image>,
+	back: Option<Image>,
+	// The viewport and margin of the frame in the front texture, which lags the newest frame while
+	// that uploads
+	shown: Option<(Viewport, usize)>,
+	// Frame pixels per texture pixel along each side, of the front texture and the one uploading
+	front_factor: usize,
+	back_factor: usize,
+	// Rows of the back texture written so far, None with nothing to upload
+	uploaded_rows: Option<usize>,
+	// The whole frame through the filter, worked out when the upload starts while effects are on as
+	// those can't be applied a band at a time
+	processed: Option<Vec<Color>>,
+}
+
+fn blank(context: &Context, width: usize, height: usize) -> Image {
+	Image::from_pixels(context, &vec![0; width * height * 4], ImageFormat::Rgba8UnormSrgb, width as u32, height as u32)
+}
+
+// Row positions and sizes are in pixels, the bytes are one RGBA row after another
+fn write(context: &Context, image: &Image, (x, y): (usize, usize), (width, height): (usize, usize), bytes: &[u8]) {
+	let (texture, _) = image.wgpu();
+
+	context.gfx.wgpu().queue.write_texture(
+		wgpu::ImageCopyTexture {
+			texture,
+			mip_level: 0,
+			origin: wgpu::Origin3d { x: x as u32, y: y as u32, z: 0 },
+			aspect: wgpu::TextureAspect::All,
+		},
+		bytes,
+		wgpu::ImageDataLayout {
+			offset: 0,
+			bytes_per_row: NonZeroU32::new(width as u32 * 4),
+			rows_per_image: None,
+		},
+		wgpu::Extent3d { width: width as u32, height: height as u32, depth_or_array_layers: 1 },
+	);
+}
+
+impl FrameTexture {
+	pub fn new() -> FrameTexture {
+		FrameTexture {
+			front: None,
+			back: None,
+			shown: None,
+			front_factor: 1,
+			back_factor: 1,
+			uploaded_rows: None,
+			processed: None,
+		}
+	}
+
+	// Starts over whenever the frame changes, including when an accumulation pass lands in it
+	pub fn begin(&mut self) {
+		self.uploaded_rows = Some(0);
+	}
+
+	pub fn is_uploading(&self) -> bool {
+		self.uploaded_rows.is_some()
+	}
+
+	// Writes the next band of rows, returning whether the frame was completed and swapped in. The
+	// factor is taken when the upload starts
+	pub fn advance(&mut self, context: &Context, frame: &Frame, filter: DisplayFilter, factor: usize) -> bool {
+		let Some(start) = self.uploaded_rows else {
+			return false;
+		};
+
+		if start == 0 {
+			self.back_factor = factor.max(1);
+		}
+
+		let (width, height) = capture::downscaled_size((frame.width, frame.height), self.back_factor);
+
+		let back = match self.back.take() {
+			Some(back) if (back.width() as usize, back.height() as usize) == (width, height) => back,
+			_ => blank(context, width, height),
+		};
+
+		let rows = (UPLOAD_BYTES_PER_UPDATE / (width * 4)).clamp(1, height - start);
+		let pixels = start * width..(start + rows) * width;
+
+		if start == 0 {
+			self.processed = match (filter.has_effects(), self.back_factor) {
+				(false, 1) => None,
+				(true, 1) => Some(filter.apply_frame(frame)),
+				(_, factor) => Some(capture::downscale(&filter.apply_frame(frame), (frame.width, frame.height), factor)),
+			};
+		}
+
+		let colors: Vec<_> = match &self.processed {
+			Some(processed) => processed[pixels].to_vec(),
+			None => frame.pixels[pixels.clone()].iter().zip(frame.mean_iterations_in(pixels))
+				.map(|(&color, iterations)| filter.apply(color, iterations))
+				.collect(),
+		};
+
+		write(context, &back, (0, start), (width, rows), &export::rgba_bytes(&colors, 8));
+
+		if start + rows < height {
+			self.back = Some(back);
+			self.uploaded_rows = Some(start + rows);
+			return false;
+		}
+
+		self.back = self.front.replace(back);
+		self.shown = Some((frame.viewport, frame.margin));
+		self.front_factor = self.back_factor;
+		self.uploaded_rows = None;
+		self.processed = None;
+
+		true
+	}
+
+	// Completes any upload in one go, as tiles are drawn over the finished frame
+	pub fn finish(&mut self, context: &Context, frame: &Frame, filter: DisplayFilter, factor: usize) {
+		while self.is_uploading() {
+			self.advance(context, frame, filter, factor);
+		}
+	}
+
+	// Draws a tile of the render in progress straight onto the displayed frame, averaged down like it
+	// when the tile lines up with the blocks it was averaged in
+	pub fn write_tile(&mut self, context: &Context, rendered: &RenderedTile, filter: DisplayFilter) {
+		let Some(front) = &self.front else {
+			return;
+		};
+
+		let factor = self.front_factor;
+		let tile = rendered.tile;
+
+		if (front.width() as usize, front.height() as usize) != capture::downscaled_size((rendered.frame_width, rendered.frame_height), factor) {
+			return;
+		}
+
+		if !tile.x.is_multiple_of(factor) || !tile.y.is_multiple_of(factor) {
+			return;
+		}
+
+		let colors: Vec<_> = rendered.pixels.iter().map(|&color| filter.apply(color, None)).collect();
+		let colors = capture::downscale(&colors, (tile.width, tile.height), factor);
+
+		write(context, front, (tile.x / factor, tile.y / factor), capture::downscaled_size((tile.width, tile.height), factor), &export::rgba_bytes(&colors, 8));
+	}
+
+	pub fn shown(&self) -> Option<(Viewport, usize)> {
+		self.shown
+	}
+
+	// Placed by the frame's top left and the window pixels each frame pixel covers. Nearest neighbour
+	// when enlarged, so frames rendered below window resolution keep their hard pixel edges, and
+	// filtered when shrunk by what averaging left
+	pub fn draw(&self, canvas: &mut Canvas, origin: Point2<f32>, scale: [f32; 2]) {
+		let Some(front) = &self.front else {
+			return;
+		};
+
+		let factor = self.front_factor as f32;
+		let scale = [scale[0] * factor, scale[1] * factor];
+
+		canvas.set_sampler(if scale[0] < 1.0 { Sampler::linear_clamp() } else { Sampler::nearest_clamp() });
+		canvas.draw(front, DrawParam::new().dest(origin).scale(scale));
+		canvas.set_sampler(Sampler::default());
+	}
+}
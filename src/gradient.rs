@@ -0,0 +1,309 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+
+use ggez::graphics::Color;
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use serde::Deserialize;
+
+pub const PALETTE_DIR: &str = "palettes";
+
+pub const DEFAULT_NAME: &str = "rainbow";
+
+// Palette files are TOML with a list of stops, positions run from 0 to 1:
+//
+// stops = [
+//     { position = 0.0, color = "#000764" },
+//     { position = 1.0, color = "#ffffff" },
+// ]
+
+#[derive(Deserialize)]
+struct GradientFile {
+	stops: Vec<StopFile>,
+}
+
+#[derive(Deserialize)]
+struct StopFile {
+	position: f32,
+	color: String,
+}
+
+pub fn parse_hex(hex: &str) -> Result<Color, String> {
+	let digits = hex.strip_prefix('#').unwrap_or(hex);
+
+	if digits.len() != 6 {
+		return Err(format!("expected a colour like #rrggbb, got {}", hex));
+	}
+
+	let value = u32::from_str_radix(digits, 16).map_err(|_| format!("invalid colour {}", hex))?;
+
+	Ok(Color::from_rgb_u32(value))
+}
+
+pub fn to_hex(color: Color) -> String {
+	let (r, g, b) = color.to_rgb();
+	format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+#[derive(Clone)]
+pub struct Gradient {
+	pub name: String,
+	// Sorted by position
+	stops: Vec<(f32, Color)>,
+	// Points that never escape
+	interior: Color,
+}
+
+impl Gradient {
+	pub fn new(name: &str, mut stops: Vec<(f32, Color)>) -> Gradient {
+		stops.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+		Gradient {
+			name: name.to_string(),
+			stops,
+			interior: Color::BLACK,
+		}
+	}
+
+	pub fn with_interior(mut self, interior: Color) -> Gradient {
+		self.interior = interior;
+		self
+	}
+
+	pub fn interior(&self) -> Color {
+		self.interior
+	}
+
+	pub fn stops(&self) -> &[(f32, Color)] {
+		&self.stops
+	}
+
+	// In the palette file format, which parse reads back
+	pub fn to_toml(&self) -> String {
+		let stops: String = self.stops.iter()
+			.map(|&(position, color)| format!("\t{{ position = {}, color = \"{}\" }},\n", position, to_hex(color)))
+			.collect();
+
+		format!("stops = [\n{}]\n", stops)
+	}
+
+	// Written as <name>.toml, which reloads it into the palette list when the directory is watched
+	pub fn save(&self, dir: &Path) -> io::Result<PathBuf> {
+		fs::create_dir_all(dir)?;
+
+		let path = dir.join(format!("{}.toml", self.name));
+		fs::write(&path, self.to_toml())?;
+
+		Ok(path)
+	}
+
+	// Full saturation HSV hues are linear in RGB between the primaries and secondaries
+	pub fn rainbow() -> Gradient {
+		Gradient::new(DEFAULT_NAME, vec![
+			(0.0, Color::new(1.0, 0.0, 0.0, 1.0)),
+			(1.0 / 6.0, Color::new(1.0, 1.0, 0.0, 1.0)),
+			(2.0 / 6.0, Color::new(0.0, 1.0, 0.0, 1.0)),
+			(3.0 / 6.0, Color::new(0.0, 1.0, 1.0, 1.0)),
+			(4.0 / 6.0, Color::new(0.0, 0.0, 1.0, 1.0)),
+			(5.0 / 6.0, Color::new(1.0, 0.0, 1.0, 1.0)),
+			(1.0, Color::new(1.0, 0.0, 0.0, 1.0)),
+		])
+	}
+
+	pub fn parse(name: &str, contents: &str) -> Result<Gradient, String> {
+		let file: GradientFile = toml::from_str(contents).map_err(|e| e.to_string())?;
+
+		if file.stops.is_empty() {
+			return Err(String::from("a palette needs at least one stop"));
+		}
+
+		let stops = file.stops.iter()
+			.map(|stop| Ok((stop.position.clamp(0.0, 1.0), parse_hex(&stop.color)?)))
+			.collect::<Result<Vec<_>, String>>()?;
+
+		Ok(Gradient::new(name, stops))
+	}
+
+	// Linear interpolation between the surrounding stops, t is clamped to [0, 1]
+	pub fn sample(&self, t: f32) -> Color {
+		let t = t.clamp(0.0, 1.0);
+
+		let upper = self.stops.iter().position(|&(position, _)| position >= t);
+
+		let (lower, upper) = match upper {
+			Some(0) => return self.stops[0].1,
+			Some(i) => (self.stops[i - 1], self.stops[i]),
+			None => return self.stops[self.stops.len() - 1].1,
+		};
+
+		let span = upper.0 - lower.0;
+		let amount = if span > 0.0 { (t - lower.0) / span } else { 0.0 };
+
+		let (a, b) = (lower.1, upper.1);
+
+		Color::new(
+			a.r + (b.r - a.r) * amount,
+			a.g + (b.g - a.g) * amount,
+			a.b + (b.b - a.b) * amount,
+			a.a + (b.a - a.a) * amount,
+		)
+	}
+}
+
+// The built in rainbow followed by every valid palette in the directory, sorted by name, all
+// sharing the interior colour. Broken files are reported and skipped so a half-saved edit doesn't lose the others
+pub fn load_palettes(dir: &Path, interior: Color) -> Vec<Gradient> {
+	let mut palettes = Vec::new();
+
+	if let Ok(entries) = fs::read_dir(dir) {
+		for entry in entries.flatten() {
+			let path = entry.path();
+
+			if path.extension().is_none_or(|extension| extension != "toml") {
+				continue;
+			}
+
+			let name = path.file_stem().unwrap_or_default().to_string_lossy().into_owned();
+
+			match fs::read_to_string(&path).map_err(|e| e.to_string()).and_then(|contents| Gradient::parse(&name, &contents)) {
+				Ok(gradient) => palettes.push(gradient),
+				Err(e) => eprintln!("could not load palette {}: {}", path.display(), e),
+			}
+		}
+	}
+
+	palettes.sort_by(|a, b| a.name.cmp(&b.name));
+	palettes.insert(0, Gradient::rainbow());
+
+	palettes.into_iter().map(|palette| palette.with_interior(interior)).collect()
+}
+
+// Reports when anything in the palette directory is written, so palettes can be reloaded live
+pub struct PaletteWatcher {
+	_watcher: RecommendedWatcher,
+	events: Receiver<notify::Result<notify::Event>>,
+}
+
+impl PaletteWatcher {
+	// None if the directory doesn't exist or can't be watched
+	pub fn new(dir: &Path) -> Option<PaletteWatcher> {
+		let (sender, events) = mpsc::channel();
+
+		let mut watcher = notify::recommended_watcher(sender).ok()?;
+		watcher.watch(dir, RecursiveMode::NonRecursive).ok()?;
+
+		Some(PaletteWatcher {
+			_watcher: watcher,
+			events,
+		})
+	}
+
+	// Drains pending events, editors tend to produce a burst of them per save
+	pub fn has_changed(&self) -> bool {
+		let mut has_changed = false;
+
+		while let Ok(event) = self.events.try_recv() {
+			if let Ok(event) = event {
+				has_changed |= event.kind.is_create() || event.kind.is_modify() || event.kind.is_remove();
+			}
+		}
+
+		has_changed
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn assert_color(a: Color, b: Color) {
+		let close = |x: f32, y: f32| (x - y).abs() < 1e-6;
+
+		assert!(close(a.r, b.r) && close(a.g, b.g) && close(a.b, b.b) && close(a.a, b.a), "{:?} vs {:?}", a, b);
+	}
+
+	#[test]
+	fn sample_interpolates_between_stops() {
+		let gradient = Gradient::new("test", vec![
+			(1.0, Color::new(1.0, 1.0, 1.0, 1.0)),
+			(0.0, Color::new(0.0, 0.0, 0.0, 1.0)),
+		]);
+
+		assert_color(gradient.sample(0.0), Color::new(0.0, 0.0, 0.0, 1.0));
+		assert_color(gradient.sample(0.25), Color::new(0.25, 0.25, 0.25, 1.0));
+		assert_color(gradient.sample(1.0), Color::new(1.0, 1.0, 1.0, 1.0));
+	}
+
+	#[test]
+	fn sample_clamps_outside_stops() {
+		let gradient = Gradient::new("test", vec![
+			(0.25, Color::new(1.0, 0.0, 0.0, 1.0)),
+			(0.75, Color::new(0.0, 0.0, 1.0, 1.0)),
+		]);
+
+		assert_color(gradient.sample(0.0), Color::new(1.0, 0.0, 0.0, 1.0));
+		assert_color(gradient.sample(-3.0), Color::new(1.0, 0.0, 0.0, 1.0));
+		assert_color(gradient.sample(2.0), Color::new(0.0, 0.0, 1.0, 1.0));
+	}
+
+	#[test]
+	fn rainbow_matches_hsv_hues() {
+		let rainbow = Gradient::rainbow();
+
+		// Hue 30 is halfway between red and yellow
+		assert_color(rainbow.sample(30.0 / 360.0), Color::new(1.0, 0.5, 0.0, 1.0));
+		assert_color(rainbow.sample(240.0 / 360.0), Color::new(0.0, 0.0, 1.0, 1.0));
+	}
+
+	#[test]
+	fn parse_palette_file() {
+		let gradient = Gradient::parse("file", r##"
+			stops = [
+				{ position = 0.0, color = "#000000" },
+				{ position = 1.0, color = "#ff8000" },
+			]
+		"##).unwrap();
+
+		assert_color(gradient.sample(1.0), Color::from_rgb(255, 128, 0));
+	}
+
+	#[test]
+	fn saved_palettes_parse_back() {
+		let gradient = Gradient::new("saved", vec![(0.0, Color::from_rgb(0, 7, 100)), (0.6425, Color::from_rgb(255, 170, 0))]);
+		let parsed = Gradient::parse("saved", &gradient.to_toml()).unwrap();
+
+		assert_eq!(parsed.stops().len(), 2);
+
+		for (&(a, a_color), &(b, b_color)) in gradient.stops().iter().zip(parsed.stops()) {
+			assert_eq!(a, b);
+			assert_color(a_color, b_color);
+		}
+	}
+
+	#[test]
+	fn parse_rejects_bad_palettes() {
+		assert!(Gradient::parse("empty", "stops = []").is_err());
+		assert!(Gradient::parse("colour", r#"stops = [{ position = 0.0, color = "red" }]"#).is_err());
+		assert!(Gradient::parse("syntax", "stops = [").is_err());
+	}
+
+	#[test]
+	fn loaded_palettes_share_the_interior() {
+		let palettes = load_palettes(Path::new(env!("CARGO_MANIFEST_DIR")).join(PALETTE_DIR).as_path(), Color::WHITE);
+
+		assert!(palettes.len() > 1);
+		assert!(palettes.iter().all(|palette| palette.interior() == Color::WHITE));
+		assert_eq!(Gradient::rainbow().interior(), Color::BLACK);
+	}
+
+	#[test]
+	fn shipped_palettes_parse() {
+		for entry in fs::read_dir(Path::new(env!("CARGO_MANIFEST_DIR")).join(PALETTE_DIR)).unwrap() {
+			let path = entry.unwrap().path();
+			let contents = fs::read_to_string(&path).unwrap();
+
+			assert!(Gradient::parse("shipped", &contents).is_ok(), "{} does not parse", path.display());
+		}
+	}
+}
@@ -0,0 +1,49 @@
+// The smallest of 1, 2 or 5 times a power of ten which is at least min_step
+pub fn nice_step(min_step: f64) -> f64 {
+	let power = 10f64.powf(min_step.log10().floor());
+
+	[1.0, 2.0, 5.0, 10.0].iter()
+		.map(|multiple| multiple * power)
+		.find(|&step| step >= min_step)
+		.unwrap_or(10.0 * power)
+}
+
+// Every multiple of step from min to max inclusive
+pub fn grid_values(min: f64, max: f64, step: f64) -> Vec<f64> {
+	let first = (min / step).ceil() as i64;
+	let last = (max / step).floor() as i64;
+
+	(first..=last).map(|i| i as f64 * step).collect()
+}
+
+// Enough decimal places to tell neighbouring grid lines apart
+pub fn label_decimals(step: f64) -> usize {
+	(-step.log10().floor()).max(0.0) as usize
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn steps_are_round_numbers() {
+		assert_eq!(nice_step(0.3), 0.5);
+		assert_eq!(nice_step(1.0), 1.0);
+		assert_eq!(nice_step(1.1), 2.0);
+		assert_eq!(nice_step(6.0), 10.0);
+		assert!((nice_step(0.00013) - 0.0002).abs() < 1e-12);
+	}
+
+	#[test]
+	fn values_cover_the_range() {
+		assert_eq!(grid_values(-1.1, 1.0, 0.5), vec![-1.0, -0.5, 0.0, 0.5, 1.0]);
+		assert!(grid_values(0.1, 0.2, 0.5).is_empty());
+	}
+
+	#[test]
+	fn decimals_follow_the_step() {
+		assert_eq!(label_decimals(5.0), 0);
+		assert_eq!(label_decimals(0.5), 1);
+		assert_eq!(label_decimals(0.002), 3);
+	}
+}
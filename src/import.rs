@@ -0,0 +1,425 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use crate::complex::Complex;
+use crate::config::Config;
+use crate::formula::Formula;
+use crate::gradient;
+use crate::library::Location;
+use crate::renderer::IterationSettings;
+use crate::selection::Region;
+use crate::session::Session;
+use crate::viewport::Viewport;
+
+// Reads lists of locations other programs and communities share, for bringing a collection over
+// into the location library. Fractint's PAR files hold whole parameter sets, of which the view,
+// iteration cap, bailout and the fractal types this viewer has are kept. CSV lists hold a center
+// and zoom per row. Anything not in the file comes from the config, as for a fresh view
+
+pub struct Import {
+	pub locations: Vec<Location>,
+	// Why each entry that couldn't be brought over was left out
+	pub skipped: Vec<String>,
+}
+
+// Fractint's images are 4:3, and its magnification fits the plane's height to the image's. Views
+// are framed on a viewport of the same shape, so what was the shorter side stays the shorter side
+const FRACTINT_ASPECT: (f64, f64) = (4.0, 3.0);
+
+// The formula and whether the type is its Julia set, which takes params as c rather than as a
+// perturbation of the starting z. Fractint's fp types are the same fractals in floating point
+fn fractint_type(name: &str) -> Option<(Formula, bool)> {
+	Some(match name.strip_suffix("fp").unwrap_or(name) {
+		"mandel" => (Formula::Mandelbrot, false),
+		"julia" => (Formula::Mandelbrot, true),
+		"mandellambda" => (Formula::Lambda, false),
+		"lambda" => (Formula::Lambda, true),
+		"magnet1m" => (Formula::MagnetOne, false),
+		"magnet1j" => (Formula::MagnetOne, true),
+		"magnet2m" => (Formula::MagnetTwo, false),
+		"magnet2j" => (Formula::MagnetTwo, true),
+		_ => return None,
+	})
+}
+
+fn numbers(value: &str) -> Result<Vec<f64>, String> {
+	value.split('/')
+		.map(|number| match number.trim().parse::<f64>() {
+			Ok(number) if number.is_finite() => Ok(number),
+			_ => Err(format!("{} isn't a number", number)),
+		})
+		.collect()
+}
+
+fn framed(region: Region) -> Viewport {
+	region.frame(&Viewport::new(FRACTINT_ASPECT.0, FRACTINT_ASPECT.1))
+}
+
+// Splits a PAR file into its entries' names, bodies and comments. Entries are "name { ... }", ';'
+// comments run to the end of the line and a '\' at the end of a line carries on to the next
+fn par_entries(text: &str) -> Vec<(String, String, Vec<String>)> {
+	let mut entries = Vec::new();
+	let mut name = String::new();
+	let mut body: Option<(String, Vec<String>)> = None;
+	let mut chars = text.chars().peekable();
+
+	while let Some(c) = chars.next() {
+		match c {
+			';' => {
+				let comment: String = chars.by_ref().take_while(|&c| c != '\n').collect();
+
+				match &mut body {
+					Some((code, comments)) => {
+						comments.push(comment.trim().to_string());
+						code.push('\n');
+					},
+					None => name.push('\n'),
+				}
+			},
+
+			'\\' if body.is_some() => {
+				while chars.next_if(|&c| c == ' ' || c == '\t').is_some() {}
+
+				chars.next_if_eq(&'\r');
+
+				if let (None, Some((code, _))) = (chars.next_if_eq(&'\n'), &mut body) {
+					code.push('\\');
+				}
+			},
+
+			'{' if body.is_none() => body = Some((String::new(), Vec::new())),
+
+			'}' if body.is_some() => {
+				let (code, comments) = body.take().unwrap();
+				let entry_name = name.split_whitespace().last().unwrap_or_default().to_string();
+
+				entries.push((entry_name, code, comments));
+				name.clear();
+			},
+
+			c => match &mut body {
+				Some((code, _)) => code.push(c),
+				None => name.push(c),
+			},
+		}
+	}
+
+	entries
+}
+
+fn par_location(name: &str, code: &str, comments: &[String], config: &Config) -> Result<Location, String> {
+	let fields: Vec<(String, &str)> = code.split_whitespace()
+		.map(|field| {
+			let (key, value) = field.split_once('=').unwrap_or((field, ""));
+			(key.to_ascii_lowercase(), value)
+		})
+		.collect();
+
+	let field = |key: &str| fields.iter().rev().find(|(name, _)| name == key).map(|&(_, value)| value);
+
+	let kind = field("type").unwrap_or("mandel").to_ascii_lowercase();
+	let (formula, is_julia) = fractint_type(&kind).ok_or_else(|| format!("type {} has no counterpart here", kind))?;
+
+	let mut settings = IterationSettings::for_formula(config, formula);
+
+	if let Some(params) = field("params") {
+		let params = numbers(params)?;
+		let c = Complex::new(params.first().copied().unwrap_or(0.0), params.get(1).copied().unwrap_or(0.0));
+
+		if is_julia {
+			settings.julia = Some(c);
+		} else {
+			settings.initial_z = c;
+		}
+	} else if is_julia {
+		settings.julia = Some(Complex::new(0.0, 0.0));
+	}
+
+	if let Some(maxiter) = field("maxiter") {
+		settings.max_iterations = numbers(maxiter)?.first().copied().filter(|&maxiter| maxiter >= 1.0).ok_or("maxiter must be at least 1")?;
+	}
+
+	// Fractint compares the squared modulus against its bailout
+	if let Some(bailout) = field("bailout") {
+		settings.bailout = numbers(bailout)?.first().copied().filter(|&bailout| bailout > 0.0).ok_or("bailout must be greater than 0")?.sqrt();
+	}
+
+	// Corners are the plane's left, right, bottom and top, any third corner skewing it is dropped.
+	// center-mag's height is 2 / mag, its width that times the aspect then divided by the x
+	// magnification. Its rotation is in degrees and its skew is dropped
+	let viewport = match (field("corners").filter(|value| !value.is_empty()), field("center-mag").filter(|value| !value.is_empty())) {
+		(_, Some(center_mag)) => {
+			let values = numbers(center_mag)?;
+
+			let [real, imaginary, magnification] = values[..values.len().min(3)] else {
+				return Err(String::from("center-mag needs a center and magnification"));
+			};
+
+			if magnification <= 0.0 {
+				return Err(String::from("center-mag's magnification must be greater than 0"));
+			}
+
+			let x_magnification = values.get(3).copied().filter(|&factor| factor > 0.0).unwrap_or(1.0);
+			let height = 2.0 / magnification;
+			let width = height * FRACTINT_ASPECT.0 / FRACTINT_ASPECT.1 / x_magnification;
+
+			let mut viewport = framed(Region {
+				real: [real - width / 2.0, real + width / 2.0],
+				imaginary: [imaginary - height / 2.0, imaginary + height / 2.0],
+			});
+
+			viewport.set_rotation(values.get(4).copied().unwrap_or(0.0).to_radians());
+			viewport
+		},
+
+		(Some(corners), None) => {
+			let values = numbers(corners)?;
+
+			let [left, right, bottom, top] = values[..values.len().min(4)] else {
+				return Err(String::from("corners needs four values"));
+			};
+
+			if left == right || bottom == top {
+				return Err(String::from("corners cover no area"));
+			}
+
+			framed(Region { real: [left.min(right), left.max(right)], imaginary: [bottom.min(top), bottom.max(top)] })
+		},
+
+		// Julia sets are centered on the origin, where the parameter planes' presets aren't
+		(None, None) if is_julia => Viewport::new(FRACTINT_ASPECT.0, FRACTINT_ASPECT.1),
+		(None, None) => formula.preset().viewport(FRACTINT_ASPECT.0, FRACTINT_ASPECT.1),
+	};
+
+	let notes: Vec<&str> = comments.iter().map(String::as_str).filter(|comment| !comment.is_empty()).collect();
+
+	Ok(Location {
+		name: name.to_string(),
+		notes: notes.join(" "),
+		saved: 0,
+		view: Session::capture(&viewport, &settings, gradient::DEFAULT_NAME),
+	})
+}
+
+pub fn parse_par(text: &str, config: &Config) -> Import {
+	let mut import = Import { locations: Vec::new(), skipped: Vec::new() };
+
+	for (name, code, comments) in par_entries(text) {
+		let name = if name.is_empty() { format!("entry {}", import.locations.len() + import.skipped.len() + 1) } else { name };
+
+		match par_location(&name, &code, &comments, config) {
+			Ok(location) => import.locations.push(location),
+			Err(e) => import.skipped.push(format!("{}: {}", name, e)),
+		}
+	}
+
+	import
+}
+
+// Fields separated by commas, or tabs when a line has no commas. Fields can be quoted to hold
+// either, with "" for a quote
+fn csv_fields(line: &str) -> Vec<String> {
+	let separator = if !line.contains(',') && line.contains('\t') { '\t' } else { ',' };
+
+	let mut fields = vec![String::new()];
+	let mut quoted = false;
+	let mut chars = line.chars().peekable();
+
+	while let Some(c) = chars.next() {
+		let field = fields.last_mut().unwrap();
+
+		match c {
+			'"' if quoted && chars.next_if_eq(&'"').is_some() => field.push('"'),
+			'"' => quoted = !quoted,
+			c if c == separator && !quoted => fields.push(String::new()),
+			c => field.push(c),
+		}
+	}
+
+	fields.into_iter().map(|field| field.trim().to_string()).collect()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Column {
+	Real,
+	Imaginary,
+	Zoom,
+	Name,
+	Notes,
+	Iterations,
+}
+
+// Headers are matched loosely, as every program names them differently
+fn column(header: &str) -> Option<Column> {
+	Some(match header.to_ascii_lowercase().replace([' ', '-'], "_").as_str() {
+		"real" | "re" | "x" | "cx" | "center_real" | "center_x" => Column::Real,
+		"imaginary" | "imag" | "im" | "y" | "cy" | "center_imaginary" | "center_y" => Column::Imaginary,
+		"zoom" | "magnification" | "mag" => Column::Zoom,
+		"name" | "title" | "label" => Column::Name,
+		"notes" | "note" | "description" | "comment" => Column::Notes,
+		"iterations" | "max_iterations" | "maxiter" => Column::Iterations,
+		_ => return None,
+	})
+}
+
+fn csv_location(columns: &[Option<Column>], fields: &[String], config: &Config) -> Result<Location, String> {
+	let field = |wanted: Column| columns.iter().position(|&column| column == Some(wanted))
+		.and_then(|i| fields.get(i))
+		.map(String::as_str)
+		.filter(|value| !value.is_empty());
+
+	let number = |wanted: Column, name: &str| -> Result<f64, String> {
+		let value = field(wanted).ok_or_else(|| format!("no {}", name))?;
+
+		match value.parse::<f64>() {
+			Ok(number) if number.is_finite() => Ok(number),
+			_ => Err(format!("{} {} isn't a number", name, value)),
+		}
+	};
+
+	let center = Complex::new(number(Column::Real, "real part")?, number(Column::Imaginary, "imaginary part")?);
+	let zoom = number(Column::Zoom, "zoom")?;
+
+	if zoom <= 0.0 {
+		return Err(String::from("zoom must be greater than 0"));
+	}
+
+	let mut settings = IterationSettings::from_config(config);
+
+	if field(Column::Iterations).is_some() {
+		settings.max_iterations = number(Column::Iterations, "iteration cap")?.max(1.0);
+	}
+
+	let mut viewport = Viewport::new(FRACTINT_ASPECT.0, FRACTINT_ASPECT.1);
+	viewport.set_center(center);
+	viewport.set_magnification(zoom);
+
+	Ok(Location {
+		name: field(Column::Name).map_or_else(|| center.to_string(), str::to_string),
+		notes: field(Column::Notes).unwrap_or_default().to_string(),
+		saved: 0,
+		view: Session::capture(&viewport, &settings, gradient::DEFAULT_NAME),
+	})
+}
+
+// Without a header row, the columns are the center's real and imaginary parts, the zoom, then an
+// optional name, notes and iteration cap. The zoom is this viewer's magnification, how many times
+// closer than the formula's default view. Lines starting with '#' are comments
+pub fn parse_csv(text: &str, config: &Config) -> Import {
+	let mut import = Import { locations: Vec::new(), skipped: Vec::new() };
+	let mut columns = [Column::Real, Column::Imaginary, Column::Zoom, Column::Name, Column::Notes, Column::Iterations].map(Some).to_vec();
+	let mut is_first = true;
+
+	for (number, line) in text.lines().enumerate().map(|(i, line)| (i + 1, line.trim())) {
+		if line.is_empty() || line.starts_with('#') {
+			continue;
+		}
+
+		let fields = csv_fields(line);
+
+		// A first row that doesn't start with a number names the columns, unknown ones are never read
+		if std::mem::take(&mut is_first) && fields[0].parse::<f64>().is_err() {
+			columns = fields.iter().map(|header| column(header)).collect();
+
+			if ![Column::Real, Column::Imaginary, Column::Zoom].iter().all(|&wanted| columns.contains(&Some(wanted))) {
+				import.skipped.push(format!("line {}: the header needs real, imaginary and zoom columns", number));
+				return import;
+			}
+
+			continue;
+		}
+
+		match csv_location(&columns, &fields, config) {
+			Ok(location) => import.locations.push(location),
+			Err(e) => import.skipped.push(format!("line {}: {}", number, e)),
+		}
+	}
+
+	import
+}
+
+// Told apart by extension, .par or .csv
+pub fn read(path: &Path, config: &Config) -> io::Result<Import> {
+	let extension = path.extension().and_then(|extension| extension.to_str()).map(str::to_ascii_lowercase);
+
+	let parse = match extension.as_deref() {
+		Some("par") => parse_par,
+		Some("csv") | Some("tsv") | Some("txt") => parse_csv,
+		_ => return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("{} isn't a .par or .csv file", path.display()))),
+	};
+
+	Ok(parse(&fs::read_to_string(path)?, config))
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const EPSILON: f64 = 1e-9;
+
+	const PAR: &str = "\
+Seahorse           { ; The valley between the cardioid and the bulb
+                     ; found in 1989
+  reset=2004 type=mandel corners=-0.76/-0.74/0.09/0.105 params=0/0
+  maxiter=500 inside=0 bailout=16 colors=000<3>zzz<10>\\
+  aaa<5>000
+  }
+
+spiral {
+  reset=2004 type=julia center-mag=0/0/2/1/30 params=-0.8/0.156
+  }
+
+plasmic { reset=2004 type=plasma }
+";
+
+	#[test]
+	fn reads_fractint_parameter_files() {
+		let import = parse_par(PAR, &Config::default());
+
+		assert_eq!(import.locations.len(), 2);
+		assert_eq!(import.skipped.len(), 1);
+		assert!(import.skipped[0].starts_with("plasmic"));
+
+		let seahorse = &import.locations[0];
+		assert_eq!(seahorse.name, "Seahorse");
+		assert_eq!(seahorse.notes, "The valley between the cardioid and the bulb found in 1989");
+		assert!((seahorse.view.center[0] + 0.75).abs() < EPSILON && (seahorse.view.center[1] - 0.0975).abs() < EPSILON);
+		assert_eq!((seahorse.view.max_iterations, seahorse.view.bailout, seahorse.view.julia), (500.0, 4.0, None));
+
+		// What was the height of the image stays so, 0.015 of the plane over the default span of 4
+		assert!((seahorse.view.magnification - 4.0 / 0.015).abs() < 1e-6);
+
+		let spiral = &import.locations[1];
+		assert_eq!(spiral.view.julia, Some([-0.8, 0.156]));
+		assert!((spiral.view.magnification - 4.0).abs() < EPSILON);
+		assert!((spiral.view.rotation - 30f64.to_radians()).abs() < EPSILON);
+	}
+
+	#[test]
+	fn reads_csv_lists() {
+		let csv = "\
+# exported from somewhere
+Name,Re,Im,Zoom,Description
+\"Elephants, left\",0.28,0.008,250,trunks
+Bad,-0.5,nope,4,
+,-0.1,0.65,1e6,
+";
+		let import = parse_csv(csv, &Config::default());
+
+		assert_eq!(import.locations.len(), 2);
+		assert_eq!(import.skipped, ["line 4: imaginary part nope isn't a number"]);
+
+		let elephants = &import.locations[0];
+		assert_eq!((elephants.name.as_str(), elephants.notes.as_str()), ("Elephants, left", "trunks"));
+		assert_eq!((elephants.view.center, elephants.view.magnification), ([0.28, 0.008], 250.0));
+
+		// Unnamed rows are named by their center
+		assert_eq!(import.locations[1].name, Complex::new(-0.1, 0.65).to_string());
+
+		// Without a header the columns come in order
+		let import = parse_csv("-0.75\t0.1\t40\tvalley\t\t800", &Config::default());
+		assert_eq!(import.locations[0].name, "valley");
+		assert_eq!(import.locations[0].view.max_iterations, 800.0);
+	}
+}
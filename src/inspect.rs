@@ -0,0 +1,204 @@
+use ggez::graphics::Color;
+
+use crate::complex::Complex;
+use crate::gradient;
+use crate::renderer::{Frame, IterationSettings};
+
+// Everything that went into one pixel of a frame, for tracking down colouring and precision
+// problems. The orbit is iterated again from the pixel's center, as frames only keep escape counts
+
+pub struct Inspection {
+	pub pixel: (usize, usize),
+	pub point: Complex,
+
+	// The orbit from the pixel's center, None where it stayed bounded up to the cap
+	pub iterations: Option<f64>,
+	pub converged: bool,
+	pub final_z: Complex,
+	// Fractional escape count, which smooth colouring would use. Assumes the step squares z, as most
+	// of the formulas do, and is None for orbits that didn't escape past the bailout
+	pub smooth: Option<f64>,
+
+	// What the frame holds for the pixel, over all of its samples
+	pub samples: usize,
+	pub bounded_samples: usize,
+	pub mean_iterations: Option<f64>,
+	pub distance: Option<f64>,
+	// Where in the palette the mean falls, 0 to 1 before clamping, and which palette that is
+	pub palette_position: Option<f64>,
+	pub palette: String,
+	pub color: Color,
+}
+
+// The same loop as the renderer's, keeping the last z
+fn orbit(point: Complex, settings: IterationSettings) -> (Option<f64>, bool, Complex) {
+	let (mut z, c) = match settings.julia {
+		Some(c) => (point, c),
+		None => (settings.formula.critical_point() + settings.initial_z, point),
+	};
+
+	let formula = settings.formula;
+	let bailout_sqr = settings.bailout * settings.bailout;
+	let mut iterations = 0.0;
+
+	while z.norm_sqr() < bailout_sqr {
+		if iterations > settings.max_iterations {
+			return (None, false, z);
+		}
+
+		iterations += 1.0;
+
+		let previous = z;
+		z = formula.iterate(z, c);
+
+		if formula.has_converged(previous, z) {
+			return (Some(iterations), true, z);
+		}
+	}
+
+	(Some(iterations), false, z)
+}
+
+// n + 1 - log2(ln |z| / ln bailout), continuous across iteration bands
+fn smooth_iterations(iterations: f64, z: Complex, bailout: f64) -> Option<f64> {
+	let log_z = z.norm_sqr().ln() / 2.0;
+
+	if log_z <= 0.0 || bailout <= 1.0 {
+		return None;
+	}
+
+	Some(iterations + 1.0 - (log_z / bailout.ln()).log2())
+}
+
+// None outside the frame
+pub fn inspect(frame: &Frame, x: usize, y: usize) -> Option<Inspection> {
+	if x >= frame.width || y >= frame.height {
+		return None;
+	}
+
+	let point = frame.viewport.pixel_to_complex(x as f64, y as f64);
+	let settings = frame.settings;
+	let (iterations, converged, final_z) = orbit(point, settings);
+
+	let smooth = match iterations {
+		Some(iterations) if !converged => smooth_iterations(iterations, final_z, settings.bailout),
+		_ => None,
+	};
+
+	let pixel = y * frame.width + x;
+	let samples_per_pixel = frame.samples * frame.samples;
+	let samples = pixel * samples_per_pixel..(pixel + 1) * samples_per_pixel;
+
+	let mean_iterations = frame.mean_iterations_in(pixel..pixel + 1).next().flatten();
+	let bounded_samples = frame.iterations[samples.clone()].iter().filter(|sample| sample.is_none()).count();
+
+	let distances: Vec<f64> = frame.distance[samples.clone()].iter().flatten().copied().collect();
+	let distance = (!distances.is_empty()).then(|| distances.iter().sum::<f64>() / distances.len() as f64);
+
+	// Converged orbits run the palette backwards, and odd bands take the stripe palette
+	let [low, high] = frame.palette_range.unwrap_or([0.0, settings.max_iterations]);
+	let any_converged = frame.converged[samples].iter().any(|&converged| converged);
+
+	let palette_position = mean_iterations.map(|mean| {
+		let t = (mean - low) / (high - low);
+		if any_converged { 1.0 - t } else { t }
+	});
+
+	let palette = match (&frame.stripes, mean_iterations) {
+		(Some(stripes), Some(mean)) if mean.floor().rem_euclid(2.0) == 1.0 => stripes.name.clone(),
+		_ => frame.gradient.name.clone(),
+	};
+
+	Some(Inspection {
+		pixel: (x, y),
+		point,
+		iterations,
+		converged,
+		final_z,
+		smooth,
+		samples: samples_per_pixel,
+		bounded_samples,
+		mean_iterations,
+		distance,
+		palette_position,
+		palette,
+		color: frame.pixels[pixel],
+	})
+}
+
+// Fields that may be missing, such as the escape count of a bounded orbit
+pub fn or_dash(value: Option<f64>, precision: usize) -> String {
+	value.map_or_else(|| String::from("-"), |value| format!("{:.*}", precision, value))
+}
+
+impl Inspection {
+	// What the orbit did, as one word
+	pub fn outcome(&self) -> &'static str {
+		match (self.iterations, self.converged) {
+			(None, _) => "bounded",
+			(Some(_), true) => "converged",
+			(Some(_), false) => "escaped",
+		}
+	}
+
+	// For printing, one field per line
+	pub fn describe(&self) -> String {
+		[
+			format!("pixel          {}, {}", self.pixel.0, self.pixel.1),
+			format!("point          {:.17}", self.point),
+			format!("orbit          {} after {}", self.outcome(), or_dash(self.iterations, 0)),
+			format!("final z        {:.17}", self.final_z),
+			format!("smooth         {}", or_dash(self.smooth, 6)),
+			format!("frame samples  {}, {} bounded, mean {}", self.samples, self.bounded_samples, or_dash(self.mean_iterations, 3)),
+			format!("distance       {}", or_dash(self.distance, 3)),
+			format!("palette        {} at {}", self.palette, or_dash(self.palette_position, 6)),
+			format!("colour         {}", gradient::to_hex(self.color)),
+		].join("\n")
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use crate::renderer;
+
+	fn frame() -> Frame {
+		renderer::test_frame((20, 16), Complex::new(-0.75, 0.1), 4.0, 200.0)
+	}
+
+	#[test]
+	fn agrees_with_the_render() {
+		let frame = frame();
+
+		for y in 0..frame.height {
+			for x in 0..frame.width {
+				let inspection = inspect(&frame, x, y).unwrap();
+				let stored = frame.iterations[y * frame.width + x];
+
+				assert_eq!(inspection.iterations, stored, "pixel {}, {}", x, y);
+				assert_eq!(inspection.mean_iterations, stored);
+				assert_eq!(inspection.bounded_samples, stored.is_none() as usize);
+				assert_eq!(inspection.color, frame.pixels[y * frame.width + x]);
+
+				// Escaped orbits end past the bailout, and smoothing moves them by less than a band
+				if let (Some(iterations), Some(smooth)) = (inspection.iterations, inspection.smooth) {
+					assert!(inspection.final_z.norm_sqr() >= 4.0);
+					assert!(smooth > iterations - 1.0 && smooth <= iterations + 1.0);
+				}
+			}
+		}
+
+		assert!(inspect(&frame, frame.width, 0).is_none());
+	}
+
+	#[test]
+	fn describes_every_field() {
+		let inspection = inspect(&frame(), 0, 0).unwrap();
+		let description = inspection.describe();
+
+		assert_eq!(description.lines().count(), 9);
+		assert!(description.contains(inspection.outcome()));
+		assert!(description.contains(&gradient::to_hex(inspection.color)));
+	}
+}
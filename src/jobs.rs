@@ -0,0 +1,179 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::memory::MemoryBudget;
+
+// Exports running on background threads, so closing the window can ask first rather than cutting
+// hours of rendering short, and can ask them to stop between frames when it does close
+
+// Of the budget, taken by every job however many others are running. What a job renders with goes
+// into its checkpoint, so an interrupted export only resumes if it has the same budget the second
+// time. More than this many jobs at once go over the budget between them
+const JOB_SHARE: f64 = 0.25;
+
+struct Running {
+	id: u64,
+	kind: String,
+	cancelled: Arc<AtomicBool>,
+}
+
+#[derive(Default)]
+struct State {
+	running: Vec<Running>,
+	next_id: u64,
+}
+
+#[derive(Clone)]
+pub struct Jobs {
+	state: Arc<(Mutex<State>, Condvar)>,
+	budget: MemoryBudget,
+}
+
+// Held by the thread doing the work, which stops being listed as running when it's dropped
+pub struct Job {
+	id: u64,
+	cancelled: Arc<AtomicBool>,
+	budget: MemoryBudget,
+	jobs: Jobs,
+}
+
+impl Jobs {
+	// The budget is what the jobs running at once share
+	pub fn new(budget: MemoryBudget) -> Jobs {
+		Jobs { state: Arc::default(), budget }
+	}
+
+	// The kind names the job to the user, such as "animation"
+	pub fn start(&self, kind: &str) -> Job {
+		let (state, _) = &*self.state;
+		let mut state = state.lock().unwrap();
+
+		let id = state.next_id;
+		let cancelled = Arc::new(AtomicBool::new(false));
+
+		state.next_id += 1;
+		state.running.push(Running { id, kind: kind.to_string(), cancelled: Arc::clone(&cancelled) });
+
+		Job { id, cancelled, budget: self.budget.share(JOB_SHARE), jobs: self.clone() }
+	}
+
+	// Kinds of the jobs still running, oldest first
+	pub fn running(&self) -> Vec<String> {
+		self.state.0.lock().unwrap().running.iter().map(|job| job.kind.clone()).collect()
+	}
+
+	pub fn is_idle(&self) -> bool {
+		self.state.0.lock().unwrap().running.is_empty()
+	}
+
+	// Jobs notice on their own time, usually after the frame they're on
+	pub fn cancel_all(&self) {
+		for job in &self.state.0.lock().unwrap().running {
+			job.cancelled.store(true, Ordering::Relaxed);
+		}
+	}
+
+	// Returns whether every job finished within the timeout
+	pub fn wait(&self, timeout: Duration) -> bool {
+		let (state, finished) = &*self.state;
+		let deadline = Instant::now() + timeout;
+		let mut state = state.lock().unwrap();
+
+		while !state.running.is_empty() {
+			let Some(remaining) = deadline.checked_duration_since(Instant::now()) else {
+				return false;
+			};
+
+			state = finished.wait_timeout(state, remaining).unwrap().0;
+		}
+
+		true
+	}
+}
+
+impl Job {
+	// Shared with whatever does the work, for checking between steps
+	pub fn cancelled(&self) -> Arc<AtomicBool> {
+		Arc::clone(&self.cancelled)
+	}
+
+	pub fn budget(&self) -> MemoryBudget {
+		self.budget
+	}
+}
+
+impl Drop for Job {
+	fn drop(&mut self) {
+		let (state, finished) = &*self.jobs.state;
+
+		// A job ending in a panic still has to come off the list
+		let mut state = state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+		state.running.retain(|job| job.id != self.id);
+
+		finished.notify_all();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::thread;
+
+	#[test]
+	fn jobs_are_listed_until_dropped() {
+		let jobs = Jobs::new(MemoryBudget::UNLIMITED);
+		assert!(jobs.is_idle());
+
+		let animation = jobs.start("animation");
+		let wallpaper = jobs.start("wallpaper");
+		assert_eq!(jobs.running(), ["animation", "wallpaper"]);
+
+		drop(animation);
+		assert_eq!(jobs.running(), ["wallpaper"]);
+		assert!(!jobs.wait(Duration::from_millis(10)));
+
+		drop(wallpaper);
+		assert!(jobs.is_idle() && jobs.wait(Duration::ZERO));
+	}
+
+	#[test]
+	fn cancelled_jobs_stop_and_are_waited_for() {
+		let jobs = Jobs::new(MemoryBudget::UNLIMITED);
+		let job = jobs.start("animation");
+		let cancelled = job.cancelled();
+
+		let worker = thread::spawn(move || {
+			let _job = job;
+
+			while !cancelled.load(Ordering::Relaxed) {
+				thread::sleep(Duration::from_millis(1));
+			}
+		});
+
+		jobs.cancel_all();
+
+		assert!(jobs.wait(Duration::from_secs(10)));
+		assert!(jobs.is_idle());
+
+		worker.join().unwrap();
+	}
+
+	#[test]
+	fn jobs_get_the_same_budget_however_many_run() {
+		let jobs = Jobs::new(MemoryBudget::megabytes(100.0));
+		let megabytes = |job: &Job| job.budget().bytes() / (1024 * 1024);
+
+		let animation = jobs.start("animation");
+		assert_eq!(megabytes(&animation), 25);
+
+		// Run again after an interruption while others are running, it renders the same as before
+		drop(animation);
+
+		let morph = jobs.start("morph");
+		let wallpaper = jobs.start("wallpaper");
+		let animation = jobs.start("animation");
+		assert_eq!((megabytes(&morph), megabytes(&wallpaper), megabytes(&animation)), (25, 25, 25));
+	}
+}
@@ -0,0 +1,100 @@
+use std::sync::Arc;
+
+use ggez::graphics::{Canvas, Color, DrawMode, DrawParam, Image, ImageFormat, Mesh, Rect};
+use ggez::mint::Point2;
+use ggez::{Context, GameResult as Result};
+
+use mandelbrot_viewer::complex::Complex;
+use mandelbrot_viewer::export;
+use mandelbrot_viewer::formula::Formula;
+use mandelbrot_viewer::gradient::Gradient;
+use mandelbrot_viewer::queue::Priority;
+use mandelbrot_viewer::renderer::{IterationSettings, RenderJob, Renderer};
+use mandelbrot_viewer::viewport::Viewport;
+
+pub const SIZE: usize = 128;
+
+// Few enough to keep up with the cursor, the shape is what matters at this size
+const MAX_ITERATIONS: f64 = 64.0;
+
+const BORDER_COLOR: Color = Color::new(1.0, 1.0, 1.0, 0.7);
+
+// A thumbnail of the Julia set for the point under the cursor, rendered on its own thread so the
+// view never waits on it. Only one render is in flight, the newest point is picked up after it
+pub struct JuliaPreview {
+	renderer: Renderer,
+	image: Option<Image>,
+	size: (usize, usize),
+	// Iteration cap, the view's own when None
+	max_iterations: Option<f64>,
+	// The formula, constant and size of the render in flight, or of the image when nothing is
+	constant: Option<(Formula, Complex, (usize, usize))>,
+}
+
+impl JuliaPreview {
+	pub fn new() -> JuliaPreview {
+		JuliaPreview::with_size((SIZE, SIZE), Some(MAX_ITERATIONS))
+	}
+
+	// A larger pane, as the linked view shows beside the Mandelbrot set
+	pub fn with_size(size: (usize, usize), max_iterations: Option<f64>) -> JuliaPreview {
+		JuliaPreview {
+			renderer: Renderer::new(),
+			image: None,
+			size,
+			max_iterations,
+			constant: None,
+		}
+	}
+
+	// Renders again at the new size on the next request
+	pub fn resize(&mut self, size: (usize, usize)) {
+		self.size = (size.0.max(1), size.1.max(1));
+	}
+
+	// The view's settings give the formula and bailout
+	pub fn request(&mut self, c: Complex, view: IterationSettings, gradient: Arc<Gradient>) {
+		if !self.renderer.is_idle() || self.constant == Some((view.formula, c, self.size)) {
+			return;
+		}
+
+		let (width, height) = self.size;
+		let settings = IterationSettings { julia: Some(c), max_iterations: self.max_iterations.unwrap_or(view.max_iterations), ..view };
+
+		self.renderer.submit(RenderJob {
+			priority: Priority::Interactive,
+			..RenderJob::new(width, height, 1, Viewport::new(width as f64, height as f64), settings, gradient)
+		});
+
+		self.constant = Some((view.formula, c, self.size));
+	}
+
+	// Picks up a finished render, returning whether there's a new image to draw
+	pub fn update(&mut self, context: &Context) -> bool {
+		// Progress isn't shown, but has to be drained
+		self.renderer.poll_tiles();
+
+		let Some(Ok(frame)) = self.renderer.poll().pop() else {
+			return false;
+		};
+
+		let bytes = export::rgba_bytes(&frame.pixels, 8);
+		self.image = Some(Image::from_pixels(context, &bytes, ImageFormat::Rgba8UnormSrgb, frame.width as u32, frame.height as u32));
+
+		true
+	}
+
+	// The top left corner goes at the position
+	pub fn draw(&self, canvas: &mut Canvas, context: &Context, position: Point2<f32>) -> Result {
+		let Some(image) = &self.image else {
+			return Ok(());
+		};
+
+		canvas.draw(image, DrawParam::new().dest(position));
+
+		let border = Rect::new(position.x, position.y, image.width() as f32, image.height() as f32);
+		canvas.draw(&Mesh::new_rectangle(context, DrawMode::stroke(1.0), border, BORDER_COLOR)?, DrawParam::new());
+
+		Ok(())
+	}
+}
@@ -0,0 +1,47 @@
+// The rendering core, shared by the viewer and anything embedding it
+
+pub mod accumulation;
+pub mod affinity;
+pub mod animation;
+pub mod attract;
+pub mod backend;
+pub mod bench;
+pub mod build_info;
+pub mod capture;
+pub mod checkpoint;
+pub mod checksum;
+pub mod complex;
+pub mod config;
+pub mod contours;
+pub mod diff;
+pub mod display;
+pub mod distance;
+pub mod eta;
+pub mod export;
+pub mod formula;
+pub mod gradient;
+pub mod grid;
+pub mod import;
+pub mod inspect;
+pub mod jobs;
+pub mod library;
+pub mod locale;
+pub mod memory;
+#[cfg(feature = "opencl")]
+pub mod opencl;
+pub mod outlines;
+pub mod palette_editor;
+pub mod postprocess;
+pub mod quality;
+pub mod queue;
+pub mod random_palette;
+pub mod recording;
+pub mod rays;
+pub mod renderer;
+pub mod selection;
+pub mod selftest;
+pub mod session;
+pub mod stats;
+pub mod tiles;
+pub mod viewport;
+pub mod wallpaper;
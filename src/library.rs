@@ -0,0 +1,338 @@
+use std::fs::{self, File};
+use std::io;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ggez::graphics::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::export;
+use crate::session::Session;
+
+// Saved locations, each a view with a name, notes and a thumbnail of how it looked. Every location
+// is a pair of files in the library's directory, <id>.toml and the thumbnail <id>.png, so they can be
+// copied between machines or deleted by hand
+
+pub const THUMBNAIL_SIZE: (usize, usize) = (160, 120);
+
+// Ids longer than this are cut short, the name keeps the rest
+const MAX_ID_LENGTH: usize = 40;
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Location {
+	pub name: String,
+	#[serde(default)]
+	pub notes: String,
+	// Seconds since the Unix epoch, the library is listed oldest first
+	#[serde(default)]
+	pub saved: u64,
+	pub view: Session,
+}
+
+pub struct Entry {
+	// The file name both of the location's files share
+	pub id: String,
+	pub location: Location,
+}
+
+pub struct Library {
+	dir: PathBuf,
+	entries: Vec<Entry>,
+}
+
+// Lowercase letters and digits joined by dashes, safe as a file name everywhere
+fn slug(name: &str) -> String {
+	let words: Vec<String> = name.split(|c: char| !c.is_ascii_alphanumeric())
+		.filter(|word| !word.is_empty())
+		.map(|word| word.to_ascii_lowercase())
+		.collect();
+
+	let mut slug = words.join("-");
+	slug.truncate(MAX_ID_LENGTH);
+
+	match slug.trim_end_matches('-') {
+		"" => String::from("location"),
+		slug => slug.to_string(),
+	}
+}
+
+fn now() -> u64 {
+	SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |duration| duration.as_secs())
+}
+
+// The source pixels the i-th of count thumbnail pixels covers along one side, at least one
+fn block(i: usize, count: usize, length: usize, start: usize) -> Range<usize> {
+	let from = i * length / count;
+	start + from..start + ((i + 1) * length / count).max(from + 1)
+}
+
+// Cropped to the thumbnail's shape about the middle, then each thumbnail pixel averages the block of
+// pixels it covers
+pub fn thumbnail(pixels: &[Color], (width, height): (usize, usize)) -> Vec<Color> {
+	let (thumbnail_width, thumbnail_height) = THUMBNAIL_SIZE;
+
+	let (crop_width, crop_height) = match width * thumbnail_height > height * thumbnail_width {
+		true => ((height * thumbnail_width / thumbnail_height).max(1), height),
+		false => (width, (width * thumbnail_height / thumbnail_width).max(1)),
+	};
+
+	let (left, top) = ((width - crop_width) / 2, (height - crop_height) / 2);
+	let mut scaled = Vec::with_capacity(thumbnail_width * thumbnail_height);
+
+	for y in 0..thumbnail_height {
+		let rows = block(y, thumbnail_height, crop_height, top);
+
+		for x in 0..thumbnail_width {
+			let columns = block(x, thumbnail_width, crop_width, left);
+			let mut total = Color::new(0.0, 0.0, 0.0, 0.0);
+
+			for row in rows.clone() {
+				for &color in &pixels[row * width + columns.start..row * width + columns.end] {
+					total.r += color.r;
+					total.g += color.g;
+					total.b += color.b;
+					total.a += color.a;
+				}
+			}
+
+			let count = (rows.len() * columns.len()) as f32;
+			scaled.push(Color::new(total.r / count, total.g / count, total.b / count, total.a / count));
+		}
+	}
+
+	scaled
+}
+
+// 8 bit RGBA, as the thumbnails are written, for drawing straight from
+pub fn load_thumbnail(path: &Path) -> io::Result<(Vec<u8>, (usize, usize))> {
+	let mut decoder = png::Decoder::new(File::open(path)?);
+	decoder.set_transformations(png::Transformations::normalize_to_color8());
+
+	let mut reader = decoder.read_info()?;
+	let mut bytes = vec![0; reader.output_buffer_size()];
+	let info = reader.next_frame(&mut bytes)?;
+
+	if info.color_type != png::ColorType::Rgba {
+		return Err(io::Error::new(io::ErrorKind::InvalidData, format!("{} isn't an RGBA image", path.display())));
+	}
+
+	bytes.truncate(info.buffer_size());
+
+	Ok((bytes, (info.width as usize, info.height as usize)))
+}
+
+impl Library {
+	pub fn default_dir() -> Option<PathBuf> {
+		dirs::data_dir().map(|dir| dir.join("mandelbrot_viewer").join("locations"))
+	}
+
+	// A missing directory is an empty library, and is only made once a location is saved. Locations
+	// that can't be read are reported and left out rather than failing the rest
+	pub fn open(dir: &Path) -> Library {
+		let mut entries = Vec::new();
+
+		let files = match fs::read_dir(dir) {
+			Ok(files) => files.flatten().map(|file| file.path()).collect(),
+			Err(e) if e.kind() == io::ErrorKind::NotFound => Vec::new(),
+			Err(e) => {
+				eprintln!("could not read the location library {}: {}", dir.display(), e);
+				Vec::new()
+			},
+		};
+
+		for path in files.into_iter().filter(|path| path.extension().is_some_and(|extension| extension == "toml")) {
+			let Some(id) = path.file_stem().and_then(|stem| stem.to_str()) else {
+				continue;
+			};
+
+			let location = fs::read_to_string(&path)
+				.map_err(|e| e.to_string())
+				.and_then(|contents| toml::from_str::<Location>(&contents).map_err(|e| e.to_string()));
+
+			match location {
+				Ok(location) => entries.push(Entry { id: id.to_string(), location }),
+				Err(e) => eprintln!("could not read location {}: {}", path.display(), e),
+			}
+		}
+
+		entries.sort_by(|a, b| (a.location.saved, &a.id).cmp(&(b.location.saved, &b.id)));
+
+		Library { dir: dir.to_path_buf(), entries }
+	}
+
+	pub fn dir(&self) -> &Path {
+		&self.dir
+	}
+
+	pub fn entries(&self) -> &[Entry] {
+		&self.entries
+	}
+
+	pub fn thumbnail_path(&self, index: usize) -> PathBuf {
+		self.dir.join(format!("{}.png", self.entries[index].id))
+	}
+
+	fn location_path(&self, id: &str) -> PathBuf {
+		self.dir.join(format!("{}.toml", id))
+	}
+
+	// Written beside and renamed over the old file, like the session
+	fn write(&self, index: usize) -> io::Result<()> {
+		let entry = &self.entries[index];
+		let path = self.location_path(&entry.id);
+
+		let contents = toml::to_string(&entry.location).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+		let temporary = path.with_extension("toml.partial");
+
+		fs::write(&temporary, contents)?;
+		fs::rename(&temporary, path)
+	}
+
+	// The id comes from the name, numbered when another location already has it. The pixels are of
+	// the view as shown, and are shrunk to the thumbnail. Returns the new entry's index
+	pub fn add(&mut self, name: &str, notes: &str, view: Session, pixels: &[Color], size: (usize, usize)) -> io::Result<usize> {
+		fs::create_dir_all(&self.dir)?;
+
+		let base = slug(name);
+		let taken = |id: &str| self.entries.iter().any(|entry| entry.id == id) || self.location_path(id).exists();
+
+		let id = (1..)
+			.map(|n| if n == 1 { base.clone() } else { format!("{}-{}", base, n) })
+			.find(|id| !taken(id))
+			.unwrap();
+
+		let location = Location { name: name.to_string(), notes: notes.to_string(), saved: now(), view };
+
+		if size.0 > 0 && size.1 > 0 {
+			export::save_png(&self.dir.join(format!("{}.png", id)), &thumbnail(pixels, size), THUMBNAIL_SIZE, 8, &location.view)?;
+		}
+
+		self.entries.push(Entry { id, location });
+		let index = self.entries.len() - 1;
+
+		if let Err(e) = self.write(index) {
+			let entry = self.entries.pop().unwrap();
+			let _ = fs::remove_file(self.dir.join(format!("{}.png", entry.id)));
+
+			return Err(e);
+		}
+
+		Ok(index)
+	}
+
+	// The id stays the same, so renaming doesn't move the files
+	pub fn update(&mut self, index: usize, name: &str, notes: &str) -> io::Result<()> {
+		let previous = self.entries[index].location.clone();
+
+		self.entries[index].location.name = name.to_string();
+		self.entries[index].location.notes = notes.to_string();
+
+		let result = self.write(index);
+
+		if result.is_err() {
+			self.entries[index].location = previous;
+		}
+
+		result
+	}
+
+	// A missing thumbnail isn't an error, the location may have been saved without one
+	pub fn remove(&mut self, index: usize) -> io::Result<()> {
+		fs::remove_file(self.location_path(&self.entries[index].id))?;
+
+		match fs::remove_file(self.thumbnail_path(index)) {
+			Err(e) if e.kind() != io::ErrorKind::NotFound => eprintln!("could not remove thumbnail {}: {}", self.thumbnail_path(index).display(), e),
+			_ => {},
+		}
+
+		self.entries.remove(index);
+
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use crate::formula::Formula;
+	use crate::viewport::IDENTITY_SKEW;
+
+	fn session(real: f64) -> Session {
+		Session {
+			center: [real, 0.1],
+			magnification: 40.0,
+			rotation: 0.0,
+			skew: IDENTITY_SKEW,
+			inverted: false,
+			formula: Formula::Mandelbrot,
+			bailout: 2.0,
+			initial_z: [0.0, 0.0],
+			julia: None,
+			max_iterations: 500.0,
+			palette: String::from("rainbow"),
+		}
+	}
+
+	#[test]
+	fn slugs_are_safe_file_names() {
+		assert_eq!(slug("Seahorse Valley"), "seahorse-valley");
+		assert_eq!(slug("  spiral #2 (deep!) "), "spiral-2-deep");
+		assert_eq!(slug("毛"), "location");
+		assert!(slug(&"a".repeat(100)).len() <= MAX_ID_LENGTH);
+	}
+
+	#[test]
+	fn thumbnails_are_cropped_to_shape() {
+		// Left half black and right half white, wider than the thumbnail so the sides are cropped
+		let (width, height) = (400, 120);
+		let pixels: Vec<Color> = (0..width * height).map(|i| if i % width < width / 2 { Color::BLACK } else { Color::WHITE }).collect();
+
+		let thumbnail = thumbnail(&pixels, (width, height));
+		assert_eq!(thumbnail.len(), THUMBNAIL_SIZE.0 * THUMBNAIL_SIZE.1);
+
+		assert_eq!(thumbnail[0], Color::BLACK);
+		assert_eq!(thumbnail[THUMBNAIL_SIZE.0 - 1], Color::WHITE);
+
+		// Smaller images are stretched up
+		assert_eq!(super::thumbnail(&[Color::WHITE; 4], (2, 2)).len(), THUMBNAIL_SIZE.0 * THUMBNAIL_SIZE.1);
+	}
+
+	#[test]
+	fn locations_survive_reopening() {
+		let dir = std::env::temp_dir().join(format!("mandelbrot_viewer_library_{}", std::process::id()));
+		let _ = fs::remove_dir_all(&dir);
+
+		let mut library = Library::open(&dir);
+		assert!(library.entries().is_empty());
+
+		let pixels = vec![Color::new(0.2, 0.4, 0.6, 1.0); 32 * 24];
+		let first = library.add("Seahorse valley", "", session(-0.75), &pixels, (32, 24)).unwrap();
+		let second = library.add("Seahorse Valley", "the other one", session(-0.74), &pixels, (32, 24)).unwrap();
+
+		assert_eq!(library.entries()[first].id, "seahorse-valley");
+		assert_eq!(library.entries()[second].id, "seahorse-valley-2");
+
+		library.update(first, "Seahorses", "tails at 200 iterations").unwrap();
+
+		let (bytes, size) = load_thumbnail(&library.thumbnail_path(second)).unwrap();
+		assert_eq!(size, THUMBNAIL_SIZE);
+		assert_eq!(bytes.len(), size.0 * size.1 * 4);
+		assert!(export::load_view(&library.thumbnail_path(second)).unwrap() == session(-0.74));
+
+		let reopened = Library::open(&dir);
+		let names: Vec<&str> = reopened.entries().iter().map(|entry| entry.location.name.as_str()).collect();
+
+		assert_eq!(names, ["Seahorses", "Seahorse Valley"]);
+		assert_eq!(reopened.entries()[0].location.notes, "tails at 200 iterations");
+		assert!(reopened.entries()[1].location.view == session(-0.74));
+
+		let mut library = reopened;
+		library.remove(0).unwrap();
+		assert_eq!(Library::open(&dir).entries().len(), 1);
+		assert!(!dir.join("seahorse-valley.png").exists());
+
+		fs::remove_dir_all(&dir).unwrap();
+	}
+}
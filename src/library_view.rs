@@ -0,0 +1,319 @@
+use std::collections::HashMap;
+
+use ggez::graphics::{Canvas, Color, DrawMode, DrawParam, Image, ImageFormat, Mesh, Rect, Text};
+use ggez::input::keyboard::KeyMods;
+use ggez::mint::Point2;
+use ggez::winit::event::VirtualKeyCode;
+use ggez::{Context, GameResult as Result};
+
+use mandelbrot_viewer::library::{self, Library, THUMBNAIL_SIZE};
+use mandelbrot_viewer::locale::Locale;
+use mandelbrot_viewer::session::Session;
+
+use crate::overlay;
+
+const BACKGROUND_COLOR: Color = Color::new(0.05, 0.05, 0.05, 0.92);
+const MISSING_COLOR: Color = Color::new(0.3, 0.3, 0.3, 1.0);
+const NAME_COLOR: Color = Color::new(1.0, 1.0, 1.0, 0.9);
+
+// Space around each thumbnail, and below it for the name
+const SPACING: f32 = 10.0;
+const NAME_HEIGHT: f32 = 20.0;
+const NAME_SIZE: f32 = 13.0;
+// Longer names are cut short under the thumbnail, the footer shows them whole
+const MAX_NAME_LENGTH: usize = 20;
+
+// Room along the bottom for the selected location and the keys
+const FOOTER_HEIGHT: f32 = 110.0;
+
+const CELL_WIDTH: f32 = THUMBNAIL_SIZE.0 as f32 + SPACING;
+const CELL_HEIGHT: f32 = THUMBNAIL_SIZE.1 as f32 + NAME_HEIGHT + SPACING;
+
+// What the viewer should do after input to the library
+pub enum Action {
+	Nothing,
+	Close,
+	Go(Session),
+	// The name to save the current view under
+	Save(String),
+}
+
+#[derive(Clone, Copy)]
+enum Field {
+	New,
+	Name,
+	Notes,
+}
+
+// Columns across the window, visible rows and the row at the top, which scrolls to keep the
+// selection in view
+struct Layout {
+	columns: usize,
+	first_row: usize,
+	rows: usize,
+}
+
+// Saved locations as a grid of thumbnails over the view, drawn with the same overlay panels as the
+// rest of the viewer. Thumbnails are loaded the first time they're drawn
+pub struct LibraryView {
+	library: Library,
+	selected: usize,
+	// None for thumbnails that couldn't be read, so they aren't tried every frame
+	thumbnails: HashMap<String, Option<Image>>,
+	prompt: Option<(Field, String)>,
+}
+
+impl LibraryView {
+	pub fn new(library: Library) -> LibraryView {
+		let selected = library.entries().len().saturating_sub(1);
+
+		LibraryView { library, selected, thumbnails: HashMap::new(), prompt: None }
+	}
+
+	fn layout(&self, (width, height): (f32, f32)) -> Layout {
+		let columns = ((width - SPACING) / CELL_WIDTH).floor().max(1.0) as usize;
+		let rows = ((height - SPACING - FOOTER_HEIGHT) / CELL_HEIGHT).floor().max(1.0) as usize;
+
+		Layout { columns, first_row: (self.selected / columns + 1).saturating_sub(rows), rows }
+	}
+
+	fn cell(layout: &Layout, index: usize) -> Option<Point2<f32>> {
+		let row = (index / layout.columns).checked_sub(layout.first_row).filter(|&row| row < layout.rows)?;
+		let column = index % layout.columns;
+
+		Some(Point2 { x: SPACING + column as f32 * CELL_WIDTH, y: SPACING + row as f32 * CELL_HEIGHT })
+	}
+
+	// Saves the view under the name, selecting it
+	pub fn add(&mut self, name: &str, view: Session, pixels: &[Color], size: (usize, usize)) {
+		match self.library.add(name, "", view, pixels, size) {
+			Ok(index) => self.selected = index,
+			Err(e) => eprintln!("could not save location {} to {}: {}", name, self.library.dir().display(), e),
+		}
+	}
+
+	fn confirm(&mut self, field: Field, text: String) -> Action {
+		if let Field::New = field {
+			return match text.trim() {
+				"" => Action::Nothing,
+				name => Action::Save(name.to_string()),
+			};
+		}
+
+		let Some(entry) = self.library.entries().get(self.selected) else {
+			return Action::Nothing;
+		};
+
+		let (name, notes) = match field {
+			Field::Name if !text.trim().is_empty() => (text.trim().to_string(), entry.location.notes.clone()),
+			Field::Notes => (entry.location.name.clone(), text.trim().to_string()),
+			_ => return Action::Nothing,
+		};
+
+		if let Err(e) = self.library.update(self.selected, &name, &notes) {
+			eprintln!("could not update location {}: {}", name, e);
+		}
+
+		Action::Nothing
+	}
+
+	fn edit_prompt(&mut self, keycode: VirtualKeyCode) -> Action {
+		let Some((field, text)) = &mut self.prompt else {
+			return Action::Nothing;
+		};
+
+		match keycode {
+			VirtualKeyCode::Back => {
+				text.pop();
+			},
+
+			VirtualKeyCode::Escape => self.prompt = None,
+
+			VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => {
+				let (field, text) = (*field, std::mem::take(text));
+				self.prompt = None;
+
+				return self.confirm(field, text);
+			},
+
+			_ => {},
+		}
+
+		Action::Nothing
+	}
+
+	// Every key goes to the library while it's open. Renaming and notes are on keys which type
+	// nothing, so the key opening a prompt doesn't end up in it
+	pub fn key(&mut self, keycode: VirtualKeyCode, mods: KeyMods, window_size: (f32, f32)) -> Action {
+		if self.prompt.is_some() {
+			return self.edit_prompt(keycode);
+		}
+
+		let count = self.library.entries().len();
+		let columns = self.layout(window_size).columns;
+		let selected = self.library.entries().get(self.selected);
+
+		match keycode {
+			VirtualKeyCode::Escape => return Action::Close,
+			VirtualKeyCode::L if mods.contains(KeyMods::CTRL) => return Action::Close,
+
+			VirtualKeyCode::S if mods.contains(KeyMods::CTRL) => self.prompt = Some((Field::New, String::new())),
+			VirtualKeyCode::N if mods.contains(KeyMods::CTRL) => {
+				if let Some(entry) = selected {
+					self.prompt = Some((Field::Notes, entry.location.notes.clone()));
+				}
+			},
+			VirtualKeyCode::F2 => {
+				if let Some(entry) = selected {
+					self.prompt = Some((Field::Name, entry.location.name.clone()));
+				}
+			},
+
+			VirtualKeyCode::Delete if selected.is_some() => {
+				let id = selected.unwrap().id.clone();
+
+				match self.library.remove(self.selected) {
+					Ok(()) => {
+						self.thumbnails.remove(&id);
+						self.selected = self.selected.min(count.saturating_sub(2));
+					},
+					Err(e) => eprintln!("could not remove location {}: {}", id, e),
+				}
+			},
+
+			VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => {
+				if let Some(entry) = selected {
+					return Action::Go(entry.location.view.clone());
+				}
+			},
+
+			VirtualKeyCode::Left => self.selected = self.selected.saturating_sub(1),
+			VirtualKeyCode::Right => self.selected = (self.selected + 1).min(count.saturating_sub(1)),
+			VirtualKeyCode::Up => self.selected = self.selected.checked_sub(columns).unwrap_or(self.selected),
+			VirtualKeyCode::Down if self.selected + columns < count => self.selected += columns,
+
+			_ => {},
+		}
+
+		Action::Nothing
+	}
+
+	// Returns whether the character went into a prompt
+	pub fn text(&mut self, character: char) -> bool {
+		let Some((_, text)) = &mut self.prompt else {
+			return false;
+		};
+
+		if character.is_control() {
+			return false;
+		}
+
+		text.push(character);
+
+		true
+	}
+
+	// Clicking a location selects it, clicking it again goes there
+	pub fn click(&mut self, position: Point2<f32>, window_size: (f32, f32)) -> Action {
+		let layout = self.layout(window_size);
+
+		let clicked = (0..self.library.entries().len()).find(|&index| {
+			LibraryView::cell(&layout, index).is_some_and(|corner| {
+				Rect::new(corner.x, corner.y, THUMBNAIL_SIZE.0 as f32, THUMBNAIL_SIZE.1 as f32 + NAME_HEIGHT).contains(position)
+			})
+		});
+
+		match clicked {
+			Some(index) if index == self.selected => Action::Go(self.library.entries()[index].location.view.clone()),
+			Some(index) => {
+				self.selected = index;
+				Action::Nothing
+			},
+			None => Action::Nothing,
+		}
+	}
+
+	fn thumbnail(&mut self, context: &Context, index: usize) -> Option<&Image> {
+		let id = self.library.entries()[index].id.clone();
+
+		if !self.thumbnails.contains_key(&id) {
+			let image = match library::load_thumbnail(&self.library.thumbnail_path(index)) {
+				Ok((bytes, (width, height))) => Some(Image::from_pixels(context, &bytes, ImageFormat::Rgba8UnormSrgb, width as u32, height as u32)),
+				Err(e) => {
+					eprintln!("could not load thumbnail of {}: {}", id, e);
+					None
+				},
+			};
+
+			self.thumbnails.insert(id.clone(), image);
+		}
+
+		self.thumbnails[&id].as_ref()
+	}
+
+	pub fn draw(&mut self, canvas: &mut Canvas, context: &Context, locale: &Locale, window_size: (f32, f32)) -> Result {
+		let (width, height) = window_size;
+		let background = Mesh::new_rectangle(context, DrawMode::fill(), Rect::new(0.0, 0.0, width, height), BACKGROUND_COLOR)?;
+		canvas.draw(&background, DrawParam::new());
+
+		let layout = self.layout(window_size);
+		let (thumbnail_width, thumbnail_height) = (THUMBNAIL_SIZE.0 as f32, THUMBNAIL_SIZE.1 as f32);
+
+		for index in 0..self.library.entries().len() {
+			let Some(corner) = LibraryView::cell(&layout, index) else {
+				continue;
+			};
+
+			let area = Rect::new(corner.x, corner.y, thumbnail_width, thumbnail_height);
+
+			match self.thumbnail(context, index) {
+				Some(image) => {
+					let scale = [thumbnail_width / image.width() as f32, thumbnail_height / image.height() as f32];
+					canvas.draw(image, DrawParam::new().dest(corner).scale(scale));
+				},
+				None => canvas.draw(&Mesh::new_rectangle(context, DrawMode::fill(), area, MISSING_COLOR)?, DrawParam::new()),
+			}
+
+			if index == self.selected {
+				let outline = Rect::new(area.x - 3.0, area.y - 3.0, area.w + 6.0, area.h + NAME_HEIGHT + 6.0);
+				canvas.draw(&Mesh::new_rectangle(context, DrawMode::stroke(2.0), outline, overlay::SELECTION_COLOR)?, DrawParam::new());
+			}
+
+			let name = &self.library.entries()[index].location.name;
+			let name = match name.chars().count() > MAX_NAME_LENGTH {
+				true => format!("{}…", name.chars().take(MAX_NAME_LENGTH - 1).collect::<String>()),
+				false => name.clone(),
+			};
+
+			let mut text = Text::new(name);
+			text.set_scale(NAME_SIZE);
+			canvas.draw(&text, DrawParam::new().dest([corner.x, corner.y + thumbnail_height + 3.0]).color(NAME_COLOR));
+		}
+
+		let footer = match (&self.prompt, self.library.entries().get(self.selected)) {
+			(Some((field, text)), _) => {
+				let key = match field {
+					Field::New => "library.new",
+					Field::Name => "library.rename",
+					Field::Notes => "library.notes",
+				};
+
+				locale.format(key, &[("text", text)])
+			},
+			(None, Some(entry)) => {
+				let location = &entry.location;
+				let notes = if location.notes.is_empty() { locale.text("library.no-notes") } else { &location.notes };
+
+				locale.format("library", &[
+					("count", &self.library.entries().len()),
+					("name", &location.name),
+					("notes", &notes),
+					("magnification", &format!("{:.3e}", location.view.magnification)),
+				])
+			},
+			(None, None) => locale.text("library.empty").to_string(),
+		};
+
+		overlay::draw_text_panel(canvas, context, &footer, Point2 { x: SPACING, y: height - FOOTER_HEIGHT + SPACING })
+	}
+}
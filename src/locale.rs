@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+use std::env;
+use std::fmt::Display;
+
+// On-screen text by language, embedded so the viewer doesn't depend on the working directory.
+// English is complete, other languages fall back to it for anything they leave out
+
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+const LANGUAGES: [(&str, &str); 2] = [
+	("en", include_str!("../locales/en.toml")),
+	("de", include_str!("../locales/de.toml")),
+];
+
+pub fn languages() -> impl Iterator<Item = &'static str> {
+	LANGUAGES.iter().map(|(name, _)| *name)
+}
+
+pub fn is_known(language: &str) -> bool {
+	languages().any(|name| name == language)
+}
+
+// "auto" picks the language from the environment, as set by the system's locale
+pub fn resolve(setting: &str) -> &str {
+	if setting != "auto" {
+		return setting;
+	}
+
+	let requested = ["LC_ALL", "LC_MESSAGES", "LANG"]
+		.into_iter()
+		.filter_map(|name| env::var(name).ok())
+		.find(|value| !value.is_empty())
+		.unwrap_or_default();
+
+	// Values look like de_DE.UTF-8
+	let language = requested.split(['_', '.', '@']).next().unwrap_or_default();
+	languages().find(|name| *name == language).unwrap_or(DEFAULT_LANGUAGE)
+}
+
+fn load_messages(language: &str) -> HashMap<String, String> {
+	LANGUAGES
+		.iter()
+		.find(|(name, _)| *name == language)
+		.map(|(name, contents)| toml::from_str(contents).unwrap_or_else(|e| panic!("locales/{}.toml is invalid: {}", name, e)))
+		.unwrap_or_default()
+}
+
+pub struct Locale {
+	messages: HashMap<String, String>,
+}
+
+impl Locale {
+	// Unknown languages get English, config validation reports them first
+	pub fn new(language: &str) -> Locale {
+		let mut messages = load_messages(DEFAULT_LANGUAGE);
+		messages.extend(load_messages(language));
+
+		Locale { messages }
+	}
+
+	// The key itself stands in for a missing message, so the gap is visible rather than blank
+	pub fn text<'a>(&'a self, key: &'a str) -> &'a str {
+		self.messages.get(key).map_or(key, String::as_str)
+	}
+
+	pub fn format(&self, key: &str, args: &[(&str, &dyn Display)]) -> String {
+		args.iter().fold(self.text(key).to_string(), |text, (name, value)| {
+			text.replace(&format!("{{{}}}", name), &value.to_string())
+		})
+	}
+}
+
+impl Default for Locale {
+	fn default() -> Locale {
+		Locale::new(DEFAULT_LANGUAGE)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn placeholders(message: &str) -> Vec<&str> {
+		let mut names: Vec<&str> = message.split('{').skip(1).filter_map(|rest| rest.split_once('}')).map(|(name, _)| name).collect();
+		names.sort_unstable();
+		names
+	}
+
+	#[test]
+	fn languages_translate_every_message() {
+		let english = load_messages(DEFAULT_LANGUAGE);
+
+		for language in languages() {
+			let translated = load_messages(language);
+
+			let mut missing: Vec<&String> = english.keys().filter(|key| !translated.contains_key(*key)).collect();
+			missing.sort_unstable();
+			assert!(missing.is_empty(), "{} is missing {:?}", language, missing);
+
+			for (key, message) in &translated {
+				let original = english.get(key).unwrap_or_else(|| panic!("{} has unknown key {}", language, key));
+				assert_eq!(placeholders(message), placeholders(original), "{} {}", language, key);
+			}
+		}
+	}
+
+	#[test]
+	fn formats_placeholders() {
+		let locale = Locale::new("de");
+		assert_eq!(locale.format("julia", &[("c", &"0.25")]), "Julia-Menge von c = 0.25");
+	}
+
+	#[test]
+	fn falls_back_to_english_and_then_the_key() {
+		let locale = Locale::new("xx");
+
+		assert_eq!(locale.text("quality.high"), "high");
+		assert_eq!(locale.text("no.such.key"), "no.such.key");
+	}
+}
@@ -1,284 +1,3202 @@
 #![windows_subsystem = "windows"]
 
-mod complex;
-
-use std::collections::HashMap;
+mod cli;
+mod diff_view;
+mod frame_texture;
+mod julia_preview;
+mod library_view;
+mod overlay;
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
 use std::thread;
-
-use complex::Complex;
-
-use ggez::input::keyboard::KeyInput;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use clap::Parser;
+use cli::Args;
+use frame_texture::FrameTexture;
+use diff_view::DiffView;
+use julia_preview::JuliaPreview;
+use library_view::LibraryView;
+use mandelbrot_viewer::accumulation::{self, Accumulation};
+use mandelbrot_viewer::animation::{self, Animation, Easing, FrameExport, Flythrough, JuliaMorph, Keyframe};
+use mandelbrot_viewer::attract::{self, Autopilot};
+use mandelbrot_viewer::backend::{self, Cpu, RenderBackend};
+use mandelbrot_viewer::bench;
+use mandelbrot_viewer::build_info;
+use mandelbrot_viewer::capture::{self, FrameRecorder};
+use mandelbrot_viewer::checksum::FrameHash;
+use mandelbrot_viewer::config::Config;
+use mandelbrot_viewer::contours::{self, Segment};
+use mandelbrot_viewer::display::DisplayFilter;
+use mandelbrot_viewer::eta::{self, Eta};
+use mandelbrot_viewer::export::{self, Transparency};
+use mandelbrot_viewer::formula::Formula;
+use mandelbrot_viewer::gradient::{self, Gradient, PaletteWatcher, PALETTE_DIR};
+use mandelbrot_viewer::import;
+use mandelbrot_viewer::inspect::{self, Inspection};
+use mandelbrot_viewer::jobs::Jobs;
+use mandelbrot_viewer::library::{self, Library};
+use mandelbrot_viewer::locale::{self, Locale};
+use mandelbrot_viewer::memory::{self, MemoryBudget};
+use mandelbrot_viewer::complex::Complex;
+use mandelbrot_viewer::outlines;
+use mandelbrot_viewer::palette_editor::PaletteEditor;
+use mandelbrot_viewer::postprocess::Effects;
+use mandelbrot_viewer::random_palette;
+use mandelbrot_viewer::quality::{self, Quality};
+use mandelbrot_viewer::queue::{Priority, WorkQueue};
+use mandelbrot_viewer::recording::{InputEvent, Recording, Replay, TimedEvent};
+use mandelbrot_viewer::rays::{self, Angle};
+use mandelbrot_viewer::renderer::{self, Frame, IterationSettings, RenderJob, RenderedTile, Renderer};
+use mandelbrot_viewer::selection::Region;
+use mandelbrot_viewer::selftest;
+use mandelbrot_viewer::session::Session;
+use mandelbrot_viewer::stats::{self, FrameStats};
+use mandelbrot_viewer::tiles;
+use mandelbrot_viewer::viewport::Viewport;
+use mandelbrot_viewer::wallpaper;
+
+use ggez::input::keyboard::{KeyInput, KeyMods};
+use ggez::input::mouse::MouseButton;
 use ggez::mint::Point2;
+use ggez::winit::dpi::{LogicalSize, PhysicalPosition};
 use ggez::winit::event::VirtualKeyCode;
-use ggez::{Context, ContextBuilder, GameResult as Result};
+use ggez::{Context, ContextBuilder, GameError, GameResult as Result};
 use ggez::conf;
-use ggez::graphics::{self, Color, DrawParam, InstanceArray};
+use ggez::graphics::{self, Color, Rect};
 use ggez::event::{self, EventHandler};
 
-use palette::{self, FromColor};
-
+// Logical window size, the pixel buffer matches the physical size which differs on HiDPI displays
 const WIDTH: f32 = 500.0;
 const HEIGHT: f32 = 500.0;
-const SCREEN_SIZE: f32 = WIDTH * HEIGHT;
 
-const FPS: u32 = 144;
+const IDLE_FRAME_DURATION: Duration = Duration::from_millis(16);
+// Ticks between Julia previews, each is a fraction of the view's cost but there's no need for one
+// every tick
+const JULIA_PREVIEW_INTERVAL: u64 = 3;
+
+// Window pixels per second the keyboard crosshair moves while an arrow key is held
+const CROSSHAIR_SPEED: f32 = 300.0;
+
+// Once nothing has happened for the idle timeout, input is still picked up this often
+const DORMANT_FRAME_DURATION: Duration = Duration::from_millis(100);
+
+// How close in time and space, in physical pixels, two clicks must be to count as a double click
+const DOUBLE_CLICK_TIME: Duration = Duration::from_millis(400);
+const DOUBLE_CLICK_DISTANCE: f32 = 4.0;
+
+// Right button drags shorter than this, in pixels, are taken as a stray click rather than a region
+const MIN_DRAG_DISTANCE: f64 = 4.0;
+
+// The cardioid and bulb outlines are only a guide at shallow zooms
+const OUTLINE_MAX_MAGNIFICATION: f64 = 1000.0;
+const OUTLINE_POINTS: usize = 720;
+
+// Equipotential lines are drawn between every this many iteration bands
+const CONTOUR_SPACING: f64 = 5.0;
+
+// Keyframes dropped in the viewer are this many seconds apart, and exported at this frame rate
+const KEYFRAME_SPACING: f64 = 3.0;
+const ANIMATION_FRAME_RATE: f64 = 30.0;
+const ANIMATION_DIR: &str = "animations";
+const RECORDING_DIR: &str = "recordings";
+
+// The Julia morph circles its constant at this radius, divided by the magnification Julia mode was
+// entered at so the circle stays close to the boundary point that was picked
+const MORPH_RADIUS: f64 = 0.05;
+const MORPH_PERIOD: f64 = 8.0;
+
+const HISTOGRAM_BINS: usize = 50;
+const HISTOGRAM_SIZE: (f32, f32) = (200.0, 80.0);
+
+// How long the thread count or render scale stays up after changing it
+const SETTING_NOTICE_TIME: f64 = 2.0;
+
+// How long a random palette's name stays up, long enough to note its seed
+const PALETTE_NOTICE_TIME: f64 = 4.0;
+
+// Seconds between writing the view to the recovery file, when it's changed
+const AUTOSAVE_TIME: f64 = 5.0;
 
-const MAX_ITERATIONS: f64 = 100.0;
-const MAX_STABLE: f64 = 2.0;
+// How long closing waits for stopped exports to finish the frame they're on
+const JOB_STOP_TIME: Duration = Duration::from_secs(5);
 
-const THREADS: usize = 10;
+// Renders quicker than this in seconds don't show how long they have left
+const ETA_DELAY: f64 = 2.0;
+
+// A frame this close to the view's scale has been panned rather than zoomed, as rounding the frame
+// to whole pixels leaves it slightly off
+const PAN_SCALE_TOLERANCE: f64 = 0.01;
+
+// Seconds between palettes while the attract mode dives
+const ATTRACT_PALETTE_TIME: f64 = 12.0;
+
+// Seconds a stretched palette takes to move most of the way to a new frame's escape counts, and
+// how close to them, as a fraction of their span, it snaps the rest of the way
+const STRETCH_TIME: f64 = 0.3;
+const STRETCH_SETTLED: f64 = 0.005;
+
+// Windows builds are GUI programs so launching one from Explorer opens no console, which also
+// leaves them nowhere to print to. Run from a terminal, output goes to that terminal instead
+#[cfg(windows)]
+fn attach_console() {
+	const STD_OUTPUT_HANDLE: u32 = -11i32 as u32;
+	const ATTACH_PARENT_PROCESS: u32 = u32::MAX;
+
+	#[link(name = "kernel32")]
+	extern "system" {
+		fn GetStdHandle(handle: u32) -> isize;
+		fn AttachConsole(process_id: u32) -> i32;
+	}
+
+	// Redirected output already has somewhere to go. Attaching fails without a parent console,
+	// such as when started from Explorer, which leaves output unseen as before
+	unsafe {
+		if matches!(GetStdHandle(STD_OUTPUT_HANDLE), 0 | -1) {
+			AttachConsole(ATTACH_PARENT_PROCESS);
+		}
+	}
+}
 
 fn main() -> Result {
+	// Before anything prints, including the help and errors for the arguments
+	#[cfg(windows)]
+	attach_console();
+
+	let args = Args::parse();
+
+	// Needs no config, so a broken one doesn't stop completions being installed
+	if let Some(cli::Command::Generate { target }) = args.command {
+		return cli::generate(target, &mut io::stdout().lock()).map_err(GameError::from);
+	}
+
+	let mut config = Config::load(args.config.as_deref())?;
+	args.apply(&mut config);
+	config.validate()?;
+
+	WorkQueue::shared().set_pinned(config.pin_threads);
+	WorkQueue::shared().set_threads(config.threads);
+
+	let replay = args.replay.as_deref().map(Recording::load).transpose()?;
+
+	// Replays run at the recorded tick rate from the recorded starting view, so they play out the same
+	if let Some(recording) = &replay {
+		config.tick_rate = recording.tick_rate;
+	}
+
+	let mut session = match (&args.open, &replay) {
+		(_, Some(recording)) => Some(recording.start.clone()),
+		(Some(path), None) => Some(export::load_view(path)?),
+		(None, None) if !args.fresh => Session::load(),
+		(None, None) => None,
+	};
+
+	// A skew given on the command line applies on top of whichever view is opened
+	if let Some(skew) = args.skew {
+		let mut view = session.unwrap_or_else(|| {
+			Session::capture(&config.formula.preset().viewport(1.0, 1.0), &IterationSettings::from_config(&config), gradient::DEFAULT_NAME)
+		});
+
+		view.skew = skew;
+		session = Some(view);
+	}
+
+	if let Some(size) = args.bench {
+		return run_bench(size, &config);
+	}
+
+	if let Some(size) = args.selftest {
+		return run_selftest(size, &config);
+	}
+
+	if let Some(size) = args.hash {
+		return print_hash(size, &config, session);
+	}
+
+	if let Some(size) = args.poster {
+		let output = args.output.as_deref().unwrap_or(Path::new("poster.png"));
+		return render_poster(output, size, &config, session);
+	}
+
+	if let Some(max_zoom) = args.pyramid {
+		let output = args.output.as_deref().unwrap_or(Path::new("tiles"));
+		return render_pyramid(output, max_zoom, &config, session);
+	}
+
+	if let Some(path) = &args.animate {
+		let output = args.output.as_deref().unwrap_or(Path::new("frames"));
+		return render_flythrough(path, output, &config);
+	}
+
+	if !args.import.is_empty() {
+		return import_locations(&args.import, &config);
+	}
+
+	if let Some(address) = &args.serve {
+		return serve_tiles(address, &config, session);
+	}
+
 	let window_setup = conf::WindowSetup::default()
 		.title("Mandelbrot Viewer")
-		.vsync(true);
+		.vsync(config.vsync);
+
+	let window_mode = conf::WindowMode {
+		logical_size: Some(LogicalSize::new(WIDTH, HEIGHT)),
 
-	let window_mode = conf::WindowMode::default()
-		.dimensions(WIDTH, HEIGHT)
-		.resizable(false);
+		..conf::WindowMode::default()
+			.dimensions(WIDTH, HEIGHT)
+			.resizable(false)
+			.resize_on_scale_factor_change(true)
+			.borderless(config.borderless)
+	};
 
 	let (mut context, event_loop) = ContextBuilder::new("mandelbrot_viewer", "ReturnedTrue")
 		.window_setup(window_setup)
 		.window_mode(window_mode)
 		.build()?;
 
-	let viewer = MandelbrotViewer::new(&mut context);
+	place_window(&context, &config);
+
+	let mut viewer = MandelbrotViewer::new(&mut context, &config);
+
+	if let Some(session) = session {
+		session.restore(&mut viewer.viewport, &mut viewer.iteration_settings);
+		viewer.select_palette(&session.palette);
+	}
+
+	// Replays have to start where they were recorded
+	if let (Some(recovered), None) = (Session::load_recovery(), &replay) {
+		viewer.offer_recovery(recovered);
+	}
+
+	if let Some(path) = args.record {
+		viewer.start_recording(path);
+	}
+
+	if let Some(recording) = replay {
+		viewer.start_replay(recording);
+	}
+
 	event::run(context, event_loop, viewer);
 }
 
-const fn blank_point() -> Point2<f64> {
-	Point2 { x: 0.0, y: 0.0 }
+// winit has no way to pick the monitor a window opens on, so it's moved there once open. Moving
+// to a monitor with a different scale factor resizes it, which the viewer handles like any resize
+fn place_window(context: &Context, config: &Config) {
+	if config.monitor.is_none() && config.window_position.is_none() {
+		return;
+	}
+
+	let window = context.gfx.window();
+
+	let monitor = match config.monitor {
+		Some(index) => window.available_monitors().nth(index).or_else(|| {
+			let names: Vec<String> = window.available_monitors()
+				.map(|monitor| monitor.name().unwrap_or_else(|| String::from("unnamed")))
+				.collect();
+
+			eprintln!("there is no monitor {}, the monitors are {}", index, names.join(", "));
+			None
+		}),
+
+		None => window.current_monitor(),
+	};
+
+	let Some(monitor) = monitor else {
+		return;
+	};
+
+	let origin = monitor.position();
+
+	let position = match config.window_position {
+		Some([x, y]) => PhysicalPosition::new(origin.x + x, origin.y + y),
+
+		None => {
+			let (monitor_size, window_size) = (monitor.size(), window.outer_size());
+
+			PhysicalPosition::new(
+				origin.x + (monitor_size.width as i32 - window_size.width as i32) / 2,
+				origin.y + (monitor_size.height as i32 - window_size.height as i32) / 2,
+			)
+		},
+	};
+
+	window.set_outer_position(position);
+}
+
+// ggez keeps its adapter to itself, so this asks for the one its high performance request would
+// have been given, the first discrete GPU that can present to the window
+fn display_adapter(context: &Context) -> String {
+	let wgpu = context.gfx.wgpu();
+
+	let adapter = wgpu.instance.enumerate_adapters(wgpu::Backends::all())
+		.filter(|adapter| adapter.is_surface_supported(&wgpu.surface))
+		.map(|adapter| adapter.get_info())
+		.min_by_key(|info| match info.device_type {
+			wgpu::DeviceType::DiscreteGpu => 0,
+			wgpu::DeviceType::IntegratedGpu => 1,
+			wgpu::DeviceType::VirtualGpu => 2,
+			wgpu::DeviceType::Cpu => 3,
+			wgpu::DeviceType::Other => 4,
+		});
+
+	match adapter {
+		Some(info) => format!("{} on {:?}", info.name, info.backend),
+		None => String::from("unknown"),
+	}
 }
 
-#[inline]
-fn into_range(value: f64, constant: f64, magnification: f64) -> f64 {
-	return (((value / constant) / magnification) * 4.0) - 2.0;
+fn available_cores() -> usize {
+	thread::available_parallelism().map_or(1, |cores| cores.get())
 }
 
-fn calculate_for_pixel(x: usize, y: usize, view_offset: Point2<f64>, magnification: f64) -> Color {
-	let translated_x = x as f64 + view_offset.x;
-	let translated_y = y as f64 + view_offset.y;
-	
-	let c = Complex::new(
-		into_range(translated_x, WIDTH as f64, magnification),
-		into_range(translated_y, HEIGHT as f64, magnification)
-	);
+// Posters use the configured quality's sampling, but always render at the requested size
+fn render_poster(path: &Path, (width, height): (usize, usize), config: &Config, session: Option<Session>) -> Result {
+	let mut viewport = config.formula.preset().viewport(width as f64, height as f64);
+	let mut settings = IterationSettings::from_config(config);
+	let mut palette = String::from(gradient::DEFAULT_NAME);
 
-	let mut z = Complex::new(0.01, 0.01);
-	let mut iterations = 0.0;
+	if let Some(session) = session {
+		session.restore(&mut viewport, &mut settings);
+		palette = session.palette;
+	}
 
-	while z.abs() < MAX_STABLE {
-		if iterations > MAX_ITERATIONS {
-			return Color::new(0.0, 0.0, 0.0, 1.0);
-		}
+	settings.max_iterations = (settings.max_iterations * config.quality.iteration_multiplier()).max(1.0).round();
 
-		iterations += 1.0;
-		z = (z * z) + c;
+	let job = RenderJob {
+		tile_size: config.tile_size,
+		shade_interior: config.shade_interior,
+		boundary: config.boundary,
+		stripes: stripe_palette(config),
+		..RenderJob::new(width, height, config.quality.samples(), viewport, settings, find_palette(&palette, config.interior()))
+	};
+
+	export::save_poster(path, job, config.bit_depth, config.post_process.then_some(config.effects), config.transparency, config.budget())?;
+
+	Ok(())
+}
+
+// A single pass at the configured quality, the same render a poster of this size would start from
+fn print_hash((width, height): (usize, usize), config: &Config, session: Option<Session>) -> Result {
+	let mut viewport = config.formula.preset().viewport(width as f64, height as f64);
+	let mut settings = IterationSettings::from_config(config);
+	let mut palette = String::from(gradient::DEFAULT_NAME);
+
+	if let Some(session) = session {
+		session.restore(&mut viewport, &mut settings);
+		palette = session.palette;
+	}
+
+	settings.max_iterations = (settings.max_iterations * config.quality.iteration_multiplier()).max(1.0).round();
+
+	let job = RenderJob {
+		shade_interior: config.shade_interior,
+		boundary: config.boundary,
+		stripes: stripe_palette(config),
+		..RenderJob::new(width, height, config.quality.samples(), viewport, settings, find_palette(&palette, config.interior()))
+	};
+	let hash = FrameHash::of(&renderer::render(job, None));
+
+	println!("iterations {:016x}", hash.iterations);
+	println!("pixels     {:016x}", hash.pixels);
+
+	Ok(())
+}
+
+// Tiles always cover the default view, only the iteration settings and palette come from the session
+fn tile_settings(config: &Config, session: Option<Session>) -> (IterationSettings, Arc<Gradient>) {
+	let mut settings = IterationSettings::from_config(config);
+	let mut palette = String::from(gradient::DEFAULT_NAME);
+
+	if let Some(session) = session {
+		session.restore(&mut Viewport::new(1.0, 1.0), &mut settings);
+		palette = session.palette;
 	}
 
-	let alpha = iterations / MAX_ITERATIONS;
+	(settings, find_palette(&palette, config.interior()))
+}
+
+fn serve_tiles(address: &str, config: &Config, session: Option<Session>) -> Result {
+	let (settings, gradient) = tile_settings(config, session);
+	tiles::serve(address, settings, gradient, config.quality.samples(), config.budget())?;
+
+	Ok(())
+}
+
+fn render_pyramid(dir: &Path, max_zoom: u32, config: &Config, session: Option<Session>) -> Result {
+	let (settings, gradient) = tile_settings(config, session);
+	let rendered = tiles::render_pyramid(dir, max_zoom, settings, gradient, config.quality.samples())?;
+
+	eprintln!("rendered {} tiles into {}", rendered, dir.display());
+
+	Ok(())
+}
+
+// Frames come out the same on every run, so the file can be kept under version control instead of the video
+fn render_flythrough(path: &Path, dir: &Path, config: &Config) -> Result {
+	let contents = fs::read_to_string(path)
+		.map_err(|e| GameError::ConfigError(format!("could not read {}: {}", path.display(), e)))?;
+
+	let start = Session::capture(&config.formula.preset().viewport(1.0, 1.0), &IterationSettings::from_config(config), gradient::DEFAULT_NAME);
+	let flythrough = Flythrough::parse(&contents, &start)
+		.map_err(|e| GameError::ConfigError(format!("could not parse {}: {}", path.display(), e)))?;
+
+	let options = FrameExport {
+		size: flythrough.size,
+		samples: config.quality.samples(),
+		iteration_multiplier: config.quality.iteration_multiplier(),
+		bit_depth: config.bit_depth,
+		shade_interior: config.shade_interior,
+		boundary: config.boundary,
+		stripes: stripe_palette(config),
+		effects: config.post_process.then_some(config.effects),
+		transparency: config.transparency,
+		budget: config.budget(),
+		cancelled: None,
+	};
+
+	let palettes: Vec<Arc<Gradient>> = gradient::load_palettes(Path::new(PALETTE_DIR), config.interior()).into_iter().map(Arc::new).collect();
+	let rendered = animation::render_frames(flythrough.animation.views(flythrough.frame_rate), dir, &options, &palettes)?;
+
+	eprintln!("rendered {} frames into {}", rendered, dir.display());
+
+	Ok(())
+}
+
+fn run_bench((width, height): (usize, usize), config: &Config) -> Result {
+	eprintln!("rendering {}x{} at {} quality", width, height, config.quality.name());
+
+	// Every backend compiled in, skipping those with nothing to run on
+	let backends: Vec<Arc<dyn RenderBackend>> = backend::NAMES.iter()
+		.filter_map(|name| backend::create(name).map_err(|e| eprintln!("skipping {}: {}", name, e)).ok())
+		.collect();
+
+	let results = bench::run(&backends, (width, height), config.quality.samples(), config.quality.iteration_multiplier(), config.bailout)
+		.map_err(GameError::RenderError)?;
+
+	println!("{}", bench::report(&results));
+
+	Ok(())
+}
+
+fn run_selftest((width, height): (usize, usize), config: &Config) -> Result {
+	eprintln!("comparing {}x{} renders at {} quality against cpu", width, height, config.quality.name());
+
+	let reference = backend::create("cpu").map_err(GameError::RenderError)?;
+	let backends: Vec<Arc<dyn RenderBackend>> = backend::NAMES.iter()
+		.filter_map(|name| backend::create(name).map_err(|e| eprintln!("skipping {}: {}", name, e)).ok())
+		.collect();
+
+	let results = selftest::run(reference.as_ref(), &backends, (width, height), config.quality.samples(), config.quality.iteration_multiplier(), config.bailout)
+		.map_err(GameError::RenderError)?;
 
-	let hsv = palette::Hsv::new(alpha as f32 * 360.0, 1.0, 1.0);
-	let srgb = palette::Srgb::from_color(hsv);
+	println!("{}", selftest::report(&results));
 
-	Color::new(srgb.red, srgb.green, srgb.blue, 1.0)
+	let failed = results.iter().filter(|result| !result.passed()).count();
 
+	if failed > 0 {
+		return Err(GameError::RenderError(format!("{} of {} renders disagree with cpu", failed, results.len())));
+	}
+
+	Ok(())
 }
 
-fn calculate_for_range(x_start: usize, x_end: usize, view_offset: Point2<f64>, magnification: f64) -> Vec<DrawParam> {
-	let mut range_results = Vec::with_capacity((x_end - x_start) * (HEIGHT as usize));
+// Each file's locations are added as they're rendered, so an interrupted import keeps what it got
+// through. Entries that can't be brought over are listed and skipped
+fn import_locations(paths: &[PathBuf], config: &Config) -> Result {
+	let dir = Library::default_dir().ok_or_else(|| GameError::FilesystemError(String::from("no data directory to keep the location library in")))?;
+	let mut library = Library::open(&dir);
+	let (width, height) = library::THUMBNAIL_SIZE;
+
+	for path in paths {
+		let import = import::read(path, config)?;
+
+		for reason in &import.skipped {
+			eprintln!("skipping {} in {}", reason, path.display());
+		}
 
-	for x in x_start..x_end {
-		for y in 0..(HEIGHT as usize) {
-			let pixel_color = calculate_for_pixel(x, y, view_offset, magnification);
+		for location in &import.locations {
+			let mut viewport = Viewport::new(width as f64, height as f64);
+			let mut settings = IterationSettings::from_config(config);
+			location.view.restore(&mut viewport, &mut settings);
 
-			let params = DrawParam::new()
-				.dest([x as f32, y as f32])
-				.color(pixel_color);
+			let job = RenderJob::new(width, height, 1, viewport, settings, find_palette(&location.view.palette, config.interior()));
+			let thumbnail = renderer::render(job, None);
 
-			range_results.push(params);
+			library.add(&location.name, &location.notes, location.view.clone(), &thumbnail.pixels, (width, height))?;
 		}
+
+		println!("imported {} locations from {}, skipped {}", import.locations.len(), path.display(), import.skipped.len());
 	}
 
-	range_results
+	Ok(())
+}
+
+fn find_palette(name: &str, interior: Color) -> Arc<Gradient> {
+	let gradient = gradient::load_palettes(Path::new(PALETTE_DIR), interior).into_iter()
+		.find(|gradient| gradient.name == name)
+		.or_else(|| random_palette::regenerate(name))
+		.unwrap_or_else(|| Gradient::rainbow().with_interior(interior));
+
+	Arc::new(gradient)
+}
+
+// The palette odd iteration bands are coloured with, when striping is on
+fn stripe_palette(config: &Config) -> Option<Arc<Gradient>> {
+	config.stripes.then(|| find_palette(&config.stripe_palette, config.interior()))
+}
+
+// A frame's top left in window pixels and the window pixels each of its pixels covers, and whether
+// it has been slid along with a pan
+#[derive(Copy, Clone)]
+struct Placement {
+	origin: Point2<f32>,
+	scale: [f32; 2],
+	panned: bool,
+}
+
+// The autopilot dive that runs while the viewer is left alone, and the view to go back to
+struct Attract {
+	autopilot: Autopilot,
+	home: Session,
+	next_palette: f64,
 }
 
 struct MovementKeyData {
 	is_down: bool,
-	velocity: Point2<f64>
+	velocity: Point2<f64>,
+	// Radians per second
+	angular_velocity: f64,
 }
 
 impl MovementKeyData {
 	pub fn new(x_velocity: f64, y_velocity: f64) -> MovementKeyData {
 		MovementKeyData {
 			is_down: false,
-			velocity: Point2 { x: x_velocity, y: y_velocity }
+			velocity: Point2 { x: x_velocity, y: y_velocity },
+			angular_velocity: 0.0,
+		}
+	}
+
+	pub fn rotation(angular_velocity: f64) -> MovementKeyData {
+		MovementKeyData {
+			angular_velocity,
+			..MovementKeyData::new(0.0, 0.0)
 		}
 	}
 }
 
+// A view kept while another tab is shown, the palette by name as reloading can reorder them
+struct Tab {
+	viewport: Viewport,
+	settings: IterationSettings,
+	palette: String,
+	mandelbrot_viewport: Option<Viewport>,
+}
+
 struct MandelbrotViewer {
-	batch: InstanceArray,
+	frame_texture: FrameTexture,
+
+	renderer: Renderer,
+	backend: Arc<dyn RenderBackend>,
+	// Compares the view against other settings while on, and what it compares against
+	diff_view: Option<DiffView>,
+	diff_backend: String,
+	diff_iterations: f64,
+	tile_size: usize,
+	// Multiple of the window's resolution rendered on top of the quality's, and the tick it was last
+	// changed on
+	render_scale: f64,
+	render_scale_changed: Option<u64>,
+	// Pixels rendered past each edge of the window, at window resolution
+	render_margin: usize,
+	// Caps the frames' buffers, lowering samples and no longer keeping a spare frame to render into
+	budget: MemoryBudget,
+	// The frame currently shown, and storage for the next one to be rendered into
+	front_frame: Option<Frame>,
+	back_frame: Option<Frame>,
+
+	// Bumped whenever the view changes, so passes of an old view can be told apart
+	generation: u64,
+	accumulation: Option<Accumulation>,
+
+	palettes: Vec<Arc<Gradient>>,
+	palette_index: usize,
+	palette_watcher: Option<PaletteWatcher>,
+	interior_color: Color,
+	// The palette being edited in place of the current one, whether its selected stop is being
+	// dragged, and the colour being typed in for it
+	palette_editor: Option<PaletteEditor>,
+	dragging_stop: bool,
+	stop_prompt: Option<String>,
+
+	show_histogram: bool,
+	show_stats: bool,
+	show_grid: bool,
+	show_about: bool,
+	// Whether clicks inspect the pixel under the cursor, and the last one inspected
+	inspecting: bool,
+	inspection: Option<Inspection>,
+	// Saved locations, shown over the view while open
+	library_view: Option<LibraryView>,
+	// Tick the render thread count was last changed on
+	threads_changed: Option<u64>,
+	// Tick the last random palette was picked on
+	palette_randomized: Option<u64>,
+	// Tiles finished of the newest view's first pass, by its generation
+	render_eta: Option<(u64, Eta)>,
+	// Where the last run crashed, offered until Enter goes back there or Escape dismisses it. The
+	// view last written to the recovery file and the tick it was checked on
+	recovery: Option<Session>,
+	autosaved: Option<Session>,
+	// Exports on background threads. Closing while any run asks first, and closes by itself once
+	// they're done unless Escape keeps the window open
+	jobs: Jobs,
+	quit_prompt: bool,
+	quit_confirmed: bool,
+	// Set once the window is closing and everything has been saved
+	quitting: bool,
+	last_autosave: u64,
+	display_adapter: String,
+	locale: Locale,
+	display_filter: DisplayFilter,
+	// Shown through the display filter while post processing is on
+	effects: Effects,
+	show_outlines: bool,
+
+	// Traced from the front frame when it changes, only while shown
+	show_contours: bool,
+	contour_segments: Vec<Segment>,
+
+	// Whether renders shade the interior by distance from the boundary, or draw only the boundary
+	shade_interior: bool,
+	boundary: bool,
+	// Colours odd iteration bands while striping, looked up by name when it's turned on
+	stripes: Option<Arc<Gradient>>,
+	stripe_palette: String,
+
+	// The escape counts the palette is stretched across while stretching, easing towards those
+	// of the latest fresh frame
+	stretch_palette: bool,
+	palette_range: Option<[f64; 2]>,
+	palette_target: Option<[f64; 2]>,
+
+	// Why the last render failed, cleared once one succeeds
+	render_error: Option<String>,
+
+	animation: Animation,
+	// The tick playback started at, None while stopped
+	playback_start: Option<u64>,
+
+	// Where the Mandelbrot set was left when Julia mode was entered
+	mandelbrot_viewport: Option<Viewport>,
+	morph: Option<(JuliaMorph, u64)>,
+
+	// Every open tab, the shown one's entry is only brought up to date when leaving it
+	tabs: Vec<Tab>,
+	tab: usize,
+
+	// Traced external rays in the c plane, and the angle being typed in for the next one
+	rays: Vec<Vec<Complex>>,
+	ray_prompt: Option<String>,
+
+	// Where a right button drag started, then the bounds of the region it picked as they're typed
+	// over, until Enter zooms to them
+	drag_start: Option<Point2<f64>>,
+	region_prompt: Option<String>,
+	// The bailout and starting z being typed over
+	escape_prompt: Option<String>,
+
+	quality: Quality,
+	bit_depth: u8,
+	transparency: Transparency,
+
+	zoom_factor: f64,
+	fine_zoom_factor: f64,
+
+	// When and where the left button was last pressed, cleared once it completes a double click
+	last_click: Option<(u64, Point2<f32>)>,
+
+	// Size of the pixel buffer in physical pixels, which is the left half of the window's width
+	// while the linked view is shown
+	width: usize,
+	height: usize,
+	window_width: usize,
 
 	movement_data: HashMap<VirtualKeyCode, MovementKeyData>,
 
 	has_parameters_changed: bool,
-	view_offset: Point2<f64>,
-	magnification: f64,
+	// ggez keeps presenting the last canvas, so drawing is only needed when its contents change
+	needs_redraw: bool,
+	viewport: Viewport,
+
+	iteration_settings: IterationSettings,
+	// What resetting or switching formulas starts the settings from
+	config: Config,
+
+	tick_rate: u32,
+	// Fixed rate ticks run so far
+	ticks: u64,
+
+	recording: Option<Recording>,
+	recording_path: Option<PathBuf>,
+	replay: Option<Replay>,
+	// The recorded cursor position, stands in for the real one during a replay
+	cursor_override: Option<Point2<f32>>,
+	// A cursor moved with the arrow keys, which stands in for the mouse while it's shown
+	crosshair: Option<Point2<f32>>,
+	crosshair_keys: HashSet<VirtualKeyCode>,
+	// Shown in the corner while the Mandelbrot set is, with the tick it was last asked for a point
+	julia_preview: Option<JuliaPreview>,
+	julia_preview_tick: u64,
+	// The Julia set of the last point clicked, shown beside the Mandelbrot set at full size
+	linked_view: Option<JuliaPreview>,
+	linked_c: Complex,
+	// Captures what's on screen every tick while running
+	frame_recorder: Option<FrameRecorder>,
+	frame_duration: Option<Duration>,
+	last_frame: Instant,
+	// Tick of the last input, and how many seconds after it the viewer goes dormant
+	last_input: u64,
+	idle_timeout: Option<f64>,
+	attract_after: Option<f64>,
+	attract: Option<Attract>,
 }
 
 impl MandelbrotViewer {
-	pub fn new(context: &mut Context) -> MandelbrotViewer {
-		let mut batch = InstanceArray::new(context, None);
-		batch.resize(context, SCREEN_SIZE as u32);
+	pub fn new(context: &mut Context, config: &Config) -> MandelbrotViewer {
+		// A backend with nothing to run on shouldn't stop the viewer opening
+		let backend = backend::create(&config.backend).unwrap_or_else(|e| {
+			eprintln!("could not start the {} backend, using the cpu instead: {}", config.backend, e);
+			Arc::new(Cpu)
+		});
+
+		let (width, height) = context.gfx.drawable_size();
+		let (width, height) = (width as usize, height as usize);
 
 		MandelbrotViewer { 
-			batch,
+			frame_texture: FrameTexture::new(),
+
+			renderer: Renderer::with_backend(Arc::clone(&backend)),
+			backend,
+			diff_view: None,
+			diff_backend: config.diff_backend.clone(),
+			diff_iterations: config.diff_iterations,
+			tile_size: config.tile_size,
+			render_scale: config.render_scale,
+			render_scale_changed: None,
+			render_margin: config.render_margin,
+			budget: config.budget(),
+			front_frame: None,
+			back_frame: None,
+
+			generation: 0,
+			accumulation: None,
+
+			palettes: MandelbrotViewer::load_palettes(config.interior()),
+			palette_index: 0,
+			palette_watcher: PaletteWatcher::new(Path::new(PALETTE_DIR)),
+			interior_color: config.interior(),
+			palette_editor: None,
+			dragging_stop: false,
+			stop_prompt: None,
+
+			show_histogram: false,
+			show_stats: false,
+			show_grid: false,
+			show_about: false,
+			inspecting: false,
+			inspection: None,
+			library_view: None,
+			threads_changed: None,
+			palette_randomized: None,
+			render_eta: None,
+			recovery: None,
+			autosaved: None,
+			// Background exports share the half of the budget the view leaves
+			jobs: Jobs::new(config.budget().share(0.5)),
+			quit_prompt: false,
+			quit_confirmed: false,
+			quitting: false,
+			last_autosave: 0,
+			display_adapter: display_adapter(context),
+			locale: Locale::new(locale::resolve(&config.language)),
+			display_filter: DisplayFilter { effects: config.post_process.then_some(config.effects), ..DisplayFilter::default() },
+			effects: config.effects,
+			show_outlines: false,
+
+			show_contours: false,
+			contour_segments: Vec::new(),
+
+			shade_interior: config.shade_interior,
+			boundary: config.boundary,
+			stripes: stripe_palette(config),
+			stripe_palette: config.stripe_palette.clone(),
+
+			stretch_palette: config.stretch_palette,
+			palette_range: None,
+			palette_target: None,
+
+			render_error: None,
+
+			animation: Animation::default(),
+			playback_start: None,
+
+			mandelbrot_viewport: None,
+			morph: None,
+
+			tabs: Vec::new(),
+			tab: 0,
+
+			rays: Vec::new(),
+			ray_prompt: None,
+
+			drag_start: None,
+			region_prompt: None,
+			escape_prompt: None,
+
+			quality: config.quality,
+			bit_depth: config.bit_depth,
+			transparency: config.transparency,
+
+			zoom_factor: config.zoom_factor,
+			fine_zoom_factor: config.fine_zoom_factor,
+
+			last_click: None,
+
+			width,
+			height,
+			window_width: width,
 
 			movement_data: HashMap::from([
 				(VirtualKeyCode::W, MovementKeyData::new(0.0, -10.0)),
 				(VirtualKeyCode::A, MovementKeyData::new(-10.0, 0.0)),
 				(VirtualKeyCode::S, MovementKeyData::new(0.0, 10.0)),
 				(VirtualKeyCode::D, MovementKeyData::new(10.0, 0.0)),
+				(VirtualKeyCode::Z, MovementKeyData::rotation(config.rotation_speed.to_radians())),
+				(VirtualKeyCode::X, MovementKeyData::rotation(-config.rotation_speed.to_radians())),
 			]),
 
 			// In order to invoke first render
 			has_parameters_changed: true,
-			view_offset: blank_point(),
-			magnification: 1.0,
+			needs_redraw: true,
+			viewport: config.formula.preset().viewport(width as f64, height as f64),
+
+			iteration_settings: IterationSettings::from_config(config),
+			config: config.clone(),
+
+			tick_rate: config.tick_rate,
+			ticks: 0,
+
+			recording: None,
+			recording_path: None,
+			replay: None,
+			cursor_override: None,
+			crosshair: None,
+			crosshair_keys: HashSet::new(),
+			julia_preview: None,
+			julia_preview_tick: 0,
+			linked_view: None,
+			linked_c: Complex::new(0.0, 0.0),
+			frame_recorder: None,
+			frame_duration: (config.fps > 0).then(|| Duration::from_secs_f64(1.0 / config.fps as f64)),
+			last_frame: Instant::now(),
+			last_input: 0,
+			idle_timeout: (config.idle_timeout > 0.0).then_some(config.idle_timeout),
+			attract_after: (config.attract_after > 0.0).then_some(config.attract_after),
+			attract: None,
 		}
 	}
 
-	fn construct_batch(&mut self) -> () {
-		let mut results = Vec::with_capacity(SCREEN_SIZE as usize);
-		let mut threads = Vec::with_capacity(THREADS);
+	// Pass 0 is a fresh render of a changed view, later passes refine the current one
+	fn submit_render(&mut self, context: &Context, pass: u32) {
+		if pass == 0 {
+			self.generation += 1;
+		}
+
+		let scale = self.quality.resolution_scale() * self.render_scale;
+		let shown_width = ((self.width as f64 * scale).round() as usize).max(1);
+		let shown_height = ((self.height as f64 * scale).round() as usize).max(1);
 
-		let mut accumulated_x = 0;
-		let per_thread_x = (WIDTH as usize) / THREADS;
+		// Same view, covered with fewer or more pixels, and carried on past each edge by the margin
+		let margin = (self.render_margin as f64 * scale).round() as usize;
+		let (width, height) = (shown_width + 2 * margin, shown_height + 2 * margin);
 
-		for _ in 0..THREADS {
-			let acc = accumulated_x;
-			let offset = self.view_offset;
-			let mag = self.magnification;
+		let mut viewport = self.viewport;
+		viewport.resize(shown_width as f64, shown_height as f64);
+		let viewport = viewport.crop(-(margin as f64), -(margin as f64), width as f64, height as f64);
 
-			let t = thread::spawn(move || calculate_for_range(acc, acc + per_thread_x, offset, mag));
-			threads.push(t);
+		// Refine around the cursor when it's over the window, otherwise the middle of the screen
+		let mouse_pos = self.mouse_pixel(context);
+		let is_mouse_inside = (0.0..self.width as f64).contains(&mouse_pos.x) && (0.0..self.height as f64).contains(&mouse_pos.y);
 
-			accumulated_x += per_thread_x;
+		let focus = if is_mouse_inside { mouse_pos } else { Point2 { x: self.width as f64 / 2.0, y: self.height as f64 / 2.0 } };
+
+		if pass == 0 {
+			self.render_eta = Some((self.generation, Eta::new(width * height)));
 		}
 
-		for t in threads {
-			for params in t.join().expect("thread panicked") {
-				results.push(params);
-			}
+		let settings = IterationSettings {
+			max_iterations: (self.iteration_settings.max_iterations * self.quality.iteration_multiplier()).max(1.0).round(),
+			..self.iteration_settings
+		};
+
+		self.renderer.submit(RenderJob {
+			width,
+			height,
+			// The front frame and the one rendered into both have to fit
+			samples: self.budget.samples_for((width, height), self.quality.samples(), 2),
+			jitter: accumulation::jitter(pass),
+			margin,
+			focus: Point2 { x: focus.x * scale + margin as f64, y: focus.y * scale + margin as f64 },
+			tile_size: self.tile_size,
+			shade_interior: self.shade_interior,
+			boundary: self.boundary,
+			palette_range: self.palette_range,
+			stripes: self.stripes.clone(),
+
+			generation: self.generation,
+			pass,
+
+			viewport,
+			settings,
+			gradient: self.palettes[self.palette_index].clone(),
+
+			priority: Priority::Interactive,
+
+			recycled: self.back_frame.take(),
+		});
+	}
+
+	// Where a frame lands in the window. At rest its margin hangs off each edge, and frames with one
+	// slide along as the view pans, until the next frame arrives or the pan uncovers the window.
+	// Zooms and rotations leave them where they were
+	fn frame_placement(&self, viewport: &Viewport, margin: usize) -> Placement {
+		let (frame_width, frame_height) = viewport.size();
+		let (shown_width, shown_height) = (frame_width - 2.0 * margin as f64, frame_height - 2.0 * margin as f64);
+
+		let scale = [self.width as f64 / shown_width, self.height as f64 / shown_height];
+		let resting = Placement {
+			origin: Point2 { x: (-(margin as f64) * scale[0]) as f32, y: (-(margin as f64) * scale[1]) as f32 },
+			scale: [scale[0] as f32, scale[1] as f32],
+			panned: false,
+		};
+
+		if margin == 0 {
+			return resting;
 		}
-		
-		self.batch.set(results);
+
+		let Some((origin, pan_scale)) = viewport.placement_in(&self.viewport) else {
+			return resting;
+		};
+
+		let is_pan = (pan_scale / scale[0] - 1.0).abs() < PAN_SCALE_TOLERANCE;
+		let covers = origin.x <= 0.5 && origin.y <= 0.5
+			&& origin.x + frame_width * pan_scale >= self.width as f64 - 0.5
+			&& origin.y + frame_height * pan_scale >= self.height as f64 - 0.5;
+
+		let moved = (origin.x as f32 - resting.origin.x).abs() > 0.5 || (origin.y as f32 - resting.origin.y).abs() > 0.5;
+
+		if !is_pan || !covers || !moved {
+			return resting;
+		}
+
+		Placement { origin: Point2 { x: origin.x as f32, y: origin.y as f32 }, scale: [pan_scale as f32; 2], panned: true }
 	}
-}
 
-impl EventHandler for MandelbrotViewer {
-	fn update(&mut self, context: &mut Context) -> Result {
-		while context.time.check_update_time(FPS) {
-			let delta_time = context.time.delta().as_secs_f64();
+	// Whole number of a frame's pixels averaged into each pixel shown, for frames rendered at two or
+	// more times the window's resolution
+	fn downsampling(frame: &Frame, window_width: usize) -> usize {
+		let shown_width = frame.width - 2 * frame.margin;
+		((shown_width as f64 / window_width as f64 + 1e-9).floor() as usize).max(1)
+	}
 
-			for (_key, key_data) in self.movement_data.iter() {
-				if !key_data.is_down {
-					continue;
-				}
+	// Window pixel to frame pixel, None off the frame
+	fn frame_pixel(&self, frame: &Frame, window: Point2<f64>) -> Option<(usize, usize)> {
+		let placement = self.frame_placement(&frame.viewport, frame.margin);
 
-				self.view_offset.x += key_data.velocity.x * delta_time;
-				self.view_offset.y += key_data.velocity.y * delta_time;
+		let x = ((window.x - placement.origin.x as f64) / placement.scale[0] as f64).floor();
+		let y = ((window.y - placement.origin.y as f64) / placement.scale[1] as f64).floor();
 
-				self.has_parameters_changed = true;
+		(x >= 0.0 && y >= 0.0 && (x as usize) < frame.width && (y as usize) < frame.height).then_some((x as usize, y as usize))
+	}
+
+	// Swaps a completed frame in, the old front frame becomes the next render target
+	fn present_frame(&mut self, mut frame: Frame) {
+		// Palette changes made while the frame was rendering still apply
+		let gradient = self.palettes[self.palette_index].clone();
+
+		let stripes_changed = frame.stripes.as_ref().map(Arc::as_ptr) != self.stripes.as_ref().map(Arc::as_ptr);
+
+		if !Arc::ptr_eq(&frame.gradient, &gradient) || frame.palette_range != self.palette_range || stripes_changed {
+			frame.palette_range = self.palette_range;
+			frame.stripes = self.stripes.clone();
+			frame.recolor(gradient);
+		}
+
+		if frame.pass > 0 {
+			self.accumulate_frame(frame);
+			return;
+		}
+
+		if self.render_eta.as_ref().is_some_and(|(generation, _)| frame.generation >= *generation) {
+			self.render_eta = None;
+		}
+
+		if let Some(attract) = &mut self.attract {
+			if attract::is_exhausted(&frame) {
+				attract.autopilot = Autopilot::new();
+				self.viewport = self.default_viewport();
+			} else {
+				attract.autopilot.observe(&frame);
 			}
 		}
 
-		if self.has_parameters_changed {
-			self.construct_batch();
-			self.has_parameters_changed = false;
+		if self.stretch_palette {
+			self.stretch_towards(MandelbrotViewer::escape_range(&frame), frame.settings.max_iterations);
 		}
-		
-		Ok(())
-	}
 
-	fn draw(&mut self, context: &mut Context) -> Result {
-		let mut canvas = graphics::Canvas::from_frame(context, Color::BLACK);
-		canvas.draw(&self.batch, DrawParam::new());
+		if let Some(diff_view) = &mut self.diff_view {
+			diff_view.request(&frame);
+		}
 
-		canvas.finish(context)?;
-		ggez::timer::yield_now();
+		self.accumulation = Some(Accumulation::start(&frame));
+		self.back_frame = self.front_frame.replace(frame);
 
-		Ok(())
+		// Renders allocate afresh rather than keep a spare frame over budget, which even single
+		// samples can be at huge window sizes
+		let front = self.front_frame.as_ref().expect("frame just presented");
+
+		if !self.budget.fits(memory::frame_bytes(front.width, front.height, front.samples).saturating_mul(2)) {
+			self.back_frame = None;
+		}
+
+		self.upload_front_frame();
 	}
 
-	fn key_down_event(&mut self, ctx: &mut Context, input: KeyInput, repeated: bool) -> Result {
-		if repeated {
-			return Ok(())
+	// Passes of a view which has since changed are dropped, the storage is still reused
+	fn accumulate_frame(&mut self, frame: Frame) {
+		if let (Some(accumulation), Some(front_frame)) = (&mut self.accumulation, &mut self.front_frame) {
+			if accumulation.generation == frame.generation && front_frame.generation == frame.generation {
+				accumulation.add(&frame, front_frame);
+				self.back_frame = Some(frame);
+
+				self.upload_front_frame();
+				return;
+			}
 		}
 
-		if let Some(keycode) = input.keycode {
-			if let Some(key_data) = self.movement_data.get_mut(&keycode) {
-				key_data.is_down = true;
+		self.back_frame = Some(frame);
+	}
 
-			} else {
-				match keycode {
-					VirtualKeyCode::R => {
-						self.view_offset = blank_point();
-						self.magnification = 1.0;
-						self.has_parameters_changed = true;
-					},
+	// The texture catches up over the next few updates
+	fn upload_front_frame(&mut self) {
+		if self.front_frame.is_none() {
+			return;
+		}
+
+		self.frame_texture.begin();
+		self.update_contours();
+	}
 
-					VirtualKeyCode::E => {
-						let old_mag = self.magnification;
-						let new_mag = 2.0 * old_mag;
-	
-						let mouse_pos = ctx.mouse.position();
-						let offset = self.view_offset;
+	// Contours follow the boundaries between iteration bands, which approximate equipotentials
+	fn update_contours(&mut self) {
+		self.contour_segments.clear();
 
-						let pivot_x = (offset.x + mouse_pos.x as f64) / old_mag * new_mag;
-						let pivot_y = (offset.y + mouse_pos.y as f64) / old_mag * new_mag;
+		if let (true, Some(frame)) = (self.show_contours, &self.front_frame) {
+			let values: Vec<Option<f64>> = frame.mean_iterations().collect();
+			self.contour_segments = contours::contours(&values, frame.width, frame.height, CONTOUR_SPACING, 0.5);
+		}
+	}
 
-						self.magnification = new_mag;
+	// Draws a tile of the render in progress over the displayed frame. Only possible while the
+	// displayed frame has the same layout, otherwise the texture is left until the whole frame arrives.
+	// Tiles carry no iteration counts and effects need the whole frame, so they're also skipped while a
+	// band is highlighted or effects are on
+	fn upload_tile(&mut self, context: &Context, rendered: RenderedTile) {
+		let Some(frame) = &self.front_frame else {
+			return;
+		};
+
+		if self.display_filter.band.is_some() || self.display_filter.has_effects() || rendered.generation != self.generation {
+			return;
+		}
 
-						self.view_offset.x = pivot_x - (WIDTH as f64) / 2.0;
-						self.view_offset.y = pivot_y - (HEIGHT as f64) / 2.0;
-	
-						self.has_parameters_changed = true;
-					},
+		if (frame.width, frame.height) != (rendered.frame_width, rendered.frame_height) {
+			return;
+		}
 
-					VirtualKeyCode::Q => {
-						let old_mag = self.magnification;
-						let new_mag = (0.5 * old_mag).max(1.0);
+		// Tiles of the new view would land out of place on a frame slid along with a pan, whose
+		// margin covers the edges until the new frame is done
+		if self.frame_placement(&frame.viewport, frame.margin).panned {
+			return;
+		}
 
-						let mouse_pos = ctx.mouse.position();
-						let offset = self.view_offset;
+		self.frame_texture.finish(context, frame, self.display_filter, MandelbrotViewer::downsampling(frame, self.width));
+		self.frame_texture.write_tile(context, &rendered, self.display_filter);
 
-						let pivot_x = (offset.x + mouse_pos.x as f64) / old_mag * new_mag;
-						let pivot_y = (offset.y + mouse_pos.y as f64) / old_mag * new_mag;
+		self.needs_redraw = true;
+	}
 
-						self.magnification = new_mag;
+	fn load_palettes(interior: Color) -> Vec<Arc<Gradient>> {
+		gradient::load_palettes(Path::new(PALETTE_DIR), interior).into_iter().map(Arc::new).collect()
+	}
 
-						self.view_offset.x = pivot_x - (WIDTH as f64) / 2.0;
-						self.view_offset.y = pivot_y - (HEIGHT as f64) / 2.0;
-	
-						self.has_parameters_changed = true;
-					}
-					_ => {}
-				}
+	// Random palettes are regenerated from their name and added to the list
+	pub fn select_palette(&mut self, name: &str) {
+		if !self.palettes.iter().any(|palette| palette.name == name) {
+			if let Some(gradient) = random_palette::regenerate(name) {
+				self.palettes.push(Arc::new(gradient.with_interior(self.interior_color)));
 			}
 		}
 
-		Ok(())
+		self.palette_index = self.palettes.iter().position(|palette| palette.name == name).unwrap_or(0);
+	}
+
+	// Replaces the last random palette, so C doesn't cycle through every one tried
+	fn randomize_palette(&mut self) {
+		self.palettes.retain(|palette| random_palette::seed(&palette.name).is_none());
+		self.select_palette(&random_palette::name(random_palette::new_seed()));
+
+		self.palette_editor = None;
+		self.palette_randomized = Some(self.ticks);
+		self.apply_palette();
+	}
+
+	// Lowest and highest escape count in a frame, None when nothing escaped
+	fn escape_range(frame: &Frame) -> Option<[f64; 2]> {
+		let stats = FrameStats::compute(&frame.iterations, frame.settings.max_iterations);
+		Some([stats.min?, stats.max?.max(stats.min? + 1.0)])
+	}
+
+	// Aims the palette at a frame's escape counts, from wherever it's stretched to now. Frames with
+	// nothing escaping leave it be
+	fn stretch_towards(&mut self, target: Option<[f64; 2]>, max_iterations: f64) {
+		if target.is_some() {
+			self.palette_target = target;
+			self.palette_range = self.palette_range.or(Some([0.0, max_iterations]));
+		}
 	}
 
-	fn key_up_event(&mut self, _ctx: &mut Context, input: KeyInput) -> Result {
-		if let Some(keycode) = input.keycode {
-			if let Some(key_data) = self.movement_data.get_mut(&keycode) {
-				key_data.is_down = false;
+	fn toggle_palette_stretching(&mut self) {
+		self.stretch_palette = !self.stretch_palette;
+		self.palette_range = None;
+		self.palette_target = None;
+
+		if let Some(frame) = &mut self.front_frame {
+			frame.palette_range = None;
+
+			if self.stretch_palette {
+				let (target, max_iterations) = (MandelbrotViewer::escape_range(frame), frame.settings.max_iterations);
+				self.stretch_towards(target, max_iterations);
 			}
 		}
 
+		self.apply_palette();
+	}
+
+	// Moves the stretched palette a tick's worth towards its target, recolouring the displayed frame
+	fn ease_palette_range(&mut self, delta_time: f64) {
+		let (Some(range), Some(target)) = (self.palette_range, self.palette_target) else {
+			return;
+		};
+
+		if range == target {
+			return;
+		}
+
+		let amount = 1.0 - (-delta_time / STRETCH_TIME).exp();
+		let eased = [0, 1].map(|i| range[i] + (target[i] - range[i]) * amount);
+		let is_settled = (0..2).all(|i| (target[i] - eased[i]).abs() <= (target[1] - target[0]) * STRETCH_SETTLED);
+
+		self.palette_range = Some(if is_settled { target } else { eased });
+
+		if let Some(frame) = &mut self.front_frame {
+			frame.palette_range = self.palette_range;
+		}
+
+		self.apply_palette();
+	}
+
+	// Recolors the displayed frame straight away rather than waiting on a render
+	fn apply_palette(&mut self) {
+		let gradient = self.palettes[self.palette_index].clone();
+
+		if let Some(frame) = &mut self.front_frame {
+			frame.stripes = self.stripes.clone();
+			frame.recolor(gradient);
+
+			// Earlier passes had the old colours baked in
+			self.accumulation = Some(Accumulation::start(frame));
+		}
+
+		self.upload_front_frame();
+	}
+
+	// Clicks in the pixel inspector are taken by it, printing the pixel's record and showing it until
+	// the next click
+	fn inspect_pixel(&mut self, mouse: Point2<f64>) -> bool {
+		if !self.inspecting {
+			return false;
+		}
+
+		// The frame can be of another size than the window while it catches up, and past its edges
+		let inspection = self.front_frame.as_ref().and_then(|frame| {
+			let (x, y) = self.frame_pixel(frame, mouse)?;
+			inspect::inspect(frame, x, y)
+		});
+
+		if let Some(inspection) = &inspection {
+			println!("{}\n", inspection.describe());
+		}
+
+		self.inspection = inspection;
+		self.needs_redraw = true;
+
+		true
+	}
+
+	// Read from disk each time it's opened, so locations copied in while running show up
+	fn toggle_library(&mut self) {
+		self.needs_redraw = true;
+
+		if self.library_view.take().is_some() {
+			return;
+		}
+
+		match Library::default_dir() {
+			Some(dir) => self.library_view = Some(LibraryView::new(Library::open(&dir))),
+			None => eprintln!("could not open the location library: no data directory"),
+		}
+	}
+
+	fn library_action(&mut self, action: library_view::Action) {
+		self.needs_redraw = true;
+
+		match action {
+			library_view::Action::Nothing => {},
+			library_view::Action::Close => self.library_view = None,
+
+			library_view::Action::Go(view) => {
+				view.restore(&mut self.viewport, &mut self.iteration_settings);
+				self.select_palette(&view.palette);
+				self.apply_palette();
+
+				self.library_view = None;
+				self.has_parameters_changed = true;
+			},
+
+			// The thumbnail is of the view as it's shown, which is blank before the first frame is in
+			library_view::Action::Save(name) => {
+				let view = Session::capture(&self.viewport, &self.iteration_settings, &self.palettes[self.palette_index].name);
+				let frame = self.front_frame.as_ref().map(|frame| frame.shown());
+				let (pixels, size) = frame.as_ref().map_or((&[][..], (0, 0)), |frame| (&frame.pixels[..], (frame.width, frame.height)));
+
+				if let Some(library) = &mut self.library_view {
+					library.add(&name, view, pixels, size);
+				}
+			},
+		}
+	}
+
+	// Started on the view's own backend when none is configured, or when the configured one fails
+	fn toggle_diff_view(&mut self) {
+		self.needs_redraw = true;
+
+		if self.diff_view.take().is_some() {
+			return;
+		}
+
+		let backend = match self.diff_backend.as_str() {
+			"" => self.backend.clone(),
+			name => backend::create(name).unwrap_or_else(|e| {
+				eprintln!("could not start the {} backend to compare against, using the view's: {}", name, e);
+				self.backend.clone()
+			}),
+		};
+
+		let mut diff_view = DiffView::new(backend, self.diff_iterations);
+
+		if let Some(frame) = &self.front_frame {
+			diff_view.request(frame);
+		}
+
+		self.diff_view = Some(diff_view);
+	}
+
+	fn toggle_palette_editor(&mut self) {
+		self.palette_editor = match self.palette_editor {
+			Some(_) => None,
+			None => Some(PaletteEditor::new(&self.palettes[self.palette_index])),
+		};
+
+		self.dragging_stop = false;
+		self.stop_prompt = None;
+		self.needs_redraw = true;
+	}
+
+	// Along the bottom of the view
+	fn editor_bar(&self) -> Rect {
+		Rect::new(20.0, self.height as f32 - 90.0, (self.width as f32 - 40.0).max(1.0), 24.0)
+	}
+
+	// Shows the edited palette in place of the one it started from, recolouring from the escape
+	// counts already rendered
+	fn apply_edited_palette(&mut self) {
+		if let Some(editor) = &self.palette_editor {
+			self.palettes[self.palette_index] = Arc::new(editor.gradient().clone());
+			self.apply_palette();
+		}
+	}
+
+	// Clicking the bar or a handle picks the stop there, or adds one, and starts dragging it.
+	// Returns whether the click was the editor's
+	fn click_palette_editor(&mut self, mouse: Point2<f64>) -> bool {
+		let bar = self.editor_bar();
+		let is_on_editor = (bar.x - overlay::HANDLE_SIZE..bar.x + bar.w + overlay::HANDLE_SIZE).contains(&(mouse.x as f32))
+			&& (bar.y..bar.y + bar.h + overlay::HANDLE_SIZE * 2.0).contains(&(mouse.y as f32));
+
+		let Some(editor) = self.palette_editor.as_mut().filter(|_| is_on_editor) else {
+			return false;
+		};
+
+		let position = (mouse.x as f32 - bar.x) / bar.w;
+
+		match editor.stop_near(position, overlay::HANDLE_SIZE / bar.w) {
+			Some(stop) => editor.select(Some(stop)),
+			None => editor.add(position),
+		}
+
+		self.dragging_stop = true;
+		self.stop_prompt = None;
+		self.apply_edited_palette();
+
+		true
+	}
+
+	// Follows the cursor while a stop is held, each tick so replays drag the same way
+	fn drag_stop(&mut self, context: &Context) {
+		if !self.dragging_stop {
+			return;
+		}
+
+		let bar = self.editor_bar();
+		let position = ((self.mouse_pixel(context).x as f32 - bar.x) / bar.w).clamp(0.0, 1.0);
+
+		let Some(editor) = &mut self.palette_editor else {
+			return;
+		};
+
+		if editor.selected().is_some_and(|stop| editor.gradient().stops()[stop].0 != position) {
+			editor.move_selected(position);
+			self.apply_edited_palette();
+		}
+	}
+
+	fn save_edited_palette(&self) {
+		if let Some(editor) = &self.palette_editor {
+			match editor.gradient().save(Path::new(PALETTE_DIR)) {
+				Ok(path) => println!("saved palette to {}", path.display()),
+				Err(e) => eprintln!("could not save palette: {}", e),
+			}
+		}
+	}
+
+	// Enter recolours the selected stop with the colour typed in
+	fn edit_stop_prompt(&mut self, keycode: VirtualKeyCode) {
+		let Some(prompt) = &mut self.stop_prompt else {
+			return;
+		};
+
+		match keycode {
+			VirtualKeyCode::Back => {
+				prompt.pop();
+			},
+
+			VirtualKeyCode::Escape => self.stop_prompt = None,
+
+			VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => match gradient::parse_hex(prompt) {
+				Ok(color) => {
+					if let Some(editor) = &mut self.palette_editor {
+						editor.recolor_selected(color);
+					}
+
+					self.stop_prompt = None;
+					self.apply_edited_palette();
+				},
+				Err(e) => eprintln!("invalid colour {}: {}", prompt, e),
+			},
+
+			_ => {},
+		}
+
+		self.needs_redraw = true;
+	}
+
+	fn reload_palettes(&mut self) {
+		let current = self.palettes[self.palette_index].name.clone();
+
+		self.palettes = MandelbrotViewer::load_palettes(self.interior_color);
+		self.select_palette(&current);
+
+		self.apply_palette();
+	}
+
+	// In physical window pixels, wherever the pointer is for now
+	fn cursor(&self, context: &Context) -> Point2<f32> {
+		self.cursor_override.or(self.crosshair).unwrap_or_else(|| context.mouse.position())
+	}
+
+	// ggez reports the cursor in physical window pixels, this maps it onto the pixel buffer
+	fn mouse_pixel(&self, context: &Context) -> Point2<f64> {
+		let mouse_pos = self.cursor(context);
+		let (drawable_width, drawable_height) = context.gfx.drawable_size();
+
+		Point2 {
+			x: mouse_pos.x as f64 * (self.window_width as f64 / drawable_width as f64),
+			y: mouse_pos.y as f64 * (self.height as f64 / drawable_height as f64),
+		}
+	}
+
+	fn toggle_frame_recording(&mut self) {
+		if let Some(recorder) = self.frame_recorder.take() {
+			MandelbrotViewer::finish_frame_recording(recorder);
+		} else {
+			let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_millis()).unwrap_or(0);
+			let dir = Path::new(RECORDING_DIR).join(format!("recording-{}", timestamp));
+
+			match FrameRecorder::start(&dir) {
+				Ok(recorder) => self.frame_recorder = Some(recorder),
+				Err(e) => eprintln!("could not start recording: {}", e),
+			}
+		}
+
+		self.needs_redraw = true;
+	}
+
+	fn finish_frame_recording(recorder: FrameRecorder) {
+		let dir = recorder.dir().to_path_buf();
+
+		match recorder.finish() {
+			Ok(count) => eprintln!("recorded {} frames into {}", count, dir.display()),
+			Err(e) => eprintln!("could not save recorded frames: {}", e),
+		}
+	}
+
+	// The displayed frame at window resolution with the display filter and effects applied, overlays
+	// aren't included. Taken every tick so the frames play back at the tick rate whatever the draw rate is
+	fn capture_frame(&mut self) {
+		let (Some(recorder), Some(frame)) = (&self.frame_recorder, &self.front_frame) else {
+			return;
+		};
+
+		let frame = frame.shown();
+		let factor = MandelbrotViewer::downsampling(&frame, self.width);
+
+		let pixels = capture::downscale(&self.display_filter.apply_frame(&frame), (frame.width, frame.height), factor);
+		let pixels = capture::upscale(&pixels, capture::downscaled_size((frame.width, frame.height), factor), (self.width, self.height));
+		let session = Session::capture(&frame.viewport, &frame.settings, &frame.gradient.name);
+
+		if !recorder.push(pixels, (self.width, self.height), session) {
+			if let Some(recorder) = self.frame_recorder.take() {
+				MandelbrotViewer::finish_frame_recording(recorder);
+			}
+
+			self.needs_redraw = true;
+		}
+	}
+
+	fn add_keyframe(&mut self) {
+		let time = if self.animation.is_empty() { 0.0 } else { self.animation.duration() + KEYFRAME_SPACING };
+		let view = Session::capture(&self.viewport, &self.iteration_settings, &self.palettes[self.palette_index].name);
+
+		self.animation.add(Keyframe { time, view, easing: Easing::Smooth });
+		self.needs_redraw = true;
+	}
+
+	// Rendered on a background thread at window size, so exploring can carry on meanwhile
+	fn export_frames(&self, name: &str, views: Vec<Session>) {
+		let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_millis()).unwrap_or(0);
+		let dir = Path::new(ANIMATION_DIR).join(format!("{}-{}", name, timestamp));
+
+		let palettes = self.palettes.clone();
+		let job = self.jobs.start(name);
+		let name = name.to_string();
+
+		let options = FrameExport {
+			size: (self.width, self.height),
+			samples: self.quality.samples(),
+			iteration_multiplier: self.quality.iteration_multiplier(),
+			bit_depth: self.bit_depth,
+			shade_interior: self.shade_interior,
+			boundary: self.boundary,
+			stripes: self.stripes.clone(),
+			effects: self.display_filter.effects,
+			transparency: self.transparency,
+			budget: job.budget(),
+			cancelled: Some(job.cancelled()),
+		};
+
+		thread::spawn(move || {
+			let _job = job;
+
+			match animation::render_frames(views, &dir, &options, &palettes) {
+				Ok(count) => eprintln!("rendered {} frames into {}", count, dir.display()),
+				Err(e) if e.kind() == io::ErrorKind::Interrupted => eprintln!("stopped the {} export, the frames so far are in {}", name, dir.display()),
+				Err(e) => eprintln!("could not export animation: {}", e),
+			}
+		});
+	}
+
+	// The current view at the primary monitor's resolution, rendered on a background thread like
+	// other exports
+	fn set_wallpaper(&self, context: &Context) {
+		let window = context.gfx.window();
+		let size = window.primary_monitor().or_else(|| window.current_monitor()).map(|monitor| monitor.size());
+
+		let Some(size) = size.filter(|size| size.width > 0 && size.height > 0) else {
+			eprintln!("could not find a monitor to size the wallpaper for");
+			return;
+		};
+
+		let (width, height) = (size.width as usize, size.height as usize);
+
+		let mut viewport = Viewport::new(width as f64, height as f64);
+		let mut settings = self.iteration_settings;
+		Session::capture(&self.viewport, &self.iteration_settings, "").restore(&mut viewport, &mut settings);
+
+		settings.max_iterations = (settings.max_iterations * self.quality.iteration_multiplier()).max(1.0).round();
+
+		let job = RenderJob {
+			tile_size: self.tile_size,
+			shade_interior: self.shade_interior,
+			boundary: self.boundary,
+			stripes: self.stripes.clone(),
+			..RenderJob::new(width, height, self.quality.samples(), viewport, settings, self.palettes[self.palette_index].clone())
+		};
+
+		let effects = self.display_filter.effects;
+		// A single render with nothing between frames to stop at, so closing waits for it
+		let running = self.jobs.start("wallpaper");
+
+		thread::spawn(move || match wallpaper::render_and_set(job, effects, running.budget()) {
+			Ok(path) => eprintln!("set the wallpaper to {}", path.display()),
+			Err(e) => eprintln!("could not set the wallpaper: {}", e),
+		});
+	}
+
+	// Julia sets are framed like the Mandelbrot set's default view, which is kept to return to
+	fn toggle_julia(&mut self, context: &Context) {
+		self.morph = None;
+
+		match self.iteration_settings.julia {
+			Some(_) => {
+				self.iteration_settings.julia = None;
+				self.viewport = self.mandelbrot_viewport.take().unwrap_or_else(|| self.default_viewport());
+			},
+
+			None => {
+				let mouse = self.mouse_pixel(context);
+				let is_mouse_inside = (0.0..self.width as f64).contains(&mouse.x) && (0.0..self.height as f64).contains(&mouse.y);
+
+				let c = if is_mouse_inside { self.viewport.pixel_to_complex(mouse.x, mouse.y) } else { self.viewport.center() };
+
+				self.iteration_settings.julia = Some(c);
+				self.mandelbrot_viewport = Some(self.viewport);
+				self.viewport = Viewport::new(self.width as f64, self.height as f64);
+			},
+		}
+
+		self.has_parameters_changed = true;
+	}
+
+	fn current_tab(&self) -> Tab {
+		Tab {
+			viewport: self.viewport,
+			settings: self.iteration_settings,
+			palette: self.palettes[self.palette_index].name.clone(),
+			mandelbrot_viewport: self.mandelbrot_viewport,
+		}
+	}
+
+	// Tabs render on the same threads, so only the shown one is rendered and the rest wait where
+	// they were left
+	fn switch_tab(&mut self, index: usize) {
+		if self.tabs.len() < 2 {
+			return;
+		}
+
+		self.tabs[self.tab] = self.current_tab();
+		self.show_tab(index % self.tabs.len());
+	}
+
+	// Shows a tab in place of the current view, which is dropped
+	fn show_tab(&mut self, index: usize) {
+		let tab = &self.tabs[index];
+		let (width, height) = (self.width as f64, self.height as f64);
+
+		self.viewport = tab.viewport;
+		self.viewport.resize(width, height);
+		self.iteration_settings = tab.settings;
+		self.mandelbrot_viewport = tab.mandelbrot_viewport.map(|mut viewport| {
+			viewport.resize(width, height);
+			viewport
+		});
+
+		let palette = tab.palette.clone();
+		self.select_palette(&palette);
+
+		self.tab = index;
+		self.morph = None;
+		self.drag_start = None;
+		self.region_prompt = None;
+		self.has_parameters_changed = true;
+	}
+
+	// Opens a tab where this one is, to carry on exploring from without losing the place
+	fn open_tab(&mut self) {
+		if self.tabs.is_empty() {
+			self.tabs.push(self.current_tab());
+		}
+
+		self.tabs.insert(self.tab + 1, self.current_tab());
+		self.switch_tab(self.tab + 1);
+	}
+
+	// The last tab stays open
+	fn close_tab(&mut self) {
+		if self.tabs.len() < 2 {
+			return;
+		}
+
+		self.tabs.remove(self.tab);
+		self.show_tab(self.tab.min(self.tabs.len() - 1));
+	}
+
+	// The formula's preset in the parameter plane, Julia sets are all framed the same
+	fn default_viewport(&self) -> Viewport {
+		match self.iteration_settings.julia {
+			Some(_) => Viewport::new(self.width as f64, self.height as f64),
+			None => self.iteration_settings.formula.preset().viewport(self.width as f64, self.height as f64),
+		}
+	}
+
+	// Back to the default view, with the configured settings raised to the formula's preset
+	fn reset_view(&mut self) {
+		let settings = IterationSettings::for_formula(&self.config, self.iteration_settings.formula);
+
+		self.iteration_settings = IterationSettings { julia: self.iteration_settings.julia, ..settings };
+		self.viewport = self.default_viewport();
+		self.has_parameters_changed = true;
+	}
+
+	// Circles the current constant, stopping puts it back where it started
+	fn toggle_morph(&mut self) {
+		match (self.morph.take(), self.iteration_settings.julia) {
+			(Some((morph, _)), _) => {
+				self.iteration_settings.julia = Some(morph.center);
+				self.has_parameters_changed = true;
+			},
+
+			(None, Some(c)) => self.morph = Some((self.julia_morph(c), self.ticks)),
+			(None, None) => (),
+		}
+	}
+
+	// Animations run on ticks rather than the clock, so they play out the same in a replay
+	fn seconds_since(&self, tick: u64) -> f64 {
+		(self.ticks - tick) as f64 / self.tick_rate as f64
+	}
+
+	// Left alone with nothing moving, so drawing slows down and refinement waits for the next input.
+	// Recording frames keeps it awake, as every tick's frame is wanted
+	fn is_dormant(&self) -> bool {
+		self.idle_timeout.is_some_and(|timeout| self.seconds_since(self.last_input) >= timeout)
+			&& self.playback_start.is_none()
+			&& self.morph.is_none()
+			&& self.frame_recorder.is_none()
+			&& !self.movement_data.values().any(|key_data| key_data.is_down)
+			&& self.crosshair_keys.is_empty()
+			&& self.attract.is_none()
+	}
+
+	// Takes over once nothing else is driving the view. Replays are left alone so they play out the
+	// same whatever the config says
+	fn update_attract(&mut self, delta_time: f64) {
+		let Some(attract) = &mut self.attract else {
+			let is_free = self.replay.is_none() && self.playback_start.is_none() && self.morph.is_none() && self.frame_recorder.is_none();
+
+			if is_free && self.attract_after.is_some_and(|after| self.seconds_since(self.last_input) >= after) {
+				self.attract = Some(Attract {
+					autopilot: Autopilot::new(),
+					home: Session::capture(&self.viewport, &self.iteration_settings, &self.palettes[self.palette_index].name),
+					next_palette: ATTRACT_PALETTE_TIME,
+				});
+			}
+
+			return;
+		};
+
+		attract.autopilot.step(&mut self.viewport, delta_time);
+		self.iteration_settings.max_iterations = attract::iterations_for(attract.home.max_iterations, self.viewport.magnification());
+
+		if attract.autopilot.time() >= attract.next_palette {
+			attract.next_palette += ATTRACT_PALETTE_TIME;
+
+			self.palette_index = (self.palette_index + 1) % self.palettes.len();
+			self.apply_palette();
+		}
+
+		self.has_parameters_changed = true;
+	}
+
+	// Puts the view back as it was before the dive. Returns whether there was one
+	fn stop_attract(&mut self) -> bool {
+		let Some(attract) = self.attract.take() else {
+			return false;
+		};
+
+		attract.home.restore(&mut self.viewport, &mut self.iteration_settings);
+		self.select_palette(&attract.home.palette);
+		self.apply_palette();
+
+		self.has_parameters_changed = true;
+
+		true
+	}
+
+	// Only offered when the crash happened somewhere other than where this run starts
+	pub fn offer_recovery(&mut self, recovered: Session) {
+		if recovered != self.current_session() {
+			self.recovery = Some(recovered);
+		}
+	}
+
+	fn answer_recovery(&mut self, restore: bool) {
+		let Some(recovered) = self.recovery.take() else {
+			return;
+		};
+
+		if restore {
+			recovered.restore(&mut self.viewport, &mut self.iteration_settings);
+			self.select_palette(&recovered.palette);
+			self.apply_palette();
+
+			self.has_parameters_changed = true;
+		}
+
+		self.needs_redraw = true;
+	}
+
+	// The view as it would be saved, from before any dive
+	fn current_session(&self) -> Session {
+		match &self.attract {
+			Some(attract) => attract.home.clone(),
+			None => Session::capture(&self.viewport, &self.iteration_settings, &self.palettes[self.palette_index].name),
+		}
+	}
+
+	// Enter closes now, stopping the exports, and Escape keeps the window open
+	fn answer_quit_prompt(&mut self, context: &mut Context, keycode: VirtualKeyCode) {
+		match keycode {
+			VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => {
+				self.quit_confirmed = true;
+				context.request_quit();
+			},
+			VirtualKeyCode::Escape => self.quit_prompt = false,
+			_ => return,
+		}
+
+		self.needs_redraw = true;
+	}
+
+	// Keeps the recovery file up to date, leaving it alone while it's still being offered
+	fn autosave(&mut self) {
+		if self.recovery.is_some() || self.seconds_since(self.last_autosave) < AUTOSAVE_TIME {
+			return;
+		}
+
+		self.last_autosave = self.ticks;
+
+		let view = self.current_session();
+
+		if self.autosaved.as_ref() == Some(&view) {
+			return;
+		}
+
+		// Kept even on failure, so it's reported once rather than every few seconds
+		if let Err(e) = view.save_recovery() {
+			eprintln!("could not save the recovery file: {}", e);
+		}
+
+		self.autosaved = Some(view);
+	}
+
+	// Shared with background exports, so fewer threads leaves cores free while one runs. Raising
+	// it stops at the core count, though a configured count above that is kept
+	fn change_threads(&mut self, more: bool) {
+		let queue = WorkQueue::shared();
+		let threads = queue.threads();
+
+		queue.set_threads(match more {
+			true if threads < available_cores() => threads + 1,
+			true => threads,
+			false => threads.saturating_sub(1).max(1),
+		});
+
+		self.threads_changed = Some(self.ticks);
+		self.needs_redraw = true;
+	}
+
+	fn change_render_scale(&mut self, up: bool) {
+		self.render_scale = quality::step_render_scale(self.render_scale, up);
+
+		self.render_scale_changed = Some(self.ticks);
+		self.has_parameters_changed = true;
+		self.needs_redraw = true;
+	}
+
+	fn julia_morph(&self, center: Complex) -> JuliaMorph {
+		JuliaMorph { center, radius: MORPH_RADIUS / self.mandelbrot_viewport.map_or(1.0, |viewport| viewport.magnification()), period: MORPH_PERIOD }
+	}
+
+	// The band under the cursor, or the lowest band in the frame when the cursor isn't over one
+	fn starting_band(&self, context: &Context) -> Option<u32> {
+		let frame = self.front_frame.as_ref()?;
+		let iterations: Vec<Option<f64>> = frame.mean_iterations().collect();
+
+		let under_mouse = self.frame_pixel(frame, self.mouse_pixel(context))
+			.and_then(|(x, y)| iterations[y * frame.width + x]);
+
+		let lowest = || iterations.iter().flatten().copied().reduce(f64::min);
+
+		Some(under_mouse.or_else(lowest).map_or(0, |iterations| iterations.floor() as u32))
+	}
+
+	// Keys typed while the external angle prompt is open go to it rather than the viewer
+	fn edit_ray_prompt(&mut self, keycode: VirtualKeyCode) {
+		let Some(prompt) = &mut self.ray_prompt else {
+			return;
+		};
+
+		match keycode {
+			VirtualKeyCode::Back => {
+				prompt.pop();
+			},
+
+			VirtualKeyCode::Escape => self.ray_prompt = None,
+
+			VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => {
+				if prompt.is_empty() {
+					self.rays.clear();
+					self.ray_prompt = None;
+				} else {
+					match prompt.parse::<Angle>() {
+						Ok(angle) => {
+							self.rays.push(rays::trace_ray(angle));
+							self.ray_prompt = None;
+						},
+						Err(e) => eprintln!("invalid external angle {}: {}", prompt, e),
+					}
+				}
+			},
+
+			_ => {},
+		}
+
+		self.needs_redraw = true;
+	}
+
+	// Enter frames the region typed in, which a drag fills in to start from
+	fn edit_region_prompt(&mut self, keycode: VirtualKeyCode) {
+		let Some(prompt) = &mut self.region_prompt else {
+			return;
+		};
+
+		match keycode {
+			VirtualKeyCode::Back => {
+				prompt.pop();
+			},
+
+			VirtualKeyCode::Escape => self.region_prompt = None,
+
+			VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => match prompt.parse::<Region>() {
+				Ok(region) => {
+					self.viewport = region.frame(&self.viewport);
+					self.region_prompt = None;
+					self.has_parameters_changed = true;
+				},
+				Err(e) => eprintln!("invalid region {}: {}", prompt, e),
+			},
+
+			_ => {},
+		}
+
+		self.needs_redraw = true;
+	}
+
+	fn open_escape_prompt(&mut self) {
+		let (bailout, z) = (self.iteration_settings.bailout, self.iteration_settings.initial_z);
+
+		self.escape_prompt = Some(format!("{}, {}, {}", bailout, z.real(), z.imaginary()));
+		self.needs_redraw = true;
+	}
+
+	// Enter applies the bailout and starting z typed in, as the config's bailout and initial_z would
+	fn edit_escape_prompt(&mut self, keycode: VirtualKeyCode) {
+		let Some(prompt) = &mut self.escape_prompt else {
+			return;
+		};
+
+		match keycode {
+			VirtualKeyCode::Back => {
+				prompt.pop();
+			},
+
+			VirtualKeyCode::Escape => self.escape_prompt = None,
+
+			VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter => match renderer::parse_escape(prompt) {
+				Ok((bailout, initial_z)) => {
+					self.iteration_settings.bailout = bailout;
+					self.iteration_settings.initial_z = initial_z;
+					self.escape_prompt = None;
+					self.has_parameters_changed = true;
+				},
+				Err(e) => eprintln!("invalid bailout and starting z {}: {}", prompt, e),
+			},
+
+			_ => {},
+		}
+
+		self.needs_redraw = true;
+	}
+
+	// Shift zooms in finer steps, for framing a feature precisely
+	fn key_down(&mut self, ctx: &mut Context, keycode: VirtualKeyCode, mods: KeyMods) {
+		if let Some(library) = &mut self.library_view {
+			let action = library.key(keycode, mods, (self.width as f32, self.height as f32));
+			return self.library_action(action);
+		}
+
+		if self.ray_prompt.is_some() {
+			self.edit_ray_prompt(keycode);
+			return;
+		}
+
+		if self.region_prompt.is_some() {
+			self.edit_region_prompt(keycode);
+			return;
+		}
+
+		if self.stop_prompt.is_some() {
+			self.edit_stop_prompt(keycode);
+			return;
+		}
+
+		if self.escape_prompt.is_some() {
+			self.edit_escape_prompt(keycode);
+			return;
+		}
+
+		if self.recovery.is_some() && matches!(keycode, VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter | VirtualKeyCode::Escape) {
+			self.answer_recovery(keycode != VirtualKeyCode::Escape);
+			return;
+		}
+
+		if self.quit_prompt {
+			self.answer_quit_prompt(ctx, keycode);
+			return;
+		}
+
+		// Tab shortcuts come first, as W would otherwise move the view
+		if mods.contains(KeyMods::CTRL) {
+			match keycode {
+				VirtualKeyCode::Tab if mods.contains(KeyMods::SHIFT) => return self.switch_tab(self.tab + self.tabs.len().max(1) - 1),
+				VirtualKeyCode::Tab => return self.switch_tab(self.tab + 1),
+				VirtualKeyCode::T => return self.open_tab(),
+				VirtualKeyCode::W => return self.close_tab(),
+				VirtualKeyCode::S => {
+					// Claimed with the editor closed too, where it would otherwise pan the view
+					if self.palette_editor.is_some() {
+						self.save_edited_palette();
+					}
+
+					return;
+				},
+				VirtualKeyCode::R => return self.randomize_palette(),
+				VirtualKeyCode::D => return self.toggle_diff_view(),
+				VirtualKeyCode::L => return self.toggle_library(),
+				VirtualKeyCode::B => return self.select_region(ctx),
+				VirtualKeyCode::E => return self.open_escape_prompt(),
+				_ => {},
+			}
+		}
+
+		if let Some(key_data) = self.movement_data.get_mut(&keycode) {
+			key_data.is_down = true;
+
+		} else {
+			match keycode {
+				VirtualKeyCode::R => self.reset_view(),
+
+				VirtualKeyCode::P if mods.contains(KeyMods::SHIFT) => self.set_wallpaper(ctx),
+
+				VirtualKeyCode::P => {
+					if let Some(frame) = &self.front_frame {
+						let frame = frame.shown();
+						let session = Session::capture(&frame.viewport, &frame.settings, &frame.gradient.name);
+
+						if let Err(e) = export::save_screenshot(&frame, self.display_filter, self.transparency, self.bit_depth, &session) {
+							eprintln!("could not save screenshot: {}", e);
+						}
+					}
+				},
+
+				VirtualKeyCode::I if mods.contains(KeyMods::SHIFT) => {
+					self.inspecting = !self.inspecting;
+					self.inspection = None;
+				},
+
+				VirtualKeyCode::I => {
+					if let Some(frame) = &self.front_frame {
+						if let Err(e) = export::save_iterations(&frame.shown()) {
+							eprintln!("could not save iterations: {}", e);
+						}
+					}
+				},
+
+				VirtualKeyCode::C if mods.contains(KeyMods::SHIFT) => self.toggle_palette_stretching(),
+
+				VirtualKeyCode::C => {
+					self.palette_index = (self.palette_index + 1) % self.palettes.len();
+					self.apply_palette();
+				},
+
+				VirtualKeyCode::H => {
+					self.show_histogram = !self.show_histogram;
+					self.needs_redraw = true;
+				},
+
+				VirtualKeyCode::L => {
+					self.show_contours = !self.show_contours;
+					self.update_contours();
+					self.needs_redraw = true;
+				},
+
+				VirtualKeyCode::Y if mods.contains(KeyMods::SHIFT) => {
+					self.boundary = !self.boundary;
+					self.has_parameters_changed = true;
+				},
+
+				VirtualKeyCode::Y => {
+					self.shade_interior = !self.shade_interior;
+					self.has_parameters_changed = true;
+				},
+
+				VirtualKeyCode::O => {
+					self.show_outlines = !self.show_outlines;
+					self.needs_redraw = true;
+				},
+
+				VirtualKeyCode::B if mods.contains(KeyMods::SHIFT) => {
+					self.stripes = match self.stripes {
+						Some(_) => None,
+						None => Some(find_palette(&self.stripe_palette, self.interior_color)),
+					};
+
+					self.apply_palette();
+				},
+
+				VirtualKeyCode::B => {
+					self.display_filter.grayscale = !self.display_filter.grayscale;
+					self.upload_front_frame();
+				},
+
+				VirtualKeyCode::N => {
+					self.display_filter.inverted = !self.display_filter.inverted;
+					self.upload_front_frame();
+				},
+
+				VirtualKeyCode::K => {
+					self.display_filter.band = match self.display_filter.band {
+						Some(_) => None,
+						None => self.starting_band(ctx),
+					};
+
+					self.upload_front_frame();
+				},
+
+				VirtualKeyCode::LBracket | VirtualKeyCode::RBracket => {
+					if let Some(band) = self.display_filter.band {
+						let max_band = self.iteration_settings.max_iterations as u32;
+
+						self.display_filter.band = Some(match keycode {
+							VirtualKeyCode::LBracket => band.saturating_sub(1),
+							_ => (band + 1).min(max_band),
+						});
+
+						self.upload_front_frame();
+					}
+				},
+
+				VirtualKeyCode::F5 => self.add_keyframe(),
+
+				VirtualKeyCode::F6 => {
+					self.animation.remove_last();
+					self.needs_redraw = true;
+				},
+
+				VirtualKeyCode::F7 => {
+					self.playback_start = match self.playback_start {
+						None if !self.animation.is_empty() => Some(self.ticks),
+						_ => None,
+					};
+
+					self.needs_redraw = true;
+				},
+
+				VirtualKeyCode::F8 if !self.animation.is_empty() => {
+					self.export_frames("animation", self.animation.views(ANIMATION_FRAME_RATE).collect());
+				},
+
+				VirtualKeyCode::F9 => self.toggle_frame_recording(),
+
+				VirtualKeyCode::F10 => self.toggle_linked_view(),
+
+				VirtualKeyCode::F11 => self.toggle_palette_editor(),
+
+				VirtualKeyCode::F12 => {
+					self.display_filter.effects = match self.display_filter.effects {
+						Some(_) => None,
+						None => Some(self.effects),
+					};
+
+					self.upload_front_frame();
+				},
+
+				VirtualKeyCode::Delete | VirtualKeyCode::Back if self.palette_editor.is_some() => {
+					if let Some(editor) = &mut self.palette_editor {
+						editor.remove_selected();
+					}
+
+					self.apply_edited_palette();
+				},
+
+				VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter if self.palette_editor.as_ref().is_some_and(|editor| editor.selected().is_some()) => {
+					let editor = self.palette_editor.as_ref().unwrap();
+					let (_, color) = editor.gradient().stops()[editor.selected().unwrap()];
+
+					self.stop_prompt = Some(gradient::to_hex(color));
+					self.needs_redraw = true;
+				},
+
+				VirtualKeyCode::J => self.toggle_julia(ctx),
+
+				VirtualKeyCode::M if mods.contains(KeyMods::SHIFT) => {
+					if let Some(c) = self.iteration_settings.julia {
+						let morph = self.morph.map_or_else(|| self.julia_morph(c), |(morph, _)| morph);
+						let view = Session::capture(&self.viewport, &self.iteration_settings, &self.palettes[self.palette_index].name);
+
+						self.export_frames("morph", morph.views(&view, ANIMATION_FRAME_RATE).collect());
+					}
+				},
+
+				VirtualKeyCode::M => self.toggle_morph(),
+
+				VirtualKeyCode::F1 => {
+					self.show_about = !self.show_about;
+					self.needs_redraw = true;
+				},
+
+				VirtualKeyCode::F2 => {
+					self.show_grid = !self.show_grid;
+					self.needs_redraw = true;
+				},
+
+				VirtualKeyCode::F3 => {
+					self.show_stats = !self.show_stats;
+					self.needs_redraw = true;
+				},
+
+				// A Julia constant picked from one formula means nothing to the next, so this returns to
+				// the parameter plane
+				VirtualKeyCode::F => {
+					self.morph = None;
+					self.mandelbrot_viewport = None;
+
+					self.iteration_settings.formula = self.iteration_settings.formula.next();
+					self.iteration_settings.julia = None;
+					self.reset_view();
+				},
+
+				VirtualKeyCode::U => {
+					self.julia_preview = match self.julia_preview {
+						Some(_) => None,
+						None => Some(JuliaPreview::new()),
+					};
+
+					self.needs_redraw = true;
+				},
+
+				VirtualKeyCode::F4 => {
+					let (width, height) = ctx.gfx.drawable_size();
+
+					self.crosshair = match self.crosshair {
+						Some(_) => None,
+						None => Some(Point2 { x: width / 2.0, y: height / 2.0 }),
+					};
+
+					self.crosshair_keys.clear();
+					self.needs_redraw = true;
+				},
+
+				VirtualKeyCode::Up | VirtualKeyCode::Down | VirtualKeyCode::Left | VirtualKeyCode::Right if self.crosshair.is_some() => {
+					self.crosshair_keys.insert(keycode);
+				},
+
+				// The keyboard's middle click and double click
+				VirtualKeyCode::Space if self.crosshair.is_some() => self.recenter_on_mouse(ctx),
+
+				// The inspector takes it as a click, as it does the mouse's
+				VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter if self.crosshair.is_some() && self.inspecting => {
+					self.inspect_pixel(self.mouse_pixel(ctx));
+				},
+
+				VirtualKeyCode::Return | VirtualKeyCode::NumpadEnter if self.crosshair.is_some() => {
+					// As both clicks of a double click would
+					self.pick_linked_c(self.mouse_pixel(ctx));
+
+					let new_mag = match mods.contains(KeyMods::SHIFT) {
+						true => (self.viewport.magnification() / self.zoom_factor).max(1.0),
+						false => self.zoom_factor * self.viewport.magnification(),
+					};
+
+					self.zoom_to_mouse(ctx, new_mag);
+				},
+
+				VirtualKeyCode::Comma | VirtualKeyCode::Period => self.change_threads(keycode == VirtualKeyCode::Period),
+				VirtualKeyCode::Minus | VirtualKeyCode::Equals => self.change_render_scale(keycode == VirtualKeyCode::Equals),
+
+				// Starts from the default view, the region around the origin was at infinity before
+				VirtualKeyCode::V => {
+					let inverted = !self.viewport.inverted();
+
+					self.viewport = Viewport::new(self.width as f64, self.height as f64);
+					self.viewport.set_inverted(inverted);
+
+					self.has_parameters_changed = true;
+				},
+
+				VirtualKeyCode::T => {
+					self.ray_prompt = Some(String::new());
+					self.needs_redraw = true;
+				},
+
+				VirtualKeyCode::G => {
+					self.quality = self.quality.next();
+					let quality = self.locale.text(&format!("quality.{}", self.quality.name())).to_string();
+					ctx.gfx.set_window_title(&self.locale.format("title.quality", &[("quality", &quality)]));
+
+					self.has_parameters_changed = true;
+				},
+
+				VirtualKeyCode::E => {
+					let new_mag = self.zoom_step(mods) * self.viewport.magnification();
+					self.zoom_to_mouse(ctx, new_mag);
+				},
+
+				VirtualKeyCode::Q => {
+					let new_mag = (self.viewport.magnification() / self.zoom_step(mods)).max(1.0);
+					self.zoom_to_mouse(ctx, new_mag);
+				}
+				_ => {}
+			}
+		}
+	}
+
+	fn text_input(&mut self, character: char) {
+		if let Some(library) = &mut self.library_view {
+			self.needs_redraw |= library.text(character);
+			return;
+		}
+
+		if let Some(prompt) = &mut self.ray_prompt {
+			if character.is_ascii_digit() || character == '/' {
+				prompt.push(character);
+				self.needs_redraw = true;
+			}
+		}
+
+		if let Some(prompt) = &mut self.stop_prompt {
+			if character.is_ascii_hexdigit() || character == '#' {
+				prompt.push(character);
+				self.needs_redraw = true;
+			}
+		}
+
+		if let Some(prompt) = &mut self.region_prompt {
+			if character.is_ascii_digit() || ".,-+eE ".contains(character) {
+				prompt.push(character);
+				self.needs_redraw = true;
+			}
+		}
+
+		if let Some(prompt) = &mut self.escape_prompt {
+			if character.is_ascii_digit() || ".,-+eE ".contains(character) {
+				prompt.push(character);
+				self.needs_redraw = true;
+			}
+		}
+	}
+
+	// Double clicks zoom like E or Q about the clicked point, shift zooms out instead. They're timed
+	// in ticks so a replay sees the same double clicks
+	fn mouse_down(&mut self, ctx: &mut Context, button: MouseButton, position: Point2<f32>, mods: KeyMods) {
+		// The library covers the view, so clicks can't reach it
+		if let Some(library) = &mut self.library_view {
+			if button == MouseButton::Left {
+				let action = library.click(position, (self.width as f32, self.height as f32));
+				self.library_action(action);
+			}
+
+			return;
+		}
+
+		match button {
+			MouseButton::Middle => self.recenter_on_mouse(ctx),
+
+			MouseButton::Right => {
+				self.drag_start = Some(self.mouse_pixel(ctx));
+				self.region_prompt = None;
+				self.needs_redraw = true;
+			},
+
+			MouseButton::Left => {
+				let mouse = self.mouse_pixel(ctx);
+
+				if self.click_palette_editor(mouse) || self.inspect_pixel(mouse) {
+					return;
+				}
+
+				self.pick_linked_c(mouse);
+
+				let double_click_ticks = (DOUBLE_CLICK_TIME.as_secs_f64() * self.tick_rate as f64) as u64;
+
+				let is_double_click = self.last_click.take().is_some_and(|(tick, last)| {
+					let distance = ((position.x - last.x).powi(2) + (position.y - last.y).powi(2)).sqrt();
+					self.ticks - tick <= double_click_ticks && distance <= DOUBLE_CLICK_DISTANCE
+				});
+
+				if !is_double_click {
+					self.last_click = Some((self.ticks, position));
+				} else if mods.contains(KeyMods::SHIFT) {
+					let new_mag = (self.viewport.magnification() / self.zoom_factor).max(1.0);
+					self.zoom_to_mouse(ctx, new_mag);
+				} else {
+					let new_mag = self.zoom_factor * self.viewport.magnification();
+					self.zoom_to_mouse(ctx, new_mag);
+				}
+			},
+
+			_ => {},
+		}
+	}
+
+	// Clicks in the Mandelbrot set's plane pick the linked pane's Julia set
+	fn pick_linked_c(&mut self, mouse: Point2<f64>) {
+		let is_mouse_inside = (0.0..self.width as f64).contains(&mouse.x) && (0.0..self.height as f64).contains(&mouse.y);
+
+		if self.linked_view.is_some() && is_mouse_inside && self.iteration_settings.julia.is_none() {
+			self.linked_c = self.viewport.pixel_to_complex(mouse.x, mouse.y);
+			self.needs_redraw = true;
+		}
+	}
+
+	// Letting go of a right button drag opens its bounds for editing
+	fn mouse_up(&mut self, ctx: &mut Context, button: MouseButton) {
+		if button == MouseButton::Left {
+			self.dragging_stop = false;
+		}
+
+		let (MouseButton::Right, Some(start)) = (button, self.drag_start.take()) else {
+			return;
+		};
+
+		self.end_selection(start, self.mouse_pixel(ctx));
+	}
+
+	fn end_selection(&mut self, start: Point2<f64>, end: Point2<f64>) {
+		if (end.x - start.x).hypot(end.y - start.y) >= MIN_DRAG_DISTANCE {
+			let region = Region::from_pixels(&self.viewport, start, end);
+			self.region_prompt = Some(region.describe(self.viewport.scale()));
+		}
+
+		self.needs_redraw = true;
+	}
+
+	// The keyboard's right drag, from one press at the crosshair to the next. Without the crosshair
+	// the bounds of the whole view open for typing over
+	fn select_region(&mut self, ctx: &Context) {
+		let mouse = self.mouse_pixel(ctx);
+
+		match (self.crosshair, self.drag_start.take()) {
+			(Some(_), Some(start)) => self.end_selection(start, mouse),
+			(Some(_), None) => self.drag_start = Some(mouse),
+			(None, _) => {
+				let corner = Point2 { x: self.width as f64, y: self.height as f64 };
+				self.region_prompt = Some(Region::from_pixels(&self.viewport, Point2 { x: 0.0, y: 0.0 }, corner).describe(self.viewport.scale()));
+			},
+		}
+
+		self.needs_redraw = true;
+	}
+
+	fn key_up(&mut self, keycode: VirtualKeyCode) {
+		self.crosshair_keys.remove(&keycode);
+
+		if let Some(key_data) = self.movement_data.get_mut(&keycode) {
+			key_data.is_down = false;
+		}
+	}
+
+	// Live input is recorded if a recording is running, and ignored while a replay is
+	fn input(&mut self, ctx: &mut Context, event: InputEvent) {
+		if self.replay.is_some() {
+			return;
+		}
+
+		let cursor = self.cursor(ctx);
+
+		if let Some(recording) = &mut self.recording {
+			recording.events.push(TimedEvent { tick: self.ticks, cursor: [cursor.x, cursor.y], event });
+		}
+
+		self.handle_input(ctx, event);
+	}
+
+	fn handle_input(&mut self, ctx: &mut Context, event: InputEvent) {
+		self.last_input = self.ticks;
+
+		// The input that ends the attract mode is only for waking the viewer
+		if self.stop_attract() {
+			return;
+		}
+
+		match event {
+			InputEvent::KeyDown { key, mods } => self.key_down(ctx, key, KeyMods::from_bits_truncate(mods)),
+			InputEvent::KeyUp { key } => self.key_up(key),
+			InputEvent::MouseDown { button, x, y, mods } => self.mouse_down(ctx, button, Point2 { x, y }, KeyMods::from_bits_truncate(mods)),
+			InputEvent::MouseUp { button, .. } => self.mouse_up(ctx, button),
+			InputEvent::Text { character } => self.text_input(character),
+		}
+	}
+
+	// Recorded events are handled at the start of the tick they arrived before, with the cursor
+	// where it was then
+	fn replay_tick(&mut self, ctx: &mut Context) {
+		let Some(replay) = &mut self.replay else {
+			return;
+		};
+
+		let mut due = Vec::new();
+
+		while let Some(event) = replay.next_due(self.ticks) {
+			due.push(event);
+		}
+
+		if replay.is_finished() {
+			self.replay = None;
+			eprintln!("replay finished");
+		}
+
+		for event in due {
+			self.cursor_override = Some(Point2 { x: event.cursor[0], y: event.cursor[1] });
+			self.handle_input(ctx, event.event);
+		}
+
+		if self.replay.is_none() {
+			self.cursor_override = None;
+		}
+	}
+
+	pub fn start_recording(&mut self, path: PathBuf) {
+		self.recording = Some(Recording {
+			size: [self.width, self.height],
+			tick_rate: self.tick_rate,
+			start: Session::capture(&self.viewport, &self.iteration_settings, &self.palettes[self.palette_index].name),
+			events: Vec::new(),
+		});
+
+		self.recording_path = Some(path);
+	}
+
+	pub fn start_replay(&mut self, recording: Recording) {
+		if recording.size != [self.width, self.height] {
+			eprintln!("replaying a recording made at {}x{} in a {}x{} window, it may play out differently",
+				recording.size[0], recording.size[1], self.width, self.height);
+		}
+
+		self.replay = Some(Replay::new(recording.events));
+	}
+
+	// Follows the cursor over the Mandelbrot set's plane, and keeps the last preview when it leaves
+	fn update_julia_preview(&mut self, context: &Context) {
+		let mouse = self.mouse_pixel(context);
+		let is_mouse_inside = (0.0..self.width as f64).contains(&mouse.x) && (0.0..self.height as f64).contains(&mouse.y);
+
+		let Some(preview) = &mut self.julia_preview else {
+			return;
+		};
+
+		if is_mouse_inside && self.iteration_settings.julia.is_none() && self.ticks - self.julia_preview_tick >= JULIA_PREVIEW_INTERVAL {
+			let c = self.viewport.pixel_to_complex(mouse.x, mouse.y);
+			preview.request(c, self.iteration_settings, self.palettes[self.palette_index].clone());
+
+			self.julia_preview_tick = self.ticks;
+		}
+
+		if preview.update(context) {
+			self.needs_redraw = true;
+		}
+	}
+
+	// Splits the window's width between the view and the linked pane, while it's shown
+	fn layout(&mut self) {
+		self.width = match self.linked_view {
+			Some(_) => (self.window_width / 2).max(1),
+			None => self.window_width,
+		};
+
+		self.viewport.resize(self.width as f64, self.height as f64);
+
+		if let Some(view) = &mut self.linked_view {
+			view.resize((self.window_width - self.width, self.height));
+		}
+
+		self.has_parameters_changed = true;
+	}
+
+	// Starts from the center of the view, until a point is clicked
+	fn toggle_linked_view(&mut self) {
+		self.linked_view = match self.linked_view {
+			Some(_) => None,
+			None => Some(JuliaPreview::with_size((self.window_width - self.window_width / 2, self.height), None)),
+		};
+
+		self.linked_c = self.iteration_settings.julia.unwrap_or_else(|| self.viewport.center());
+		self.layout();
+	}
+
+	// Renders the linked pane at the view's iteration cap, one render at a time so it catches up
+	// with the latest click
+	fn update_linked_view(&mut self, context: &Context) {
+		let Some(view) = &mut self.linked_view else {
+			return;
+		};
+
+		view.request(self.linked_c, self.iteration_settings, self.palettes[self.palette_index].clone());
+
+		if view.update(context) {
+			self.needs_redraw = true;
+		}
+	}
+
+	fn move_crosshair(&mut self, context: &Context, delta_time: f32) {
+		let Some(crosshair) = &mut self.crosshair else {
+			return;
+		};
+
+		if self.crosshair_keys.is_empty() {
+			return;
+		}
+
+		let (width, height) = context.gfx.drawable_size();
+		let axis = |negative, positive| {
+			let held = |key| self.crosshair_keys.contains(&key) as i32 as f32;
+			(held(positive) - held(negative)) * CROSSHAIR_SPEED * delta_time
+		};
+
+		crosshair.x = (crosshair.x + axis(VirtualKeyCode::Left, VirtualKeyCode::Right)).clamp(0.0, width - 1.0);
+		crosshair.y = (crosshair.y + axis(VirtualKeyCode::Up, VirtualKeyCode::Down)).clamp(0.0, height - 1.0);
+
+		self.needs_redraw = true;
+	}
+
+	fn zoom_step(&self, mods: KeyMods) -> f64 {
+		if mods.contains(KeyMods::SHIFT) { self.fine_zoom_factor } else { self.zoom_factor }
+	}
+
+	// The point under the mouse becomes the new center, the magnification is unchanged
+	fn recenter_on_mouse(&mut self, context: &Context) {
+		let mouse_pos = self.mouse_pixel(context);
+
+		let pivot = self.viewport.pixel_to_plane(mouse_pos.x, mouse_pos.y);
+		self.viewport.set_center(pivot);
+
+		self.has_parameters_changed = true;
+	}
+
+	fn zoom_to_mouse(&mut self, context: &Context, magnification: f64) {
+		self.recenter_on_mouse(context);
+		self.viewport.set_magnification(magnification);
+	}
+}
+
+impl EventHandler for MandelbrotViewer {
+	fn update(&mut self, context: &mut Context) -> Result {
+		// Fixed rate ticks, independent of how often frames are drawn
+		while context.time.check_update_time(self.tick_rate) {
+			let delta_time = 1.0 / self.tick_rate as f64;
+
+			self.replay_tick(context);
+
+			// Closing was put off for exports which have since finished
+			if self.quit_prompt && self.jobs.is_idle() {
+				self.quit_confirmed = true;
+				context.request_quit();
+			}
+
+			for (_key, key_data) in self.movement_data.iter() {
+				if !key_data.is_down {
+					continue;
+				}
+
+				self.viewport.pan(key_data.velocity.x * delta_time, key_data.velocity.y * delta_time);
+				self.viewport.set_rotation(self.viewport.rotation() + key_data.angular_velocity * delta_time);
+
+				self.has_parameters_changed = true;
+			}
+
+			self.move_crosshair(context, delta_time as f32);
+			self.drag_stop(context);
+			self.ease_palette_range(delta_time);
+			self.update_attract(delta_time);
+			self.autosave();
+
+			self.capture_frame();
+			self.ticks += 1;
+		}
+
+		if let Some(start) = self.playback_start {
+			let time = self.seconds_since(start);
+
+			if let Some(view) = self.animation.sample(time) {
+				view.restore(&mut self.viewport, &mut self.iteration_settings);
+				self.select_palette(&view.palette);
+
+				self.has_parameters_changed = true;
+			}
+
+			if time >= self.animation.duration() {
+				self.playback_start = None;
+				self.needs_redraw = true;
+			}
+		}
+
+		if self.threads_changed.is_some_and(|tick| self.seconds_since(tick) >= SETTING_NOTICE_TIME) {
+			self.threads_changed = None;
+			self.needs_redraw = true;
+		}
+
+		if self.render_scale_changed.is_some_and(|tick| self.seconds_since(tick) >= SETTING_NOTICE_TIME) {
+			self.render_scale_changed = None;
+			self.needs_redraw = true;
+		}
+
+		if self.palette_randomized.is_some_and(|tick| self.seconds_since(tick) >= PALETTE_NOTICE_TIME) {
+			self.palette_randomized = None;
+			self.needs_redraw = true;
+		}
+
+		if let Some((morph, start)) = self.morph {
+			self.iteration_settings.julia = Some(morph.constant(self.seconds_since(start)));
+			self.has_parameters_changed = true;
+		}
+
+		if self.has_parameters_changed {
+			// Frames with a margin slide along with the view until the next one arrives
+			self.needs_redraw |= self.render_margin > 0;
+
+			self.submit_render(context, 0);
+			self.has_parameters_changed = false;
+		}
+
+		for tile in self.renderer.poll_tiles() {
+			if let Some((generation, eta)) = &mut self.render_eta {
+				if tile.generation == *generation {
+					eta.record(tile.tile.width * tile.tile.height);
+					self.needs_redraw |= eta.elapsed() >= ETA_DELAY;
+				}
+			}
+
+			self.upload_tile(context, tile);
+		}
+
+		for frame in self.renderer.poll() {
+			match frame {
+				Ok(frame) => {
+					if frame.pass == 0 && self.render_error.take().is_some() {
+						self.needs_redraw = true;
+					}
+
+					self.present_frame(frame);
+				},
+
+				// The last good frame stays up, with no further passes queued on top of the failed one
+				Err(e) => {
+					self.render_error = Some(self.locale.format("render-failed", &[("error", &e)]));
+					self.accumulation = None;
+					self.needs_redraw = true;
+				},
+			}
+		}
+
+		self.update_julia_preview(context);
+		self.update_linked_view(context);
+
+		if let (Some(diff_view), Some(frame)) = (&mut self.diff_view, &self.front_frame) {
+			match diff_view.update(context, frame, MandelbrotViewer::downsampling(frame, self.width)) {
+				Ok(updated) => self.needs_redraw |= updated,
+				Err(e) => {
+					self.render_error = Some(self.locale.format("render-failed", &[("error", &e)]));
+					self.needs_redraw = true;
+				},
+			}
+		}
+
+		if let Some(frame) = &self.front_frame {
+			if self.frame_texture.advance(context, frame, self.display_filter, MandelbrotViewer::downsampling(frame, self.width)) {
+				self.needs_redraw = true;
+			}
+		}
+
+		// While the view is still, keep averaging in jittered passes to antialias over time
+		if self.renderer.is_idle() && !self.is_dormant() {
+			let next_pass = self.accumulation.as_ref()
+				.filter(|accumulation| accumulation.generation == self.generation)
+				.and_then(|accumulation| accumulation.next_pass());
+
+			if let Some(pass) = next_pass {
+				self.submit_render(context, pass);
+			}
+		}
+
+		if self.palette_watcher.as_ref().is_some_and(|watcher| watcher.has_changed()) {
+			self.reload_palettes();
+		}
+		
+		Ok(())
+	}
+
+	fn draw(&mut self, context: &mut Context) -> Result {
+		let is_idle = !self.needs_redraw;
+
+		if self.needs_redraw {
+			let mut canvas = graphics::Canvas::from_frame(context, Color::BLACK);
+			if let Some((viewport, margin)) = self.frame_texture.shown() {
+				let placement = self.frame_placement(&viewport, margin);
+				self.frame_texture.draw(&mut canvas, placement.origin, placement.scale);
+			}
+
+			if let (Some(diff_view), Some(frame)) = (&self.diff_view, &self.front_frame) {
+				let placement = self.frame_placement(&frame.viewport, frame.margin);
+				diff_view.draw(&mut canvas, placement.origin, placement.scale);
+			}
+
+			if let (true, Some(frame)) = (self.show_histogram, &self.front_frame) {
+				let counts = stats::histogram(&frame.iterations, frame.settings.max_iterations, HISTOGRAM_BINS);
+				let area = Rect::new(10.0, self.height as f32 - HISTOGRAM_SIZE.1 - 10.0, HISTOGRAM_SIZE.0, HISTOGRAM_SIZE.1);
+
+				overlay::draw_histogram(&mut canvas, context, &counts, area)?;
+			}
+
+			if let (true, Some(frame)) = (self.show_stats, &self.front_frame) {
+				let stats = FrameStats::compute(&frame.iterations, frame.settings.max_iterations);
+
+				overlay::draw_stats(&mut canvas, context, &self.locale, &stats, Point2 { x: 10.0, y: 10.0 })?;
+			}
+
+			let window_size = (self.width as f32, self.height as f32);
+
+			if let (true, Some(frame)) = (self.show_contours, &self.front_frame) {
+				let placement = self.frame_placement(&frame.viewport, frame.margin);
+				overlay::draw_contours(&mut canvas, context, &self.contour_segments, placement.origin, placement.scale)?;
+			}
+
+			if self.show_grid {
+				overlay::draw_grid(&mut canvas, context, &self.viewport, window_size)?;
+			}
+
+			let to_screen = |c: Complex| {
+				let point = self.viewport.complex_to_pixel(c);
+				Point2 { x: point.x as f32, y: point.y as f32 }
+			};
+
+			// Outlines and rays belong to the Mandelbrot set's plane
+			let is_mandelbrot = self.iteration_settings.julia.is_none() && self.iteration_settings.formula == Formula::Mandelbrot;
+
+			if self.show_outlines && is_mandelbrot && self.viewport.magnification() <= OUTLINE_MAX_MAGNIFICATION {
+				for (outline, period, label_at) in [
+					(outlines::main_cardioid(OUTLINE_POINTS), "1", Complex::new(-0.15, 0.0)),
+					(outlines::period_two_bulb(OUTLINE_POINTS), "2", Complex::new(-1.0, 0.0)),
+				] {
+					let points: Vec<Point2<f32>> = outline.into_iter().map(to_screen).collect();
+
+					overlay::draw_path(&mut canvas, context, &points, overlay::OUTLINE_COLOR, window_size)?;
+					overlay::draw_label(&mut canvas, context, period, to_screen(label_at), overlay::OUTLINE_COLOR);
+				}
+			}
+
+			for ray in self.rays.iter().filter(|_| is_mandelbrot) {
+				let points: Vec<Point2<f32>> = ray.iter().map(|&c| to_screen(c)).collect();
+
+				overlay::draw_path(&mut canvas, context, &points, overlay::RAY_COLOR, window_size)?;
+			}
+
+			if let Some(band) = self.display_filter.band {
+				let text = self.locale.format("band", &[("band", &band)]);
+				overlay::draw_text_panel(&mut canvas, context, &text, Point2 { x: self.width as f32 - 190.0, y: 10.0 })?;
+			}
+
+			if self.iteration_settings.formula != Formula::Mandelbrot {
+				let formula = self.locale.text(&format!("formula.{}", self.iteration_settings.formula.name())).to_string();
+				let text = self.locale.format("formula", &[("formula", &formula)]);
+
+				overlay::draw_text_panel(&mut canvas, context, &text, Point2 { x: self.width as f32 - 190.0, y: 110.0 })?;
+			}
+
+			if self.tabs.len() > 1 {
+				let text = self.locale.format("tab", &[("tab", &(self.tab + 1)), ("count", &self.tabs.len())]);
+				overlay::draw_text_panel(&mut canvas, context, &text, Point2 { x: self.width as f32 / 2.0 - 95.0, y: self.height as f32 - 40.0 })?;
+			}
+
+			if let Some(c) = self.iteration_settings.julia {
+				let text = self.locale.format("julia", &[("c", &c)]);
+				overlay::draw_text_panel(&mut canvas, context, &text, Point2 { x: 10.0, y: 80.0 })?;
+			}
+
+			if !self.animation.is_empty() {
+				let time = self.playback_start.map_or(0.0, |start| self.seconds_since(start).min(self.animation.duration()));
+				let text = self.locale.format("keyframes", &[
+					("count", &self.animation.keyframes().len()),
+					("time", &format!("{:.1}", time)),
+					("duration", &format!("{:.1}", self.animation.duration())),
+				]);
+
+				overlay::draw_text_panel(&mut canvas, context, &text, Point2 { x: self.width as f32 - 190.0, y: 60.0 })?;
+			}
+
+			if let Some(prompt) = &self.ray_prompt {
+				let text = self.locale.format("ray-prompt", &[("angle", prompt)]);
+				overlay::draw_text_panel(&mut canvas, context, &text, Point2 { x: 10.0, y: self.height as f32 / 2.0 })?;
+			}
+
+			if let Some(prompt) = &self.escape_prompt {
+				let text = self.locale.format("escape-prompt", &[("values", prompt)]);
+				overlay::draw_text_panel(&mut canvas, context, &text, Point2 { x: 10.0, y: self.height as f32 / 2.0 })?;
+			}
+
+			// The region being dragged out, or typed in once it has been
+			let dragged = self.drag_start.map(|start| Region::from_pixels(&self.viewport, start, self.mouse_pixel(context)));
+			let bounds = self.region_prompt.clone().or_else(|| dragged.map(|region| region.describe(self.viewport.scale())));
+
+			if let Some(bounds) = bounds {
+				if let Ok(region) = bounds.parse::<Region>() {
+					let mut corners: Vec<Point2<f32>> = region.corners().into_iter().map(|w| {
+						let point = self.viewport.plane_to_pixel(w);
+						Point2 { x: point.x as f32, y: point.y as f32 }
+					}).collect();
+					corners.push(corners[0]);
+
+					overlay::draw_path(&mut canvas, context, &corners, overlay::SELECTION_COLOR, window_size)?;
+				}
+
+				let text = self.locale.format("region-prompt", &[("bounds", &bounds)]);
+				overlay::draw_text_panel(&mut canvas, context, &text, Point2 { x: 10.0, y: self.height as f32 / 2.0 + 60.0 })?;
+			}
+
+			if let Some(editor) = &self.palette_editor {
+				let bar = self.editor_bar();
+				overlay::draw_palette_editor(&mut canvas, context, editor.gradient(), editor.selected(), bar)?;
+
+				let text = match &self.stop_prompt {
+					Some(prompt) => self.locale.format("stop-prompt", &[("color", prompt)]),
+					None => self.locale.format("palette-editor", &[("name", &editor.gradient().name)]),
+				};
+
+				overlay::draw_text_panel(&mut canvas, context, &text, Point2 { x: bar.x - 4.0, y: bar.y - 70.0 })?;
+			}
+
+			if self.frame_recorder.is_some() {
+				overlay::draw_text_panel(&mut canvas, context, self.locale.text("recording-frames"), Point2 { x: self.width as f32 - 190.0, y: self.height as f32 - 40.0 })?;
+			}
+
+			if self.threads_changed.is_some() {
+				let text = self.locale.format("threads", &[("threads", &WorkQueue::shared().threads()), ("cores", &available_cores())]);
+				overlay::draw_text_panel(&mut canvas, context, &text, Point2 { x: self.width as f32 / 2.0 - 95.0, y: 10.0 })?;
+			}
+
+			if self.render_scale_changed.is_some() {
+				let scale = self.quality.resolution_scale() * self.render_scale;
+				let (width, height) = ((self.width as f64 * scale).round(), (self.height as f64 * scale).round());
+				let text = self.locale.format("render-scale", &[("scale", &self.render_scale), ("width", &width), ("height", &height)]);
+
+				overlay::draw_text_panel(&mut canvas, context, &text, Point2 { x: self.width as f32 / 2.0 - 95.0, y: 160.0 })?;
+			}
+
+			if self.palette_randomized.is_some() {
+				let text = self.locale.format("random-palette", &[("name", &self.palettes[self.palette_index].name)]);
+				overlay::draw_text_panel(&mut canvas, context, &text, Point2 { x: self.width as f32 / 2.0 - 95.0, y: 60.0 })?;
+			}
+
+			if let Some((_, eta)) = self.render_eta.as_ref().filter(|(_, eta)| eta.elapsed() >= ETA_DELAY) {
+				if let Some(remaining) = eta.remaining() {
+					let percent = (eta.fraction() * 100.0).floor();
+					let text = self.locale.format("render-eta", &[("percent", &percent), ("eta", &eta::format_duration(remaining))]);
+
+					overlay::draw_text_panel(&mut canvas, context, &text, Point2 { x: self.width as f32 / 2.0 - 95.0, y: 110.0 })?;
+				}
+			}
+
+			if let Some(diff_view) = &self.diff_view {
+				let (multiplier, backend) = (diff_view.iteration_multiplier(), diff_view.backend());
+
+				let text = match diff_view.comparison() {
+					Some(comparison) => self.locale.format("diff", &[
+						("multiplier", &multiplier),
+						("backend", &backend),
+						("percent", &format!("{:.2}", comparison.differing_percent())),
+						("mismatches", &comparison.escape_mismatches),
+						("max", &format!("{:.2}", comparison.max_difference)),
+					]),
+					None => self.locale.format("diff-pending", &[("multiplier", &multiplier), ("backend", &backend)]),
+				};
+
+				overlay::draw_text_panel(&mut canvas, context, &text, Point2 { x: 10.0, y: self.height as f32 - 120.0 })?;
+			}
+
+			if self.inspecting {
+				let text = match &self.inspection {
+					Some(inspection) => {
+						let or_dash = inspect::or_dash;
+
+						self.locale.format("inspector", &[
+							("x", &inspection.pixel.0),
+							("y", &inspection.pixel.1),
+							("point", &format!("{:.15}", inspection.point)),
+							("outcome", &self.locale.text(&format!("orbit.{}", inspection.outcome()))),
+							("iterations", &or_dash(inspection.iterations, 0)),
+							("z", &format!("{:.6}", inspection.final_z)),
+							("smooth", &or_dash(inspection.smooth, 4)),
+							("mean", &or_dash(inspection.mean_iterations, 2)),
+							("samples", &inspection.samples),
+							("bounded", &inspection.bounded_samples),
+							("palette", &inspection.palette),
+							("position", &or_dash(inspection.palette_position, 4)),
+							("color", &gradient::to_hex(inspection.color)),
+						])
+					},
+					None => self.locale.text("inspector-hint").to_string(),
+				};
+
+				overlay::draw_text_panel(&mut canvas, context, &text, Point2 { x: 10.0, y: self.height as f32 / 2.0 - 200.0 })?;
+
+				if let Some(inspection) = &self.inspection {
+					let marker = self.viewport.complex_to_pixel(inspection.point);
+					overlay::draw_crosshair(&mut canvas, context, Point2 { x: marker.x as f32, y: marker.y as f32 })?;
+				}
+			}
+
+			if self.recovery.is_some() {
+				overlay::draw_text_panel(&mut canvas, context, self.locale.text("recovery"), Point2 { x: self.width as f32 / 2.0 - 95.0, y: self.height as f32 / 2.0 - 40.0 })?;
+			}
+
+			if self.quit_prompt {
+				let running: Vec<String> = self.jobs.running().iter().map(|kind| self.locale.text(&format!("job.{}", kind)).to_string()).collect();
+				let text = self.locale.format("quit-prompt", &[("jobs", &running.join(", "))]);
+
+				overlay::draw_text_panel(&mut canvas, context, &text, Point2 { x: self.width as f32 / 2.0 - 95.0, y: self.height as f32 / 2.0 - 40.0 })?;
+			}
+
+			if self.show_about {
+				let text = build_info::summary(&self.locale, &self.backend.name(), &self.display_adapter);
+
+				overlay::draw_text_panel(&mut canvas, context, &text, Point2 { x: 10.0, y: 120.0 })?;
+			}
+
+			if let (Some(preview), None) = (&self.julia_preview, self.iteration_settings.julia) {
+				let corner = Point2 {
+					x: self.width as f32 - julia_preview::SIZE as f32 - 10.0,
+					y: self.height as f32 - julia_preview::SIZE as f32 - 50.0,
+				};
+
+				preview.draw(&mut canvas, context, corner)?;
+			}
+
+			if let Some(view) = &self.linked_view {
+				view.draw(&mut canvas, context, Point2 { x: self.width as f32, y: 0.0 })?;
+
+				let text = self.locale.format("julia", &[("c", &self.linked_c)]);
+				overlay::draw_text_panel(&mut canvas, context, &text, Point2 { x: self.width as f32 + 10.0, y: 10.0 })?;
+
+				if self.iteration_settings.julia.is_none() {
+					let marker = self.viewport.complex_to_pixel(self.linked_c);
+					overlay::draw_crosshair(&mut canvas, context, Point2 { x: marker.x as f32, y: marker.y as f32 })?;
+				}
+			}
+
+			if self.crosshair.is_some() {
+				let position = self.mouse_pixel(context);
+				overlay::draw_crosshair(&mut canvas, context, Point2 { x: position.x as f32, y: position.y as f32 })?;
+			}
+
+			if let Some(library) = &mut self.library_view {
+				library.draw(&mut canvas, context, &self.locale, window_size)?;
+			}
+
+			if let Some(error) = &self.render_error {
+				overlay::draw_error_banner(&mut canvas, context, error, (self.width as f32, self.height as f32))?;
+			}
+
+			canvas.finish(context)?;
+			self.needs_redraw = false;
+		}
+
+		// Idle frames are throttled even when uncapped, input is still picked up every frame
+		let frame_duration = match self.frame_duration {
+			_ if is_idle && self.is_dormant() => Some(DORMANT_FRAME_DURATION),
+			None if is_idle => Some(IDLE_FRAME_DURATION),
+			duration => duration,
+		};
+
+		// Sleep off the rest of the frame rather than spinning
+		if let Some(frame_duration) = frame_duration {
+			let elapsed = self.last_frame.elapsed();
+
+			if elapsed < frame_duration {
+				thread::sleep(frame_duration - elapsed);
+			}
+		} else {
+			ggez::timer::yield_now();
+		}
+
+		self.last_frame = Instant::now();
+
+		Ok(())
+	}
+
+	fn key_down_event(&mut self, ctx: &mut Context, input: KeyInput, repeated: bool) -> Result {
+		if let (Some(key), false) = (input.keycode, repeated) {
+			self.input(ctx, InputEvent::KeyDown { key, mods: input.mods.bits() });
+		}
+
+		Ok(())
+	}
+
+	// Fired with physical sizes when the window moves to a display with a different scale factor
+	fn resize_event(&mut self, _context: &mut Context, width: f32, height: f32) -> Result {
+		if width < 1.0 || height < 1.0 {
+			return Ok(());
+		}
+
+		self.window_width = width as usize;
+		self.height = height as usize;
+
+		self.layout();
+
+		Ok(())
+	}
+
+	// Called twice for each close of the window, so asking has to be the same both times and the
+	// saving is only done the first
+	fn quit_event(&mut self, _ctx: &mut Context) -> Result<bool> {
+		if self.quitting {
+			return Ok(false);
+		}
+
+		if !self.quit_confirmed && !self.jobs.is_idle() {
+			self.quit_prompt = true;
+			self.needs_redraw = true;
+
+			return Ok(true);
+		}
+
+		self.quitting = true;
+
+		// The session holds the view from before any dive
+		self.stop_attract();
+
+		// Failing to save shouldn't stop the window closing
+		let palette = &self.palettes[self.palette_index].name;
+
+		if let Err(e) = Session::capture(&self.viewport, &self.iteration_settings, palette).save() {
+			eprintln!("could not save session: {}", e);
+		}
+
+		if let Err(e) = Session::clear_recovery() {
+			eprintln!("could not remove the recovery file: {}", e);
+		}
+
+		if let Some(recorder) = self.frame_recorder.take() {
+			MandelbrotViewer::finish_frame_recording(recorder);
+		}
+
+		if let (Some(recording), Some(path)) = (&self.recording, &self.recording_path) {
+			match recording.save(path) {
+				Ok(()) => eprintln!("recorded {} events to {}", recording.events.len(), path.display()),
+				Err(e) => eprintln!("could not save recording: {}", e),
+			}
+		}
+
+		// Exports stop after the frame they're on, keeping the frames written so far
+		self.jobs.cancel_all();
+
+		if !self.jobs.wait(JOB_STOP_TIME) {
+			eprintln!("closing with {} still running", self.jobs.running().join(", "));
+		}
+
+		Ok(false)
+	}
+
+	// Not recorded, but enough to wake the viewer
+	fn mouse_motion_event(&mut self, _context: &mut Context, _x: f32, _y: f32, _dx: f32, _dy: f32) -> Result {
+		self.last_input = self.ticks;
+		self.stop_attract();
+
+		// The dragged region follows the cursor
+		if self.drag_start.is_some() {
+			self.needs_redraw = true;
+		}
+
+		Ok(())
+	}
+
+	fn text_input_event(&mut self, ctx: &mut Context, character: char) -> Result {
+		self.input(ctx, InputEvent::Text { character });
+		Ok(())
+	}
+
+	fn mouse_button_down_event(&mut self, ctx: &mut Context, button: MouseButton, x: f32, y: f32) -> Result {
+		let mods = ctx.keyboard.active_mods().bits();
+		self.input(ctx, InputEvent::MouseDown { button, x, y, mods });
+
+		Ok(())
+	}
+
+	fn mouse_button_up_event(&mut self, ctx: &mut Context, button: MouseButton, x: f32, y: f32) -> Result {
+		self.input(ctx, InputEvent::MouseUp { button, x, y });
+		Ok(())
+	}
+
+	fn key_up_event(&mut self, ctx: &mut Context, input: KeyInput) -> Result {
+		if let Some(key) = input.keycode {
+			self.input(ctx, InputEvent::KeyUp { key });
+		}
+
 		Ok(())
 	}
 }
@@ -0,0 +1,192 @@
+use std::collections::{HashMap, VecDeque};
+use std::hash::Hash;
+use std::mem::size_of;
+
+use ggez::graphics::Color;
+
+// A cap on the largest buffers, so low RAM machines and huge frames degrade predictably instead
+// of swapping: views render with fewer samples, posters in thinner bands and caches drop their
+// oldest entries
+
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct MemoryBudget {
+	bytes: usize,
+}
+
+// Bytes a frame's buffers take, its samples' escape counts, convergence and distances and its
+// pixels' colours
+pub fn frame_bytes(width: usize, height: usize, samples: usize) -> usize {
+	let per_sample = 2 * size_of::<Option<f64>>() + size_of::<bool>();
+	let pixels = width.saturating_mul(height);
+
+	pixels.saturating_mul(size_of::<Color>().saturating_add(samples.saturating_mul(samples).saturating_mul(per_sample)))
+}
+
+impl MemoryBudget {
+	pub const UNLIMITED: MemoryBudget = MemoryBudget { bytes: usize::MAX };
+
+	// 0 for no limit
+	pub fn megabytes(megabytes: f64) -> MemoryBudget {
+		if megabytes <= 0.0 {
+			return MemoryBudget::UNLIMITED;
+		}
+
+		MemoryBudget { bytes: (megabytes * 1024.0 * 1024.0) as usize }
+	}
+
+	pub fn bytes(self) -> usize {
+		self.bytes
+	}
+
+	pub fn fits(self, bytes: usize) -> bool {
+		bytes <= self.bytes
+	}
+
+	pub fn share(self, fraction: f64) -> MemoryBudget {
+		if self == MemoryBudget::UNLIMITED {
+			return self;
+		}
+
+		MemoryBudget { bytes: (self.bytes as f64 * fraction) as usize }
+	}
+
+	// The most samples per axis, up to the ones asked for, that frames of this many buffers can be
+	// rendered with. Never below 1, as a frame has to be rendered somehow
+	pub fn samples_for(self, (width, height): (usize, usize), samples: usize, frames: usize) -> usize {
+		(1..=samples).rev()
+			.find(|&samples| self.fits(frame_bytes(width, height, samples).saturating_mul(frames)))
+			.unwrap_or(1)
+	}
+
+	// Rows of a band rendered at once, up to the ones asked for, counting the band's frame, its
+	// margin rows either side and the copies made of its pixels on the way out. Never below 1
+	pub fn band_rows(self, width: usize, samples: usize, margin: usize, rows: usize) -> usize {
+		let row_bytes = frame_bytes(width, 1, samples).saturating_add(width.saturating_mul(2 * size_of::<Color>() + 8));
+		let fitting = (self.bytes / row_bytes.max(1)).saturating_sub(2 * margin);
+
+		fitting.clamp(1, rows.max(1))
+	}
+}
+
+// Encoded pieces looked up by key, dropping the least recently used once they'd go over budget.
+// Pieces bigger than the whole budget aren't kept at all
+pub struct Cache<K> {
+	budget: MemoryBudget,
+	bytes: usize,
+	entries: HashMap<K, Vec<u8>>,
+	// Least recently used first
+	order: VecDeque<K>,
+}
+
+impl<K: Hash + Eq + Clone> Cache<K> {
+	pub fn new(budget: MemoryBudget) -> Cache<K> {
+		Cache { budget, bytes: 0, entries: HashMap::new(), order: VecDeque::new() }
+	}
+
+	pub fn bytes(&self) -> usize {
+		self.bytes
+	}
+
+	pub fn len(&self) -> usize {
+		self.entries.len()
+	}
+
+	pub fn is_empty(&self) -> bool {
+		self.entries.is_empty()
+	}
+
+	fn touch(&mut self, key: &K) {
+		if let Some(position) = self.order.iter().position(|entry| entry == key) {
+			let key = self.order.remove(position).expect("position within the order");
+			self.order.push_back(key);
+		}
+	}
+
+	pub fn get(&mut self, key: &K) -> Option<Vec<u8>> {
+		let value = self.entries.get(key).cloned()?;
+		self.touch(key);
+
+		Some(value)
+	}
+
+	pub fn insert(&mut self, key: K, value: Vec<u8>) {
+		if let Some(old) = self.entries.remove(&key) {
+			self.bytes -= old.len();
+			self.order.retain(|entry| *entry != key);
+		}
+
+		if !self.budget.fits(value.len()) {
+			return;
+		}
+
+		while !self.budget.fits(self.bytes + value.len()) {
+			let Some(oldest) = self.order.pop_front() else {
+				break;
+			};
+
+			if let Some(evicted) = self.entries.remove(&oldest) {
+				self.bytes -= evicted.len();
+			}
+		}
+
+		self.bytes += value.len();
+		self.order.push_back(key.clone());
+		self.entries.insert(key, value);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const MEGABYTE: f64 = 1024.0 * 1024.0;
+
+	#[test]
+	fn samples_drop_until_frames_fit() {
+		let budget = MemoryBudget::megabytes(100.0);
+		let size = (1920, 1080);
+
+		// 1080p takes about 100 MB with one sample, 290 MB with two by two and 620 MB with three
+		assert_eq!(budget.samples_for(size, 1, 2), 1);
+		assert_eq!(budget.samples_for(size, 3, 1), 1);
+		assert_eq!(MemoryBudget::megabytes(700.0).samples_for(size, 3, 2), 2);
+		assert_eq!(MemoryBudget::megabytes(1300.0).samples_for(size, 3, 2), 3);
+		assert_eq!(MemoryBudget::UNLIMITED.samples_for(size, 4, 2), 4);
+
+		assert!((frame_bytes(1920, 1080, 1) as f64 / MEGABYTE - 96.9).abs() < 0.1);
+	}
+
+	#[test]
+	fn bands_thin_to_fit() {
+		let width = 32768;
+		let rows = MemoryBudget::megabytes(512.0).band_rows(width, 2, 10, 256);
+
+		assert!(rows < 256 && rows > 1);
+		assert!(frame_bytes(width, rows + 20, 2) <= 512 * 1024 * 1024);
+
+		assert_eq!(MemoryBudget::UNLIMITED.band_rows(width, 2, 10, 256), 256);
+		assert_eq!(MemoryBudget::megabytes(1.0).band_rows(width, 4, 10, 256), 1);
+	}
+
+	#[test]
+	fn caches_drop_the_least_recently_used() {
+		let mut cache = Cache::new(MemoryBudget { bytes: 10 });
+
+		cache.insert("a", vec![0; 4]);
+		cache.insert("b", vec![0; 4]);
+		assert!(cache.get(&"a").is_some());
+
+		// b is the oldest since a was looked up
+		cache.insert("c", vec![0; 4]);
+		assert!(cache.get(&"b").is_none());
+		assert!(cache.get(&"a").is_some() && cache.get(&"c").is_some());
+		assert_eq!((cache.len(), cache.bytes()), (2, 8));
+
+		// Replacing an entry frees the old one, anything over budget is left out
+		cache.insert("a", vec![0; 6]);
+		assert_eq!(cache.bytes(), 10);
+		cache.insert("d", vec![0; 11]);
+		assert!(cache.get(&"d").is_none());
+		assert_eq!(cache.len(), 2);
+	}
+}
@@ -0,0 +1,399 @@
+use std::ffi::{c_char, c_void, CString};
+use std::ptr;
+use std::sync::mpsc::Sender;
+use std::sync::Mutex;
+
+use crate::backend::RenderBackend;
+use crate::formula::Formula;
+use crate::renderer::{self, Frame, RenderJob, RenderedTile};
+
+// Iterates on an OpenCL device, for GPUs and drivers which do better outside wgpu. Sample points
+// are computed here exactly as the CPU does and only the iteration runs on the device, in double
+// precision with contraction off, so frames match the CPU's. Only the handful of OpenCL calls
+// needed are declared, linking against the system's OpenCL library rather than a binding crate
+
+type ClInt = i32;
+type ClUint = u32;
+type ClBitfield = u64;
+type Handle = *mut c_void;
+
+const CL_SUCCESS: ClInt = 0;
+const CL_TRUE: ClUint = 1;
+
+const CL_DEVICE_TYPE_GPU: ClBitfield = 1 << 2;
+const CL_DEVICE_TYPE_ALL: ClBitfield = 0xffff_ffff;
+
+const CL_DEVICE_NAME: ClUint = 0x102b;
+const CL_DEVICE_DOUBLE_FP_CONFIG: ClUint = 0x1032;
+const CL_PROGRAM_BUILD_LOG: ClUint = 0x1183;
+
+const CL_MEM_WRITE_ONLY: ClBitfield = 1 << 1;
+const CL_MEM_READ_ONLY: ClBitfield = 1 << 2;
+const CL_MEM_COPY_HOST_PTR: ClBitfield = 1 << 5;
+
+#[cfg_attr(target_os = "macos", link(name = "OpenCL", kind = "framework"))]
+#[cfg_attr(not(target_os = "macos"), link(name = "OpenCL"))]
+extern "C" {
+	fn clGetPlatformIDs(num_entries: ClUint, platforms: *mut Handle, num_platforms: *mut ClUint) -> ClInt;
+	fn clGetDeviceIDs(platform: Handle, device_type: ClBitfield, num_entries: ClUint, devices: *mut Handle, num_devices: *mut ClUint) -> ClInt;
+	fn clGetDeviceInfo(device: Handle, param_name: ClUint, value_size: usize, value: *mut c_void, value_size_ret: *mut usize) -> ClInt;
+
+	fn clCreateContext(properties: *const isize, num_devices: ClUint, devices: *const Handle, notify: *const c_void, user_data: *mut c_void, error: *mut ClInt) -> Handle;
+	fn clCreateCommandQueue(context: Handle, device: Handle, properties: ClBitfield, error: *mut ClInt) -> Handle;
+
+	fn clCreateProgramWithSource(context: Handle, count: ClUint, strings: *const *const c_char, lengths: *const usize, error: *mut ClInt) -> Handle;
+	fn clBuildProgram(program: Handle, num_devices: ClUint, devices: *const Handle, options: *const c_char, notify: *const c_void, user_data: *mut c_void) -> ClInt;
+	fn clGetProgramBuildInfo(program: Handle, device: Handle, param_name: ClUint, value_size: usize, value: *mut c_void, value_size_ret: *mut usize) -> ClInt;
+	fn clCreateKernel(program: Handle, name: *const c_char, error: *mut ClInt) -> Handle;
+
+	fn clCreateBuffer(context: Handle, flags: ClBitfield, size: usize, host_ptr: *mut c_void, error: *mut ClInt) -> Handle;
+	fn clSetKernelArg(kernel: Handle, index: ClUint, size: usize, value: *const c_void) -> ClInt;
+	fn clEnqueueNDRangeKernel(
+		queue: Handle,
+		kernel: Handle,
+		work_dim: ClUint,
+		global_offset: *const usize,
+		global_size: *const usize,
+		local_size: *const usize,
+		num_events: ClUint,
+		wait_list: *const Handle,
+		event: *mut Handle,
+	) -> ClInt;
+	fn clEnqueueReadBuffer(
+		queue: Handle,
+		buffer: Handle,
+		blocking: ClUint,
+		offset: usize,
+		size: usize,
+		ptr: *mut c_void,
+		num_events: ClUint,
+		wait_list: *const Handle,
+		event: *mut Handle,
+	) -> ClInt;
+
+	fn clReleaseMemObject(memory: Handle) -> ClInt;
+	fn clReleaseKernel(kernel: Handle) -> ClInt;
+	fn clReleaseProgram(program: Handle) -> ClInt;
+	fn clReleaseCommandQueue(queue: Handle) -> ClInt;
+	fn clReleaseContext(context: Handle) -> ClInt;
+}
+
+// The same loop as the CPU's calculate_for_point, -1 marking points that never escape and converged
+// flagging orbits of the converging formulas that settled instead. Formulas
+// are numbered in the order of Formula::ALL, and step matches Formula::iterate operation for
+// operation so the results agree exactly
+const KERNEL: &str = r#"
+#pragma OPENCL EXTENSION cl_khr_fp64 : enable
+#pragma OPENCL FP_CONTRACT OFF
+
+double2 mul(double2 a, double2 b) {
+	return (double2)((a.x * b.x) - (a.y * b.y), (a.x * b.y) + (a.y * b.x));
+}
+
+double2 div(double2 a, double2 b) {
+	double denominator = (b.x * b.x) + (b.y * b.y);
+	return (double2)(((a.x * b.x) + (a.y * b.y)) / denominator, ((a.y * b.x) - (a.x * b.y)) / denominator);
+}
+
+double2 fold_real(double2 z) {
+	return (double2)(fabs(z.x), z.y);
+}
+
+double2 step(int formula, double2 z, double2 c) {
+	switch (formula) {
+		case 1: return mul(fabs(z), fabs(z)) + c;
+		case 2: return fold_real(mul(z, z)) + c;
+		case 3: {
+			double2 folded = (double2)(z.x, -fabs(z.y));
+			return mul(folded, folded) + c;
+		}
+		case 4: return fold_real(mul(fabs(z), fabs(z))) + c;
+		case 5: {
+			double2 quotient = div(mul(z, z) + (c + (double2)(-1.0, 0.0)), z * 2.0 + (c + (double2)(-2.0, 0.0)));
+			return mul(quotient, quotient);
+		}
+		case 6: {
+			double2 a = c + (double2)(-1.0, 0.0);
+			double2 b = c + (double2)(-2.0, 0.0);
+			double2 numerator = mul(mul(z, z), z) + mul(a * 3.0, z) + mul(a, b);
+			double2 denominator = mul(z, z) * 3.0 + mul(b * 3.0, z) + mul(a, b) + (double2)(1.0, 0.0);
+			double2 quotient = div(numerator, denominator);
+			return mul(quotient, quotient);
+		}
+		case 7: return mul(mul(c, z), -z + (double2)(1.0, 0.0));
+		case 8: return z - div(mul(mul(z, z), z) + (double2)(-1.0, 0.0), mul(z, z) * 3.0) + c;
+		default: return mul(z, z) + c;
+	}
+}
+
+__kernel void escape(
+	__global const double2 *points,
+	const int formula,
+	const double2 initial_z,
+	const int is_julia,
+	const double2 julia,
+	const double bailout_sqr,
+	const double max_iterations,
+	__global double *iterations,
+	__global int *converged
+) {
+	size_t i = get_global_id(0);
+
+	double2 z = is_julia ? points[i] : initial_z;
+	double2 c = is_julia ? julia : points[i];
+	double count = 0.0;
+
+	converged[i] = 0;
+
+	while (z.x * z.x + z.y * z.y < bailout_sqr) {
+		if (count > max_iterations) {
+			iterations[i] = -1.0;
+			return;
+		}
+
+		count += 1.0;
+
+		double2 previous = z;
+		z = step(formula, z, c);
+
+		// Formula::converges, the magnets and Nova
+		double2 offset = z - previous;
+
+		if ((formula == 5 || formula == 6 || formula == 8) && (offset.x * offset.x) + (offset.y * offset.y) < 1e-12) {
+			converged[i] = 1;
+			break;
+		}
+	}
+
+	iterations[i] = count;
+}
+"#;
+
+fn check(code: ClInt, call: &str) -> Result<(), String> {
+	if code == CL_SUCCESS {
+		Ok(())
+	} else {
+		Err(format!("{} failed with OpenCL error {}", call, code))
+	}
+}
+
+// Released on drop, so an error part way through a render doesn't leak device memory
+struct Buffer(Handle);
+
+impl Drop for Buffer {
+	fn drop(&mut self) {
+		unsafe { clReleaseMemObject(self.0) };
+	}
+}
+
+fn device_info(device: Handle, param: ClUint) -> Result<Vec<u8>, String> {
+	let mut size = 0;
+	check(unsafe { clGetDeviceInfo(device, param, 0, ptr::null_mut(), &mut size) }, "clGetDeviceInfo")?;
+
+	let mut value = vec![0u8; size];
+	check(unsafe { clGetDeviceInfo(device, param, size, value.as_mut_ptr().cast(), ptr::null_mut()) }, "clGetDeviceInfo")?;
+
+	Ok(value)
+}
+
+fn devices(platform: Handle, device_type: ClBitfield) -> Vec<Handle> {
+	let mut count = 0;
+
+	if unsafe { clGetDeviceIDs(platform, device_type, 0, ptr::null_mut(), &mut count) } != CL_SUCCESS || count == 0 {
+		return Vec::new();
+	}
+
+	let mut devices = vec![ptr::null_mut(); count as usize];
+	let code = unsafe { clGetDeviceIDs(platform, device_type, count, devices.as_mut_ptr(), ptr::null_mut()) };
+
+	if code == CL_SUCCESS { devices } else { Vec::new() }
+}
+
+fn supports_doubles(device: Handle) -> bool {
+	device_info(device, CL_DEVICE_DOUBLE_FP_CONFIG).is_ok_and(|config| config.iter().any(|&byte| byte != 0))
+}
+
+// A GPU if there's one with double precision, otherwise any such device, from any platform
+fn pick_device() -> Result<Handle, String> {
+	let mut count = 0;
+	check(unsafe { clGetPlatformIDs(0, ptr::null_mut(), &mut count) }, "clGetPlatformIDs")?;
+
+	let mut platforms = vec![ptr::null_mut(); count as usize];
+	check(unsafe { clGetPlatformIDs(count, platforms.as_mut_ptr(), ptr::null_mut()) }, "clGetPlatformIDs")?;
+
+	[CL_DEVICE_TYPE_GPU, CL_DEVICE_TYPE_ALL]
+		.into_iter()
+		.flat_map(|device_type| platforms.iter().flat_map(move |&platform| devices(platform, device_type)))
+		.find(|&device| supports_doubles(device))
+		.ok_or_else(|| String::from("no OpenCL device supports double precision"))
+}
+
+pub struct OpenCl {
+	device_name: String,
+
+	context: Handle,
+	queue: Handle,
+	program: Handle,
+	// Arguments are set on the kernel itself, so only one render can use it at a time
+	kernel: Mutex<Handle>,
+}
+
+// OpenCL objects can be used from any thread, apart from setting kernel arguments which the mutex covers
+unsafe impl Send for OpenCl {}
+unsafe impl Sync for OpenCl {}
+
+impl OpenCl {
+	pub fn new() -> Result<OpenCl, String> {
+		let device = pick_device()?;
+
+		let name = device_info(device, CL_DEVICE_NAME)?;
+		let device_name = String::from_utf8_lossy(&name).trim_end_matches('\0').trim().to_string();
+
+		let mut error = CL_SUCCESS;
+
+		let context = unsafe { clCreateContext(ptr::null(), 1, &device, ptr::null(), ptr::null_mut(), &mut error) };
+		check(error, "clCreateContext")?;
+
+		// Built up as it goes, so anything created before a failure is still released
+		let mut backend = OpenCl {
+			device_name,
+			context,
+			queue: ptr::null_mut(),
+			program: ptr::null_mut(),
+			kernel: Mutex::new(ptr::null_mut()),
+		};
+
+		backend.queue = unsafe { clCreateCommandQueue(context, device, 0, &mut error) };
+		check(error, "clCreateCommandQueue")?;
+
+		let source = KERNEL.as_ptr().cast::<c_char>();
+		let length = KERNEL.len();
+
+		backend.program = unsafe { clCreateProgramWithSource(context, 1, &source, &length, &mut error) };
+		check(error, "clCreateProgramWithSource")?;
+
+		let code = unsafe { clBuildProgram(backend.program, 1, &device, ptr::null(), ptr::null(), ptr::null_mut()) };
+
+		if code != CL_SUCCESS {
+			let mut size = 0;
+			let mut log = Vec::new();
+
+			if unsafe { clGetProgramBuildInfo(backend.program, device, CL_PROGRAM_BUILD_LOG, 0, ptr::null_mut(), &mut size) } == CL_SUCCESS {
+				log = vec![0u8; size];
+				unsafe { clGetProgramBuildInfo(backend.program, device, CL_PROGRAM_BUILD_LOG, size, log.as_mut_ptr().cast(), ptr::null_mut()) };
+			}
+
+			return Err(format!("the kernel didn't build: {}", String::from_utf8_lossy(&log).trim_end_matches('\0').trim()));
+		}
+
+		let name = CString::new("escape").unwrap();
+		let kernel = unsafe { clCreateKernel(backend.program, name.as_ptr(), &mut error) };
+		check(error, "clCreateKernel")?;
+
+		backend.kernel = Mutex::new(kernel);
+
+		Ok(backend)
+	}
+
+	fn iterate(&self, points: &mut [[f64; 2]], job: &RenderJob) -> Result<(Vec<f64>, Vec<ClInt>), String> {
+		let mut error = CL_SUCCESS;
+		let mut iterations = vec![0.0f64; points.len()];
+		let mut converged: Vec<ClInt> = vec![0; points.len()];
+
+		let input = Buffer(unsafe {
+			clCreateBuffer(self.context, CL_MEM_READ_ONLY | CL_MEM_COPY_HOST_PTR, points.len() * 16, points.as_mut_ptr().cast(), &mut error)
+		});
+		check(error, "clCreateBuffer")?;
+
+		let output = Buffer(unsafe { clCreateBuffer(self.context, CL_MEM_WRITE_ONLY, iterations.len() * 8, ptr::null_mut(), &mut error) });
+		check(error, "clCreateBuffer")?;
+
+		let converged_output = Buffer(unsafe { clCreateBuffer(self.context, CL_MEM_WRITE_ONLY, converged.len() * 4, ptr::null_mut(), &mut error) });
+		check(error, "clCreateBuffer")?;
+
+		let settings = job.settings;
+		let formula = Formula::ALL.iter().position(|&formula| formula == settings.formula).unwrap_or(0) as ClInt;
+		let start = settings.formula.critical_point() + settings.initial_z;
+		let initial_z = [start.real(), start.imaginary()];
+		let is_julia = settings.julia.is_some() as ClInt;
+		let julia = settings.julia.map_or([0.0, 0.0], |c| [c.real(), c.imaginary()]);
+		let bailout_sqr = settings.bailout * settings.bailout;
+
+		let guard = self.kernel.lock().unwrap();
+		let kernel = *guard;
+
+		unsafe {
+			let arguments: [(usize, *const c_void); 9] = [
+				(size_of::<Handle>(), (&input.0 as *const Handle).cast()),
+				(4, (&formula as *const ClInt).cast()),
+				(16, initial_z.as_ptr().cast()),
+				(4, (&is_julia as *const ClInt).cast()),
+				(16, julia.as_ptr().cast()),
+				(8, (&bailout_sqr as *const f64).cast()),
+				(8, (&settings.max_iterations as *const f64).cast()),
+				(size_of::<Handle>(), (&output.0 as *const Handle).cast()),
+				(size_of::<Handle>(), (&converged_output.0 as *const Handle).cast()),
+			];
+
+			for (index, (size, value)) in arguments.into_iter().enumerate() {
+				check(clSetKernelArg(kernel, index as ClUint, size, value), "clSetKernelArg")?;
+			}
+
+			let global_size = points.len();
+			check(
+				clEnqueueNDRangeKernel(self.queue, kernel, 1, ptr::null(), &global_size, ptr::null(), 0, ptr::null(), ptr::null_mut()),
+				"clEnqueueNDRangeKernel",
+			)?;
+
+			check(
+				clEnqueueReadBuffer(self.queue, output.0, CL_TRUE, 0, iterations.len() * 8, iterations.as_mut_ptr().cast(), 0, ptr::null(), ptr::null_mut()),
+				"clEnqueueReadBuffer",
+			)?;
+
+			check(
+				clEnqueueReadBuffer(self.queue, converged_output.0, CL_TRUE, 0, converged.len() * 4, converged.as_mut_ptr().cast(), 0, ptr::null(), ptr::null_mut()),
+				"clEnqueueReadBuffer",
+			)?;
+		}
+
+		Ok((iterations, converged))
+	}
+}
+
+impl RenderBackend for OpenCl {
+	fn name(&self) -> String {
+		format!("opencl ({})", self.device_name)
+	}
+
+	fn render(&self, job: RenderJob, _progress: Option<&Sender<RenderedTile>>) -> Result<Frame, String> {
+		let mut points: Vec<[f64; 2]> = renderer::sample_points(&job).into_iter().map(|c| [c.real(), c.imaginary()]).collect();
+
+		let (iterations, converged) = if points.is_empty() { (Vec::new(), Vec::new()) } else { self.iterate(&mut points, &job)? };
+		let iterations = iterations.into_iter().map(|count| (count >= 0.0).then_some(count)).collect();
+		let converged = converged.into_iter().map(|flag| flag != 0).collect();
+
+		Ok(renderer::frame_from_iterations(job, iterations, converged))
+	}
+}
+
+impl Drop for OpenCl {
+	fn drop(&mut self) {
+		unsafe {
+			let kernel = *self.kernel.get_mut().unwrap();
+
+			if !kernel.is_null() {
+				clReleaseKernel(kernel);
+			}
+
+			if !self.program.is_null() {
+				clReleaseProgram(self.program);
+			}
+
+			if !self.queue.is_null() {
+				clReleaseCommandQueue(self.queue);
+			}
+
+			clReleaseContext(self.context);
+		}
+	}
+}
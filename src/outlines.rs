@@ -0,0 +1,50 @@
+use std::f64::consts::TAU;
+
+use crate::complex::Complex;
+
+// Points around the boundary of the main cardioid, the period 1 component,
+// c = e^it / 2 - e^2it / 4 which has its cusp at 1/4
+pub fn main_cardioid(count: usize) -> Vec<Complex> {
+	(0..=count).map(|i| {
+		let t = TAU * i as f64 / count as f64;
+		Complex::from_polar(0.5, t) - Complex::from_polar(0.25, 2.0 * t)
+	}).collect()
+}
+
+// The period 2 component is exactly the disc of radius 1/4 centred on -1
+pub fn period_two_bulb(count: usize) -> Vec<Complex> {
+	(0..=count).map(|i| Complex::new(-1.0, 0.0) + Complex::from_polar(0.25, TAU * i as f64 / count as f64)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const EPSILON: f64 = 1e-12;
+
+	#[test]
+	fn cardioid_meets_the_real_axis_at_the_cusp_and_the_bulb() {
+		let points = main_cardioid(4);
+
+		assert!((points[0] - Complex::new(0.25, 0.0)).abs() < EPSILON);
+		assert!((points[2] - Complex::new(-0.75, 0.0)).abs() < EPSILON);
+		assert!((points[4] - points[0]).abs() < EPSILON);
+	}
+
+	#[test]
+	fn bulb_touches_the_cardioid() {
+		let points = period_two_bulb(2);
+
+		assert!((points[0] - Complex::new(-0.75, 0.0)).abs() < EPSILON);
+		assert!((points[1] - Complex::new(-1.25, 0.0)).abs() < EPSILON);
+	}
+
+	#[test]
+	fn cardioid_boundary_has_a_neutral_fixed_point() {
+		// On the boundary the fixed point z = z^2 + c has multiplier |2z| = 1
+		for c in main_cardioid(16) {
+			let z = (Complex::new(1.0, 0.0) - (Complex::new(1.0, 0.0) - c * 4.0).powf(0.5)) * 0.5;
+			assert!(((z * 2.0).abs() - 1.0).abs() < 1e-9, "{}", c);
+		}
+	}
+}
@@ -0,0 +1,294 @@
+use ggez::graphics::{Canvas, Color, DrawMode, DrawParam, Drawable, Mesh, MeshBuilder, Rect, Text};
+use ggez::mint::Point2;
+use ggez::{Context, GameResult as Result};
+
+use mandelbrot_viewer::complex::Complex;
+use mandelbrot_viewer::contours::Segment;
+use mandelbrot_viewer::gradient::Gradient;
+use mandelbrot_viewer::grid;
+use mandelbrot_viewer::locale::Locale;
+use mandelbrot_viewer::stats::FrameStats;
+use mandelbrot_viewer::viewport::Viewport;
+
+const PANEL_COLOR: Color = Color::new(0.0, 0.0, 0.0, 0.6);
+const BAR_COLOR: Color = Color::new(1.0, 1.0, 1.0, 0.85);
+const ERROR_COLOR: Color = Color::new(0.6, 0.0, 0.0, 0.85);
+pub const RAY_COLOR: Color = Color::new(1.0, 1.0, 1.0, 0.9);
+pub const OUTLINE_COLOR: Color = Color::new(1.0, 1.0, 0.4, 0.8);
+pub const SELECTION_COLOR: Color = Color::new(0.4, 0.8, 1.0, 0.9);
+const CONTOUR_COLOR: Color = Color::new(0.0, 0.0, 0.0, 0.6);
+const GRID_COLOR: Color = Color::new(1.0, 1.0, 1.0, 0.25);
+const AXIS_COLOR: Color = Color::new(1.0, 1.0, 1.0, 0.7);
+const CROSSHAIR_COLOR: Color = Color::new(1.0, 1.0, 1.0, 0.9);
+const CROSSHAIR_SHADOW_COLOR: Color = Color::new(0.0, 0.0, 0.0, 0.6);
+
+// Half the length of each crosshair arm, and the gap left in the middle
+const CROSSHAIR_SIZE: f32 = 10.0;
+const CROSSHAIR_GAP: f32 = 3.0;
+
+// Side of the square handle under each of the palette editor's stops
+pub const HANDLE_SIZE: f32 = 10.0;
+
+// Grid lines are at least this many pixels apart
+const GRID_SPACING: f64 = 80.0;
+
+// Path points further than this many window sizes off screen are dropped before drawing
+const PATH_MARGIN: f32 = 4.0;
+
+const PADDING: f32 = 4.0;
+
+const TEXT_SIZE: f32 = 14.0;
+
+// Bar heights are logarithmic, otherwise the low iteration bands dwarf everything else
+pub fn draw_histogram(canvas: &mut Canvas, context: &Context, counts: &[usize], area: Rect) -> Result {
+	let mut builder = MeshBuilder::new();
+	builder.rectangle(DrawMode::fill(), area, PANEL_COLOR)?;
+
+	let largest = counts.iter().copied().max().unwrap_or(0);
+
+	if largest > 0 {
+		let inner_width = area.w - PADDING * 2.0;
+		let inner_height = area.h - PADDING * 2.0;
+
+		let bar_width = inner_width / counts.len() as f32;
+		let log_largest = (1.0 + largest as f32).ln();
+
+		for (i, &count) in counts.iter().enumerate() {
+			if count == 0 {
+				continue;
+			}
+
+			let bar_height = inner_height * ((1.0 + count as f32).ln() / log_largest);
+
+			let bar = Rect::new(
+				area.x + PADDING + i as f32 * bar_width,
+				area.y + PADDING + inner_height - bar_height,
+				bar_width.max(1.0),
+				bar_height,
+			);
+
+			builder.rectangle(DrawMode::fill(), bar, BAR_COLOR)?;
+		}
+	}
+
+	canvas.draw(&Mesh::from_data(context, builder.build()), DrawParam::new());
+
+	Ok(())
+}
+
+// The gradient across the bar, with a handle under each stop in its colour. The selected stop's
+// handle is outlined in the selection colour
+pub fn draw_palette_editor(canvas: &mut Canvas, context: &Context, gradient: &Gradient, selected: Option<usize>, bar: Rect) -> Result {
+	let mut builder = MeshBuilder::new();
+
+	let panel = Rect::new(bar.x - PADDING, bar.y - PADDING, bar.w + PADDING * 2.0, bar.h + HANDLE_SIZE + PADDING * 3.0);
+	builder.rectangle(DrawMode::fill(), panel, PANEL_COLOR)?;
+
+	// A strip per pixel
+	let strips = bar.w.max(1.0) as usize;
+
+	for i in 0..strips {
+		let color = gradient.sample((i as f32 + 0.5) / strips as f32);
+		builder.rectangle(DrawMode::fill(), Rect::new(bar.x + i as f32, bar.y, 1.0, bar.h), color)?;
+	}
+
+	for (i, &(position, color)) in gradient.stops().iter().enumerate() {
+		let handle = Rect::new(bar.x + position * bar.w - HANDLE_SIZE / 2.0, bar.y + bar.h + PADDING, HANDLE_SIZE, HANDLE_SIZE);
+		let (width, outline) = if selected == Some(i) { (2.0, SELECTION_COLOR) } else { (1.0, Color::WHITE) };
+
+		builder.rectangle(DrawMode::fill(), handle, color)?;
+		builder.rectangle(DrawMode::stroke(width), handle, outline)?;
+	}
+
+	canvas.draw(&Mesh::from_data(context, builder.build()), DrawParam::new());
+
+	Ok(())
+}
+
+// Text on a translucent panel so it stays readable over any palette
+pub fn draw_text_panel(canvas: &mut Canvas, context: &Context, lines: &str, position: Point2<f32>) -> Result {
+	let mut text = Text::new(lines);
+	text.set_scale(TEXT_SIZE);
+
+	let Some(bounds) = text.dimensions(context) else {
+		return Ok(());
+	};
+
+	let panel = Rect::new(position.x, position.y, bounds.w + PADDING * 2.0, bounds.h + PADDING * 2.0);
+	canvas.draw(&Mesh::new_rectangle(context, DrawMode::fill(), panel, PANEL_COLOR)?, DrawParam::new());
+
+	let text_position = Point2 { x: position.x + PADDING, y: position.y + PADDING };
+	canvas.draw(&text, DrawParam::new().dest(text_position).color(Color::WHITE));
+
+	Ok(())
+}
+
+fn format_iterations(value: Option<f64>) -> String {
+	value.map_or_else(|| String::from("-"), |value| format!("{:.1}", value))
+}
+
+pub fn draw_stats(canvas: &mut Canvas, context: &Context, locale: &Locale, stats: &FrameStats, position: Point2<f32>) -> Result {
+	let lines = locale.format("stats", &[
+		("min", &format_iterations(stats.min)),
+		("mean", &format_iterations(stats.mean)),
+		("max", &format_iterations(stats.max)),
+		("interior", &format!("{:.1}", stats.interior_fraction * 100.0)),
+		("total", &stats.total_iterations),
+	]);
+
+	draw_text_panel(canvas, context, &lines, position)
+}
+
+// A strip across the bottom of the window, text wraps to fit its width
+pub fn draw_error_banner(canvas: &mut Canvas, context: &Context, message: &str, (width, height): (f32, f32)) -> Result {
+	let mut text = Text::new(message);
+	text.set_scale(TEXT_SIZE);
+	text.set_bounds([width - PADDING * 2.0, f32::INFINITY]);
+
+	let Some(bounds) = text.dimensions(context) else {
+		return Ok(());
+	};
+
+	let banner_height = bounds.h + PADDING * 2.0;
+	let banner = Rect::new(0.0, height - banner_height, width, banner_height);
+	canvas.draw(&Mesh::new_rectangle(context, DrawMode::fill(), banner, ERROR_COLOR)?, DrawParam::new());
+
+	let text_position = Point2 { x: PADDING, y: banner.y + PADDING };
+	canvas.draw(&text, DrawParam::new().dest(text_position).color(Color::WHITE));
+
+	Ok(())
+}
+
+// Points are in window pixels, rays start far outside the window so only the nearby part is drawn
+pub fn draw_path(canvas: &mut Canvas, context: &Context, points: &[Point2<f32>], color: Color, (width, height): (f32, f32)) -> Result {
+	let nearby: Vec<Point2<f32>> = points.iter()
+		.copied()
+		.filter(|point| point.x.abs() < width * PATH_MARGIN && point.y.abs() < height * PATH_MARGIN)
+		.collect();
+
+	if nearby.len() < 2 {
+		return Ok(());
+	}
+
+	canvas.draw(&Mesh::new_line(context, &nearby, 1.5, color)?, DrawParam::new());
+
+	Ok(())
+}
+
+// Outlined in black so it shows up over light and dark palettes alike
+pub fn draw_crosshair(canvas: &mut Canvas, context: &Context, position: Point2<f32>) -> Result {
+	let mut builder = MeshBuilder::new();
+
+	for (width, color) in [(3.5, CROSSHAIR_SHADOW_COLOR), (1.5, CROSSHAIR_COLOR)] {
+		for (dx, dy) in [(1.0, 0.0), (-1.0, 0.0), (0.0, 1.0), (0.0, -1.0)] {
+			let start = Point2 { x: position.x + dx * CROSSHAIR_GAP, y: position.y + dy * CROSSHAIR_GAP };
+			let end = Point2 { x: position.x + dx * CROSSHAIR_SIZE, y: position.y + dy * CROSSHAIR_SIZE };
+
+			builder.line(&[start, end], width, color)?;
+		}
+	}
+
+	canvas.draw(&Mesh::from_data(context, builder.build()), DrawParam::new());
+
+	Ok(())
+}
+
+// Centred on the position
+pub fn draw_label(canvas: &mut Canvas, context: &Context, label: &str, position: Point2<f32>, color: Color) {
+	let mut text = Text::new(label);
+	text.set_scale(TEXT_SIZE);
+
+	let offset = text.dimensions(context).map_or((0.0, 0.0), |bounds| (bounds.w / 2.0, bounds.h / 2.0));
+
+	canvas.draw(&text, DrawParam::new().dest([position.x - offset.0, position.y - offset.1]).color(color));
+}
+
+// Segments are in frame pixels, placed over the window like the frame
+pub fn draw_contours(canvas: &mut Canvas, context: &Context, segments: &[Segment], origin: Point2<f32>, scale: [f32; 2]) -> Result {
+	if segments.is_empty() {
+		return Ok(());
+	}
+
+	let mut builder = MeshBuilder::new();
+
+	// Offset by half a pixel, as each pixel's value is drawn as the square to its bottom right
+	let point = |(x, y): (f64, f64)| Point2 { x: origin.x + (x as f32 + 0.5) * scale[0], y: origin.y + (y as f32 + 0.5) * scale[1] };
+
+	for &[start, end] in segments {
+		builder.line(&[point(start), point(end)], 1.0, CONTOUR_COLOR)?;
+	}
+
+	canvas.draw(&Mesh::from_data(context, builder.build()), DrawParam::new());
+
+	Ok(())
+}
+
+// Lines of constant real and imaginary part across the visible region, spaced to round numbers,
+// with the axes highlighted. Labels sit where each line meets the other axis, or the edge of the
+// view when that axis is off screen. Lines of the inverted plane would be circles, so it has none
+pub fn draw_grid(canvas: &mut Canvas, context: &Context, viewport: &Viewport, (width, height): (f32, f32)) -> Result {
+	if viewport.inverted() {
+		return Ok(());
+	}
+
+	let corners = [(0.0, 0.0), (width, 0.0), (0.0, height), (width, height)]
+		.map(|(x, y)| viewport.pixel_to_complex(x as f64, y as f64));
+
+	let extent = |part: fn(&Complex) -> f64| {
+		let values = corners.map(|corner| part(&corner));
+		(values.iter().copied().fold(f64::INFINITY, f64::min), values.iter().copied().fold(f64::NEG_INFINITY, f64::max))
+	};
+
+	let (real_min, real_max) = extent(Complex::real);
+	let (imaginary_min, imaginary_max) = extent(Complex::imaginary);
+
+	let step = grid::nice_step(GRID_SPACING * viewport.scale());
+	let decimals = grid::label_decimals(step);
+
+	let to_screen = |c: Complex| {
+		let point = viewport.complex_to_pixel(c);
+		Point2 { x: point.x as f32, y: point.y as f32 }
+	};
+
+	let is_visible = |point: Point2<f32>| (0.0..width).contains(&point.x) && (0.0..height).contains(&point.y);
+
+	// Far enough inside the visible region for labels on the edge to be readable
+	let margin = step * 0.2;
+	let label_imaginary = 0.0f64.clamp(imaginary_min + margin, (imaginary_max - margin).max(imaginary_min + margin));
+	let label_real = 0.0f64.clamp(real_min + margin, (real_max - margin).max(real_min + margin));
+
+	let mut builder = MeshBuilder::new();
+	let mut labels = Vec::new();
+
+	for real in grid::grid_values(real_min, real_max, step) {
+		let color = if real == 0.0 { AXIS_COLOR } else { GRID_COLOR };
+		builder.line(&[to_screen(Complex::new(real, imaginary_min)), to_screen(Complex::new(real, imaginary_max))], 1.0, color)?;
+
+		labels.push((format!("{:.*}", decimals, real), to_screen(Complex::new(real, label_imaginary))));
+	}
+
+	for imaginary in grid::grid_values(imaginary_min, imaginary_max, step) {
+		let color = if imaginary == 0.0 { AXIS_COLOR } else { GRID_COLOR };
+		builder.line(&[to_screen(Complex::new(real_min, imaginary)), to_screen(Complex::new(real_max, imaginary))], 1.0, color)?;
+
+		// The real axis is already labelled by the real lines
+		if imaginary != 0.0 {
+			labels.push((format!("{:.*}i", decimals, imaginary), to_screen(Complex::new(label_real, imaginary))));
+		}
+	}
+
+	canvas.draw(&Mesh::from_data(context, builder.build()), DrawParam::new());
+
+	for (label, position) in labels {
+		if !is_visible(position) {
+			continue;
+		}
+
+		let mut text = Text::new(label);
+		text.set_scale(TEXT_SIZE);
+
+		canvas.draw(&text, DrawParam::new().dest([position.x + 2.0, position.y + 2.0]).color(AXIS_COLOR));
+	}
+
+	Ok(())
+}
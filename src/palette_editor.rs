@@ -0,0 +1,174 @@
+use ggez::graphics::Color;
+
+use crate::gradient::{self, Gradient};
+
+// Name edits of the built in rainbow are saved under, so the file doesn't shadow it
+pub const CUSTOM_NAME: &str = "custom";
+
+// A copy of a palette whose stops are being moved, added, recoloured and removed, one of them
+// selected at a time. Stops keep in order of position, selection follows a stop as it moves
+pub struct PaletteEditor {
+	gradient: Gradient,
+	selected: Option<usize>,
+}
+
+impl PaletteEditor {
+	pub fn new(gradient: &Gradient) -> PaletteEditor {
+		let mut gradient = gradient.clone();
+
+		if gradient.name == gradient::DEFAULT_NAME {
+			gradient.name = String::from(CUSTOM_NAME);
+		}
+
+		PaletteEditor { gradient, selected: None }
+	}
+
+	pub fn gradient(&self) -> &Gradient {
+		&self.gradient
+	}
+
+	pub fn selected(&self) -> Option<usize> {
+		self.selected
+	}
+
+	// The stop nearest a position within the tolerance, both as fractions of the gradient
+	pub fn stop_near(&self, position: f32, tolerance: f32) -> Option<usize> {
+		self.gradient.stops().iter()
+			.enumerate()
+			.map(|(i, &(stop, _))| (i, (stop - position).abs()))
+			.filter(|&(_, distance)| distance <= tolerance)
+			.min_by(|a, b| a.1.total_cmp(&b.1))
+			.map(|(i, _)| i)
+	}
+
+	pub fn select(&mut self, stop: Option<usize>) {
+		self.selected = stop;
+	}
+
+	// A new stop takes the colour already there, so adding one changes nothing until it's dragged
+	// or recoloured
+	pub fn add(&mut self, position: f32) {
+		let position = position.clamp(0.0, 1.0);
+		self.replace(None, (position, self.gradient.sample(position)));
+	}
+
+	pub fn move_selected(&mut self, position: f32) {
+		if let Some(i) = self.selected {
+			let color = self.gradient.stops()[i].1;
+			self.replace(Some(i), (position.clamp(0.0, 1.0), color));
+		}
+	}
+
+	pub fn recolor_selected(&mut self, color: Color) {
+		if let Some(i) = self.selected {
+			let position = self.gradient.stops()[i].0;
+			self.replace(Some(i), (position, color));
+		}
+	}
+
+	// At least two stops are kept, for there to be a gradient
+	pub fn remove_selected(&mut self) {
+		let Some(i) = self.selected.filter(|_| self.gradient.stops().len() > 2) else {
+			return;
+		};
+
+		let mut stops = self.gradient.stops().to_vec();
+		stops.remove(i);
+
+		self.rebuild(stops);
+		self.selected = None;
+	}
+
+	// Swaps a stop for another, or adds it, and selects it wherever it ends up
+	fn replace(&mut self, stop: Option<usize>, (position, color): (f32, Color)) {
+		let mut stops = self.gradient.stops().to_vec();
+
+		match stop {
+			Some(i) => stops[i] = (position, color),
+			None => stops.push((position, color)),
+		}
+
+		let moved = stops.len() - 1;
+		stops.swap(stop.unwrap_or(moved), moved);
+
+		// A stable sort keeps the moved stop after any others at the same position
+		self.rebuild(stops);
+		self.selected = self.gradient.stops().iter().rposition(|&stop| stop == (position, color));
+	}
+
+	fn rebuild(&mut self, stops: Vec<(f32, Color)>) {
+		self.gradient = Gradient::new(&self.gradient.name, stops).with_interior(self.gradient.interior());
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn editor() -> PaletteEditor {
+		PaletteEditor::new(&Gradient::new("test", vec![(0.0, Color::BLACK), (1.0, Color::WHITE)]))
+	}
+
+	fn positions(editor: &PaletteEditor) -> Vec<f32> {
+		editor.gradient().stops().iter().map(|&(position, _)| position).collect()
+	}
+
+	#[test]
+	fn added_stops_keep_the_colours() {
+		let mut editor = editor();
+		let before = editor.gradient().sample(0.25);
+
+		editor.add(0.5);
+
+		assert_eq!(positions(&editor), vec![0.0, 0.5, 1.0]);
+		assert_eq!(editor.selected(), Some(1));
+		assert_eq!(editor.gradient().sample(0.25), before);
+	}
+
+	#[test]
+	fn selection_follows_a_moving_stop() {
+		let mut editor = editor();
+		editor.select(Some(0));
+
+		editor.move_selected(0.75);
+		assert_eq!(positions(&editor), vec![0.75, 1.0]);
+		assert_eq!(editor.selected(), Some(0));
+
+		editor.move_selected(2.0);
+		assert_eq!(positions(&editor), vec![1.0, 1.0]);
+		assert_eq!(editor.selected(), Some(1));
+		assert_eq!(editor.gradient().stops()[1].1, Color::BLACK);
+	}
+
+	#[test]
+	fn recolours_and_removes_the_selected_stop() {
+		let mut editor = editor();
+		editor.add(0.5);
+
+		editor.recolor_selected(Color::RED);
+		assert_eq!(editor.gradient().sample(0.5), Color::RED);
+
+		editor.remove_selected();
+		assert_eq!(positions(&editor), vec![0.0, 1.0]);
+
+		// The last two stay
+		editor.select(Some(0));
+		editor.remove_selected();
+		assert_eq!(positions(&editor), vec![0.0, 1.0]);
+	}
+
+	#[test]
+	fn finds_the_nearest_stop() {
+		let editor = editor();
+
+		assert_eq!(editor.stop_near(0.04, 0.05), Some(0));
+		assert_eq!(editor.stop_near(0.97, 0.05), Some(1));
+		assert_eq!(editor.stop_near(0.5, 0.05), None);
+	}
+
+	#[test]
+	fn the_rainbow_is_saved_under_another_name() {
+		assert_eq!(PaletteEditor::new(&Gradient::rainbow()).gradient().name, CUSTOM_NAME);
+		assert_eq!(editor().gradient().name, "test");
+	}
+}
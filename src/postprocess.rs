@@ -0,0 +1,236 @@
+use ggez::graphics::Color;
+use serde::{Deserialize, Serialize};
+
+// Blur kernels reach this many standard deviations either side, past which weights are negligible
+const KERNEL_REACH: f32 = 3.0;
+
+// Effects over the coloured frame, applied in the order listed. Any of them at 0 is left out
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Effects {
+	// Gaussian blur, its standard deviation in pixels
+	pub blur: f32,
+	// How strongly pixels brighter than the threshold glow into their surroundings, the glow's
+	// spread in pixels, and the luma it starts at from 0 to 1
+	pub bloom: f32,
+	pub bloom_radius: f32,
+	pub bloom_threshold: f32,
+	// Steepens midtones along an S curve, negative values flatten them instead
+	pub contrast: f32,
+	// How much the corners are darkened, from 0 to 1
+	pub vignette: f32,
+}
+
+impl Default for Effects {
+	// A light glow and vignette, which flatters thin filaments on dark palettes
+	fn default() -> Effects {
+		Effects {
+			blur: 0.0,
+			bloom: 0.6,
+			bloom_radius: 6.0,
+			bloom_threshold: 0.7,
+			contrast: 0.2,
+			vignette: 0.3,
+		}
+	}
+}
+
+fn luma(color: Color) -> f32 {
+	0.2126 * color.r + 0.7152 * color.g + 0.0722 * color.b
+}
+
+// Normalised weights from -radius to radius
+fn kernel(sigma: f32) -> Vec<f32> {
+	let radius = (sigma * KERNEL_REACH).ceil() as i64;
+	let weights: Vec<f32> = (-radius..=radius).map(|x| (-(x * x) as f32 / (2.0 * sigma * sigma)).exp()).collect();
+	let total: f32 = weights.iter().sum();
+
+	weights.into_iter().map(|weight| weight / total).collect()
+}
+
+// Separable, with edge pixels repeated past the border. Alpha is left alone
+fn gaussian_blur(pixels: &[Color], (width, height): (usize, usize), sigma: f32) -> Vec<Color> {
+	let kernel = kernel(sigma);
+	let radius = (kernel.len() / 2) as i64;
+
+	let pass = |source: &[Color], step: (i64, i64)| -> Vec<Color> {
+		(0..width * height).map(|i| {
+			let (x, y) = ((i % width) as i64, (i / width) as i64);
+			let mut total = Color::new(0.0, 0.0, 0.0, source[i].a);
+
+			for (k, &weight) in kernel.iter().enumerate() {
+				let offset = k as i64 - radius;
+				let sx = (x + offset * step.0).clamp(0, width as i64 - 1) as usize;
+				let sy = (y + offset * step.1).clamp(0, height as i64 - 1) as usize;
+				let color = source[sy * width + sx];
+
+				total.r += color.r * weight;
+				total.g += color.g * weight;
+				total.b += color.b * weight;
+			}
+
+			total
+		}).collect()
+	};
+
+	pass(&pass(pixels, (1, 0)), (0, 1))
+}
+
+// Smoothstep pulled towards or away from the identity
+fn curve(value: f32, contrast: f32) -> f32 {
+	let s = value * value * (3.0 - 2.0 * value);
+	(value + (s - value) * contrast).clamp(0.0, 1.0)
+}
+
+impl Effects {
+	pub fn is_empty(&self) -> bool {
+		self.blur <= 0.0 && self.bloom <= 0.0 && self.contrast == 0.0 && self.vignette <= 0.0
+	}
+
+	// How far blurs reach, so images processed in pieces can be given this many rows of context
+	pub fn margin(&self) -> usize {
+		let reach = |sigma: f32| if sigma > 0.0 { (sigma * KERNEL_REACH).ceil() as usize } else { 0 };
+
+		reach(self.blur) + if self.bloom > 0.0 { reach(self.bloom_radius) } else { 0 }
+	}
+
+	pub fn validate(&self) -> Result<(), String> {
+		let values = [self.blur, self.bloom, self.bloom_radius, self.bloom_threshold, self.contrast, self.vignette];
+
+		if values.iter().any(|value| !value.is_finite()) {
+			return Err(String::from("effects must be finite"));
+		}
+
+		if self.blur < 0.0 || self.bloom < 0.0 || self.bloom_radius < 0.0 {
+			return Err(String::from("blur, bloom and bloom_radius can't be negative"));
+		}
+
+		if !(0.0..1.0).contains(&self.bloom_threshold) {
+			return Err(String::from("bloom_threshold must be at least 0 and below 1"));
+		}
+
+		if !(0.0..=1.0).contains(&self.vignette) {
+			return Err(String::from("vignette must be from 0 to 1"));
+		}
+
+		Ok(())
+	}
+
+	pub fn apply(&self, pixels: &[Color], size: (usize, usize)) -> Vec<Color> {
+		self.apply_rows(pixels, size, 0, size.1)
+	}
+
+	// For a band of rows from a taller image, starting at row top of image_height, so the vignette
+	// lines up across bands. Rows within the margin of the band's edges see only part of the blur
+	pub fn apply_rows(&self, pixels: &[Color], (width, height): (usize, usize), top: usize, image_height: usize) -> Vec<Color> {
+		let mut pixels = pixels.to_vec();
+
+		if self.blur > 0.0 {
+			pixels = gaussian_blur(&pixels, (width, height), self.blur);
+		}
+
+		if self.bloom > 0.0 && self.bloom_radius > 0.0 {
+			let bright: Vec<Color> = pixels.iter().map(|&color| {
+				let amount = ((luma(color) - self.bloom_threshold) / (1.0 - self.bloom_threshold)).max(0.0);
+				Color::new(color.r * amount, color.g * amount, color.b * amount, color.a)
+			}).collect();
+
+			let glow = gaussian_blur(&bright, (width, height), self.bloom_radius);
+
+			for (pixel, glow) in pixels.iter_mut().zip(glow) {
+				pixel.r = (pixel.r + glow.r * self.bloom).min(1.0);
+				pixel.g = (pixel.g + glow.g * self.bloom).min(1.0);
+				pixel.b = (pixel.b + glow.b * self.bloom).min(1.0);
+			}
+		}
+
+		if self.contrast != 0.0 {
+			for pixel in &mut pixels {
+				*pixel = Color::new(curve(pixel.r, self.contrast), curve(pixel.g, self.contrast), curve(pixel.b, self.contrast), pixel.a);
+			}
+		}
+
+		if self.vignette > 0.0 {
+			let (half_width, half_height) = (width as f32 / 2.0, image_height as f32 / 2.0);
+
+			for (i, pixel) in pixels.iter_mut().enumerate() {
+				// 0 at the centre and 1 in the corners
+				let dx = ((i % width) as f32 + 0.5 - half_width) / half_width;
+				let dy = ((top + i / width) as f32 + 0.5 - half_height) / half_height;
+				let shade = 1.0 - self.vignette * (dx * dx + dy * dy) / 2.0;
+
+				*pixel = Color::new(pixel.r * shade, pixel.g * shade, pixel.b * shade, pixel.a);
+			}
+		}
+
+		pixels
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const NONE: Effects = Effects { blur: 0.0, bloom: 0.0, bloom_radius: 0.0, bloom_threshold: 0.5, contrast: 0.0, vignette: 0.0 };
+
+	#[test]
+	fn empty_effects_leave_pixels_alone() {
+		let pixels = [Color::RED, Color::new(0.2, 0.4, 0.6, 0.5)];
+
+		assert!(NONE.is_empty());
+		assert_eq!(NONE.apply(&pixels, (2, 1)), pixels);
+		assert!(!Effects::default().is_empty());
+	}
+
+	#[test]
+	fn blur_spreads_and_keeps_brightness() {
+		let mut pixels = vec![Color::BLACK; 9 * 9];
+		pixels[4 * 9 + 4] = Color::WHITE;
+
+		let blurred = Effects { blur: 1.0, ..NONE }.apply(&pixels, (9, 9));
+		let total: f32 = blurred.iter().map(|color| color.r).sum();
+
+		assert!((total - 1.0).abs() < 1e-3);
+		assert!(blurred[4 * 9 + 4].r < 1.0 && blurred[4 * 9 + 5].r > 0.0);
+		assert!(blurred[4 * 9 + 5].r == blurred[5 * 9 + 4].r);
+	}
+
+	#[test]
+	fn bloom_only_glows_from_bright_pixels() {
+		let mut pixels = vec![Color::new(0.6, 0.6, 0.6, 1.0); 11 * 11];
+		let bloom = Effects { bloom: 1.0, bloom_radius: 1.0, bloom_threshold: 0.7, ..NONE };
+
+		assert_eq!(bloom.apply(&pixels, (11, 11)), pixels);
+
+		// Out of the glow's reach in the corner
+		pixels[5 * 11 + 5] = Color::WHITE;
+		let glowing = bloom.apply(&pixels, (11, 11));
+
+		assert!(glowing[5 * 11 + 6].r > 0.6 && glowing[0].r == 0.6);
+	}
+
+	#[test]
+	fn contrast_and_vignette_keep_their_anchors() {
+		let pixels = [Color::BLACK, Color::new(0.5, 0.5, 0.5, 1.0), Color::WHITE, Color::new(0.25, 0.75, 0.25, 1.0)];
+		let curved = Effects { contrast: 1.0, ..NONE }.apply(&pixels, (4, 1));
+
+		assert_eq!(&curved[..3], &pixels[..3]);
+		assert!(curved[3].r < 0.25 && curved[3].g > 0.75);
+
+		// Darkest in the corners, and bands line up with the whole image
+		let white = vec![Color::WHITE; 4 * 4];
+		let vignette = Effects { vignette: 0.5, ..NONE };
+		let whole = vignette.apply(&white, (4, 4));
+
+		assert!(whole[0].r < whole[5].r && whole[0].r > 0.5);
+		assert_eq!(vignette.apply_rows(&white[8..], (4, 2), 2, 4), &whole[8..]);
+	}
+
+	#[test]
+	fn validation_rejects_out_of_range_effects() {
+		assert!(Effects::default().validate().is_ok());
+		assert!(Effects { blur: -1.0, ..NONE }.validate().is_err());
+		assert!(Effects { bloom_threshold: 1.0, ..NONE }.validate().is_err());
+		assert!(Effects { vignette: f32::NAN, ..NONE }.validate().is_err());
+	}
+}
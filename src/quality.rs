@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+
+// Bundles the settings traded off between navigation speed and final image quality
+
+// Scales the view steps through, as multiples of the window's resolution on top of the quality's.
+// Below 1 renders faster, above it renders more pixels than are shown and averages them down
+pub const RENDER_SCALES: [f64; 7] = [0.5, 0.75, 1.0, 1.5, 2.0, 3.0, 4.0];
+
+// The next step up or down from any scale, which needn't be one of the steps
+pub fn step_render_scale(scale: f64, up: bool) -> f64 {
+	let next = match up {
+		true => RENDER_SCALES.iter().find(|&&step| step > scale + 1e-9),
+		false => RENDER_SCALES.iter().rev().find(|&&step| step < scale - 1e-9),
+	};
+
+	next.copied().unwrap_or(scale)
+}
+
+#[derive(Copy, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Quality {
+	Draft,
+	Normal,
+	High,
+}
+
+impl Quality {
+	pub fn name(self) -> &'static str {
+		match self {
+			Quality::Draft => "draft",
+			Quality::Normal => "normal",
+			Quality::High => "high",
+		}
+	}
+
+	pub fn next(self) -> Quality {
+		match self {
+			Quality::Draft => Quality::Normal,
+			Quality::Normal => Quality::High,
+			Quality::High => Quality::Draft,
+		}
+	}
+
+	// Fraction of the window resolution rendered, the result is scaled up to fill the window
+	pub fn resolution_scale(self) -> f64 {
+		match self {
+			Quality::Draft => 0.5,
+			Quality::Normal | Quality::High => 1.0,
+		}
+	}
+
+	// Antialiasing samples per axis
+	pub fn samples(self) -> usize {
+		match self {
+			Quality::Draft | Quality::Normal => 1,
+			Quality::High => 3,
+		}
+	}
+
+	// Applied to the configured iteration cap
+	pub fn iteration_multiplier(self) -> f64 {
+		match self {
+			Quality::Draft => 0.5,
+			Quality::Normal => 1.0,
+			Quality::High => 4.0,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn render_scales_step_within_range() {
+		assert_eq!(step_render_scale(1.0, true), 1.5);
+		assert_eq!(step_render_scale(1.0, false), 0.75);
+		assert_eq!(step_render_scale(1.2, false), 1.0);
+		assert_eq!(step_render_scale(4.0, true), 4.0);
+		assert_eq!(step_render_scale(0.5, false), 0.5);
+	}
+}
@@ -0,0 +1,198 @@
+use std::collections::VecDeque;
+use std::io;
+use std::sync::{Arc, Condvar, Mutex, OnceLock};
+use std::thread;
+
+use crate::affinity;
+
+// One pool of workers shared by every render, taking interactive work before anything in the
+// background. Renders are split into tiles, so an export started mid-navigation only holds the
+// workers for the tiles already in progress before the view's tiles get them
+
+pub const DEFAULT_THREADS: usize = 10;
+
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub enum Priority {
+	// The live view, which has to keep up with navigation
+	Interactive,
+	// Exports, posters, tiles and anything else nobody is watching fill in
+	Background,
+}
+
+type Task = Box<dyn FnOnce() + Send>;
+
+#[derive(Default)]
+struct State {
+	interactive: VecDeque<Task>,
+	background: VecDeque<Task>,
+
+	// How many tasks may run at once, and how many are
+	threads: usize,
+	running: usize,
+	// Workers are only ever added, those beyond the limit wait for it to rise again
+	spawned: usize,
+
+	// Whether each worker keeps to one core, checked between tasks
+	pinned: bool,
+}
+
+#[derive(Default)]
+pub struct WorkQueue {
+	state: Mutex<State>,
+	changed: Condvar,
+}
+
+impl WorkQueue {
+	pub fn shared() -> &'static Arc<WorkQueue> {
+		static SHARED: OnceLock<Arc<WorkQueue>> = OnceLock::new();
+
+		SHARED.get_or_init(|| {
+			let queue = Arc::new(WorkQueue::default());
+			queue.set_threads(DEFAULT_THREADS);
+
+			queue
+		})
+	}
+
+	pub fn threads(&self) -> usize {
+		self.state.lock().unwrap().threads
+	}
+
+	// Takes effect between tiles, so lowering it lets the tasks in progress finish first
+	pub fn set_threads(self: &Arc<Self>, threads: usize) {
+		let mut state = self.state.lock().unwrap();
+		state.threads = threads.max(1);
+
+		while state.spawned < state.threads {
+			let (queue, index) = (Arc::clone(self), state.spawned);
+			thread::spawn(move || queue.work(index));
+
+			state.spawned += 1;
+		}
+
+		self.changed.notify_all();
+	}
+
+	// Worker n is pinned to the nth core the process may use, wrapping around when there are more
+	// workers than cores. Unpinning lets them run anywhere they could before
+	pub fn set_pinned(&self, pinned: bool) {
+		self.state.lock().unwrap().pinned = pinned;
+	}
+
+	fn work(&self, index: usize) {
+		let cores = affinity::allowed_cores();
+		let mut is_pinned = false;
+
+		loop {
+			let (task, pinned) = self.next();
+
+			if pinned != is_pinned {
+				is_pinned = pinned;
+
+				let result = match &cores {
+					Ok(cores) if pinned => affinity::set_current_thread(&[cores[index % cores.len()]]),
+					Ok(cores) => affinity::set_current_thread(cores),
+					Err(e) => Err(io::Error::new(e.kind(), e.to_string())),
+				};
+
+				if let Err(e) = result {
+					eprintln!("could not change render thread {}'s affinity: {}", index, e);
+				}
+			}
+
+			task();
+			self.finished();
+		}
+	}
+
+	// Tasks of the same priority run in the order they were pushed
+	pub fn push(&self, priority: Priority, task: impl FnOnce() + Send + 'static) {
+		let mut state = self.state.lock().unwrap();
+
+		match priority {
+			Priority::Interactive => state.interactive.push_back(Box::new(task)),
+			Priority::Background => state.background.push_back(Box::new(task)),
+		}
+
+		self.changed.notify_all();
+	}
+
+	// Blocks until there's a task and room to run it, tasks catch their own panics so the lock is
+	// never poisoned
+	fn next(&self) -> (Task, bool) {
+		let mut state = self.state.lock().unwrap();
+
+		loop {
+			if state.running < state.threads {
+				if let Some(task) = state.interactive.pop_front().or_else(|| state.background.pop_front()) {
+					state.running += 1;
+					return (task, state.pinned);
+				}
+			}
+
+			state = self.changed.wait(state).unwrap();
+		}
+	}
+
+	fn finished(&self) {
+		self.state.lock().unwrap().running -= 1;
+		self.changed.notify_all();
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use std::sync::atomic::{AtomicUsize, Ordering};
+	use std::sync::mpsc;
+	use std::time::Duration;
+
+	#[test]
+	fn interactive_tasks_jump_the_queue() {
+		// No workers until everything is queued
+		let queue = Arc::new(WorkQueue::default());
+		let (sender, order) = mpsc::channel();
+
+		for (priority, name) in [
+			(Priority::Background, "export 1"),
+			(Priority::Background, "export 2"),
+			(Priority::Interactive, "view 1"),
+			(Priority::Interactive, "view 2"),
+		] {
+			let sender = sender.clone();
+			queue.push(priority, move || sender.send(name).unwrap());
+		}
+
+		queue.set_threads(1);
+
+		let order: Vec<&str> = order.iter().take(4).collect();
+		assert_eq!(order, ["view 1", "view 2", "export 1", "export 2"]);
+	}
+
+	#[test]
+	fn never_runs_more_tasks_than_threads() {
+		let queue = Arc::new(WorkQueue::default());
+		queue.set_threads(4);
+		queue.set_threads(2);
+
+		let (running, most) = (Arc::new(AtomicUsize::new(0)), Arc::new(AtomicUsize::new(0)));
+		let (sender, done) = mpsc::channel();
+
+		for _ in 0..8 {
+			let (running, most, sender) = (Arc::clone(&running), Arc::clone(&most), sender.clone());
+
+			queue.push(Priority::Background, move || {
+				most.fetch_max(running.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+				thread::sleep(Duration::from_millis(5));
+				running.fetch_sub(1, Ordering::SeqCst);
+
+				sender.send(()).unwrap();
+			});
+		}
+
+		assert_eq!(done.iter().take(8).count(), 8);
+		assert_eq!(most.load(Ordering::SeqCst), 2);
+		assert_eq!(queue.threads(), 2);
+	}
+}
@@ -0,0 +1,135 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use ggez::graphics::Color;
+use palette::{Clamp, IntoColor, Lch, Srgb};
+
+use crate::gradient::Gradient;
+
+// Random palettes are named random-<seed> and regenerated from the name wherever one is looked up,
+// so sessions, screenshots and flythroughs that use one reproduce it without a palette file
+pub const PREFIX: &str = "random-";
+
+// Hue offsets in degrees from a random base hue, the colour schemes that tend to look deliberate:
+// analogous, complementary, split complementary and triadic
+const SCHEMES: [&[f32]; 4] = [
+	&[-30.0, 0.0, 30.0],
+	&[0.0, 180.0],
+	&[0.0, 150.0, 210.0],
+	&[0.0, 120.0, 240.0],
+];
+
+// CIE lightness of the dark and light stops, which alternate so neighbouring bands always contrast
+const DARK: [f32; 2] = [8.0, 30.0];
+const LIGHT: [f32; 2] = [65.0, 92.0];
+
+// Chroma range, kept below the most saturated colours as those often fall outside sRGB
+const CHROMA: [f32; 2] = [25.0, 65.0];
+
+// Counting the stop at each end
+const STOPS: [u64; 2] = [4, 7];
+
+// SplitMix64, small and fully determined by the seed on every platform
+struct Rng(u64);
+
+impl Rng {
+	fn next(&mut self) -> u64 {
+		self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+
+		let mut z = self.0;
+		z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+		z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+
+		z ^ (z >> 31)
+	}
+
+	// Uniform in [0, 1)
+	fn unit(&mut self) -> f32 {
+		(self.next() >> 40) as f32 / (1u64 << 24) as f32
+	}
+
+	fn between(&mut self, [low, high]: [f32; 2]) -> f32 {
+		low + (high - low) * self.unit()
+	}
+}
+
+// A seed that differs between presses
+pub fn new_seed() -> u64 {
+	let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_nanos()).unwrap_or(0);
+
+	// Kept to 6 digits so the name is easy to read off and type
+	Rng(nanos as u64).next() % 1_000_000
+}
+
+pub fn name(seed: u64) -> String {
+	format!("{}{}", PREFIX, seed)
+}
+
+// The seed a random palette was named after
+pub fn seed(name: &str) -> Option<u64> {
+	name.strip_prefix(PREFIX)?.parse().ok()
+}
+
+// Evenly spaced stops running through the scheme's hues, alternating dark and light, starting dark
+// so the outermost bands stay in the background
+pub fn generate(seed: u64) -> Gradient {
+	let mut rng = Rng(seed);
+
+	let base = rng.between([0.0, 360.0]);
+	let scheme = SCHEMES[(rng.next() % SCHEMES.len() as u64) as usize];
+	let count = STOPS[0] + rng.next() % (STOPS[1] - STOPS[0] + 1);
+
+	let stops = (0..count)
+		.map(|i| {
+			let hue = base + scheme[i as usize % scheme.len()] + rng.between([-10.0, 10.0]);
+			let lightness = rng.between(if i % 2 == 0 { DARK } else { LIGHT });
+
+			let lch = Lch::new(lightness, rng.between(CHROMA), hue);
+			let rgb: Srgb = lch.into_color();
+			let rgb = rgb.clamp();
+
+			(i as f32 / (count - 1) as f32, Color::new(rgb.red, rgb.green, rgb.blue, 1.0))
+		})
+		.collect();
+
+	Gradient::new(&name(seed), stops)
+}
+
+// Looks a random palette up by name, None for any other name
+pub fn regenerate(name: &str) -> Option<Gradient> {
+	seed(name).map(generate)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn seeds_reproduce_their_palette() {
+		let gradient = generate(1234);
+
+		assert_eq!(gradient.name, "random-1234");
+		assert_eq!(gradient.stops(), generate(1234).stops());
+		assert_ne!(gradient.stops(), generate(1235).stops());
+
+		assert_eq!(regenerate("random-1234").unwrap().stops(), gradient.stops());
+		assert!(regenerate("ultra").is_none());
+		assert!(regenerate("random-").is_none());
+	}
+
+	#[test]
+	fn stops_alternate_between_dark_and_light() {
+		for seed in 0..50 {
+			let gradient = generate(seed);
+			let stops = gradient.stops();
+
+			assert!((4..=7).contains(&stops.len()));
+			assert_eq!((stops[0].0, stops[stops.len() - 1].0), (0.0, 1.0));
+
+			let luma = |color: Color| 0.2126 * color.r + 0.7152 * color.g + 0.0722 * color.b;
+
+			for pair in stops.windows(2) {
+				assert!((luma(pair[0].1) - luma(pair[1].1)).abs() > 0.1, "seed {} has low contrast stops", seed);
+			}
+		}
+	}
+}
@@ -0,0 +1,149 @@
+use std::f64::consts::TAU;
+use std::fmt;
+use std::str::FromStr;
+
+use crate::complex::Complex;
+
+// Steps taken per halving of the target radius, more gives a smoother curve
+const SHARPNESS: usize = 8;
+
+// Doubles can't follow the ray any closer to where it lands than this
+const MAX_DEPTH: usize = 48;
+
+const ESCAPE_RADIUS: f64 = 65536.0;
+
+const NEWTON_STEPS: usize = 16;
+
+// An external angle in turns, kept as an exact fraction so it can be doubled without rounding
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Angle {
+	numerator: u64,
+	denominator: u64,
+}
+
+impl Angle {
+	// Reduced into [0, 1)
+	pub fn new(numerator: u64, denominator: u64) -> Option<Angle> {
+		(denominator > 0).then(|| Angle {
+			numerator: numerator % denominator,
+			denominator,
+		})
+	}
+
+	fn doubled(self) -> Angle {
+		Angle {
+			numerator: ((self.numerator as u128 * 2) % self.denominator as u128) as u64,
+			denominator: self.denominator,
+		}
+	}
+
+	fn turns(self) -> f64 {
+		self.numerator as f64 / self.denominator as f64
+	}
+}
+
+impl fmt::Display for Angle {
+	fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+		write!(f, "{}/{}", self.numerator, self.denominator)
+	}
+}
+
+impl FromStr for Angle {
+	type Err = String;
+
+	// A fraction like 1/3, or a whole number of turns
+	fn from_str(text: &str) -> Result<Angle, String> {
+		let (numerator, denominator) = text.split_once('/').unwrap_or((text, "1"));
+
+		let parse = |value: &str| value.trim().parse::<u64>().map_err(|e| format!("{}: {}", value.trim(), e));
+
+		Angle::new(parse(numerator)?, parse(denominator)?).ok_or_else(|| String::from("the denominator can't be 0"))
+	}
+}
+
+// Follows the external ray of the given angle inwards from far outside the set, returning points
+// of the c plane along it. Each step picks a target for z_k(c) with the ray's angle and a slowly
+// shrinking radius, then moves c onto it with Newton's method
+pub fn trace_ray(angle: Angle) -> Vec<Complex> {
+	let mut angle = angle;
+	let mut c = Complex::from_polar(ESCAPE_RADIUS, TAU * angle.turns());
+
+	let mut points = vec![c];
+
+	for depth in 0..MAX_DEPTH {
+		for step in 0..SHARPNESS {
+			let radius = ESCAPE_RADIUS.powf(0.5f64.powf((step as f64 + 0.5) / SHARPNESS as f64));
+			let target = Complex::from_polar(radius, TAU * angle.turns());
+
+			for _ in 0..NEWTON_STEPS {
+				let mut z = Complex::new(0.0, 0.0);
+				let mut dz = Complex::new(0.0, 0.0);
+
+				for _ in 0..=depth {
+					dz = z * dz * 2.0 + 1.0;
+					z = z * z + c;
+				}
+
+				let next = c - (z - target) / dz;
+
+				if !next.real().is_finite() || !next.imaginary().is_finite() {
+					return points;
+				}
+
+				let has_converged = (next - c).norm_sqr() < 1e-30;
+				c = next;
+
+				if has_converged {
+					break;
+				}
+			}
+
+			points.push(c);
+		}
+
+		angle = angle.doubled();
+	}
+
+	points
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn parses_fractions() {
+		assert_eq!("1/3".parse(), Ok(Angle::new(1, 3).unwrap()));
+		assert_eq!(" 4 / 3 ".parse(), Ok(Angle::new(1, 3).unwrap()));
+		assert_eq!("0".parse(), Ok(Angle::new(0, 1).unwrap()));
+
+		assert!("1/0".parse::<Angle>().is_err());
+		assert!("a/3".parse::<Angle>().is_err());
+	}
+
+	#[test]
+	fn doubling_wraps_around() {
+		let angle = Angle::new(2, 3).unwrap().doubled();
+
+		assert_eq!(angle, Angle::new(1, 3).unwrap());
+	}
+
+	#[test]
+	fn zero_ray_runs_along_the_real_axis_to_the_cusp() {
+		let points = trace_ray(Angle::new(0, 1).unwrap());
+		let end = points[points.len() - 1];
+
+		assert!(points.iter().all(|point| point.imaginary().abs() < 1e-9 && point.real() > 0.25));
+		assert!((end.real() - 0.25).abs() < 0.05, "{}", end);
+	}
+
+	#[test]
+	fn third_ray_lands_on_the_period_two_root() {
+		let points = trace_ray(Angle::new(1, 3).unwrap());
+		let end = points[points.len() - 1];
+
+		// Parabolic landing points are approached slowly
+		assert!((end - Complex::new(-0.75, 0.0)).abs() < 0.1, "{}", end);
+		assert!(end.imaginary() > 0.0);
+	}
+}
@@ -0,0 +1,124 @@
+use std::collections::VecDeque;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+use ggez::input::mouse::MouseButton;
+use ggez::winit::event::VirtualKeyCode;
+use serde::{Deserialize, Serialize};
+
+use crate::session::Session;
+
+// Input the viewer acts on, modifiers are the bits of ggez's KeyMods
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "kebab-case")]
+pub enum InputEvent {
+	KeyDown { key: VirtualKeyCode, mods: u8 },
+	KeyUp { key: VirtualKeyCode },
+	MouseDown { button: MouseButton, x: f32, y: f32, mods: u8 },
+	MouseUp { button: MouseButton, x: f32, y: f32 },
+	Text { character: char },
+}
+
+// Stamped with the number of fixed rate ticks run before it arrived rather than the time,
+// and with where the cursor was, so a replay acts on it in exactly the same state
+#[derive(Copy, Clone, PartialEq, Debug, Serialize, Deserialize)]
+pub struct TimedEvent {
+	pub tick: u64,
+	pub cursor: [f32; 2],
+	pub event: InputEvent,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Recording {
+	// The window size and tick rate the events were recorded at, replays need the same
+	pub size: [usize; 2],
+	pub tick_rate: u32,
+	pub start: Session,
+	pub events: Vec<TimedEvent>,
+}
+
+impl Recording {
+	pub fn load(path: &Path) -> io::Result<Recording> {
+		Recording::parse(&fs::read_to_string(path)?)
+	}
+
+	pub fn parse(contents: &str) -> io::Result<Recording> {
+		toml::from_str(contents).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+	}
+
+	pub fn save(&self, path: &Path) -> io::Result<()> {
+		let contents = toml::to_string(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+		fs::write(path, contents)
+	}
+}
+
+// Hands the recorded events back in order as the ticks they arrived at come round again
+pub struct Replay {
+	events: VecDeque<TimedEvent>,
+}
+
+impl Replay {
+	pub fn new(events: Vec<TimedEvent>) -> Replay {
+		Replay {
+			events: events.into(),
+		}
+	}
+
+	pub fn is_finished(&self) -> bool {
+		self.events.is_empty()
+	}
+
+	pub fn next_due(&mut self, tick: u64) -> Option<TimedEvent> {
+		self.events.front().is_some_and(|event| event.tick <= tick).then(|| self.events.pop_front()).flatten()
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use crate::renderer::IterationSettings;
+	use crate::viewport::Viewport;
+
+	fn events() -> Vec<TimedEvent> {
+		vec![
+			TimedEvent { tick: 3, cursor: [10.0, 20.0], event: InputEvent::KeyDown { key: VirtualKeyCode::E, mods: 1 } },
+			TimedEvent { tick: 3, cursor: [10.0, 20.0], event: InputEvent::Text { character: '/' } },
+			TimedEvent { tick: 9, cursor: [5.5, 6.0], event: InputEvent::MouseDown { button: MouseButton::Middle, x: 5.5, y: 6.0, mods: 0 } },
+			TimedEvent { tick: 12, cursor: [5.5, 6.0], event: InputEvent::KeyUp { key: VirtualKeyCode::W } },
+		]
+	}
+
+	#[test]
+	fn survives_a_toml_round_trip() {
+		let settings = IterationSettings::mandelbrot(100.0);
+
+		let recording = Recording {
+			size: [500, 500],
+			tick_rate: 60,
+			start: Session::capture(&Viewport::new(500.0, 500.0), &settings, "rainbow"),
+			events: events(),
+		};
+
+		let parsed: Recording = toml::from_str(&toml::to_string(&recording).unwrap()).unwrap();
+
+		assert_eq!(parsed.events, recording.events);
+		assert_eq!((parsed.size, parsed.tick_rate), ([500, 500], 60));
+	}
+
+	#[test]
+	fn replays_events_when_their_tick_comes() {
+		let mut replay = Replay::new(events());
+
+		assert!(replay.next_due(2).is_none());
+		assert_eq!(replay.next_due(3).unwrap().event, InputEvent::KeyDown { key: VirtualKeyCode::E, mods: 1 });
+		assert_eq!(replay.next_due(3).unwrap().event, InputEvent::Text { character: '/' });
+		assert!(replay.next_due(3).is_none());
+
+		// Ticks that were skipped over still deliver everything before them in order
+		assert_eq!(replay.next_due(20).unwrap().tick, 9);
+		assert_eq!(replay.next_due(20).unwrap().tick, 12);
+		assert!(replay.is_finished());
+	}
+}
@@ -0,0 +1,1118 @@
+use std::any::Any;
+use std::borrow::Cow;
+use std::ops::Range;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender, TryRecvError};
+use std::thread;
+
+use ggez::graphics::Color;
+use ggez::mint::Point2;
+
+use crate::backend::{Cpu, RenderBackend};
+use crate::complex::Complex;
+use crate::config::Config;
+use crate::distance;
+use crate::formula::{Formula, Symmetry};
+use crate::gradient::Gradient;
+use crate::queue::{Priority, WorkQueue};
+use crate::viewport::Viewport;
+
+pub const DEFAULT_TILE_SIZE: usize = 32;
+
+// Pixels from the boundary, as a power of e, at which shaded interiors reach the interior colour
+const INTERIOR_FALLOFF: f64 = 4.0;
+
+// Pixels from the boundary at which boundary renders fade from the line into the background, which
+// is plain white to leave the line, in the interior colour, as the only thing drawn
+const BOUNDARY_WIDTH: f64 = 1.0;
+const BOUNDARY_BACKGROUND: Color = Color::WHITE;
+
+#[derive(Copy, Clone)]
+pub struct IterationSettings {
+	pub formula: Formula,
+	pub bailout: f64,
+	pub initial_z: Complex,
+	// Iterate the Julia set of this constant instead, each point being the starting z
+	pub julia: Option<Complex>,
+	pub max_iterations: f64,
+}
+
+impl IterationSettings {
+	// z² from z = 0 with the usual bailout, which most views outside the config start from
+	pub fn mandelbrot(max_iterations: f64) -> IterationSettings {
+		IterationSettings { formula: Formula::Mandelbrot, bailout: 2.0, initial_z: Complex::new(0.0, 0.0), julia: None, max_iterations }
+	}
+
+	pub fn from_config(config: &Config) -> IterationSettings {
+		IterationSettings::for_formula(config, config.formula)
+	}
+
+	// The configured settings, raised to the formula's preset where it needs more
+	pub fn for_formula(config: &Config, formula: Formula) -> IterationSettings {
+		let preset = formula.preset();
+
+		IterationSettings {
+			formula,
+			bailout: config.bailout.max(preset.bailout),
+			initial_z: Complex::new(config.initial_z[0], config.initial_z[1]),
+			julia: None,
+			max_iterations: config.max_iterations.max(preset.max_iterations),
+		}
+	}
+
+	// Settings from a config, a saved view or another program can be ones no render could finish
+	pub fn validate(&self) -> Result<(), String> {
+		if !self.bailout.is_finite() || self.bailout <= 0.0 {
+			return Err(String::from("bailout must be a finite number greater than 0"));
+		}
+
+		if !self.max_iterations.is_finite() || self.max_iterations < 1.0 {
+			return Err(String::from("max_iterations must be a finite number of at least 1"));
+		}
+
+		if !self.initial_z.is_finite() || !self.julia.is_none_or(|c| c.is_finite()) {
+			return Err(String::from("the starting z and Julia constant must be finite"));
+		}
+
+		Ok(())
+	}
+}
+
+// The bailout then the real and imaginary part of the starting z, separated by commas
+pub fn parse_escape(text: &str) -> Result<(f64, Complex), String> {
+	let values = text.split(',')
+		.map(|value| match value.trim().parse::<f64>() {
+			Ok(value) if value.is_finite() => Ok(value),
+			_ => Err(format!("invalid number {}", value.trim())),
+		})
+		.collect::<Result<Vec<f64>, String>>()?;
+
+	let [bailout, real, imaginary] = values[..] else {
+		return Err(format!("expected 3 numbers, got {}", values.len()));
+	};
+
+	if bailout <= 0.0 {
+		return Err(String::from("bailout must be greater than 0"));
+	}
+
+	Ok((bailout, Complex::new(real, imaginary)))
+}
+
+// Iterations taken to escape, None for points which stay bounded, and whether the orbit converged
+// rather than escaping
+fn calculate_for_point(point: Complex, settings: IterationSettings) -> (Option<f64>, bool) {
+	let (z, c) = match settings.julia {
+		Some(c) => (point, c),
+		None => (settings.formula.critical_point() + settings.initial_z, point),
+	};
+
+	// Matched once per point, so each formula gets a loop of its own
+	match settings.formula {
+		Formula::Mandelbrot => escape_time(Formula::Mandelbrot, z, c, settings),
+		Formula::BurningShip => escape_time(Formula::BurningShip, z, c, settings),
+		Formula::Celtic => escape_time(Formula::Celtic, z, c, settings),
+		Formula::PerpendicularBurningShip => escape_time(Formula::PerpendicularBurningShip, z, c, settings),
+		Formula::Buffalo => escape_time(Formula::Buffalo, z, c, settings),
+		Formula::MagnetOne => escape_time(Formula::MagnetOne, z, c, settings),
+		Formula::MagnetTwo => escape_time(Formula::MagnetTwo, z, c, settings),
+		Formula::Lambda => escape_time(Formula::Lambda, z, c, settings),
+		Formula::Nova => escape_time(Formula::Nova, z, c, settings),
+	}
+}
+
+// Orbits of converging formulas also stop once a step barely moves them, counting as escaped on
+// that iteration
+#[inline(always)]
+fn escape_time(formula: Formula, mut z: Complex, c: Complex, settings: IterationSettings) -> (Option<f64>, bool) {
+	let mut iterations = 0.0;
+
+	let bailout_sqr = settings.bailout * settings.bailout;
+
+	while z.norm_sqr() < bailout_sqr {
+		if iterations > settings.max_iterations {
+			return (None, false);
+		}
+
+		iterations += 1.0;
+
+		let previous = z;
+		z = formula.iterate(z, c);
+
+		if formula.has_converged(previous, z) {
+			return (Some(iterations), true);
+		}
+	}
+
+	(Some(iterations), false)
+}
+
+// Pixels between a sample and the boundary, when the job shades interiors or draws the boundary.
+// Only the Mandelbrot set has estimates, and its Julia sets only outside
+fn boundary_distance(job: &RenderJob, point: Complex, escaped: bool) -> Option<f64> {
+	let settings = job.settings;
+
+	if settings.formula != Formula::Mandelbrot {
+		return None;
+	}
+
+	let distance = match (escaped, settings.julia) {
+		(true, Some(c)) if job.boundary => distance::exterior(point, c, true, settings.max_iterations as usize)?,
+		(true, None) if job.boundary => distance::exterior(settings.initial_z, point, false, settings.max_iterations as usize)?,
+		(false, None) if job.shade_interior || job.boundary => distance::interior(point, settings.initial_z, settings.max_iterations as usize)?,
+		_ => return None,
+	};
+
+	// The inverted plane is scaled by |dw/dc| = 1 / |c|² about each point
+	let distance = if job.viewport.inverted() { distance / point.norm_sqr() } else { distance };
+
+	Some(distance / job.viewport.scale())
+}
+
+fn mix(from: Color, to: Color, t: f32) -> Color {
+	Color::new(
+		from.r + (to.r - from.r) * t,
+		from.g + (to.g - from.g) * t,
+		from.b + (to.b - from.b) * t,
+		from.a + (to.a - from.a) * t,
+	)
+}
+
+// Converged orbits run the gradient backwards, so basins stand apart from escaping regions. Shaded
+// interiors carry on from the top of the gradient at the boundary into the interior colour, and
+// boundary renders draw samples near the boundary in the interior colour and the rest as background.
+// The gradient runs across the span of escape counts
+pub fn color_for(iterations: Option<f64>, converged: bool, distance: Option<f64>, boundary: bool, [low, high]: [f64; 2], gradient: &Gradient, stripes: Option<&Gradient>) -> Color {
+	if boundary {
+		let coverage = distance.map_or(0.0, |distance| (1.0 - distance / BOUNDARY_WIDTH).clamp(0.0, 1.0));
+		return mix(BOUNDARY_BACKGROUND, gradient.interior(), coverage as f32);
+	}
+
+	// Odd iteration bands take their colour from the stripe gradient, at the same position
+	let banded = |iterations: f64| match stripes {
+		Some(stripes) if iterations.floor().rem_euclid(2.0) == 1.0 => stripes,
+		_ => gradient,
+	};
+
+	match (iterations, distance) {
+		(Some(iterations), _) if converged => banded(iterations).sample(1.0 - ((iterations - low) / (high - low)) as f32),
+		(Some(iterations), _) => banded(iterations).sample(((iterations - low) / (high - low)) as f32),
+		(None, Some(depth)) => {
+			let t = ((1.0 + depth).ln() / INTERIOR_FALLOFF).clamp(0.0, 1.0) as f32;
+			mix(gradient.sample(1.0), gradient.interior(), t)
+		},
+		(None, None) => gradient.interior(),
+	}
+}
+
+// Antialiased pixels average the colour of every sample rather than the iteration counts
+fn shade(samples: &[Option<f64>], converged: &[bool], distance: &[Option<f64>], boundary: bool, span: [f64; 2], gradient: &Gradient, stripes: Option<&Gradient>) -> Color {
+	let mut total = Color::new(0.0, 0.0, 0.0, 0.0);
+
+	for ((&sample, &converged), &distance) in samples.iter().zip(converged).zip(distance) {
+		let color = color_for(sample, converged, distance, boundary, span, gradient, stripes);
+
+		total.r += color.r;
+		total.g += color.g;
+		total.b += color.b;
+		total.a += color.a;
+	}
+
+	let count = samples.len() as f32;
+	Color::new(total.r / count, total.g / count, total.b / count, total.a / count)
+}
+
+// A rectangle of the frame, rendered as one unit of work
+#[derive(Copy, Clone)]
+pub struct Tile {
+	pub x: usize,
+	pub y: usize,
+	pub width: usize,
+	pub height: usize,
+}
+
+// Tiles covering the frame, nearest to the focus point first so that area refines first
+pub fn tile_order(width: usize, height: usize, tile_size: usize, focus: Point2<f64>) -> Vec<Tile> {
+	let mut tiles = Vec::new();
+
+	for y in (0..height).step_by(tile_size) {
+		for x in (0..width).step_by(tile_size) {
+			tiles.push(Tile {
+				x,
+				y,
+				width: tile_size.min(width - x),
+				height: tile_size.min(height - y),
+			});
+		}
+	}
+
+	let distance = |tile: &Tile| {
+		let dx = (tile.x as f64 + tile.width as f64 / 2.0) - focus.x;
+		let dy = (tile.y as f64 + tile.height as f64 / 2.0) - focus.y;
+
+		(dx * dx) + (dy * dy)
+	};
+
+	tiles.sort_by(|a, b| distance(a).total_cmp(&distance(b)));
+	tiles
+}
+
+// An evenly spaced grid within the pixel, a single sample sits on the pixel itself
+fn sample_point(job: &RenderJob, x: usize, y: usize, sample: usize) -> Complex {
+	let samples = job.samples;
+
+	let offset_x = ((sample % samples) as f64 + 0.5) / samples as f64 - 0.5 + job.jitter.0 / samples as f64;
+	let offset_y = ((sample / samples) as f64 + 0.5) / samples as f64 - 0.5 + job.jitter.1 / samples as f64;
+
+	job.viewport.pixel_to_complex(x as f64 + offset_x, y as f64 + offset_y)
+}
+
+// Samples lying this close to the image of another, in sample spacings, share its escape count
+const SYMMETRY_TOLERANCE: f64 = 1e-6;
+
+// A symmetry's action on the sample grid, counted in whole samples from the frame's top left.
+// Both symmetries are affine in pixels, even in the inverted plane, so this covers every sample
+#[derive(Copy, Clone)]
+struct GridMap {
+	origin: (i64, i64),
+	column_step: (i64, i64),
+	row_step: (i64, i64),
+}
+
+impl GridMap {
+	// None unless the images of the grid's first samples land on samples too, which rotated or
+	// panned views rarely manage
+	fn new(job: &RenderJob, symmetry: Symmetry) -> Option<GridMap> {
+		let n = job.samples;
+
+		let image = |column: usize, row: usize| {
+			let point = sample_point(job, column / n, row / n, (row % n) * n + column % n);
+			let image = job.viewport.complex_to_pixel(symmetry.apply(point));
+
+			// Undoes sample_point's offsets
+			let grid = |position: f64, jitter: f64| {
+				let spacings = (position - jitter / n as f64 + 0.5) * n as f64 - 0.5;
+				let nearest = spacings.round();
+
+				((spacings - nearest).abs() < SYMMETRY_TOLERANCE).then_some(nearest as i64)
+			};
+
+			Some((grid(image.x, job.jitter.0)?, grid(image.y, job.jitter.1)?))
+		};
+
+		let origin = image(0, 0)?;
+		let (column, row) = (image(1, 0)?, image(0, 1)?);
+
+		Some(GridMap {
+			origin,
+			column_step: (column.0 - origin.0, column.1 - origin.1),
+			row_step: (row.0 - origin.0, row.1 - origin.1),
+		})
+	}
+
+	fn apply(&self, (column, row): (i64, i64)) -> (i64, i64) {
+		(
+			self.origin.0 + column * self.column_step.0 + row * self.row_step.0,
+			self.origin.1 + column * self.column_step.1 + row * self.row_step.1,
+		)
+	}
+}
+
+// The job formula's symmetries which take the sample grid onto itself
+fn grid_symmetries(job: &RenderJob) -> Vec<GridMap> {
+	let settings = job.settings;
+
+	settings.formula.symmetries(settings.initial_z, settings.julia).into_iter()
+		.filter_map(|symmetry| GridMap::new(job, symmetry))
+		.collect()
+}
+
+// The lowest indexed of the sample's images within the frame, when that comes before the sample
+// itself. Its escape count is then copied rather than iterated again
+fn mirror_of(job: &RenderJob, symmetries: &[GridMap], x: usize, y: usize, sample: usize) -> Option<usize> {
+	let n = job.samples as i64;
+	let (columns, rows) = (job.width as i64 * n, job.height as i64 * n);
+
+	let index = |(column, row): (i64, i64)| {
+		let pixel = (row / n) * job.width as i64 + column / n;
+		(pixel * n * n + (row % n) * n + column % n) as usize
+	};
+
+	let position = (x as i64 * n + sample as i64 % n, y as i64 * n + sample as i64 / n);
+	let own = index(position);
+
+	symmetries.iter()
+		.map(|map| map.apply(position))
+		.filter(|&(column, row)| (0..columns).contains(&column) && (0..rows).contains(&row))
+		.map(index)
+		.filter(|&image| image < own)
+		.min()
+}
+
+// Every sample of the frame in the order Frame stores them, for backends iterating elsewhere
+pub fn sample_points(job: &RenderJob) -> Vec<Complex> {
+	let samples_per_pixel = job.samples * job.samples;
+
+	(0..job.width * job.height * samples_per_pixel)
+		.map(|i| {
+			let pixel = i / samples_per_pixel;
+			sample_point(job, pixel % job.width, pixel / job.width, i % samples_per_pixel)
+		})
+		.collect()
+}
+
+// Colours escape counts computed by another backend into a frame of the job, estimating distances
+// from the boundary here where it wants them
+pub fn frame_from_iterations(job: RenderJob, iterations: Vec<Option<f64>>, converged: Vec<bool>) -> Frame {
+	let samples_per_pixel = job.samples * job.samples;
+	let boundary = job.draws_boundary();
+
+	let distance: Vec<Option<f64>> = match job.shade_interior || job.boundary {
+		true => sample_points(&job).into_iter().zip(&iterations)
+			.map(|(point, sample)| boundary_distance(&job, point, sample.is_some()))
+			.collect(),
+		false => vec![None; iterations.len()],
+	};
+
+	let pixels = (0..job.width * job.height).map(|pixel| {
+		let samples = pixel * samples_per_pixel..(pixel + 1) * samples_per_pixel;
+		shade(&iterations[samples.clone()], &converged[samples.clone()], &distance[samples], boundary, job.palette_span(), &job.gradient, job.stripes.as_deref())
+	}).collect();
+
+	Frame {
+		width: job.width,
+		height: job.height,
+		samples: job.samples,
+		margin: job.margin,
+
+		generation: job.generation,
+		pass: job.pass,
+
+		iterations,
+		converged,
+		distance,
+		boundary,
+		palette_range: job.palette_range,
+		stripes: job.stripes.clone(),
+		pixels,
+
+		viewport: job.viewport,
+		settings: job.settings,
+		gradient: job.gradient,
+	}
+}
+
+// Tile pixels are row-major with each pixel's samples adjacent. Samples mirroring earlier ones are
+// left out, and returned as their index in the frame paired with the one to copy
+struct CalculatedTile {
+	iterations: Vec<Option<f64>>,
+	converged: Vec<bool>,
+	distance: Vec<Option<f64>>,
+	pixels: Vec<Color>,
+	mirrored: Vec<(usize, usize)>,
+}
+
+fn calculate_tile(tile: Tile, job: &RenderJob) -> CalculatedTile {
+	let samples = job.samples;
+	let samples_per_pixel = samples * samples;
+
+	let mut iterations = vec![None; tile.width * tile.height * samples_per_pixel];
+	let mut converged = vec![false; tile.width * tile.height * samples_per_pixel];
+	let mut distance = vec![None; tile.width * tile.height * samples_per_pixel];
+	let mut pixels = vec![Color::BLACK; tile.width * tile.height];
+	let mut mirrored = Vec::new();
+
+	let pixel_samples = iterations.chunks_mut(samples_per_pixel)
+		.zip(converged.chunks_mut(samples_per_pixel))
+		.zip(distance.chunks_mut(samples_per_pixel));
+
+	// Cheap enough to work out again for every tile
+	let symmetries = grid_symmetries(job);
+	let boundary = job.draws_boundary();
+
+	for (i, (((pixel_iterations, pixel_converged), pixel_distance), pixel)) in pixel_samples.zip(pixels.iter_mut()).enumerate() {
+		let x = tile.x + (i % tile.width);
+		let y = tile.y + (i / tile.width);
+
+		for j in 0..samples_per_pixel {
+			if let Some(image) = mirror_of(job, &symmetries, x, y, j) {
+				mirrored.push(((y * job.width + x) * samples_per_pixel + j, image));
+				continue;
+			}
+
+			let point = sample_point(job, x, y, j);
+			(pixel_iterations[j], pixel_converged[j]) = calculate_for_point(point, job.settings);
+
+			if job.shade_interior || job.boundary {
+				pixel_distance[j] = boundary_distance(job, point, pixel_iterations[j].is_some());
+			}
+		}
+
+		*pixel = shade(pixel_iterations, pixel_converged, pixel_distance, boundary, job.palette_span(), &job.gradient, job.stripes.as_deref());
+	}
+
+	CalculatedTile { iterations, converged, distance, pixels, mirrored }
+}
+
+// A finished tile of a fresh render, sent ahead of the whole frame so the display can fill in
+pub struct RenderedTile {
+	pub generation: u64,
+	pub frame_width: usize,
+	pub frame_height: usize,
+
+	pub tile: Tile,
+	pub pixels: Vec<Color>,
+}
+
+pub struct RenderJob {
+	pub width: usize,
+	pub height: usize,
+	// Per axis, so each pixel takes samples * samples
+	pub samples: usize,
+	// Pixels along each edge rendered past what's shown, for the view to pan into while the next
+	// frame renders. Included in the width and height
+	pub margin: usize,
+	// Sub-pixel offset of the sample grid, in units of a single sample's spacing
+	pub jitter: (f64, f64),
+
+	// Pixel tiles are rendered outwards from, in render resolution pixels
+	pub focus: Point2<f64>,
+	// Width and height of each tile, larger tiles mean less queueing for huge renders
+	pub tile_size: usize,
+	// Shade points inside the set by their distance from the boundary, rather than one colour
+	pub shade_interior: bool,
+	// Draw only the boundary, as a line on a plain background, where the formula has estimates
+	pub boundary: bool,
+	// Escape counts the gradient is stretched across, from 0 to the iteration cap when None
+	pub palette_range: Option<[f64; 2]>,
+	// Colours odd iteration bands in place of the gradient, striping the bands in two palettes
+	pub stripes: Option<Arc<Gradient>>,
+
+	// Which view this is a render of, and which accumulation pass of it
+	pub generation: u64,
+	pub pass: u32,
+
+	pub viewport: Viewport,
+	pub settings: IterationSettings,
+	pub gradient: Arc<Gradient>,
+
+	// Whether the view waits on this render, which puts its tiles ahead of background work
+	pub priority: Priority,
+
+	// A previously displayed frame, its storage is reused to avoid reallocating
+	pub recycled: Option<Frame>,
+}
+
+impl RenderJob {
+	// A single standalone render, as used for exports and embedding, which gives way to the view
+	pub fn new(width: usize, height: usize, samples: usize, viewport: Viewport, settings: IterationSettings, gradient: Arc<Gradient>) -> RenderJob {
+		RenderJob {
+			width,
+			height,
+			samples,
+			margin: 0,
+			jitter: (0.0, 0.0),
+			focus: Point2 { x: width as f64 / 2.0, y: height as f64 / 2.0 },
+			tile_size: DEFAULT_TILE_SIZE,
+			shade_interior: false,
+			boundary: false,
+			palette_range: None,
+			stripes: None,
+
+			generation: 0,
+			pass: 0,
+
+			viewport,
+			settings,
+			gradient,
+
+			priority: Priority::Background,
+
+			recycled: None,
+		}
+	}
+
+	// Whether pixels are coloured as a boundary line, which formulas without distance estimates
+	// leave to the palette
+	pub fn draws_boundary(&self) -> bool {
+		self.boundary && self.settings.formula == Formula::Mandelbrot
+	}
+
+	fn palette_span(&self) -> [f64; 2] {
+		self.palette_range.unwrap_or([0.0, self.settings.max_iterations])
+	}
+}
+
+#[derive(Clone)]
+pub struct Frame {
+	pub width: usize,
+	pub height: usize,
+	pub samples: usize,
+	// Pixels along each edge past what the view shows, which screenshots and exports leave out
+	pub margin: usize,
+
+	pub generation: u64,
+	pub pass: u32,
+
+	// Every sample's escape count, samples * samples per pixel, and whether it converged instead,
+	// which only formulas with Formula::converges do. Shaded interiors and boundary renders keep each
+	// sample's distance from the boundary in pixels, where there's an estimate
+	pub iterations: Vec<Option<f64>>,
+	pub converged: Vec<bool>,
+	pub distance: Vec<Option<f64>>,
+	// Whether the pixels are a boundary line rather than coloured by the gradient
+	pub boundary: bool,
+	pub palette_range: Option<[f64; 2]>,
+	pub stripes: Option<Arc<Gradient>>,
+	pub pixels: Vec<Color>,
+
+	// What the frame was rendered with, which can lag behind the live view
+	pub viewport: Viewport,
+	pub settings: IterationSettings,
+	pub gradient: Arc<Gradient>,
+}
+
+impl Frame {
+	// Mean escape count of each pixel's samples, None when every sample stayed bounded
+	pub fn mean_iterations(&self) -> impl Iterator<Item = Option<f64>> + '_ {
+		self.mean_iterations_in(0..self.width * self.height)
+	}
+
+	// The same for a range of pixel indices, such as a band of rows
+	pub fn mean_iterations_in(&self, pixels: Range<usize>) -> impl Iterator<Item = Option<f64>> + '_ {
+		let samples_per_pixel = self.samples * self.samples;
+
+		self.iterations[pixels.start * samples_per_pixel..pixels.end * samples_per_pixel].chunks(samples_per_pixel).map(|samples| {
+			let escaped: Vec<f64> = samples.iter().flatten().copied().collect();
+
+			(!escaped.is_empty()).then(|| escaped.iter().sum::<f64>() / escaped.len() as f64)
+		})
+	}
+
+	// A rectangle of the frame's pixels as a frame of its own, whose top left becomes (0, 0)
+	pub fn crop(&self, x: usize, y: usize, width: usize, height: usize) -> Frame {
+		let samples_per_pixel = self.samples * self.samples;
+		let rows = y..y + height;
+
+		let pixels = |row: usize| row * self.width + x..row * self.width + x + width;
+		let samples = |row: usize| pixels(row).start * samples_per_pixel..pixels(row).end * samples_per_pixel;
+
+		Frame {
+			width,
+			height,
+			margin: 0,
+
+			iterations: rows.clone().flat_map(|row| self.iterations[samples(row)].iter().copied()).collect(),
+			converged: rows.clone().flat_map(|row| self.converged[samples(row)].iter().copied()).collect(),
+			distance: rows.clone().flat_map(|row| self.distance[samples(row)].iter().copied()).collect(),
+			pixels: rows.flat_map(|row| self.pixels[pixels(row)].iter().copied()).collect(),
+
+			viewport: self.viewport.crop(x as f64, y as f64, width as f64, height as f64),
+			..self.clone()
+		}
+	}
+
+	// What the view shows of the frame, without its margin
+	pub fn shown(&self) -> Cow<'_, Frame> {
+		match self.margin {
+			0 => Cow::Borrowed(self),
+			margin => Cow::Owned(self.crop(margin, margin, self.width - 2 * margin, self.height - 2 * margin)),
+		}
+	}
+
+	// Coloring only needs the iteration counts, so there's no need to render again
+	pub fn recolor(&mut self, gradient: Arc<Gradient>) {
+		let samples_per_pixel = self.samples * self.samples;
+		let span = self.palette_range.unwrap_or([0.0, self.settings.max_iterations]);
+
+		for (i, pixel) in self.pixels.iter_mut().enumerate() {
+			let samples = i * samples_per_pixel..(i + 1) * samples_per_pixel;
+			let converged = &self.converged[samples.clone()];
+
+			*pixel = shade(&self.iterations[samples.clone()], converged, &self.distance[samples], self.boundary, span, &gradient, self.stripes.as_deref());
+		}
+
+		self.gradient = gradient;
+	}
+}
+
+pub fn render(mut job: RenderJob, progress: Option<&Sender<RenderedTile>>) -> Frame {
+	let (mut iterations, mut converged, mut distance, mut pixels) = match job.recycled.take() {
+		Some(frame) => (frame.iterations, frame.converged, frame.distance, frame.pixels),
+		None => (Vec::new(), Vec::new(), Vec::new(), Vec::new()),
+	};
+
+	let (width, height) = (job.width, job.height);
+	let samples_per_pixel = job.samples * job.samples;
+
+	iterations.clear();
+	iterations.resize(width * height * samples_per_pixel, None);
+	converged.clear();
+	converged.resize(width * height * samples_per_pixel, false);
+	distance.clear();
+	distance.resize(width * height * samples_per_pixel, None);
+	pixels.clear();
+	pixels.resize(width * height, Color::BLACK);
+
+	let tiles = tile_order(width, height, job.tile_size.max(1), job.focus);
+	let boundary = job.draws_boundary();
+	let job = Arc::new(job);
+
+	// Tiles still queued when the render is abandoned, as when one of them panics, are skipped
+	let cancelled = Cancelled(Arc::new(AtomicBool::new(false)));
+	let (sender, results) = mpsc::channel();
+
+	for &tile in &tiles {
+		let (job, cancelled, sender) = (Arc::clone(&job), Arc::clone(&cancelled.0), sender.clone());
+
+		WorkQueue::shared().push(job.priority, move || {
+			if !cancelled.load(Ordering::Relaxed) {
+				let _ = sender.send((tile, panic::catch_unwind(AssertUnwindSafe(|| calculate_tile(tile, &job)))));
+			}
+		});
+	}
+
+	drop(sender);
+
+	// Tiles with mirrored samples are only complete once every tile is in
+	let mut mirrored = Vec::new();
+	let mut unfinished = Vec::new();
+
+	let send = |tile: Tile, pixels: Vec<Color>| {
+		// Nobody listening just means the tile isn't shown early
+		if let Some(progress) = progress {
+			let _ = progress.send(RenderedTile {
+				generation: job.generation,
+				frame_width: width,
+				frame_height: height,
+
+				tile,
+				pixels,
+			});
+		}
+	};
+
+	for (tile, result) in results {
+		// Carries on with the tile's own message
+		let calculated = result.unwrap_or_else(|payload| panic::resume_unwind(payload));
+		let (tile_iterations, tile_converged, tile_pixels) = (calculated.iterations, calculated.converged, calculated.pixels);
+		let tile_distance = calculated.distance;
+
+		for row in 0..tile.height {
+			let start = (tile.y + row) * width + tile.x;
+			let tile_start = row * tile.width;
+
+			pixels[start..start + tile.width].copy_from_slice(&tile_pixels[tile_start..tile_start + tile.width]);
+
+			let (samples, tile_samples) = (start * samples_per_pixel, tile_start * samples_per_pixel);
+			let length = tile.width * samples_per_pixel;
+
+			iterations[samples..samples + length].copy_from_slice(&tile_iterations[tile_samples..tile_samples + length]);
+			converged[samples..samples + length].copy_from_slice(&tile_converged[tile_samples..tile_samples + length]);
+			distance[samples..samples + length].copy_from_slice(&tile_distance[tile_samples..tile_samples + length]);
+		}
+
+		if calculated.mirrored.is_empty() {
+			send(tile, tile_pixels);
+		} else {
+			mirrored.extend(calculated.mirrored);
+			unfinished.push(tile);
+		}
+	}
+
+	// Ascending, so an image which was itself mirrored has been filled in before it's copied
+	mirrored.sort_unstable();
+
+	for &(sample, image) in &mirrored {
+		iterations[sample] = iterations[image];
+		converged[sample] = converged[image];
+		distance[sample] = distance[image];
+	}
+
+	let mut reshaded = mirrored.iter().map(|&(sample, _)| sample / samples_per_pixel).collect::<Vec<_>>();
+	reshaded.dedup();
+
+	for pixel in reshaded {
+		let samples = pixel * samples_per_pixel..(pixel + 1) * samples_per_pixel;
+		pixels[pixel] = shade(&iterations[samples.clone()], &converged[samples.clone()], &distance[samples], boundary, job.palette_span(), &job.gradient, job.stripes.as_deref());
+	}
+
+	for tile in unfinished {
+		let rows = (tile.y..tile.y + tile.height).flat_map(|y| &pixels[y * width + tile.x..y * width + tile.x + tile.width]);
+		send(tile, rows.copied().collect());
+	}
+
+	Frame {
+		width,
+		height,
+		samples: job.samples,
+		margin: job.margin,
+
+		generation: job.generation,
+		pass: job.pass,
+
+		iterations,
+		converged,
+		distance,
+		boundary,
+		palette_range: job.palette_range,
+		stripes: job.stripes.clone(),
+		pixels,
+
+		viewport: job.viewport,
+		settings: job.settings,
+		gradient: Arc::clone(&job.gradient),
+	}
+}
+
+struct Cancelled(Arc<AtomicBool>);
+
+impl Drop for Cancelled {
+	fn drop(&mut self) {
+		self.0.store(true, Ordering::Relaxed);
+	}
+}
+
+fn panic_message(payload: Box<dyn Any + Send>) -> String {
+	payload.downcast_ref::<&str>().map(|message| message.to_string())
+		.or_else(|| payload.downcast_ref::<String>().cloned())
+		.unwrap_or_else(|| String::from("unknown error"))
+}
+
+// Renders on a background thread so the displayed frame stays up until the next one is complete,
+// only the newest submitted job is rendered when several queue up. Tiles of a fresh view are
+// streamed as they finish, refinement passes only make sense once averaged so they aren't.
+// A failed or panicking render is reported in place of its frame, and the thread carries on with
+// the next job
+
+pub struct Renderer {
+	jobs: Sender<(u64, RenderJob)>,
+	frames: Receiver<(u64, Result<Frame, String>)>,
+	tiles: Receiver<RenderedTile>,
+
+	latest_submitted: u64,
+	latest_received: u64,
+}
+
+impl Renderer {
+	pub fn new() -> Renderer {
+		Renderer::with_backend(Arc::new(Cpu))
+	}
+
+	pub fn with_backend(backend: Arc<dyn RenderBackend>) -> Renderer {
+		let (jobs, job_receiver) = mpsc::channel::<(u64, RenderJob)>();
+		let (frame_sender, frames) = mpsc::channel();
+		let (tile_sender, tiles) = mpsc::channel();
+
+		thread::spawn(move || {
+			while let Ok(mut job) = job_receiver.recv() {
+				while let Ok(newer) = job_receiver.try_recv() {
+					job = newer;
+				}
+
+				let (id, job) = job;
+				let progress = (job.pass == 0).then_some(&tile_sender);
+
+				let frame = panic::catch_unwind(AssertUnwindSafe(|| backend.render(job, progress))).unwrap_or_else(|payload| Err(panic_message(payload)));
+
+				if frame_sender.send((id, frame)).is_err() {
+					break;
+				}
+			}
+		});
+
+		Renderer {
+			jobs,
+			frames,
+			tiles,
+
+			latest_submitted: 0,
+			latest_received: 0,
+		}
+	}
+
+	pub fn submit(&mut self, job: RenderJob) {
+		self.latest_submitted += 1;
+		self.jobs.send((self.latest_submitted, job)).expect("render thread stopped");
+	}
+
+	// Skipped jobs never produce a frame, but the newest one always does
+	pub fn is_idle(&self) -> bool {
+		self.latest_received == self.latest_submitted
+	}
+
+	// Every tile completed since the last call, these always arrive before their frame
+	pub fn poll_tiles(&self) -> Vec<RenderedTile> {
+		self.tiles.try_iter().collect()
+	}
+
+	// Every frame completed since the last call, oldest first, or why its render failed
+	pub fn poll(&mut self) -> Vec<Result<Frame, String>> {
+		let mut frames = Vec::new();
+
+		loop {
+			match self.frames.try_recv() {
+				Ok((id, frame)) => {
+					self.latest_received = id;
+					frames.push(frame);
+				},
+				Err(TryRecvError::Empty) => return frames,
+				Err(TryRecvError::Disconnected) => panic!("render thread stopped"),
+			}
+		}
+	}
+}
+
+impl Default for Renderer {
+	fn default() -> Renderer {
+		Renderer::new()
+	}
+}
+
+// A small Mandelbrot render, for tests of what's worked out from frames
+#[cfg(test)]
+pub(crate) fn test_frame(size: (usize, usize), center: Complex, magnification: f64, max_iterations: f64) -> Frame {
+	let mut viewport = Viewport::new(size.0 as f64, size.1 as f64);
+	viewport.set_center(center);
+	viewport.set_magnification(magnification);
+
+	render(RenderJob::new(size.0, size.1, 1, viewport, IterationSettings::mandelbrot(max_iterations), Arc::new(Gradient::rainbow())), None)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn job(samples: usize) -> RenderJob {
+		let settings = IterationSettings::mandelbrot(10.0);
+
+		RenderJob::new(8, 8, samples, Viewport::new(8.0, 8.0), settings, Arc::new(Gradient::rainbow()))
+	}
+
+	fn wait_for_frame(renderer: &mut Renderer) -> Result<Frame, String> {
+		loop {
+			if let Some(frame) = renderer.poll().pop() {
+				return frame;
+			}
+
+			thread::sleep(std::time::Duration::from_millis(1));
+		}
+	}
+
+	#[test]
+	fn renderer_survives_a_panicking_render() {
+		let mut renderer = Renderer::new();
+
+		// No samples per pixel makes splitting the iteration buffer panic
+		renderer.submit(job(0));
+		let error = wait_for_frame(&mut renderer).err().unwrap();
+		assert!(error.contains("chunk size"), "{}", error);
+
+		renderer.submit(job(1));
+		assert!(wait_for_frame(&mut renderer).is_ok());
+	}
+
+	#[test]
+	fn tiles_cover_every_pixel_once() {
+		let (width, height) = (100, 70);
+		let mut covered = vec![0; width * height];
+
+		for tile in tile_order(width, height, 32, Point2 { x: 0.0, y: 0.0 }) {
+			for y in tile.y..tile.y + tile.height {
+				for x in tile.x..tile.x + tile.width {
+					covered[y * width + x] += 1;
+				}
+			}
+		}
+
+		assert!(covered.iter().all(|&count| count == 1));
+	}
+
+	#[test]
+	fn tiles_start_at_the_focus() {
+		let tiles = tile_order(256, 256, 32, Point2 { x: 200.0, y: 40.0 });
+		let first = tiles[0];
+
+		assert!((first.x..first.x + first.width).contains(&200));
+		assert!((first.y..first.y + first.height).contains(&40));
+
+		// The farthest corner from the focus comes last
+		let last = tiles[tiles.len() - 1];
+		assert_eq!((last.x, last.y), (0, 224));
+	}
+
+	#[test]
+	fn mirrored_renders_match_iterating_every_sample() {
+		for (samples, julia) in [(1, None), (2, None), (1, Some(Complex::new(-0.8, 0.0))), (3, Some(Complex::new(-0.4, 0.6)))] {
+			let job = || RenderJob {
+				settings: IterationSettings { julia, max_iterations: 50.0, ..job(samples).settings },
+				..RenderJob::new(16, 12, samples, Viewport::new(16.0, 12.0), job(samples).settings, Arc::new(Gradient::rainbow()))
+			};
+
+			assert!(!grid_symmetries(&job()).is_empty());
+
+			let expected: Vec<Option<f64>> = sample_points(&job()).into_iter().map(|c| {
+				let (iterations, _) = calculate_for_point(c, job().settings);
+				iterations
+			}).collect();
+
+			assert_eq!(render(job(), None).iterations, expected, "{} samples of {:?}", samples, julia);
+		}
+
+		// Away from the axis, or turned, the grid no longer maps onto itself
+		let mut viewport = Viewport::new(16.0, 12.0);
+		viewport.set_center(Complex::new(0.0, 0.123));
+		assert!(grid_symmetries(&RenderJob::new(16, 12, 1, viewport, job(1).settings, Arc::new(Gradient::rainbow()))).is_empty());
+
+		viewport.set_center(Complex::new(0.0, 0.0));
+		viewport.set_rotation(0.3);
+		assert!(grid_symmetries(&RenderJob::new(16, 12, 1, viewport, job(1).settings, Arc::new(Gradient::rainbow()))).is_empty());
+	}
+
+	#[test]
+	fn shaded_interiors_match_across_backends() {
+		let mut viewport = Viewport::new(16.0, 12.0);
+		viewport.set_center(Complex::new(-0.2, 0.0));
+		viewport.set_magnification(4.0);
+
+		let job = || RenderJob {
+			shade_interior: true,
+			..RenderJob::new(16, 12, 2, viewport, IterationSettings { max_iterations: 200.0, ..job(1).settings }, Arc::new(Gradient::rainbow()))
+		};
+
+		let tiled = render(job(), None);
+		let assembled = frame_from_iterations(job(), tiled.iterations.clone(), tiled.converged.clone());
+
+		assert_eq!(tiled.distance, assembled.distance);
+		assert!(tiled.pixels == assembled.pixels);
+
+		// Around the origin, half a unit or six pixels from the cusp by the estimate
+		let depth = tiled.distance[(6 * 16 + 10) * 4].unwrap();
+		assert!((4.0..8.0).contains(&depth), "{}", depth);
+		assert!(tiled.pixels[6 * 16 + 10] != job().gradient.interior());
+	}
+
+	#[test]
+	fn boundary_renders_draw_a_line() {
+		let job = |formula| RenderJob {
+			boundary: true,
+			..RenderJob::new(32, 24, 2, Viewport::new(32.0, 24.0), IterationSettings { formula, ..job(1).settings }, Arc::new(Gradient::rainbow()))
+		};
+
+		let tiled = render(job(Formula::Mandelbrot), None);
+		let assembled = frame_from_iterations(job(Formula::Mandelbrot), tiled.iterations.clone(), tiled.converged.clone());
+		assert!(tiled.pixels == assembled.pixels);
+
+		// Mostly background, with the line running through the middle of the view
+		let lines = tiled.pixels.iter().filter(|&&pixel| pixel != BOUNDARY_BACKGROUND).count();
+		assert!(lines > 0 && lines < tiled.pixels.len() / 2, "{}", lines);
+		assert_eq!(tiled.pixels[0], BOUNDARY_BACKGROUND);
+
+		// Formulas without estimates keep their colours
+		let ship = render(job(Formula::BurningShip), None);
+		assert!(!ship.boundary && ship.pixels[0] != BOUNDARY_BACKGROUND);
+	}
+
+	#[test]
+	fn margins_are_left_out_of_what_is_shown() {
+		let plain = render(job(2), None);
+
+		let viewport = Viewport::new(8.0, 8.0).crop(-2.0, -2.0, 12.0, 12.0);
+		let padded = render(RenderJob { width: 12, height: 12, margin: 2, viewport, ..job(2) }, None);
+		let shown = padded.shown();
+
+		assert_eq!((shown.width, shown.height, shown.margin), (8, 8, 0));
+		assert_eq!(shown.iterations, plain.iterations);
+		assert_eq!(shown.pixels, plain.pixels);
+		assert_eq!(shown.viewport.pixel_to_complex(1.0, 3.0), plain.viewport.pixel_to_complex(1.0, 3.0));
+
+		// Nothing to leave out without a margin
+		assert!(matches!(plain.shown(), Cow::Borrowed(_)));
+	}
+
+	#[test]
+	fn palette_ranges_stretch_the_gradient() {
+		let gradient = Gradient::rainbow();
+
+		assert!(color_for(Some(20.0), false, None, false, [20.0, 30.0], &gradient, None) == gradient.sample(0.0));
+		assert!(color_for(Some(30.0), false, None, false, [20.0, 30.0], &gradient, None) == gradient.sample(1.0));
+		assert!(color_for(Some(25.0), false, None, false, [20.0, 30.0], &gradient, None) == color_for(Some(50.0), false, None, false, [0.0, 100.0], &gradient, None));
+
+		// Frames remember their range, so recolouring keeps it
+		let mut frame = render(RenderJob { palette_range: Some([1.0, 3.0]), ..job(1) }, None);
+		let pixels = frame.pixels.clone();
+		frame.recolor(Arc::new(Gradient::rainbow()));
+
+		assert!(frame.pixels == pixels);
+		assert!(frame.pixels != render(job(1), None).pixels);
+	}
+
+	#[test]
+	fn stripes_colour_odd_bands() {
+		let gradient = Gradient::rainbow();
+		let stripes = Gradient::new("stripes", vec![(0.0, Color::WHITE)]);
+
+		assert!(color_for(Some(4.5), false, None, false, [0.0, 10.0], &gradient, Some(&stripes)) == gradient.sample(0.45));
+		assert!(color_for(Some(5.5), false, None, false, [0.0, 10.0], &gradient, Some(&stripes)) == Color::WHITE);
+		assert!(color_for(None, false, None, false, [0.0, 10.0], &gradient, Some(&stripes)) == gradient.interior());
+
+		// Recolouring keeps them
+		let mut frame = render(RenderJob { stripes: Some(Arc::new(stripes)), ..job(1) }, None);
+		let pixels = frame.pixels.clone();
+		frame.recolor(Arc::new(Gradient::rainbow()));
+
+		assert!(frame.pixels == pixels);
+		assert!(frame.pixels != render(job(1), None).pixels);
+	}
+
+	#[test]
+	fn presets_only_raise_the_configured_settings() {
+		let mut config = Config { bailout: 4.0, max_iterations: 500.0, ..Config::default() };
+
+		let magnet = IterationSettings::for_formula(&config, Formula::MagnetOne);
+		assert_eq!((magnet.bailout, magnet.max_iterations), (100.0, 500.0));
+
+		config.max_iterations = 10.0;
+		let mandelbrot = IterationSettings::for_formula(&config, Formula::Mandelbrot);
+		assert_eq!((mandelbrot.bailout, mandelbrot.max_iterations), (4.0, 100.0));
+	}
+
+	#[test]
+	fn parses_bailout_and_starting_z() {
+		assert_eq!(parse_escape("10, 0.01, -0.5"), Ok((10.0, Complex::new(0.01, -0.5))));
+		assert_eq!(parse_escape(" 2,0,0 "), Ok((2.0, Complex::new(0.0, 0.0))));
+
+		assert!(parse_escape("2, 0").is_err());
+		assert!(parse_escape("0, 0, 0").is_err());
+		assert!(parse_escape("2, a, 0").is_err());
+		assert!(parse_escape("inf, 0, 0").is_err());
+	}
+
+	#[test]
+	fn settings_no_render_could_finish_are_rejected() {
+		let settings = IterationSettings::mandelbrot(100.0);
+		assert!(settings.validate().is_ok());
+
+		assert!(IterationSettings { bailout: 0.0, ..settings }.validate().is_err());
+		assert!(IterationSettings { bailout: f64::NAN, ..settings }.validate().is_err());
+		assert!(IterationSettings::mandelbrot(f64::INFINITY).validate().is_err());
+		assert!(IterationSettings::mandelbrot(0.5).validate().is_err());
+		assert!(IterationSettings { julia: Some(Complex::new(f64::NAN, 0.0)), ..settings }.validate().is_err());
+	}
+
+	#[test]
+	fn converging_orbits_are_told_apart() {
+		let settings = |formula| IterationSettings { formula, bailout: 100.0, ..IterationSettings::mandelbrot(100.0) };
+
+		let (iterations, converged) = calculate_for_point(Complex::new(-1.0, 0.0), settings(Formula::MagnetOne));
+		assert!(iterations.is_some() && converged);
+
+		// Mandelbrot orbits only end by escaping or at the cap
+		assert_eq!(calculate_for_point(Complex::new(0.0, 0.0), settings(Formula::Mandelbrot)), (None, false));
+		assert_eq!(calculate_for_point(Complex::new(1.0, 0.0), settings(Formula::Mandelbrot)), (Some(5.0), false));
+
+		let gradient = Gradient::rainbow();
+		assert!(color_for(Some(10.0), true, None, false, [0.0, 100.0], &gradient, None) != color_for(Some(10.0), false, None, false, [0.0, 100.0], &gradient, None));
+	}
+}
@@ -0,0 +1,136 @@
+use std::str::FromStr;
+
+use ggez::mint::Point2;
+
+use crate::complex::Complex;
+use crate::viewport::{Viewport, IDENTITY_SKEW};
+
+// A rectangle of the displayed plane with its edges along the real and imaginary axes, as picked by
+// dragging and fine tuned by typing its bounds in
+#[derive(Copy, Clone, PartialEq, Debug)]
+pub struct Region {
+	pub real: [f64; 2],
+	pub imaginary: [f64; 2],
+}
+
+impl Region {
+	// Bounds the rectangle of pixels between two opposite corners, which only covers more than it
+	// when the view is rotated or skewed
+	pub fn from_pixels(viewport: &Viewport, a: Point2<f64>, b: Point2<f64>) -> Region {
+		let corners = [(a.x, a.y), (b.x, a.y), (b.x, b.y), (a.x, b.y)].map(|(x, y)| viewport.pixel_to_plane(x, y));
+
+		let bounds = |part: fn(&Complex) -> f64| corners.iter().map(part).fold([f64::INFINITY, f64::NEG_INFINITY], |[min, max], value| {
+			[min.min(value), max.max(value)]
+		});
+
+		Region { real: bounds(Complex::real), imaginary: bounds(Complex::imaginary) }
+	}
+
+	pub fn corners(&self) -> [Complex; 4] {
+		let ([left, right], [bottom, top]) = (self.real, self.imaginary);
+
+		[Complex::new(left, bottom), Complex::new(right, bottom), Complex::new(right, top), Complex::new(left, top)]
+	}
+
+	// Bounds as they're typed in, to one digit past the size of a pixel at this scale so they
+	// neither run on nor lose the framing
+	pub fn describe(&self, scale: f64) -> String {
+		let decimals = (-scale.log10()).ceil().max(0.0) as usize + 1;
+		let [left, right] = self.real;
+		let [bottom, top] = self.imaginary;
+
+		format!("{:.*}, {:.*}, {:.*}, {:.*}", decimals, left, decimals, right, decimals, bottom, decimals, top)
+	}
+
+	// A view of the same size centered on the region, just large enough to show all of it. Rotation
+	// and skew are dropped so the region's edges line up with the window's
+	pub fn frame(&self, viewport: &Viewport) -> Viewport {
+		let (width, height) = viewport.size();
+		let ([left, right], [bottom, top]) = (self.real, self.imaginary);
+
+		let scale = ((right - left) / width).max((top - bottom) / height);
+		let default_scale = viewport.scale() * viewport.magnification();
+
+		let mut framed = *viewport;
+		framed.set_rotation(0.0);
+		framed.set_skew(IDENTITY_SKEW);
+		framed.set_center(Complex::new((left + right) / 2.0, (bottom + top) / 2.0));
+		framed.set_magnification(default_scale / scale);
+
+		framed
+	}
+}
+
+impl FromStr for Region {
+	type Err = String;
+
+	// The lowest and highest real part then imaginary part, separated by commas. Each pair can come
+	// in either order
+	fn from_str(text: &str) -> Result<Region, String> {
+		let values = text.split(',')
+			.map(|value| match value.trim().parse::<f64>() {
+				Ok(value) if value.is_finite() => Ok(value),
+				_ => Err(format!("invalid bound {}", value.trim())),
+			})
+			.collect::<Result<Vec<f64>, String>>()?;
+
+		let [a, b, c, d] = values[..] else {
+			return Err(format!("expected 4 bounds, got {}", values.len()));
+		};
+
+		if a == b || c == d {
+			return Err(String::from("the region has no area"));
+		}
+
+		Ok(Region { real: [a.min(b), a.max(b)], imaginary: [c.min(d), c.max(d)] })
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn dragged_rectangles_bound_their_corners() {
+		let viewport = Viewport::new(400.0, 400.0);
+		let region = Region::from_pixels(&viewport, Point2 { x: 300.0, y: 100.0 }, Point2 { x: 100.0, y: 200.0 });
+
+		assert_eq!(region, Region { real: [-1.0, 1.0], imaginary: [-1.0, 0.0] });
+	}
+
+	#[test]
+	fn framing_fits_the_region() {
+		let mut viewport = Viewport::new(400.0, 200.0);
+		viewport.set_rotation(0.5);
+
+		// Twice as tall as the window's shape, so the height fills it and the width shows more
+		let region = Region { real: [-1.0, 0.0], imaginary: [0.0, 1.0] };
+		let framed = region.frame(&viewport);
+
+		assert_eq!(framed.rotation(), 0.0);
+		assert_eq!(framed.center(), Complex::new(-0.5, 0.5));
+		assert!((framed.scale() - 1.0 / 200.0).abs() < 1e-15);
+
+		let corner = framed.pixel_to_plane(0.0, 0.0);
+		assert!((corner - Complex::new(-1.5, 0.0)).abs() < 1e-12, "{}", corner);
+	}
+
+	#[test]
+	fn descriptions_parse_back() {
+		let region = Region { real: [-0.7436438, -0.7436401], imaginary: [0.1318259, 0.1318282] };
+		let described = region.describe(1e-8);
+
+		assert_eq!(described, "-0.743643800, -0.743640100, 0.131825900, 0.131828200");
+		assert_eq!(described.parse::<Region>(), Ok(region));
+	}
+
+	#[test]
+	fn parses_bounds_in_either_order() {
+		assert_eq!("1, -1, 0.5, -0.5".parse::<Region>(), Ok(Region { real: [-1.0, 1.0], imaginary: [-0.5, 0.5] }));
+
+		assert!("1, 2, 3".parse::<Region>().is_err());
+		assert!("1, 1, 0, 1".parse::<Region>().is_err());
+		assert!("a, 1, 0, 1".parse::<Region>().is_err());
+		assert!("inf, 1, 0, 1".parse::<Region>().is_err());
+	}
+}
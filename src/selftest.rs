@@ -0,0 +1,107 @@
+use std::sync::Arc;
+
+use crate::backend::RenderBackend;
+use crate::bench::{BenchView, VIEWS};
+use crate::diff::Comparison;
+use crate::gradient::Gradient;
+use crate::renderer::Frame;
+
+// Renders the benchmark's views on every backend and compares them pixel by pixel against the
+// reference, so an accelerated backend can be trusted before a long export
+
+// Rounding differs between devices, so a few boundary points flipping is expected. Any more than
+// this share of a view's pixels and the backend fails
+const ALLOWED_DIFFERING_PERCENT: f64 = 0.1;
+
+pub struct SelftestResult {
+	pub backend: String,
+	pub view: &'static str,
+	pub differing_percent: f64,
+	pub escape_mismatches: usize,
+	pub max_difference: f64,
+}
+
+impl SelftestResult {
+	pub fn passed(&self) -> bool {
+		self.differing_percent <= ALLOWED_DIFFERING_PERCENT
+	}
+}
+
+fn render(backend: &dyn RenderBackend, view: &BenchView, size: (usize, usize), samples: usize, iteration_multiplier: f64, bailout: f64, gradient: &Arc<Gradient>) -> Result<Frame, String> {
+	backend.render(view.job(size, samples, iteration_multiplier, bailout, gradient), None)
+		.map_err(|e| format!("{}: {}", backend.name(), e))
+}
+
+// Each backend is compared against the reference, which is usually the plain CPU one. Comparing
+// the reference against itself still checks renders are repeatable. Stops at the first failed
+// render, naming the backend
+pub fn run(reference: &dyn RenderBackend, backends: &[Arc<dyn RenderBackend>], size: (usize, usize), samples: usize, iteration_multiplier: f64, bailout: f64) -> Result<Vec<SelftestResult>, String> {
+	let gradient = Arc::new(Gradient::rainbow());
+	let mut results = Vec::new();
+
+	for view in &VIEWS {
+		let expected = render(reference, view, size, samples, iteration_multiplier, bailout, &gradient)?;
+
+		for backend in backends {
+			let frame = render(backend.as_ref(), view, size, samples, iteration_multiplier, bailout, &gradient)?;
+			let comparison = Comparison::new(&expected, &frame);
+
+			results.push(SelftestResult {
+				backend: backend.name(),
+				view: view.name,
+				differing_percent: comparison.differing_percent(),
+				escape_mismatches: comparison.escape_mismatches,
+				max_difference: comparison.max_difference,
+			});
+		}
+	}
+
+	Ok(results)
+}
+
+pub fn report(results: &[SelftestResult]) -> String {
+	let mut lines = vec![format!("{:<28} {:<18} {:>10} {:>10} {:>14} {:>6}", "backend", "view", "differing", "escapes", "max difference", "result")];
+
+	for result in results {
+		lines.push(format!(
+			"{:<28} {:<18} {:>9.3}% {:>10} {:>14.3} {:>6}",
+			result.backend,
+			result.view,
+			result.differing_percent,
+			result.escape_mismatches,
+			result.max_difference,
+			if result.passed() { "ok" } else { "FAIL" },
+		));
+	}
+
+	lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use crate::backend;
+
+	#[test]
+	fn the_reference_agrees_with_itself() {
+		let reference = backend::create("cpu").unwrap();
+		let backends = [backend::create("cpu").unwrap()];
+		let results = run(reference.as_ref(), &backends, (16, 12), 1, 0.1, 2.0).unwrap();
+
+		assert_eq!(results.len(), VIEWS.len());
+		assert!(results.iter().all(|result| result.passed() && result.differing_percent == 0.0 && result.escape_mismatches == 0));
+
+		let report = report(&results);
+		assert_eq!(report.lines().count(), results.len() + 1);
+		assert!(!report.contains("FAIL"));
+	}
+
+	#[test]
+	fn too_many_differing_pixels_fail() {
+		let result = SelftestResult { backend: String::from("cpu"), view: "full set", differing_percent: 2.5, escape_mismatches: 10, max_difference: 40.0 };
+
+		assert!(!result.passed());
+		assert!(report(&[result]).contains("FAIL"));
+	}
+}
@@ -0,0 +1,163 @@
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+
+use serde::{Deserialize, Serialize};
+
+use crate::complex::Complex;
+use crate::formula::Formula;
+use crate::gradient;
+use crate::renderer::IterationSettings;
+use crate::viewport::{Viewport, IDENTITY_SKEW};
+
+const SESSION_FILE: &str = "session.toml";
+
+// Written every so often while the viewer runs and removed when it closes, so finding it on launch
+// means the last run crashed
+const RECOVERY_FILE: &str = "recovery.toml";
+
+// Where the user left off, written on exit and restored on the next launch
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+	pub center: [f64; 2],
+	pub magnification: f64,
+	pub rotation: f64,
+	#[serde(default = "default_skew")]
+	pub skew: [f64; 4],
+	#[serde(default)]
+	pub inverted: bool,
+
+	#[serde(default)]
+	pub formula: Formula,
+	pub bailout: f64,
+	pub initial_z: [f64; 2],
+	// The constant of the Julia set shown, None for the Mandelbrot set
+	#[serde(default)]
+	pub julia: Option<[f64; 2]>,
+	#[serde(default = "default_max_iterations")]
+	pub max_iterations: f64,
+
+	#[serde(default = "default_palette")]
+	pub palette: String,
+}
+
+fn default_max_iterations() -> f64 {
+	100.0
+}
+
+fn default_skew() -> [f64; 4] {
+	IDENTITY_SKEW
+}
+
+fn default_palette() -> String {
+	String::from(gradient::DEFAULT_NAME)
+}
+
+impl Session {
+	pub fn capture(viewport: &Viewport, settings: &IterationSettings, palette: &str) -> Session {
+		let center = viewport.center();
+
+		Session {
+			center: [center.real(), center.imaginary()],
+			magnification: viewport.magnification(),
+			rotation: viewport.rotation(),
+			skew: viewport.skew(),
+			inverted: viewport.inverted(),
+
+			formula: settings.formula,
+			bailout: settings.bailout,
+			initial_z: [settings.initial_z.real(), settings.initial_z.imaginary()],
+			julia: settings.julia.map(|c| [c.real(), c.imaginary()]),
+			max_iterations: settings.max_iterations,
+
+			palette: palette.to_string(),
+		}
+	}
+
+	// The palette is looked up by name by the caller, as the palette list can change between runs
+	pub fn restore(&self, viewport: &mut Viewport, settings: &mut IterationSettings) {
+		viewport.set_center(Complex::new(self.center[0], self.center[1]));
+		viewport.set_magnification(self.magnification);
+		viewport.set_rotation(self.rotation);
+		viewport.set_skew(self.skew);
+		viewport.set_inverted(self.inverted);
+
+		settings.formula = self.formula;
+		settings.bailout = self.bailout;
+		settings.initial_z = Complex::new(self.initial_z[0], self.initial_z[1]);
+		settings.julia = self.julia.map(|[real, imaginary]| Complex::new(real, imaginary));
+		settings.max_iterations = self.max_iterations;
+	}
+
+	// Views from a file edited by hand or another program can hold settings which can't be rendered
+	pub fn is_valid(&self) -> bool {
+		let [a, b, c, d] = self.skew;
+		let is_finite = self.center.iter().chain(&self.skew).chain([&self.magnification, &self.rotation]).all(|value| value.is_finite());
+
+		let settings = IterationSettings {
+			formula: self.formula,
+			bailout: self.bailout,
+			initial_z: Complex::new(self.initial_z[0], self.initial_z[1]),
+			julia: self.julia.map(|[real, imaginary]| Complex::new(real, imaginary)),
+			max_iterations: self.max_iterations,
+		};
+
+		is_finite && self.magnification > 0.0 && a * d - b * c != 0.0 && settings.validate().is_ok()
+	}
+
+	fn path(name: &str) -> Option<PathBuf> {
+		dirs::data_dir().map(|dir| dir.join("mandelbrot_viewer").join(name))
+	}
+
+	fn load_from(name: &str) -> Option<Session> {
+		let contents = fs::read_to_string(Session::path(name)?).ok()?;
+		let session: Session = toml::from_str(&contents).ok()?;
+
+		session.is_valid().then_some(session)
+	}
+
+	// Written beside and renamed over the old file, so a crash mid-write leaves the old one whole
+	fn save_to(&self, name: &str) -> io::Result<()> {
+		let path = Session::path(name).ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no data directory"))?;
+
+		if let Some(parent) = path.parent() {
+			fs::create_dir_all(parent)?;
+		}
+
+		let contents = toml::to_string(self).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+		let temporary = path.with_extension("toml.partial");
+
+		fs::write(&temporary, contents)?;
+		fs::rename(&temporary, path)
+	}
+
+	// A missing or unreadable session just means starting from the defaults
+	pub fn load() -> Option<Session> {
+		Session::load_from(SESSION_FILE)
+	}
+
+	pub fn save(&self) -> io::Result<()> {
+		self.save_to(SESSION_FILE)
+	}
+
+	// Where the last run was when it crashed, None if it closed normally
+	pub fn load_recovery() -> Option<Session> {
+		Session::load_from(RECOVERY_FILE)
+	}
+
+	pub fn save_recovery(&self) -> io::Result<()> {
+		self.save_to(RECOVERY_FILE)
+	}
+
+	pub fn clear_recovery() -> io::Result<()> {
+		let Some(path) = Session::path(RECOVERY_FILE) else {
+			return Ok(());
+		};
+
+		match fs::remove_file(path) {
+			Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+			_ => Ok(()),
+		}
+	}
+}
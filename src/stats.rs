@@ -0,0 +1,90 @@
+// Escape iteration counts bucketed evenly over 0..=max_iterations, interior points aren't counted
+pub fn histogram(iterations: &[Option<f64>], max_iterations: f64, bins: usize) -> Vec<usize> {
+	let mut counts = vec![0; bins];
+
+	for &pixel in iterations {
+		if let Some(pixel) = pixel {
+			let bin = ((pixel / max_iterations) * bins as f64) as usize;
+			counts[bin.min(bins - 1)] += 1;
+		}
+	}
+
+	counts
+}
+
+pub struct FrameStats {
+	// None when every pixel is interior
+	pub min: Option<f64>,
+	pub mean: Option<f64>,
+	pub max: Option<f64>,
+
+	pub interior_fraction: f64,
+	pub total_iterations: u64,
+}
+
+impl FrameStats {
+	// Interior points are taken to have run the full iteration cap plus the final check
+	pub fn compute(iterations: &[Option<f64>], max_iterations: f64) -> FrameStats {
+		let mut min = f64::INFINITY;
+		let mut max = f64::NEG_INFINITY;
+		let mut sum = 0.0;
+
+		let mut escaped = 0;
+		let mut interior = 0;
+
+		for &pixel in iterations {
+			match pixel {
+				Some(pixel) => {
+					min = min.min(pixel);
+					max = max.max(pixel);
+					sum += pixel;
+					escaped += 1;
+				},
+				None => interior += 1,
+			}
+		}
+
+		let has_escaped = escaped > 0;
+		let total_iterations = sum.round() as u64 + interior as u64 * (max_iterations as u64 + 1);
+
+		FrameStats {
+			min: has_escaped.then_some(min),
+			mean: has_escaped.then(|| sum / escaped as f64),
+			max: has_escaped.then_some(max),
+
+			interior_fraction: if iterations.is_empty() { 0.0 } else { interior as f64 / iterations.len() as f64 },
+			total_iterations,
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn histogram_buckets_escaped_points() {
+		let iterations = [Some(0.0), Some(9.0), Some(10.0), Some(99.0), Some(100.0), None];
+
+		assert_eq!(histogram(&iterations, 100.0, 10), vec![2, 1, 0, 0, 0, 0, 0, 0, 0, 2]);
+	}
+
+	#[test]
+	fn stats_count_interior_at_full_cost() {
+		let stats = FrameStats::compute(&[Some(2.0), Some(4.0), None, None], 100.0);
+
+		assert_eq!(stats.min, Some(2.0));
+		assert_eq!(stats.mean, Some(3.0));
+		assert_eq!(stats.max, Some(4.0));
+		assert_eq!(stats.interior_fraction, 0.5);
+		assert_eq!(stats.total_iterations, 6 + 2 * 101);
+	}
+
+	#[test]
+	fn stats_of_all_interior_frame() {
+		let stats = FrameStats::compute(&[None, None], 100.0);
+
+		assert_eq!(stats.mean, None);
+		assert_eq!(stats.interior_fraction, 1.0);
+	}
+}
@@ -0,0 +1,312 @@
+use std::collections::HashSet;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::complex::Complex;
+use crate::export;
+use crate::formula::Formula;
+use crate::gradient::Gradient;
+use crate::memory::{Cache, MemoryBudget};
+use crate::renderer::{self, IterationSettings, RenderJob};
+use crate::session::Session;
+use crate::viewport::Viewport;
+
+pub const TILE_SIZE: usize = 256;
+
+// Doubles run out of precision long before this
+pub const MAX_ZOOM: u32 = 48;
+
+// A page showing the tiles in Leaflet, served at the root
+const INDEX_PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+	<title>Mandelbrot Viewer</title>
+	<link rel="stylesheet" href="https://unpkg.com/leaflet@1.9.4/dist/leaflet.css">
+	<script src="https://unpkg.com/leaflet@1.9.4/dist/leaflet.js"></script>
+	<style>html, body, #map { height: 100%; margin: 0; background: #000; }</style>
+</head>
+<body>
+	<div id="map"></div>
+	<script>
+		const map = L.map("map", { crs: L.CRS.Simple, minZoom: 0, maxZoom: 48 }).setView([-128, 128], 1);
+		L.tileLayer("/{z}/{x}/{y}.png", { noWrap: true, maxZoom: 48, bounds: [[0, 0], [-256, 256]] }).addTo(map);
+	</script>
+</body>
+</html>
+"#;
+
+// Slippy map tiles, zoom 0 is a single tile of the default view spanning -2 to 2 on both axes
+// and each level splits every tile into four. x runs right and y down, as with screen pixels
+pub fn tile_viewport(z: u32, x: u64, y: u64) -> Option<Viewport> {
+	let tiles = 1u64.checked_shl(z).filter(|_| z <= MAX_ZOOM)?;
+
+	if x >= tiles || y >= tiles {
+		return None;
+	}
+
+	let span = 4.0 / tiles as f64;
+
+	let mut viewport = Viewport::new(TILE_SIZE as f64, TILE_SIZE as f64);
+	viewport.set_center(Complex::new(-2.0 + (x as f64 + 0.5) * span, -2.0 + (y as f64 + 0.5) * span));
+	viewport.set_magnification(tiles as f64);
+
+	Some(viewport)
+}
+
+// Splits /{z}/{x}/{y}.png into its coordinates
+pub fn parse_tile_path(path: &str) -> Option<(u32, u64, u64)> {
+	let mut parts = path.strip_prefix('/')?.strip_suffix(".png")?.split('/');
+
+	let z = parts.next()?.parse().ok()?;
+	let x = parts.next()?.parse().ok()?;
+	let y = parts.next()?.parse().ok()?;
+
+	parts.next().is_none().then_some((z, x, y))
+}
+
+fn respond(stream: &mut TcpStream, status: &str, content_type: &str, body: &[u8]) -> io::Result<()> {
+	write!(
+		stream,
+		"HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nAccess-Control-Allow-Origin: *\r\nConnection: close\r\n\r\n",
+		status, content_type, body.len()
+	)?;
+
+	stream.write_all(body)
+}
+
+// Tiles served so far by their zoom and position, as map clients ask for the same ones over and over
+// while panning around
+type TileCache = Mutex<Cache<(u32, u64, u64)>>;
+
+fn handle(mut stream: TcpStream, settings: IterationSettings, gradient: &Arc<Gradient>, samples: usize, cache: &TileCache) -> io::Result<()> {
+	let mut request_line = String::new();
+	BufReader::new(&stream).read_line(&mut request_line)?;
+
+	let mut parts = request_line.split_whitespace();
+	let (method, path) = (parts.next().unwrap_or_default(), parts.next().unwrap_or_default());
+
+	if method != "GET" {
+		return respond(&mut stream, "405 Method Not Allowed", "text/plain", b"only GET is supported");
+	}
+
+	if path == "/" {
+		return respond(&mut stream, "200 OK", "text/html", INDEX_PAGE.as_bytes());
+	}
+
+	let Some((tile, viewport)) = parse_tile_path(path).and_then(|tile| Some((tile, tile_viewport(tile.0, tile.1, tile.2)?))) else {
+		return respond(&mut stream, "404 Not Found", "text/plain", b"no such tile");
+	};
+
+	// Not held while rendering, two requests for the same new tile both render it
+	let cached = cache.lock().expect("tile cache poisoned").get(&tile);
+
+	let png = match cached {
+		Some(png) => png,
+		None => {
+			let (png, _) = render_tile(viewport, settings, gradient, samples)?;
+			cache.lock().expect("tile cache poisoned").insert(tile, png.clone());
+			png
+		},
+	};
+
+	respond(&mut stream, "200 OK", "image/png", &png)
+}
+
+// The encoded tile, and whether every pixel of it is inside the set
+fn render_tile(viewport: Viewport, settings: IterationSettings, gradient: &Arc<Gradient>, samples: usize) -> io::Result<(Vec<u8>, bool)> {
+	let frame = renderer::render(RenderJob::new(TILE_SIZE, TILE_SIZE, samples, viewport, settings, gradient.clone()), None);
+	let is_interior = frame.iterations.iter().all(Option::is_none);
+
+	let session = Session::capture(&viewport, &settings, &gradient.name);
+	let png = export::png_bytes(&frame.pixels, (TILE_SIZE, TILE_SIZE), 8, &session)?;
+
+	Ok((png, is_interior))
+}
+
+// The Mandelbrot set from z = 0 and its Julia sets have no holes, so nothing inside a tile found
+// entirely inside them can escape. Other starting z, the folded formulas and the rational maps
+// enclose regions which escape or converge, so their tiles have to be rendered
+fn has_no_holes(settings: &IterationSettings) -> bool {
+	settings.formula == Formula::Mandelbrot && (settings.julia.is_some() || settings.initial_z == Complex::new(0.0, 0.0))
+}
+
+// Writes every tile from zoom 0 to max_zoom into dir/z/x/y.png, returning how many were rendered.
+// For sets without holes, the children of a tile found entirely inside it are filled in without
+// iterating, which skips most of the work deep inside the cardioid
+pub fn render_pyramid(dir: &Path, max_zoom: u32, settings: IterationSettings, gradient: Arc<Gradient>, samples: usize) -> io::Result<usize> {
+	let interior_pixels = vec![gradient.interior(); TILE_SIZE * TILE_SIZE];
+	let fills_interior = has_no_holes(&settings);
+
+	let mut interior_tiles = HashSet::new();
+	let mut rendered = 0;
+
+	for z in 0..=max_zoom.min(MAX_ZOOM) {
+		let tiles = 1u64 << z;
+		let mut next_interior_tiles = HashSet::new();
+
+		for x in 0..tiles {
+			let column = dir.join(z.to_string()).join(x.to_string());
+			fs::create_dir_all(&column)?;
+
+			for y in 0..tiles {
+				let viewport = tile_viewport(z, x, y).expect("tile within the zoom level");
+
+				let (png, is_interior) = if interior_tiles.contains(&(x / 2, y / 2)) {
+					let session = Session::capture(&viewport, &settings, &gradient.name);
+					(export::png_bytes(&interior_pixels, (TILE_SIZE, TILE_SIZE), 8, &session)?, true)
+				} else {
+					rendered += 1;
+					render_tile(viewport, settings, &gradient, samples)?
+				};
+
+				if is_interior && fills_interior {
+					next_interior_tiles.insert((x, y));
+				}
+
+				fs::write(column.join(format!("{}.png", y)), png)?;
+			}
+		}
+
+		interior_tiles = next_interior_tiles;
+		eprintln!("finished zoom {} of {}", z, max_zoom);
+	}
+
+	Ok(rendered)
+}
+
+// Serves tiles until the process is stopped, each connection is handled on its own thread.
+// Tiles are cached within half the budget, leaving the rest for the ones being rendered
+pub fn serve(address: &str, settings: IterationSettings, gradient: Arc<Gradient>, samples: usize, budget: MemoryBudget) -> io::Result<()> {
+	let listener = TcpListener::bind(address)?;
+	let cache = Arc::new(Mutex::new(Cache::new(budget.share(0.5))));
+
+	eprintln!("serving tiles at http://{}/", listener.local_addr()?);
+
+	for stream in listener.incoming() {
+		let Ok(stream) = stream else {
+			continue;
+		};
+
+		let (gradient, cache) = (gradient.clone(), cache.clone());
+
+		thread::spawn(move || {
+			if let Err(e) = handle(stream, settings, &gradient, samples, &cache) {
+				eprintln!("could not serve request: {}", e);
+			}
+		});
+	}
+
+	Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	use ggez::graphics::Color;
+
+	use crate::library;
+
+	const EPSILON: f64 = 1e-12;
+
+	fn assert_close(a: Complex, b: Complex) {
+		assert!((a - b).abs() < EPSILON, "{} vs {}", a, b);
+	}
+
+	#[test]
+	fn parses_tile_paths() {
+		assert_eq!(parse_tile_path("/3/5/2.png"), Some((3, 5, 2)));
+
+		assert_eq!(parse_tile_path("/3/5.png"), None);
+		assert_eq!(parse_tile_path("/3/5/2/1.png"), None);
+		assert_eq!(parse_tile_path("/3/5/2.jpg"), None);
+		assert_eq!(parse_tile_path("/a/5/2.png"), None);
+	}
+
+	#[test]
+	fn zoom_zero_is_the_default_view() {
+		let viewport = tile_viewport(0, 0, 0).unwrap();
+
+		assert_close(viewport.pixel_to_complex(0.0, 0.0), Complex::new(-2.0, -2.0));
+		assert_close(viewport.pixel_to_complex(256.0, 256.0), Complex::new(2.0, 2.0));
+	}
+
+	#[test]
+	fn child_tiles_split_their_parent() {
+		// The bottom right quarter of the default view
+		let viewport = tile_viewport(1, 1, 1).unwrap();
+
+		assert_close(viewport.pixel_to_complex(0.0, 0.0), Complex::new(0.0, 0.0));
+		assert_close(viewport.pixel_to_complex(256.0, 256.0), Complex::new(2.0, 2.0));
+
+		assert!(tile_viewport(1, 2, 0).is_none());
+		assert!(tile_viewport(MAX_ZOOM + 1, 0, 0).is_none());
+	}
+
+	#[test]
+	fn pyramid_has_every_tile() {
+		let dir = std::env::temp_dir().join(format!("mandelbrot_viewer_pyramid_{}", std::process::id()));
+
+		let settings = IterationSettings::mandelbrot(20.0);
+
+		render_pyramid(&dir, 2, settings, Arc::new(Gradient::rainbow()), 1).unwrap();
+
+		for (z, tiles) in [(0, 1), (1, 2), (2, 4)] {
+			for x in 0..tiles {
+				for y in 0..tiles {
+					assert!(dir.join(format!("{}/{}/{}.png", z, x, y)).is_file());
+				}
+			}
+		}
+
+		fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn only_sets_without_holes_skip_interior_tiles() {
+		let mandelbrot = IterationSettings::mandelbrot(20.0);
+
+		assert!(has_no_holes(&mandelbrot));
+		assert!(has_no_holes(&IterationSettings { julia: Some(Complex::new(-0.12, 0.75)), ..mandelbrot }));
+
+		assert!(!has_no_holes(&IterationSettings { initial_z: Complex::new(0.05, 0.0), ..mandelbrot }));
+
+		for formula in [Formula::BurningShip, Formula::MagnetOne, Formula::MagnetTwo, Formula::Lambda, Formula::Nova] {
+			assert!(!has_no_holes(&IterationSettings { formula, ..mandelbrot }), "{:?}", formula);
+		}
+	}
+
+	#[test]
+	fn pyramids_from_other_starting_z_render_every_tile() {
+		let dir = std::env::temp_dir().join(format!("mandelbrot_viewer_pyramid_z0_{}", std::process::id()));
+
+		// Nothing in the default view gets past this bailout in two iterations, so the whole of zoom 0
+		// is inside the set
+		let mandelbrot = IterationSettings { bailout: 16.0, ..IterationSettings::mandelbrot(1.0) };
+		let perturbed = IterationSettings { initial_z: Complex::new(0.05, 0.0), ..mandelbrot };
+
+		let rendered = [mandelbrot, perturbed].map(|settings| render_pyramid(&dir, 1, settings, Arc::new(Gradient::rainbow()), 1).unwrap());
+		fs::remove_dir_all(&dir).unwrap();
+
+		assert_eq!(rendered, [1, 5]);
+	}
+
+	#[test]
+	fn skipped_tiles_are_the_interior_colour() {
+		let dir = std::env::temp_dir().join(format!("mandelbrot_viewer_pyramid_interior_{}", std::process::id()));
+
+		let settings = IterationSettings { bailout: 16.0, ..IterationSettings::mandelbrot(1.0) };
+		let gradient = Arc::new(Gradient::rainbow().with_interior(Color::WHITE));
+
+		assert_eq!(render_pyramid(&dir, 1, settings, gradient, 1).unwrap(), 1);
+
+		let (bytes, _) = library::load_thumbnail(&dir.join("1/0/1.png")).unwrap();
+		fs::remove_dir_all(&dir).unwrap();
+
+		assert!(bytes.iter().all(|&byte| byte == 255));
+	}
+}
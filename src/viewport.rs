@@ -0,0 +1,343 @@
+use ggez::mint::Point2;
+
+use crate::complex::Complex;
+
+// Extent of the complex plane across the smaller window dimension at a magnification of 1,
+// the larger dimension shows proportionally more so the image is never stretched
+const DEFAULT_SPAN: f64 = 4.0;
+
+// Maps between screen pixels and the complex plane, pixel (0, 0) is the top left
+
+#[derive(Copy, Clone)]
+pub struct Viewport {
+	width: f64,
+	height: f64,
+
+	center: Complex,
+	// Complex units per pixel
+	scale: f64,
+	// Radians, anticlockwise
+	rotation: f64,
+	// Row major 2x2 matrix applied to pixel offsets before rotating, stretches the sampling grid
+	// to undo the distortion around deep minibrots. Must be invertible
+	skew: [f64; 4],
+	// Shows the plane under c -> 1/c, which brings the structure at infinity into view.
+	// Navigation happens in the displayed plane, only the final mapping to c is inverted
+	inverted: bool,
+}
+
+pub const IDENTITY_SKEW: [f64; 4] = [1.0, 0.0, 0.0, 1.0];
+
+impl Viewport {
+	pub fn new(width: f64, height: f64) -> Viewport {
+		Viewport {
+			width,
+			height,
+
+			center: Complex::new(0.0, 0.0),
+			scale: Viewport::default_scale(width, height),
+			rotation: 0.0,
+			skew: IDENTITY_SKEW,
+			inverted: false,
+		}
+	}
+
+	fn default_scale(width: f64, height: f64) -> f64 {
+		DEFAULT_SPAN / width.min(height)
+	}
+
+	pub fn size(&self) -> (f64, f64) {
+		(self.width, self.height)
+	}
+
+	pub fn center(&self) -> Complex {
+		self.center
+	}
+
+	pub fn set_center(&mut self, center: Complex) {
+		self.center = center;
+	}
+
+	pub fn scale(&self) -> f64 {
+		self.scale
+	}
+
+	pub fn rotation(&self) -> f64 {
+		self.rotation
+	}
+
+	pub fn set_rotation(&mut self, rotation: f64) {
+		self.rotation = rotation;
+	}
+
+	pub fn skew(&self) -> [f64; 4] {
+		self.skew
+	}
+
+	pub fn set_skew(&mut self, skew: [f64; 4]) {
+		self.skew = skew;
+	}
+
+	pub fn inverted(&self) -> bool {
+		self.inverted
+	}
+
+	pub fn set_inverted(&mut self, inverted: bool) {
+		self.inverted = inverted;
+	}
+
+	// Between the displayed plane and c, the inversion is its own inverse
+	fn plane_to_c(&self, w: Complex) -> Complex {
+		if self.inverted { Complex::new(1.0, 0.0) / w } else { w }
+	}
+
+	// A distance in pixels from the center, as a distance in the complex plane
+	fn offset_to_complex(&self, dx: f64, dy: f64) -> Complex {
+		let [a, b, c, d] = self.skew;
+		let skewed = Complex::new(a * dx + b * dy, c * dx + d * dy);
+
+		skewed * self.scale * Complex::from_polar(1.0, self.rotation)
+	}
+
+	// How many times closer than the default view
+	pub fn magnification(&self) -> f64 {
+		Viewport::default_scale(self.width, self.height) / self.scale
+	}
+
+	pub fn set_magnification(&mut self, magnification: f64) {
+		self.scale = Viewport::default_scale(self.width, self.height) / magnification;
+	}
+
+	// Where a pixel lies in the displayed plane, which is what the center and zoom refer to
+	pub fn pixel_to_plane(&self, x: f64, y: f64) -> Complex {
+		self.center + self.offset_to_complex(x - self.width / 2.0, y - self.height / 2.0)
+	}
+
+	// The value of c rendered at a pixel
+	pub fn pixel_to_complex(&self, x: f64, y: f64) -> Complex {
+		self.plane_to_c(self.pixel_to_plane(x, y))
+	}
+
+	pub fn complex_to_pixel(&self, c: Complex) -> Point2<f64> {
+		self.plane_to_pixel(self.plane_to_c(c))
+	}
+
+	// Where a point of the displayed plane is drawn
+	pub fn plane_to_pixel(&self, w: Complex) -> Point2<f64> {
+		let skewed = ((w - self.center) / Complex::from_polar(1.0, self.rotation)) * (1.0 / self.scale);
+
+		let [a, b, c, d] = self.skew;
+		let determinant = a * d - b * c;
+
+		let (sx, sy) = (skewed.real(), skewed.imaginary());
+
+		Point2 {
+			x: (d * sx - b * sy) / determinant + self.width / 2.0,
+			y: (a * sy - c * sx) / determinant + self.height / 2.0,
+		}
+	}
+
+	// Keeps the center and magnification, only the visible extent changes
+	pub fn resize(&mut self, width: f64, height: f64) {
+		let magnification = self.magnification();
+
+		self.width = width;
+		self.height = height;
+
+		self.set_magnification(magnification);
+	}
+
+	// The same mapping restricted to a rectangle of pixels, whose top left becomes (0, 0)
+	pub fn crop(&self, x: f64, y: f64, width: f64, height: f64) -> Viewport {
+		Viewport {
+			width,
+			height,
+
+			center: self.pixel_to_plane(x + width / 2.0, y + height / 2.0),
+			scale: self.scale,
+			rotation: self.rotation,
+			skew: self.skew,
+			inverted: self.inverted,
+		}
+	}
+
+	// Where this viewport's top left pixel lands among another's pixels, and how many of the other's
+	// pixels each of this one's covers. None unless the two differ only by panning and zooming
+	pub fn placement_in(&self, other: &Viewport) -> Option<(Point2<f64>, f64)> {
+		if self.rotation != other.rotation || self.skew != other.skew || self.inverted != other.inverted {
+			return None;
+		}
+
+		Some((other.plane_to_pixel(self.pixel_to_plane(0.0, 0.0)), self.scale / other.scale))
+	}
+
+	// Moves the view by a distance measured in screen pixels
+	pub fn pan(&mut self, dx: f64, dy: f64) {
+		self.center += self.offset_to_complex(dx, dy);
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const EPSILON: f64 = 1e-9;
+
+	fn assert_close(a: Complex, b: Complex) {
+		assert!((a - b).abs() < EPSILON, "{} vs {}", a, b);
+	}
+
+	#[test]
+	fn placements_follow_pans_and_zooms() {
+		let mut view = Viewport::new(100.0, 80.0);
+		let frame = view.crop(-10.0, -10.0, 120.0, 100.0);
+
+		let (origin, scale) = frame.placement_in(&view).unwrap();
+		assert!((origin.x + 10.0).abs() < EPSILON && (origin.y + 10.0).abs() < EPSILON && (scale - 1.0).abs() < EPSILON);
+
+		// Panning right moves the frame left
+		view.pan(4.0, -3.0);
+		let (origin, _) = frame.placement_in(&view).unwrap();
+		assert!((origin.x + 14.0).abs() < EPSILON && (origin.y + 7.0).abs() < EPSILON);
+
+		view.set_magnification(view.magnification() * 2.0);
+		assert!((frame.placement_in(&view).unwrap().1 - 2.0).abs() < EPSILON);
+
+		view.set_rotation(0.1);
+		assert!(frame.placement_in(&view).is_none());
+	}
+
+	#[test]
+	fn default_view_spans_minus_two_to_two() {
+		let viewport = Viewport::new(500.0, 500.0);
+
+		assert_close(viewport.pixel_to_complex(0.0, 0.0), Complex::new(-2.0, -2.0));
+		assert_close(viewport.pixel_to_complex(250.0, 250.0), Complex::new(0.0, 0.0));
+		assert_close(viewport.pixel_to_complex(500.0, 500.0), Complex::new(2.0, 2.0));
+	}
+
+	#[test]
+	fn wide_view_is_letterboxed() {
+		let viewport = Viewport::new(1000.0, 500.0);
+
+		// The short side keeps the default span, the long side extends past it
+		assert_close(viewport.pixel_to_complex(500.0, 0.0), Complex::new(0.0, -2.0));
+		assert_close(viewport.pixel_to_complex(500.0, 500.0), Complex::new(0.0, 2.0));
+		assert_close(viewport.pixel_to_complex(0.0, 250.0), Complex::new(-4.0, 0.0));
+		assert_close(viewport.pixel_to_complex(1000.0, 250.0), Complex::new(4.0, 0.0));
+	}
+
+	#[test]
+	fn tall_view_is_letterboxed() {
+		let viewport = Viewport::new(250.0, 500.0);
+
+		assert_close(viewport.pixel_to_complex(0.0, 250.0), Complex::new(-2.0, 0.0));
+		assert_close(viewport.pixel_to_complex(125.0, 0.0), Complex::new(0.0, -4.0));
+	}
+
+	#[test]
+	fn pixel_round_trip() {
+		let mut viewport = Viewport::new(640.0, 480.0);
+		viewport.set_center(Complex::new(-0.743, 0.131));
+		viewport.set_magnification(250.0);
+		viewport.set_rotation(0.7);
+
+		for (x, y) in [(0.0, 0.0), (123.5, 456.25), (640.0, 10.0)] {
+			let pixel = viewport.complex_to_pixel(viewport.pixel_to_complex(x, y));
+
+			assert!((pixel.x - x).abs() < EPSILON && (pixel.y - y).abs() < EPSILON);
+		}
+	}
+
+	#[test]
+	fn skew_stretches_the_grid() {
+		let mut viewport = Viewport::new(500.0, 500.0);
+		viewport.set_skew([2.0, 0.0, 0.0, 1.0]);
+
+		// Twice as wide horizontally, unchanged vertically
+		assert_close(viewport.pixel_to_complex(500.0, 250.0), Complex::new(4.0, 0.0));
+		assert_close(viewport.pixel_to_complex(250.0, 500.0), Complex::new(0.0, 2.0));
+	}
+
+	#[test]
+	fn skewed_pixel_round_trip() {
+		let mut viewport = Viewport::new(640.0, 480.0);
+		viewport.set_rotation(-1.2);
+		viewport.set_skew([1.5, 0.3, -0.2, 0.8]);
+
+		let pixel = viewport.complex_to_pixel(viewport.pixel_to_complex(17.0, 401.5));
+
+		assert!((pixel.x - 17.0).abs() < EPSILON && (pixel.y - 401.5).abs() < EPSILON);
+	}
+
+	#[test]
+	fn inverted_view_maps_through_reciprocal() {
+		let mut viewport = Viewport::new(500.0, 500.0);
+		viewport.set_center(Complex::new(2.0, 0.0));
+		viewport.set_inverted(true);
+
+		// Navigation stays in the displayed plane, the rendered c is its reciprocal
+		assert_close(viewport.pixel_to_plane(250.0, 250.0), Complex::new(2.0, 0.0));
+		assert_close(viewport.pixel_to_complex(250.0, 250.0), Complex::new(0.5, 0.0));
+		assert_close(viewport.pixel_to_complex(250.0, 0.0), Complex::new(0.25, 0.25));
+
+		let pixel = viewport.complex_to_pixel(viewport.pixel_to_complex(100.0, 321.0));
+		assert!((pixel.x - 100.0).abs() < EPSILON && (pixel.y - 321.0).abs() < EPSILON);
+	}
+
+	#[test]
+	fn magnification_shrinks_scale() {
+		let mut viewport = Viewport::new(500.0, 500.0);
+		viewport.set_magnification(4.0);
+
+		assert!((viewport.magnification() - 4.0).abs() < EPSILON);
+		assert_close(viewport.pixel_to_complex(0.0, 0.0), Complex::new(-0.5, -0.5));
+	}
+
+	#[test]
+	fn resize_keeps_center_and_magnification() {
+		let mut viewport = Viewport::new(500.0, 500.0);
+		viewport.set_center(Complex::new(-0.5, 0.25));
+		viewport.set_magnification(8.0);
+
+		viewport.resize(1000.0, 1000.0);
+
+		assert!((viewport.magnification() - 8.0).abs() < EPSILON);
+		assert_close(viewport.pixel_to_complex(500.0, 500.0), Complex::new(-0.5, 0.25));
+	}
+
+	#[test]
+	fn rotation_turns_about_center() {
+		let mut viewport = Viewport::new(500.0, 500.0);
+		viewport.set_center(Complex::new(1.0, 1.0));
+		viewport.set_rotation(std::f64::consts::FRAC_PI_2);
+
+		// The right edge midpoint now points along the imaginary axis
+		assert_close(viewport.pixel_to_complex(500.0, 250.0), Complex::new(1.0, 3.0));
+		assert_close(viewport.pixel_to_complex(250.0, 250.0), Complex::new(1.0, 1.0));
+	}
+
+	#[test]
+	fn crop_keeps_the_mapping() {
+		let mut viewport = Viewport::new(1000.0, 800.0);
+		viewport.set_center(Complex::new(-0.5, 0.1));
+		viewport.set_magnification(3.0);
+		viewport.set_rotation(0.4);
+
+		let band = viewport.crop(0.0, 320.0, 1000.0, 64.0);
+
+		assert_close(band.pixel_to_complex(0.0, 0.0), viewport.pixel_to_complex(0.0, 320.0));
+		assert_close(band.pixel_to_complex(700.0, 63.0), viewport.pixel_to_complex(700.0, 383.0));
+	}
+
+	#[test]
+	fn pan_follows_rotation() {
+		let mut viewport = Viewport::new(500.0, 500.0);
+		viewport.set_rotation(std::f64::consts::FRAC_PI_2);
+
+		let target = viewport.pixel_to_complex(260.0, 250.0);
+		viewport.pan(10.0, 0.0);
+
+		assert_close(viewport.center(), target);
+	}
+}
@@ -0,0 +1,148 @@
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::export::{self, Transparency};
+use crate::memory::MemoryBudget;
+use crate::postprocess::Effects;
+use crate::renderer::RenderJob;
+
+const WALLPAPER_DIR: &str = "wallpapers";
+
+// Kept alongside the session, each under a new name as some desktops won't reload a path they've
+// already shown
+fn wallpaper_path() -> io::Result<PathBuf> {
+	let dir = dirs::data_dir()
+		.ok_or_else(|| io::Error::other("there is no data directory to keep the wallpaper in"))?
+		.join("mandelbrot_viewer")
+		.join(WALLPAPER_DIR);
+
+	fs::create_dir_all(&dir)?;
+
+	let timestamp = SystemTime::now().duration_since(UNIX_EPOCH).map(|duration| duration.as_millis()).unwrap_or(0);
+
+	Ok(dir.join(format!("wallpaper-{}.png", timestamp)))
+}
+
+// Quoted for AppleScript's double quoted strings and PowerShell's single quoted ones
+fn escape_double_quotes(path: &Path) -> String {
+	path.display().to_string().replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+fn escape_single_quotes(path: &Path) -> String {
+	path.display().to_string().replace('\'', "''")
+}
+
+// What sets the background on this platform, or with the given XDG_CURRENT_DESKTOP on Linux and
+// the BSDs. Desktops not recognised fall back to feh, which covers most bare X window managers
+fn commands(os: &str, desktop: &str, path: &Path) -> Vec<Command> {
+	let command = |program: &str, args: &[&str]| {
+		let mut command = Command::new(program);
+		command.args(args);
+		command
+	};
+
+	match os {
+		"windows" => {
+			// SPI_SETDESKWALLPAPER, written to the profile and broadcast to running programs
+			let script = format!(
+				"Add-Type -TypeDefinition 'using System.Runtime.InteropServices; public class Wallpaper {{ [DllImport(\"user32.dll\", CharSet = CharSet.Unicode)] public static extern int SystemParametersInfo(int action, int parameter, string value, int flags); }}'; \
+				if ([Wallpaper]::SystemParametersInfo(20, 0, '{}', 3) -eq 0) {{ exit 1 }}",
+				escape_single_quotes(path),
+			);
+
+			vec![command("powershell", &["-NoProfile", "-NonInteractive", "-Command", &script])]
+		},
+
+		"macos" => {
+			let script = format!("tell application \"System Events\" to tell every desktop to set picture to \"{}\"", escape_double_quotes(path));
+			vec![command("osascript", &["-e", &script])]
+		},
+
+		_ => {
+			let path = path.display().to_string();
+			let uri = format!("file://{}", path);
+			let desktop = desktop.to_lowercase();
+
+			if ["gnome", "unity", "cinnamon", "budgie"].iter().any(|name| desktop.contains(name)) {
+				// GNOME keeps a separate picture for its dark style
+				vec![
+					command("gsettings", &["set", "org.gnome.desktop.background", "picture-uri", &uri]),
+					command("gsettings", &["set", "org.gnome.desktop.background", "picture-uri-dark", &uri]),
+				]
+			} else if desktop.contains("kde") {
+				vec![command("plasma-apply-wallpaperimage", &[&path])]
+			} else if desktop.contains("xfce") {
+				vec![command("xfconf-query", &["-c", "xfce4-desktop", "-p", "/backdrop/screen0/monitor0/workspace0/last-image", "-s", &path])]
+			} else if desktop.contains("sway") {
+				vec![command("swaymsg", &["output", "*", "bg", &path, "fill"])]
+			} else {
+				vec![command("feh", &["--bg-fill", &path])]
+			}
+		},
+	}
+}
+
+// Runs the platform's commands in turn, stopping at the first to fail
+pub fn set(path: &Path) -> io::Result<()> {
+	let path = fs::canonicalize(path)?;
+	let desktop = env::var("XDG_CURRENT_DESKTOP").unwrap_or_default();
+
+	for mut command in commands(env::consts::OS, &desktop, &path) {
+		let program = command.get_program().to_string_lossy().into_owned();
+		let status = command.status().map_err(|e| io::Error::new(e.kind(), format!("could not run {}: {}", program, e)))?;
+
+		// gsettings is let off, as GNOME before 42 has no key for the dark style's picture
+		if !status.success() && program != "gsettings" {
+			return Err(io::Error::other(format!("{} failed with {}", program, status)));
+		}
+	}
+
+	Ok(())
+}
+
+// Renders the job, sized for the monitor, like a poster and sets it as the desktop background.
+// Returns where the image was saved
+pub fn render_and_set(job: RenderJob, effects: Option<Effects>, budget: MemoryBudget) -> io::Result<PathBuf> {
+	let path = wallpaper_path()?;
+
+	export::save_poster(&path, job, 8, effects, Transparency::None, budget)?;
+	set(&path)?;
+
+	Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn programs(os: &str, desktop: &str) -> Vec<String> {
+		commands(os, desktop, Path::new("/tmp/wall's \"paper\".png")).iter()
+			.map(|command| command.get_program().to_string_lossy().into_owned())
+			.collect()
+	}
+
+	#[test]
+	fn desktops_pick_their_own_setter() {
+		assert_eq!(programs("linux", "ubuntu:GNOME"), ["gsettings", "gsettings"]);
+		assert_eq!(programs("linux", "KDE"), ["plasma-apply-wallpaperimage"]);
+		assert_eq!(programs("linux", "sway"), ["swaymsg"]);
+		assert_eq!(programs("freebsd", ""), ["feh"]);
+		assert_eq!(programs("macos", ""), ["osascript"]);
+		assert_eq!(programs("windows", ""), ["powershell"]);
+	}
+
+	#[test]
+	fn paths_are_quoted_for_scripts() {
+		let script = |os: &str| {
+			let command = commands(os, "", Path::new("/tmp/wall's \"paper\".png")).remove(0);
+			command.get_args().last().unwrap().to_string_lossy().into_owned()
+		};
+
+		assert!(script("macos").ends_with("\"/tmp/wall's \\\"paper\\\".png\""));
+		assert!(script("windows").contains("'/tmp/wall''s \"paper\".png'"));
+	}
+}
@@ -0,0 +1,128 @@
+// Renders reference views and compares them with the images in tests/golden, so changes to the
+// iteration or colouring code can't alter output unnoticed. After an intended change, regenerate the
+// images with UPDATE_GOLDEN=1 cargo test --test golden and look over the difference before committing
+
+use std::env;
+use std::fs::File;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use ggez::graphics::Color;
+
+use mandelbrot_viewer::export;
+use mandelbrot_viewer::formula::Formula;
+use mandelbrot_viewer::gradient::{self, Gradient};
+use mandelbrot_viewer::renderer::{self, IterationSettings, RenderJob};
+use mandelbrot_viewer::session::Session;
+use mandelbrot_viewer::viewport::{Viewport, IDENTITY_SKEW};
+
+const SIZE: (usize, usize) = (160, 120);
+
+// Per channel, out of 255, so libm differences in a few edge pixels don't fail the suite
+const CHANNEL_TOLERANCE: u8 = 2;
+const MAX_DIFFERING_PIXELS: f64 = 0.001;
+
+struct Case {
+	name: &'static str,
+	session: Session,
+	samples: usize,
+}
+
+fn view(center: [f64; 2], magnification: f64, max_iterations: f64, palette: &str) -> Session {
+	Session {
+		center,
+		magnification,
+		rotation: 0.0,
+		skew: IDENTITY_SKEW,
+		inverted: false,
+
+		formula: Formula::Mandelbrot,
+		bailout: 2.0,
+		initial_z: [0.0, 0.0],
+		julia: None,
+		max_iterations,
+
+		palette: palette.to_string(),
+	}
+}
+
+fn cases() -> Vec<Case> {
+	vec![
+		Case { name: "full_set", session: view([-0.5, 0.0], 1.0, 100.0, "rainbow"), samples: 1 },
+		Case { name: "seahorse_valley", session: view([-0.745, 0.11], 200.0, 500.0, "ultra"), samples: 1 },
+		Case { name: "antialiased", session: view([-0.1011, 0.9563], 100.0, 300.0, "viridis"), samples: 2 },
+		Case {
+			name: "rotated_and_skewed",
+			session: Session { rotation: 0.6, skew: [1.0, 0.2, 0.0, 1.0], ..view([-1.25, 0.0], 8.0, 200.0, "cividis") },
+			samples: 1,
+		},
+		Case { name: "inverted_plane", session: Session { inverted: true, ..view([0.0, 0.0], 1.0, 100.0, "rainbow") }, samples: 1 },
+		Case { name: "burning_ship", session: Session { formula: Formula::BurningShip, ..view([-1.76, -0.03], 20.0, 200.0, "ultra") }, samples: 1 },
+		Case { name: "julia", session: Session { julia: Some([-0.8, 0.156]), ..view([0.0, 0.0], 1.0, 300.0, "ultra") }, samples: 1 },
+	]
+}
+
+fn root() -> &'static Path {
+	Path::new(env!("CARGO_MANIFEST_DIR"))
+}
+
+fn render(case: &Case) -> Vec<Color> {
+	let mut viewport = Viewport::new(SIZE.0 as f64, SIZE.1 as f64);
+	let mut settings = IterationSettings::mandelbrot(100.0);
+
+	case.session.restore(&mut viewport, &mut settings);
+
+	let gradient = gradient::load_palettes(&root().join(gradient::PALETTE_DIR), Color::BLACK).into_iter()
+		.find(|gradient| gradient.name == case.session.palette)
+		.unwrap_or_else(Gradient::rainbow);
+
+	let job = RenderJob::new(SIZE.0, SIZE.1, case.samples, viewport, settings, Arc::new(gradient));
+	renderer::render(job, None).pixels
+}
+
+fn read_rgba(path: &Path) -> Vec<u8> {
+	let decoder = png::Decoder::new(File::open(path).unwrap_or_else(|e| panic!("could not open {}: {}", path.display(), e)));
+	let mut reader = decoder.read_info().unwrap();
+
+	let mut buffer = vec![0; reader.output_buffer_size()];
+	let info = reader.next_frame(&mut buffer).unwrap();
+
+	assert_eq!((info.width as usize, info.height as usize), SIZE, "{} is the wrong size", path.display());
+	assert_eq!(info.color_type, png::ColorType::Rgba, "{} isn't RGBA", path.display());
+
+	buffer.truncate(info.buffer_size());
+	buffer
+}
+
+#[test]
+fn renders_match_golden_images() {
+	let update = env::var_os("UPDATE_GOLDEN").is_some();
+	let mut failures = Vec::new();
+
+	for case in cases() {
+		let pixels = render(&case);
+		let golden = root().join("tests/golden").join(format!("{}.png", case.name));
+
+		if update {
+			export::save_png(&golden, &pixels, SIZE, 8, &case.session).unwrap();
+			continue;
+		}
+
+		let expected = read_rgba(&golden);
+		let actual = export::rgba_bytes(&pixels, 8);
+
+		let differing = expected.chunks(4).zip(actual.chunks(4))
+			.filter(|(expected, actual)| expected.iter().zip(actual.iter()).any(|(a, b)| a.abs_diff(*b) > CHANNEL_TOLERANCE))
+			.count();
+
+		if differing as f64 > MAX_DIFFERING_PIXELS * (SIZE.0 * SIZE.1) as f64 {
+			// Kept for comparing by eye
+			let output = PathBuf::from(env!("CARGO_TARGET_TMPDIR")).join(format!("{}.png", case.name));
+			export::save_png(&output, &pixels, SIZE, 8, &case.session).unwrap();
+
+			failures.push(format!("{}: {} pixels differ, rendered to {}", case.name, differing, output.display()));
+		}
+	}
+
+	assert!(failures.is_empty(), "renders differ from their golden images:\n{}", failures.join("\n"));
+}